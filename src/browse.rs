@@ -0,0 +1,81 @@
+use std::io;
+use std::process::Command;
+
+use regex::Regex;
+
+// The host and the path of the repository (e.g. owner/repo) of a remote URL.
+pub fn parse_remote_url(remote_url: &str) -> Option<(String, String)> {
+    // Supports the common forms of remote URLs of hosted repositories:
+    // git@github.com:owner/repo.git
+    // ssh://git@github.com/owner/repo.git
+    // https://github.com/owner/repo.git
+    let regex = Regex::new(
+        r"^(?:(?:https?|ssh|git)://)?(?:[^@/]+@)?([^:/]+)(?::\d+)?[:/](.+?)(?:\.git)?/?$",
+    )
+    .unwrap();
+
+    let captures = regex.captures(remote_url.trim())?;
+    Some((captures[1].to_string(), captures[2].to_string()))
+}
+
+pub fn web_url_from_remote(remote_url: &str, hostname: Option<&str>) -> Option<String> {
+    let (host, repo) = parse_remote_url(remote_url)?;
+
+    // The hostname overrides the host of the remote, e.g. for GitHub Enterprise instances
+    // reached through an SSH host alias.
+    let host = match hostname {
+        Some(hostname) => normalize_hostname(hostname),
+        None => host,
+    };
+    Some(format!("https://{}/{}", host, repo))
+}
+
+// Render a URL template such as https://{host}/{repo}/pulls?head={branch}, for hosts without a
+// command line tool to look up pull requests (e.g. Gitea or Bitbucket).
+pub fn render_url_template(
+    template: &str,
+    remote_url: &str,
+    base: &str,
+    branch: &str,
+) -> Option<String> {
+    let (host, repo) = parse_remote_url(remote_url)?;
+    Some(
+        template
+            .replace("{host}", &host)
+            .replace("{repo}", &repo)
+            .replace("{base}", base)
+            .replace("{branch}", branch),
+    )
+}
+
+pub fn normalize_hostname(hostname: &str) -> String {
+    // Accept hostnames given as URLs, e.g. https://github.example.com/
+    let hostname = hostname.trim();
+    let hostname = hostname
+        .strip_prefix("https://")
+        .or_else(|| hostname.strip_prefix("http://"))
+        .unwrap_or(hostname);
+    hostname.trim_end_matches('/').to_string()
+}
+
+pub fn compare_url(web_url: &str, base: &str, head: &str) -> String {
+    format!("{}/compare/{}...{}?expand=1", web_url, base, head)
+}
+
+pub fn open_url(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+
+    let status = command.arg(url).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("Unable to open: {}", url)));
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+// Ahead/behind counts of many pairs of commits, computed with a single revision walk.
+//
+// Listing a chain counts the commits of each branch relative to its parent branch. With one
+// graph_ahead_behind call per branch, the history shared by the branches is walked again for
+// every branch, which takes seconds on large repositories with many chains. Instead, the tips of
+// all pairs are walked at once, in topological order, down to their common merge-base, and each
+// commit is tagged with the set of tips that it is reachable from.
+
+use std::collections::HashMap;
+
+use git2::{ErrorCode, Oid, Repository, Sort};
+
+// The tips that a commit is reachable from, one bit per tip.
+#[derive(Clone)]
+struct TipSet(Vec<u64>);
+
+impl TipSet {
+    fn new(num_of_tips: usize) -> Self {
+        TipSet(vec![0; num_of_tips.div_ceil(64)])
+    }
+
+    fn insert(&mut self, tip: usize) {
+        self.0[tip / 64] |= 1 << (tip % 64);
+    }
+
+    fn contains(&self, tip: usize) -> bool {
+        self.0[tip / 64] & (1 << (tip % 64)) != 0
+    }
+
+    fn union(&mut self, other: &TipSet) {
+        for (word, other_word) in self.0.iter_mut().zip(other.0.iter()) {
+            *word |= other_word;
+        }
+    }
+}
+
+// For each (upstream, branch) pair: the number of commits of branch that are not in upstream, and
+// of upstream that are not in branch, as graph_ahead_behind(branch, upstream) counts them.
+// None for the pairs without a common ancestor, whose counts would include their entire history.
+pub fn ahead_behind_many(
+    repo: &Repository,
+    pairs: &[(Oid, Oid)],
+) -> Result<Vec<Option<(usize, usize)>>, git2::Error> {
+    if pairs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut tips: Vec<Oid> = vec![];
+    let mut tip_index = |oid: Oid| match tips.iter().position(|tip| *tip == oid) {
+        Some(index) => index,
+        None => {
+            tips.push(oid);
+            tips.len() - 1
+        }
+    };
+    let pair_tips: Vec<(usize, usize)> = pairs
+        .iter()
+        .map(|(upstream, branch)| (tip_index(*upstream), tip_index(*branch)))
+        .collect();
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL)?;
+    let mut tip_sets: HashMap<Oid, TipSet> = HashMap::new();
+    for (index, tip) in tips.iter().enumerate() {
+        revwalk.push(*tip)?;
+        tip_sets
+            .entry(*tip)
+            .or_insert_with(|| TipSet::new(tips.len()))
+            .insert(index);
+    }
+
+    // The commits reachable from the common merge-base are reachable from every tip, so they count
+    // for none of the pairs. Without one, some pairs share no history and the entire history of
+    // the tips is walked to tell which.
+    // merge_base_many treats the tips after the first as a single merge commit, so a common
+    // ancestor of all tips is found one tip at a time instead.
+    let merge_base = tips[1..]
+        .iter()
+        .try_fold(tips[0], |merge_base, tip| repo.merge_base(merge_base, *tip));
+    let all_related = match merge_base {
+        Ok(merge_base) => {
+            revwalk.hide(merge_base)?;
+            true
+        }
+        Err(ref e) if e.code() == ErrorCode::NotFound => false,
+        Err(e) => return Err(e),
+    };
+
+    let mut counts = vec![(0, 0); pairs.len()];
+    let mut related = vec![all_related; pairs.len()];
+    let mut num_of_commits = 0;
+    for oid in revwalk {
+        let oid = oid?;
+        num_of_commits += 1;
+        // All the children of the commit were walked before it.
+        let tip_set = match tip_sets.remove(&oid) {
+            Some(tip_set) => tip_set,
+            None => continue,
+        };
+
+        for (index, (upstream, branch)) in pair_tips.iter().enumerate() {
+            match (tip_set.contains(*upstream), tip_set.contains(*branch)) {
+                (false, true) => counts[index].0 += 1,
+                (true, false) => counts[index].1 += 1,
+                (true, true) => related[index] = true,
+                (false, false) => {}
+            }
+        }
+
+        for parent_id in repo.find_commit(oid)?.parent_ids() {
+            tip_sets
+                .entry(parent_id)
+                .or_insert_with(|| TipSet::new(tips.len()))
+                .union(&tip_set);
+        }
+    }
+    debug_log!(
+        "counted ahead/behind of {} pairs in {} commits",
+        pairs.len(),
+        num_of_commits
+    );
+
+    Ok(counts
+        .into_iter()
+        .zip(related)
+        .map(|(counts, related)| if related { Some(counts) } else { None })
+        .collect())
+}
@@ -0,0 +1,99 @@
+use std::ffi::OsStr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::process::{Command, ExitStatus, Output};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::timings;
+
+// Debug output of --debug, written to stderr or to the file given with --debug-file.
+enum Target {
+    Stderr,
+    File(File),
+}
+
+static TARGET: Mutex<Option<Target>> = Mutex::new(None);
+
+// Logs a formatted message if debug output is enabled; the message is only formatted then.
+macro_rules! debug_log {
+    ($($arg:tt)*) => {
+        if $crate::debug::is_enabled() {
+            $crate::debug::log(&format!($($arg)*));
+        }
+    };
+}
+
+pub fn enable(path: Option<&str>) -> io::Result<()> {
+    let target = match path {
+        Some(path) => Target::File(OpenOptions::new().create(true).append(true).open(path)?),
+        None => Target::Stderr,
+    };
+    *TARGET.lock().unwrap() = Some(target);
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    TARGET.lock().unwrap().is_some()
+}
+
+pub fn log(message: &str) {
+    match TARGET.lock().unwrap().as_mut() {
+        None => {}
+        Some(Target::Stderr) => eprintln!("[debug] {}", message),
+        Some(Target::File(file)) => {
+            let _ = writeln!(file, "[debug] {}", message);
+        }
+    }
+}
+
+// Runs external commands (git, gh), logging each command with its outcome and duration.
+// The duration also counts towards --timings.
+pub trait LoggedCommand {
+    fn logged_output(&mut self) -> io::Result<Output>;
+    fn logged_status(&mut self) -> io::Result<ExitStatus>;
+}
+
+impl LoggedCommand for Command {
+    fn logged_output(&mut self) -> io::Result<Output> {
+        let started_at = Instant::now();
+        let output = self.output();
+        timings::record_command(self, started_at.elapsed());
+        log_command(
+            self,
+            output.as_ref().map(|output| output.status),
+            started_at,
+        );
+        output
+    }
+
+    fn logged_status(&mut self) -> io::Result<ExitStatus> {
+        let started_at = Instant::now();
+        let status = self.status();
+        timings::record_command(self, started_at.elapsed());
+        log_command(self, status.as_ref().copied(), started_at);
+        status
+    }
+}
+
+fn log_command(command: &Command, result: Result<ExitStatus, &io::Error>, started_at: Instant) {
+    if !is_enabled() {
+        return;
+    }
+
+    let command_line = std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(OsStr::to_string_lossy)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    match result {
+        Ok(status) => log(&format!(
+            "ran: {} ({}, {} ms)",
+            command_line,
+            status,
+            started_at.elapsed().as_millis()
+        )),
+        Err(err) => log(&format!("unable to run: {} ({})", command_line, err)),
+    }
+}
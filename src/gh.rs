@@ -0,0 +1,395 @@
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::debug::LoggedCommand;
+
+pub enum GhError {
+    // The gh executable could not be found.
+    Unavailable,
+    // gh ran, but failed (e.g. not authenticated).
+    Failed(String),
+    // gh was not run: offline mode is enabled, or GitHub could not be reached earlier.
+    Offline,
+}
+
+// The reason, to follow e.g. "Unable to create the pull request: ".
+impl fmt::Display for GhError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GhError::Unavailable => write!(f, "gh is not installed"),
+            GhError::Failed(message) => write!(f, "{}", message),
+            GhError::Offline => match offline_reason() {
+                Some(reason) => write!(f, "{}", reason),
+                None => write!(f, "GitHub cannot be reached"),
+            },
+        }
+    }
+}
+
+// Why GitHub is not reached, once offline mode is enabled (--offline, GIT_CHAIN_OFFLINE or
+// chain.offline) or a network error occurred. Pull requests are then read from the cache.
+struct Offline {
+    reason: String,
+    warned: bool,
+}
+
+static OFFLINE: Mutex<Option<Offline>> = Mutex::new(None);
+
+// The file caching the pull requests last found by gh, one branch per line.
+static CACHE_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+pub fn go_offline(reason: &str) {
+    let mut offline = OFFLINE.lock().unwrap();
+    if offline.is_none() {
+        *offline = Some(Offline {
+            reason: reason.to_string(),
+            warned: false,
+        });
+    }
+}
+
+pub fn offline_reason() -> Option<String> {
+    OFFLINE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|offline| offline.reason.clone())
+}
+
+// Warns once per run that cached pull requests are displayed.
+fn warn_offline() {
+    if let Some(offline) = OFFLINE.lock().unwrap().as_mut() {
+        if !offline.warned {
+            offline.warned = true;
+            eprintln!(
+                "⚠️  {}; using the pull requests cached by earlier runs.",
+                offline.reason
+            );
+        }
+    }
+}
+
+fn is_network_error(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    [
+        "error connecting to",
+        "dial tcp",
+        "no such host",
+        "could not resolve host",
+        "connection refused",
+        "network is unreachable",
+        "i/o timeout",
+        "tls handshake timeout",
+    ]
+    .iter()
+    .any(|pattern| stderr.contains(pattern))
+}
+
+pub fn set_cache_path(path: PathBuf) {
+    *CACHE_PATH.lock().unwrap() = Some(path);
+}
+
+fn read_cache() -> Vec<(String, PullRequest)> {
+    let path = match CACHE_PATH.lock().unwrap().clone() {
+        Some(path) => path,
+        None => return vec![],
+    };
+    let contents = fs::read_to_string(path).unwrap_or_default();
+
+    // branch, number, state, review decision, mergeable, url
+    contents
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 6 {
+                return None;
+            }
+            Some((
+                fields[0].to_string(),
+                PullRequest {
+                    number: fields[1].parse().ok()?,
+                    state: fields[2].to_string(),
+                    review_decision: fields[3].to_string(),
+                    mergeable: fields[4].to_string(),
+                    url: fields[5].to_string(),
+                },
+            ))
+        })
+        .collect()
+}
+
+fn update_cache(branch_name: &str, pull_request: Option<&PullRequest>) {
+    let path = match CACHE_PATH.lock().unwrap().clone() {
+        Some(path) => path,
+        None => return,
+    };
+
+    let mut lines: Vec<String> = read_cache()
+        .into_iter()
+        .filter(|(cached_branch_name, _)| cached_branch_name != branch_name)
+        .map(|(cached_branch_name, cached)| cache_line(&cached_branch_name, &cached))
+        .collect();
+    if let Some(pull_request) = pull_request {
+        lines.push(cache_line(branch_name, pull_request));
+    }
+
+    // The cache is best effort, e.g. on read-only file systems.
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(path, lines.join("\n") + "\n");
+}
+
+fn cache_line(branch_name: &str, pull_request: &PullRequest) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}",
+        branch_name,
+        pull_request.number,
+        pull_request.state,
+        pull_request.review_decision,
+        pull_request.mergeable,
+        pull_request.url
+    )
+}
+
+fn cached_pull_request(branch_name: &str) -> Option<PullRequest> {
+    read_cache()
+        .into_iter()
+        .find(|(cached_branch_name, _)| cached_branch_name == branch_name)
+        .map(|(_, pull_request)| pull_request)
+}
+
+pub struct PullRequest {
+    pub number: u64,
+    pub url: String,
+    pub state: String,
+    pub review_decision: String,
+    pub mergeable: String,
+}
+
+const PULL_REQUEST_JQ: &str =
+    r#""\(.number)\t\(.url)\t\(.state)\t\(.reviewDecision)\t\(.mergeable)""#;
+
+fn parse_pull_request(line: &str) -> Option<PullRequest> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() != 5 {
+        return None;
+    }
+    Some(PullRequest {
+        number: fields[0].parse().ok()?,
+        url: fields[1].to_string(),
+        state: fields[2].to_string(),
+        review_decision: fields[3].to_string(),
+        mergeable: fields[4].to_string(),
+    })
+}
+
+pub fn find_pull_request(branch_name: &str) -> Result<Option<PullRequest>, GhError> {
+    if offline_reason().is_some() {
+        warn_offline();
+        return Ok(cached_pull_request(branch_name));
+    }
+
+    // gh pr view <branch> --json number,url,state,reviewDecision,mergeable --jq <...>
+    let output = match Command::new("gh")
+        .arg("pr")
+        .arg("view")
+        .arg(branch_name)
+        .arg("--json")
+        .arg("number,url,state,reviewDecision,mergeable")
+        .arg("--jq")
+        .arg(PULL_REQUEST_JQ)
+        .logged_output()
+    {
+        Ok(output) => output,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => return Err(GhError::Unavailable),
+        Err(e) => return Err(GhError::Failed(e.to_string())),
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if stderr.contains("no pull requests found") {
+            update_cache(branch_name, None);
+            return Ok(None);
+        }
+        if is_network_error(&stderr) {
+            go_offline("Unable to reach GitHub");
+            warn_offline();
+            return Ok(cached_pull_request(branch_name));
+        }
+        return Err(GhError::Failed(stderr));
+    }
+
+    // Only the newline is trimmed, as the last field may be empty.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let pull_request = match parse_pull_request(stdout.trim_end_matches('\n')) {
+        Some(pull_request) => pull_request,
+        None => return Ok(None),
+    };
+    update_cache(branch_name, Some(&pull_request));
+
+    Ok(Some(pull_request))
+}
+
+fn run_gh(args: &[&str]) -> Result<String, GhError> {
+    if offline_reason().is_some() {
+        return Err(GhError::Offline);
+    }
+
+    let output = match Command::new("gh").args(args).logged_output() {
+        Ok(output) => output,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => return Err(GhError::Unavailable),
+        Err(e) => return Err(GhError::Failed(e.to_string())),
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if is_network_error(&stderr) {
+            go_offline("Unable to reach GitHub");
+            return Err(GhError::Offline);
+        }
+        return Err(GhError::Failed(stderr));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub fn check_available() -> Result<(), GhError> {
+    run_gh(&["--version"]).map(|_| ())
+}
+
+pub fn create_pull_request(head: &str, base: &str, draft: bool) -> Result<String, GhError> {
+    // gh pr create --head <head> --base <base> --fill [--draft]
+    let mut args = vec!["pr", "create", "--head", head, "--base", base, "--fill"];
+    if draft {
+        args.push("--draft");
+    }
+
+    // gh prints the URL of the new pull request last.
+    let stdout = run_gh(&args)?;
+    Ok(stdout.lines().last().unwrap_or_default().to_string())
+}
+
+pub fn pull_request_labels(branch_name: &str) -> Result<Vec<String>, GhError> {
+    // gh pr view <branch> --json labels --jq .labels[].name
+    let stdout = run_gh(&[
+        "pr",
+        "view",
+        branch_name,
+        "--json",
+        "labels",
+        "--jq",
+        ".labels[].name",
+    ])?;
+    Ok(stdout
+        .lines()
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect())
+}
+
+pub fn create_label(name: &str, description: &str) -> Result<(), GhError> {
+    // gh label create <name> --description <description> --force
+    // --force updates the label if it already exists.
+    run_gh(&[
+        "label",
+        "create",
+        name,
+        "--description",
+        description,
+        "--force",
+    ])
+    .map(|_| ())
+}
+
+pub fn edit_pull_request_labels(
+    branch_name: &str,
+    add: &[String],
+    remove: &[String],
+) -> Result<(), GhError> {
+    // gh pr edit <branch> [--add-label <label>]... [--remove-label <label>]...
+    let mut args = vec!["pr", "edit", branch_name];
+    for label in add {
+        args.extend(["--add-label", label]);
+    }
+    for label in remove {
+        args.extend(["--remove-label", label]);
+    }
+    run_gh(&args).map(|_| ())
+}
+
+pub fn edit_pull_request_base(branch_name: &str, base: &str) -> Result<(), GhError> {
+    // gh pr edit <branch> --base <base>
+    run_gh(&["pr", "edit", branch_name, "--base", base]).map(|_| ())
+}
+
+// The branches of a pull request, as needed to follow a stack of pull requests.
+pub struct PullRequestRefs {
+    pub number: u64,
+    pub head: String,
+    pub base: String,
+    pub state: String,
+}
+
+const PULL_REQUEST_REFS_JQ: &str = r#""\(.number)\t\(.headRefName)\t\(.baseRefName)\t\(.state)""#;
+
+fn parse_pull_request_refs(line: &str) -> Option<PullRequestRefs> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() != 4 {
+        return None;
+    }
+    Some(PullRequestRefs {
+        number: fields[0].parse().ok()?,
+        head: fields[1].to_string(),
+        base: fields[2].to_string(),
+        state: fields[3].to_string(),
+    })
+}
+
+// The pull request of a branch, or with a number or URL.
+pub fn find_pull_request_refs(selector: &str) -> Result<Option<PullRequestRefs>, GhError> {
+    // gh pr view <selector> --json number,headRefName,baseRefName,state --jq <...>
+    match run_gh(&[
+        "pr",
+        "view",
+        selector,
+        "--json",
+        "number,headRefName,baseRefName,state",
+        "--jq",
+        PULL_REQUEST_REFS_JQ,
+    ]) {
+        Ok(stdout) => Ok(parse_pull_request_refs(&stdout)),
+        Err(GhError::Failed(message)) if message.contains("no pull requests found") => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+// The open pull requests into a branch.
+pub fn pull_requests_into(base: &str) -> Result<Vec<PullRequestRefs>, GhError> {
+    // gh pr list --base <base> --state open --json number,headRefName,baseRefName,state --jq <...>
+    let stdout = run_gh(&[
+        "pr",
+        "list",
+        "--base",
+        base,
+        "--state",
+        "open",
+        "--json",
+        "number,headRefName,baseRefName,state",
+        "--jq",
+        &format!(".[] | {}", PULL_REQUEST_REFS_JQ),
+    ])?;
+    Ok(stdout.lines().filter_map(parse_pull_request_refs).collect())
+}
+
+// Renames a branch on GitHub, which also moves the pull requests from and into it.
+pub fn rename_branch(branch_name: &str, new_branch_name: &str) -> Result<(), GhError> {
+    // gh api --method POST repos/{owner}/{repo}/branches/<branch>/rename -f new_name=<new_branch>
+    let endpoint = format!("repos/{{owner}}/{{repo}}/branches/{}/rename", branch_name);
+    let new_name = format!("new_name={}", new_branch_name);
+    run_gh(&["api", "--method", "POST", &endpoint, "-f", &new_name]).map(|_| ())
+}
@@ -0,0 +1,725 @@
+// Built-in documentation: the examples at the end of the help of each subcommand, and the guides
+// displayed by git chain help <topic>.
+//
+// The examples of every subcommand are defined here, in one table, so that the output of -h
+// walks through the same workflow as the guides.
+
+pub struct Topic {
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub body: &'static str,
+}
+
+pub const TOPICS: [Topic; 3] = [
+    Topic {
+        name: "stacking",
+        summary: "Stack branches with a chain, from setting it up to landing it.",
+        body: r#"STACKING BRANCHES WITH GIT CHAIN
+
+A chain is a root branch (e.g. main) and an ordered list of branches, each one based on the
+branch before it. Splitting a large change into a chain of small branches keeps each branch easy
+to review, while later branches build on earlier ones.
+
+1. Set up the chain
+
+   Create the branches as usual, each one off the previous one, then record their order:
+
+       git checkout -b feature-1 main
+       git checkout -b feature-2
+       git chain setup big-feature main feature-1 feature-2
+
+   Or add the branches one at a time, as they are created:
+
+       git chain init big-feature main
+       git chain init big-feature main --after=feature-1
+
+   Display the chain of the current branch with git chain (or git chain status), and every
+   chain with git chain list.
+
+2. Move around the chain
+
+       git chain first
+       git chain next
+       git chain prev
+       git chain last
+
+3. Keep the chain up to date
+
+   After committing to a branch in the middle of the chain, or after main moved, rebase every
+   branch onto its parent branch:
+
+       git chain fetch
+       git chain rebase --update-root
+
+   To keep the history of branches that were already published, cherry-pick the new commits
+   of each parent branch instead:
+
+       git chain cherry
+
+   Back up the chain before rewriting it, and check it afterwards:
+
+       git chain backup
+       git chain rebase --verify
+
+4. Land the chain
+
+   Once branches landed on the root branch (e.g. their pull requests were merged), update the
+   root branch, then rebase the rest of the chain onto it and drop the landed branches:
+
+       git pull
+       git chain after-land --push
+
+   Branches whose commits are on the root branch, or whose upstream branch is gone, can also
+   be removed from the chain with:
+
+       git chain prune
+
+See also: git chain help conflicts, git chain help pull-requests"#,
+    },
+    Topic {
+        name: "conflicts",
+        summary: "Recover from conflicts and mistakes while rebasing a chain.",
+        body: r#"RECOVERING FROM CONFLICTS
+
+git chain rebase rebases the branches of the chain one after the other. When the rebase of a
+branch stops on conflicts, git chain stops too, and prints a summary: the branches that were
+rebased, the files with conflicts, and the branch that last changed each file. A conflict that
+comes from an earlier branch may be better fixed in that branch.
+
+1. Resolve the conflicts, then resume
+
+       git status
+       git add <path>
+       git rebase --continue
+       git chain rebase
+
+   Running git chain rebase again picks up where it stopped: the branches that are already
+   rebased are left as they are.
+
+2. Or give up on the rebase
+
+       git rebase --abort
+
+   The branches that were already rebased stay rebased. Restore them from a backup (see
+   below) to undo the whole rebase.
+
+3. Avoid conflicts in the working directory
+
+   Rebase one branch at a time:
+
+       git chain rebase --step
+
+   Or rebase in a temporary worktree, so that the branches are only updated once every branch
+   was rebased, and the working directory is left alone:
+
+       git chain rebase --worktree
+
+   For a branch of generated code, let its own changes win:
+
+       git chain strategy set theirs
+
+4. Restore a backup
+
+   Back up the branches of the chain before a risky operation:
+
+       git chain backup
+       git chain backup --list
+
+   Check that no commits were lost, and that each branch is based on its parent branch:
+
+       git chain verify
+
+   When a check fails, verify prints the commands that restore the branches from the latest
+   backup. To restore the whole chain from a backup instead, including its order and its root
+   branch:
+
+       git chain backup --restore <timestamp>
+
+   Without a backup, git reflog <branch> lists the previous commits of a branch.
+
+See also: git chain help stacking"#,
+    },
+    Topic {
+        name: "pull-requests",
+        summary: "Publish a chain as a stack of pull requests, and keep it up to date.",
+        body: r#"STACKS OF PULL REQUESTS
+
+Each branch of a chain becomes a pull request against its parent branch, so that reviewers
+only see the changes of that branch. git chain uses gh, the GitHub CLI, for pull requests.
+
+1. Push the chain and open the pull requests
+
+       git chain push --create-pr
+       git chain pr --draft-until feature-1
+
+   The pull requests are created from the first to the last branch, each against its parent
+   branch. Label them with their position in the stack:
+
+       git chain pr --stack-order-labels
+
+   Display the chain with its pull requests, e.g. to paste into the description of a pull
+   request:
+
+       git chain status --format=markdown
+
+2. Update the pull requests
+
+   After rebasing the chain, force push the branches that changed:
+
+       git chain rebase
+       git chain push --force --only-changed
+
+   Before force pushing, check for approved or conflicting pull requests, and leave alone the
+   branches with commits pushed by reviewers:
+
+       git chain push --force --check-pr --protect-reviewed
+
+   After moving branches of the chain, rebase it, force push it, and change the base branch
+   of the pull requests whose parent branch changed:
+
+       git chain move --after=feature-2
+       git chain rebase
+       git chain push --force --only-changed
+       git chain pr --update-base-on-reorder
+
+3. Review a stack of someone else
+
+       git chain import --from-prs <pr_number>
+
+   Or, when the chain was published to the remote with git chain publish:
+
+       git chain subscribe <chain_name>
+
+4. Land the stack
+
+   Once the first pull requests are merged, rebase the rest of the chain onto the root branch,
+   drop the merged branches, and push:
+
+       git pull
+       git chain after-land --push
+
+See also: git chain help stacking"#,
+    },
+];
+
+// (subcommand, [(description, arguments of git chain)])
+const EXAMPLES: &[(&str, &[(&str, &str)])] = &[
+    (
+        "adopt",
+        &[
+            (
+                "Add a branch to the chain of the current branch, where it forked",
+                "adopt feature-3",
+            ),
+            (
+                "Add a branch to another chain",
+                "adopt feature-3 --chain=big-feature",
+            ),
+        ],
+    ),
+    (
+        "after-land",
+        &[
+            (
+                "Once the first branches were merged, drop them and rebase the rest",
+                "after-land",
+            ),
+            (
+                "Also force push the branches that changed",
+                "after-land --push",
+            ),
+        ],
+    ),
+    (
+        "backup",
+        &[
+            ("Back up the branches of the current chain", "backup"),
+            (
+                "Also back up the root branch, e.g. before updating it",
+                "backup --include-root",
+            ),
+            ("List the backups, newest first", "backup --list"),
+            ("Keep only the last 5 backups", "backup --prune --keep=5"),
+            (
+                "Restore the chain from a backup",
+                "backup --restore 20240131T235959",
+            ),
+        ],
+    ),
+    (
+        "blame",
+        &[(
+            "Find the branch of the chain that introduced each line of a file",
+            "blame src/main.rs",
+        )],
+    ),
+    (
+        "browse",
+        &[
+            ("Open the pull request of the current branch", "browse"),
+            (
+                "Print the URLs of the pull requests of the chain",
+                "browse --all --print",
+            ),
+        ],
+    ),
+    (
+        "changes",
+        &[(
+            "Warn about files changed outside of the scope of the chain",
+            "changes",
+        )],
+    ),
+    (
+        "cherry",
+        &[
+            (
+                "Cherry-pick the new commits of each parent branch, keeping the history",
+                "cherry",
+            ),
+            (
+                "Backport a hotfix to every branch of the chain",
+                "cherry --backport hotfix",
+            ),
+        ],
+    ),
+    (
+        "copy",
+        &[(
+            "Copy a chain, naming the new branches v2/<branch>",
+            "copy big-feature big-feature-v2 --template=v2/{branch}",
+        )],
+    ),
+    (
+        "diff",
+        &[
+            (
+                "Changes of the current branch against its parent branch",
+                "diff",
+            ),
+            (
+                "Changes of the whole chain against the root branch",
+                "diff --all --stat",
+            ),
+        ],
+    ),
+    (
+        "disable",
+        &[(
+            "Stop git chain while the chains are migrated",
+            "disable migrating to the new layout",
+        )],
+    ),
+    (
+        "doctor",
+        &[
+            ("Check and repair the metadata of every chain", "doctor"),
+            (
+                "Spread the sort keys of a chain evenly",
+                "doctor --renumber --chain=big-feature",
+            ),
+        ],
+    ),
+    ("enable", &[("Enable git chain again", "enable")]),
+    (
+        "fetch",
+        &[(
+            "Fetch the upstream branches of the root branch and of the chain",
+            "fetch",
+        )],
+    ),
+    (
+        "first",
+        &[
+            ("Switch to the first branch of the chain", "first"),
+            (
+                "Switch to the first branch of another chain",
+                "first --chain=big-feature",
+            ),
+        ],
+    ),
+    (
+        "help",
+        &[
+            ("Read the guide to stacking branches", "help stacking"),
+            ("Display the help of a subcommand", "help rebase"),
+        ],
+    ),
+    (
+        "history",
+        &[(
+            "The last 5 commands that modified branches or chains",
+            "history -n 5",
+        )],
+    ),
+    (
+        "import",
+        &[(
+            "Set up a chain from a stack of pull requests",
+            "import --from-prs 123 --chain=big-feature",
+        )],
+    ),
+    (
+        "init",
+        &[
+            (
+                "Add the current branch at the end of a chain",
+                "init big-feature main",
+            ),
+            (
+                "Add the current branch after another branch",
+                "init big-feature main --after=feature-1",
+            ),
+        ],
+    ),
+    (
+        "last",
+        &[("Switch to the last branch of the chain", "last")],
+    ),
+    (
+        "lint",
+        &[
+            ("Check the length of commit subjects", "lint"),
+            ("Check for conventional commits", "lint --conventional"),
+            (
+                "Remove branches that no longer exist from the chain",
+                "lint --fix",
+            ),
+        ],
+    ),
+    (
+        "list",
+        &[
+            ("List all chains", "list"),
+            ("List all chains with their pull requests", "list --pr"),
+        ],
+    ),
+    (
+        "migrate-root",
+        &[(
+            "Move the chains rooted at master to the new default branch",
+            "migrate-root --from=master",
+        )],
+    ),
+    (
+        "move",
+        &[
+            (
+                "Move the current branch after another branch",
+                "move --after=feature-1",
+            ),
+            ("Change the root branch of the chain", "move --root=main"),
+        ],
+    ),
+    (
+        "new-stack",
+        &[(
+            "Create the branches of a new stack from the default template",
+            "new-stack search",
+        )],
+    ),
+    (
+        "next",
+        &[("Switch to the next branch of the chain", "next")],
+    ),
+    (
+        "note",
+        &[
+            (
+                "Attach a note to the current branch",
+                "note set blocked on API review",
+            ),
+            ("Clear it", "note clear"),
+        ],
+    ),
+    (
+        "parent",
+        &[(
+            "Diff against the parent branch of the current branch",
+            "parent | xargs git diff",
+        )],
+    ),
+    (
+        "pr",
+        &[
+            ("Open a pull request for each branch of the chain", "pr"),
+            (
+                "Open draft pull requests for the branches after feature-1",
+                "pr --draft-until feature-1",
+            ),
+            (
+                "Change the base branch of pull requests after moving branches",
+                "pr --update-base-on-reorder",
+            ),
+            (
+                "Review the pull requests of the chain in the browser",
+                "pr --open",
+            ),
+            (
+                "Refuse to open pull requests for branches over the size limits",
+                "pr --strict",
+            ),
+        ],
+    ),
+    (
+        "prev",
+        &[("Switch to the previous branch of the chain", "prev")],
+    ),
+    (
+        "prune",
+        &[(
+            "Remove the branches that landed, or whose upstream branch is gone",
+            "prune",
+        )],
+    ),
+    (
+        "publish",
+        &[(
+            "Share the layout of the current chain with teammates",
+            "publish",
+        )],
+    ),
+    (
+        "push",
+        &[
+            ("Push the branches of the chain", "push"),
+            (
+                "Force push the branches that changed after a rebase",
+                "push --force --only-changed",
+            ),
+            ("Push and open pull requests", "push --create-pr"),
+        ],
+    ),
+    (
+        "push-ref",
+        &[
+            (
+                "Push the current branch to a branch of another name on the remote",
+                "push-ref set users/me/feature-1",
+            ),
+            (
+                "Push it to the branch of the same name again",
+                "push-ref clear",
+            ),
+        ],
+    ),
+    (
+        "rebase",
+        &[
+            (
+                "Rebase each branch of the chain onto its parent branch",
+                "rebase",
+            ),
+            (
+                "First fast-forward the root branch to its upstream branch",
+                "rebase --update-root",
+            ),
+            (
+                "Rebase in a temporary worktree, leaving the working directory alone",
+                "rebase --worktree",
+            ),
+            ("Check the chain afterwards", "rebase --verify"),
+            (
+                "End on the last rebased branch instead of the current branch",
+                "rebase --stay",
+            ),
+        ],
+    ),
+    (
+        "remove",
+        &[
+            ("Remove the current branch from its chain", "remove"),
+            (
+                "Remove a chain, listing what would be removed",
+                "remove --chain=big-feature --dry-run",
+            ),
+        ],
+    ),
+    (
+        "rename",
+        &[("Rename the current chain", "rename bigger-feature")],
+    ),
+    (
+        "rename-prefix",
+        &[
+            (
+                "Move the branches of the chain to another namespace",
+                "rename-prefix user/foo/ user/foo-v2/",
+            ),
+            (
+                "Also rename them on the remote, keeping their pull requests",
+                "rename-prefix --remote user/foo/ user/foo-v2/",
+            ),
+            (
+                "Rename the branches with a regex",
+                "rename-prefix --regex '^feature-(\\d+)$' 'feat/$1'",
+            ),
+        ],
+    ),
+    (
+        "reorder",
+        &[(
+            "Order the branches of the chain by their git ancestry",
+            "reorder --topological",
+        )],
+    ),
+    (
+        "root",
+        &[("Print the root branch of the current chain", "root")],
+    ),
+    (
+        "scope",
+        &[
+            (
+                "Scope the current chain to a directory",
+                "scope services/api",
+            ),
+            ("Remove the scope", "scope --clear"),
+        ],
+    ),
+    (
+        "setup",
+        &[
+            (
+                "Set up a chain of two branches on main",
+                "setup big-feature main feature-1 feature-2",
+            ),
+            (
+                "Add feature-3 to the end of the chain",
+                "setup --extend big-feature main feature-1 feature-2 feature-3",
+            ),
+        ],
+    ),
+    (
+        "size-guard",
+        &[(
+            "Fail if a branch has more than 10 commits or 400 changed lines",
+            "size-guard --max-commits=10 --max-changed-lines=400 --strict",
+        )],
+    ),
+    (
+        "squash-branch",
+        &[(
+            "Squash the current branch, and rebase the branches after it",
+            "squash-branch --message \"Add search\"",
+        )],
+    ),
+    (
+        "stash",
+        &[
+            ("Stash the changes of the current branch", "stash push"),
+            (
+                "Pop the last stash of the chain onto its branch",
+                "stash pop",
+            ),
+        ],
+    ),
+    (
+        "stats",
+        &[
+            ("Metrics of the current chain", "stats"),
+            (
+                "Metrics of all chains, for a dashboard",
+                "stats --all --format=json",
+            ),
+        ],
+    ),
+    (
+        "status",
+        &[
+            ("Display the chain of the current branch", "status"),
+            (
+                "Display it as a markdown table, for a pull request description",
+                "status --format=markdown",
+            ),
+            (
+                "Mark the branches that would conflict on the next rebase",
+                "status --check-conflicts",
+            ),
+        ],
+    ),
+    (
+        "strategy",
+        &[(
+            "Let the changes of a branch of generated code win on conflicts",
+            "strategy set theirs",
+        )],
+    ),
+    (
+        "subscribe",
+        &[(
+            "Set up a chain published by a teammate",
+            "subscribe big-feature",
+        )],
+    ),
+    (
+        "test",
+        &[
+            (
+                "Run the tests on each branch of the chain",
+                "test -- cargo test",
+            ),
+            (
+                "Run them in parallel, in temporary worktrees",
+                "test --jobs 4 -- cargo test",
+            ),
+        ],
+    ),
+    (
+        "undo",
+        &[(
+            "Restore the branches of the chain after a rebase went wrong",
+            "undo",
+        )],
+    ),
+    (
+        "verify",
+        &[(
+            "Check the chain against its latest backup after a rebase",
+            "verify",
+        )],
+    ),
+];
+
+pub fn topic(name: &str) -> Option<&'static Topic> {
+    TOPICS.iter().find(|topic| topic.name == name)
+}
+
+// The EXAMPLES section of the help of a subcommand.
+pub fn examples(subcommand_name: &str) -> Option<String> {
+    let (_name, examples) = EXAMPLES
+        .iter()
+        .find(|(name, _examples)| *name == subcommand_name)?;
+
+    let mut lines = vec!["EXAMPLES:".to_string()];
+    for (index, (description, arguments)) in examples.iter().enumerate() {
+        if index > 0 {
+            lines.push("".to_string());
+        }
+        lines.push(format!("    # {}", description));
+        lines.push(format!("    git chain {}", arguments));
+    }
+    Some(lines.join("\n"))
+}
+
+// The GUIDES section of the help of git chain.
+pub fn topics_help() -> String {
+    let width = TOPICS
+        .iter()
+        .map(|topic| topic.name.len())
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = vec!["GUIDES:".to_string()];
+    for topic in &TOPICS {
+        lines.push(format!(
+            "    {:width$}    {}",
+            topic.name,
+            topic.summary,
+            width = width
+        ));
+    }
+    lines.push("".to_string());
+    lines.push("Run git chain help <topic> to read a guide.".to_string());
+    lines.join("\n")
+}
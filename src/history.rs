@@ -0,0 +1,93 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+// A mutating git chain command, with the commits of the branches that it moved.
+pub struct Entry {
+    // UNIX timestamps of the start and the end of the command.
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub command: String,
+    // (branch, commit before, commit after); None if the branch did not exist.
+    pub changes: Vec<(String, Option<String>, Option<String>)>,
+}
+
+pub fn log_path(chain_dir: &Path) -> PathBuf {
+    chain_dir.join("log")
+}
+
+impl Entry {
+    // One line per entry, with tab separated fields:
+    // <started_at> <finished_at> <command> <branch>:<before>:<after> ...
+    // Branch names cannot contain spaces or colons; a missing commit is written as -.
+    fn to_line(&self) -> String {
+        let changes: Vec<String> = self
+            .changes
+            .iter()
+            .map(|(branch, before, after)| {
+                format!(
+                    "{}:{}:{}",
+                    branch,
+                    before.as_deref().unwrap_or("-"),
+                    after.as_deref().unwrap_or("-")
+                )
+            })
+            .collect();
+
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.started_at,
+            self.finished_at,
+            self.command.replace(['\t', '\n'], " "),
+            changes.join(" ")
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Entry> {
+        let mut fields = line.split('\t');
+        let started_at = fields.next()?.parse().ok()?;
+        let finished_at = fields.next()?.parse().ok()?;
+        let command = fields.next()?.to_string();
+
+        let commit = |oid: &str| match oid {
+            "-" => None,
+            oid => Some(oid.to_string()),
+        };
+
+        let mut changes = vec![];
+        for change in fields.next().unwrap_or("").split_whitespace() {
+            let mut parts = change.rsplitn(3, ':');
+            let after = commit(parts.next()?);
+            let before = commit(parts.next()?);
+            let branch = parts.next()?.to_string();
+            changes.push((branch, before, after));
+        }
+
+        Some(Entry {
+            started_at,
+            finished_at,
+            command,
+            changes,
+        })
+    }
+}
+
+pub fn append(chain_dir: &Path, entry: &Entry) -> io::Result<()> {
+    fs::create_dir_all(chain_dir)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path(chain_dir))?;
+    writeln!(file, "{}", entry.to_line())
+}
+
+pub fn read(chain_dir: &Path) -> io::Result<Vec<Entry>> {
+    let contents = match fs::read_to_string(log_path(chain_dir)) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+
+    // Lines that cannot be parsed (e.g. a partially written line) are skipped.
+    Ok(contents.lines().filter_map(Entry::from_line).collect())
+}
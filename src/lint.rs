@@ -0,0 +1,59 @@
+use regex::Regex;
+
+// https://www.conventionalcommits.org/en/v1.0.0/
+const CONVENTIONAL_COMMIT_PATTERN: &str =
+    r"^(build|chore|ci|docs|feat|fix|perf|refactor|revert|style|test)(\([^()]+\))?!?: \S";
+
+pub const DEFAULT_MAX_SUBJECT_LENGTH: usize = 72;
+
+pub struct LintRules {
+    pub pattern: Option<Regex>,
+    pub conventional: bool,
+    // A max subject length of 0 disables the check.
+    pub max_subject_length: usize,
+    pub ticket_pattern: Option<Regex>,
+}
+
+impl LintRules {
+    pub fn check(&self, message: &str) -> Vec<String> {
+        let subject = message.lines().next().unwrap_or("").trim_end();
+        let mut violations = vec![];
+
+        if subject.is_empty() {
+            violations.push("Subject is empty".to_string());
+            return violations;
+        }
+
+        if self.conventional {
+            let regex = Regex::new(CONVENTIONAL_COMMIT_PATTERN).unwrap();
+            if !regex.is_match(subject) {
+                violations.push("Subject does not follow Conventional Commits".to_string());
+            }
+        }
+
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(subject) {
+                violations.push(format!("Subject does not match pattern: {}", pattern));
+            }
+        }
+
+        let subject_length = subject.chars().count();
+        if self.max_subject_length > 0 && subject_length > self.max_subject_length {
+            violations.push(format!(
+                "Subject is longer than {} characters ({})",
+                self.max_subject_length, subject_length
+            ));
+        }
+
+        if let Some(ticket_pattern) = &self.ticket_pattern {
+            if !ticket_pattern.is_match(message) {
+                violations.push(format!(
+                    "Message does not reference a ticket matching: {}",
+                    ticket_pattern
+                ));
+            }
+        }
+
+        violations
+    }
+}
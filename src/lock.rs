@@ -0,0 +1,141 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::Mutex;
+
+// Held while a command that mutates branches runs, so that concurrent invocations
+// (e.g. from an editor integration and a shell) cannot interleave.
+pub struct ChainLock(());
+
+// The lock held by this process, so that it can be released before process::exit, which skips
+// destructors.
+static HELD_LOCK: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+pub enum LockResult {
+    Acquired(ChainLock),
+    // The lock is held by another running process.
+    Held(u32),
+}
+
+impl Drop for ChainLock {
+    fn drop(&mut self) {
+        release();
+    }
+}
+
+pub fn release() {
+    let mut held_lock = HELD_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(path) = held_lock.take() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+pub fn lock_path(lock_dir: &Path) -> PathBuf {
+    lock_dir.join("lock")
+}
+
+#[cfg(unix)]
+fn process_is_alive(pid: u32) -> bool {
+    match Command::new("kill")
+        .arg("-0")
+        .arg(pid.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+    {
+        Ok(status) => status.success(),
+        // Unable to tell; assume that the lock is still held.
+        Err(_) => true,
+    }
+}
+
+#[cfg(not(unix))]
+fn process_is_alive(_pid: u32) -> bool {
+    true
+}
+
+fn holder_of(path: &Path) -> io::Result<Option<u32>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(contents.trim().parse::<u32>().ok()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+// The lock file is linked into place once it holds the pid, so that other processes never read
+// an empty lock file and mistake it for a stale one.
+fn create(path: &Path) -> io::Result<bool> {
+    let tmp_path = path.with_file_name(format!("lock.{}.tmp", process::id()));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp_path)?;
+    writeln!(file, "{}", process::id())?;
+    drop(file);
+
+    let result = fs::hard_link(&tmp_path, path);
+    let _ = fs::remove_file(&tmp_path);
+    match result {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+// The stale lock is renamed rather than removed, so that its contents can be checked once it is
+// out of the way: another process may have taken it over in the meantime, in which case its lock
+// is put back and returned as the holder.
+fn take_over(path: &Path, stale_holder: Option<u32>) -> io::Result<Option<u32>> {
+    let stale_path = path.with_file_name(format!("lock.{}.stale", process::id()));
+    match fs::rename(path, &stale_path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    }
+
+    let holder = holder_of(&stale_path)?;
+    if holder != stale_holder {
+        // Linking does not replace a lock acquired by yet another process.
+        let _ = fs::hard_link(&stale_path, path);
+    }
+    fs::remove_file(&stale_path)?;
+
+    match holder {
+        Some(pid) if holder != stale_holder => Ok(Some(pid)),
+        _ => Ok(None),
+    }
+}
+
+pub fn acquire(lock_dir: &Path) -> io::Result<LockResult> {
+    fs::create_dir_all(lock_dir)?;
+    let path = lock_path(lock_dir);
+
+    // A stale lock (e.g. left behind by a command that was killed) is replaced once; a second
+    // failure means another process acquired the lock meanwhile.
+    for _ in 0..2 {
+        if create(&path)? {
+            *HELD_LOCK.lock().unwrap_or_else(|err| err.into_inner()) = Some(path.clone());
+            return Ok(LockResult::Acquired(ChainLock(())));
+        }
+
+        match holder_of(&path)? {
+            Some(pid) if process_is_alive(pid) => return Ok(LockResult::Held(pid)),
+            stale_holder => {
+                if let Some(pid) = take_over(&path, stale_holder)? {
+                    return Ok(LockResult::Held(pid));
+                }
+            }
+        }
+    }
+
+    Ok(LockResult::Held(holder_of(&path)?.unwrap_or(0)))
+}
+
+pub fn force_unlock(lock_dir: &Path) -> io::Result<()> {
+    match fs::remove_file(lock_path(lock_dir)) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
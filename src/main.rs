@@ -1,12 +1,18 @@
+use std::cell::Cell;
 use std::collections::HashSet;
 use std::ffi::OsString;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+use std::ops::ControlFlow;
 use std::process;
-use std::process::Command;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use std::{collections::HashMap, iter::FromIterator};
 
 use between::Between;
-use clap::{App, Arg, ArgMatches, SubCommand};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use colored::*;
 use git2::{
     BranchType, Config, ConfigLevel, Error, ErrorCode, ObjectType, Repository, RepositoryState,
@@ -14,6 +20,41 @@ use git2::{
 use rand::Rng;
 use regex::Regex;
 
+#[macro_use]
+mod debug;
+mod browse;
+mod counts;
+mod gh;
+mod guide;
+mod history;
+mod lint;
+mod lock;
+mod metadata;
+mod names;
+mod notify;
+mod original_branch;
+mod pager;
+mod partial;
+mod prompt;
+mod publish;
+mod stash;
+mod stats;
+mod summary;
+mod timings;
+mod tree;
+use debug::LoggedCommand;
+use gh::GhError;
+use lint::LintRules;
+use lock::{ChainLock, LockResult};
+use prompt::PromptMode;
+use publish::ChainDefinition;
+use stash::ChainStash;
+use summary::{
+    BranchSummary, ChainSummary, ConflictingFile, LandedSummary, PullRequestSummary, RebaseEntry,
+    RebaseOutcome, RebaseReport, TestEntry, TestOutcome, TestReport,
+};
+use tree::TreeNode;
+
 fn executable_name() -> String {
     let name = std::env::current_exe()
         .expect("Cannot get the path of current executable.")
@@ -29,6 +70,51 @@ fn executable_name() -> String {
     name
 }
 
+// When the running mutating command started, and the commits of the branches of all chains at that
+// time. It is recorded in the history when the command returns or exits early.
+type PendingHistory = (u64, HashMap<String, Option<String>>);
+static PENDING_HISTORY: Mutex<Option<PendingHistory>> = Mutex::new(None);
+
+// process::exit skips destructors, so commands stop with this instead, which records the history
+// and releases the lock.
+fn exit(code: i32) -> ! {
+    let pending_history = PENDING_HISTORY
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .take();
+    if let Some((started_at, branches_before)) = pending_history {
+        if let Ok(git_chain) = GitChain::init(PromptMode::NoInput) {
+            let _ = git_chain.record_history(started_at, branches_before);
+        }
+    }
+    lock::release();
+    process::exit(code);
+}
+
+fn env_var(name: &str) -> Option<String> {
+    match std::env::var(name) {
+        Ok(value) if !value.trim().is_empty() => Some(value.trim().to_string()),
+        _ => None,
+    }
+}
+
+// The name is the environment variable or git config key that the value was read from.
+fn parse_bool_setting(name: &str, value: &str) -> Result<bool, Error> {
+    Config::parse_bool(value).map_err(|_| {
+        Error::from_str(&format!(
+            "Invalid boolean value {} for {}",
+            value.bold(),
+            name.bold()
+        ))
+    })
+}
+
+fn env_bool_setting(name: &str) -> Result<Option<bool>, Error> {
+    env_var(name)
+        .map(|value| parse_bool_setting(name, &value))
+        .transpose()
+}
+
 fn chain_name_key(branch_name: &str) -> String {
     format!("branch.{}.chain-name", branch_name)
 }
@@ -41,6 +127,129 @@ fn root_branch_key(branch_name: &str) -> String {
     format!("branch.{}.root-branch", branch_name)
 }
 
+fn chain_scope_key(branch_name: &str) -> String {
+    format!("branch.{}.chain-scope", branch_name)
+}
+
+fn normalize_scope(scope: &str) -> Option<String> {
+    // Scopes are directories relative to the root of the repository, e.g. services/api
+    let scope = scope.trim().trim_start_matches("./").trim_matches('/');
+    if scope.is_empty() || scope == "." {
+        return None;
+    }
+    Some(scope.to_string())
+}
+
+fn path_in_scope(path: &str, scope: &str) -> bool {
+    path == scope || path.starts_with(&format!("{}/", scope))
+}
+
+fn chain_base_key(branch_name: &str) -> String {
+    format!("branch.{}.chain-base", branch_name)
+}
+
+fn chain_note_key(branch_name: &str) -> String {
+    format!("branch.{}.chain-note", branch_name)
+}
+
+// Options of the merge strategy (e.g. theirs), passed to git rebase and git cherry-pick as
+// --strategy-option when the branch is rebased or cherry-picked onto.
+fn chain_strategy_option_key(branch_name: &str) -> String {
+    format!("branch.{}.chain-strategy-option", branch_name)
+}
+
+// The ref that push sends the branch to on the remote, e.g. users/me/feature for a namespaced
+// remote, or refs/for/main for Gerrit.
+fn chain_push_ref_key(branch_name: &str) -> String {
+    format!("branch.{}.chain-push-ref", branch_name)
+}
+
+// Branch names are refs/heads/<name> on the remote; full refs (e.g. refs/for/main) are kept.
+fn qualify_push_ref(push_ref: &str) -> String {
+    if push_ref.starts_with("refs/") {
+        push_ref.to_string()
+    } else {
+        format!("refs/heads/{}", push_ref)
+    }
+}
+
+// The tip of the upstream branch on the remote, as of the last fetch or push of git chain.
+fn chain_remote_oid_key(branch_name: &str) -> String {
+    format!("branch.{}.chain-remote-oid", branch_name)
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the UNIX epoch.")
+        .as_secs()
+}
+
+fn utc_datetime(secs: u64) -> (i64, i64, i64, u64, u64, u64) {
+    let secs_of_day = secs % 86400;
+
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+    )
+}
+
+fn relative_time(timestamp: i64) -> String {
+    // Like the relative dates of git log --date=relative, e.g. 3 days ago.
+    let secs = (unix_time() as i64).saturating_sub(timestamp).max(0);
+    let (value, unit) = match secs {
+        secs if secs < 90 => (secs, "second"),
+        secs if secs < 90 * 60 => ((secs + 30) / 60, "minute"),
+        secs if secs < 36 * 3600 => ((secs + 1800) / 3600, "hour"),
+        secs if secs < 14 * 86400 => ((secs + 43200) / 86400, "day"),
+        secs if secs < 70 * 86400 => ((secs + 302400) / 604800, "week"),
+        secs if secs < 365 * 86400 => ((secs + 1296000) / 2592000, "month"),
+        secs => (secs / 31536000, "year"),
+    };
+    if value == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", value, unit)
+    }
+}
+
+fn backup_timestamp() -> String {
+    // Current UTC time, e.g. 20240131T235959, so that backups sort chronologically.
+    let (year, month, day, hours, minutes, seconds) = utc_datetime(unix_time());
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}",
+        year, month, day, hours, minutes, seconds
+    )
+}
+
+// Backups made within the same second get a counter, e.g. 20240131T235959-2; they are sorted by
+// timestamp, then by counter.
+fn backup_sort_key(timestamp: &str) -> (String, u32) {
+    match timestamp.split_once('-') {
+        Some((timestamp, counter)) => (timestamp.to_string(), counter.parse().unwrap_or(0)),
+        None => (timestamp.to_string(), 1),
+    }
+}
+
+fn backup_prefix(chain_name: &str) -> String {
+    format!("backup-{}/", chain_name)
+}
+
 fn generate_chain_order() -> String {
     let between = Between::init();
     let chars = between.chars();
@@ -81,6 +290,55 @@ fn generate_chain_order_between(before: &str, after: &str) -> Option<String> {
     between.between(before, after)
 }
 
+// Sort keys grow as branches are inserted between the same two branches over and over; past this
+// length, the keys of the chain are renumbered before inserting a branch.
+const MAX_CHAIN_ORDER_LENGTH: usize = 24;
+
+// Sort keys for count branches, in order, with the same length and spread evenly, so that there
+// is room to insert many branches between any two of them.
+fn spread_chain_orders(count: usize) -> Vec<String> {
+    let between = Between::init();
+    let chars = between.chars();
+    // Like generate_chain_order(), neither between.low() nor between.high() are used.
+    let digits = &chars[1..chars.len() - 1];
+    let base = digits.len() as u128;
+
+    // At least base^2 possible keys between two consecutive keys.
+    let mut width = 1;
+    let mut capacity = base;
+    while capacity < (count as u128 + 1) * base * base {
+        width += 1;
+        capacity *= base;
+    }
+
+    (1..=count as u128)
+        .map(|position| {
+            let mut value = position * capacity / (count as u128 + 1);
+            let mut key = vec![digits[0]; width];
+            for index in (0..width).rev() {
+                key[index] = digits[(value % base) as usize];
+                value /= base;
+            }
+            String::from_iter(key)
+        })
+        .collect()
+}
+
+// Built-in abbreviations of subcommands.
+const SUBCOMMAND_ALIASES: [(&str, &str); 3] = [("list", "ls"), ("status", "st"), ("rebase", "rb")];
+
+fn print_cherry_pick_error(executable_name: &str, branch: &str, upstream_branch: &str) {
+    eprintln!(
+        "🛑 Unable to completely cherry-pick the commits of {} onto {}",
+        upstream_branch.bold(),
+        branch.bold()
+    );
+    eprintln!(
+        "⚠️  Resolve any cherry-pick conflicts, run git cherry-pick --continue, and then run {} cherry",
+        executable_name
+    );
+}
+
 fn print_rebase_error(executable_name: &str, branch: &str, upstream_branch: &str) {
     eprintln!(
         "🛑 Unable to completely rebase {} to {}",
@@ -115,40 +373,158 @@ struct Branch {
 
 impl Branch {
     fn delete_all_configs(git_chain: &GitChain, branch_name: &str) -> Result<(), Error> {
-        git_chain.delete_git_config(&chain_name_key(branch_name))?;
-        git_chain.delete_git_config(&chain_order_key(branch_name))?;
-        git_chain.delete_git_config(&root_branch_key(branch_name))?;
+        for key in Branch::config_keys(branch_name) {
+            git_chain.delete_git_config(&key)?;
+        }
         Ok(())
     }
 
+    fn strategy_options(&self, git_chain: &GitChain) -> Result<Vec<String>, Error> {
+        // Several options are separated by spaces, e.g. "theirs ignore-all-space".
+        Ok(git_chain
+            .get_git_config(&chain_strategy_option_key(&self.branch_name))?
+            .map(|options| options.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default())
+    }
+
+    fn strategy_option_args(
+        &self,
+        git_chain: &GitChain,
+        strategy_option: Option<&str>,
+    ) -> Result<Vec<String>, Error> {
+        // The strategy option given on the command line overrides the one of the branch.
+        let options = match strategy_option {
+            Some(strategy_option) => vec![strategy_option.to_string()],
+            None => self.strategy_options(git_chain)?,
+        };
+        Ok(options
+            .iter()
+            .map(|option| format!("--strategy-option={}", option))
+            .collect())
+    }
+
+    fn note(&self, git_chain: &GitChain) -> Result<Option<String>, Error> {
+        git_chain.get_git_config(&chain_note_key(&self.branch_name))
+    }
+
+    // The git config keys that hold the chain metadata of a branch.
+    fn config_keys(branch_name: &str) -> Vec<String> {
+        vec![
+            chain_name_key(branch_name),
+            chain_order_key(branch_name),
+            root_branch_key(branch_name),
+            chain_base_key(branch_name),
+            chain_scope_key(branch_name),
+            chain_note_key(branch_name),
+            chain_strategy_option_key(branch_name),
+            chain_push_ref_key(branch_name),
+            chain_remote_oid_key(branch_name),
+        ]
+    }
+
     fn remove_from_chain(self, git_chain: &GitChain) -> Result<(), Error> {
-        Branch::delete_all_configs(git_chain, &self.branch_name)
+        for key in Branch::config_keys(&self.branch_name) {
+            git_chain.delete_git_config(&key)?;
+        }
+        Ok(())
+    }
+
+    // Whether the upstream branch has commits that someone else pushed since git chain last
+    // fetched or pushed it; i.e. its tip on the remote is neither the recorded tip, nor contained
+    // in the local branch. A stale remote-tracking branch would let --force-with-lease drop them.
+    fn has_unknown_remote_commits(&self, git_chain: &GitChain) -> Result<bool, Error> {
+        let recorded_oid =
+            match git_chain.get_git_config(&chain_remote_oid_key(&self.branch_name))? {
+                Some(recorded_oid) => recorded_oid,
+                None => return Ok(false),
+            };
+
+        let (remote_name, remote_ref, _tracking_ref) =
+            match git_chain.upstream_ref(&self.branch_name)? {
+                Some(upstream) => upstream,
+                None => return Ok(false),
+            };
+
+        let remote_oid = match git_chain.remote_ref_oid(&remote_name, &remote_ref)? {
+            Some(remote_oid) => remote_oid,
+            None => return Ok(false),
+        };
+
+        if remote_oid == recorded_oid {
+            return Ok(false);
+        }
+
+        let local_oid = git_chain.repo.revparse_single(&self.branch_name)?.id();
+        match git2::Oid::from_str(&remote_oid) {
+            Ok(remote_oid) if git_chain.repo.find_commit(remote_oid).is_ok() => Ok(remote_oid
+                != local_oid
+                && !git_chain.repo.graph_descendant_of(local_oid, remote_oid)?),
+            _ => Ok(true),
+        }
+    }
+
+    fn from_configs(git_chain: &GitChain, branch_name: &str) -> Result<Option<Branch>, Error> {
+        let chain_name = git_chain.get_git_config(&chain_name_key(branch_name))?;
+        let chain_order = git_chain.get_git_config(&chain_order_key(branch_name))?;
+        let root_branch = git_chain.get_git_config(&root_branch_key(branch_name))?;
+
+        match (chain_name, chain_order, root_branch) {
+            (Some(chain_name), Some(chain_order), Some(root_branch)) => Ok(Some(Branch {
+                branch_name: branch_name.to_string(),
+                chain_name,
+                chain_order,
+                root_branch,
+            })),
+            _ => Ok(None),
+        }
     }
 
     fn get_branch_with_chain(
         git_chain: &GitChain,
         branch_name: &str,
     ) -> Result<BranchSearchResult, Error> {
-        let chain_name = git_chain.get_git_config(&chain_name_key(branch_name))?;
-        let chain_order = git_chain.get_git_config(&chain_order_key(branch_name))?;
-        let root_branch = git_chain.get_git_config(&root_branch_key(branch_name))?;
+        let branch = match Branch::from_configs(git_chain, branch_name)? {
+            Some(branch) => Some(branch),
+            None => match git_chain.get_git_config(&chain_name_key(branch_name))? {
+                Some(_) => None,
+                None => {
+                    Branch::delete_all_configs(git_chain, branch_name)?;
+                    return Ok(BranchSearchResult::NotPartOfAnyChain(
+                        branch_name.to_string(),
+                    ));
+                }
+            },
+        };
 
-        if chain_name.is_none()
-            || chain_order.is_none()
-            || root_branch.is_none()
-            || !git_chain.git_local_branch_exists(branch_name)?
-        {
-            Branch::delete_all_configs(git_chain, branch_name)?;
+        // The configs of a deleted branch are kept, so that the chain can display it as missing.
+        if !git_chain.git_local_branch_exists(branch_name)? {
             return Ok(BranchSearchResult::NotPartOfAnyChain(
                 branch_name.to_string(),
             ));
         }
 
-        let branch = Branch {
-            branch_name: branch_name.to_string(),
-            chain_name: chain_name.unwrap(),
-            chain_order: chain_order.unwrap(),
-            root_branch: root_branch.unwrap(),
+        let branch = match branch {
+            Some(branch) => branch,
+            None => {
+                // Some of the metadata of the branch is missing; loading its chain reports it, and
+                // keeps the branch in the chain if it can be placed.
+                let chain_name = git_chain
+                    .get_git_config(&chain_name_key(branch_name))?
+                    .unwrap();
+                let chain = Chain::get_chain(git_chain, &chain_name)?;
+                match chain
+                    .branches
+                    .into_iter()
+                    .find(|branch| branch.branch_name == branch_name)
+                {
+                    Some(branch) => branch,
+                    None => {
+                        return Ok(BranchSearchResult::NotPartOfAnyChain(
+                            branch_name.to_string(),
+                        ))
+                    }
+                }
+            }
         };
 
         Ok(BranchSearchResult::Branch(branch))
@@ -159,52 +535,39 @@ impl Branch {
         chain_name: &str,
         sort_option: &SortBranch,
     ) -> Result<String, Error> {
-        let chain_order = if Chain::chain_exists(git_chain, chain_name)? {
-            // invariant: a chain exists if and only if it has at least one branch.
-            let chain = Chain::get_chain(git_chain, chain_name)?;
-            assert!(!chain.branches.is_empty());
-
-            let maybe_chain_order = match sort_option {
-                SortBranch::First => {
-                    let first_branch = chain.branches.first().unwrap();
-                    generate_chain_order_before(&first_branch.chain_order)
-                }
-                SortBranch::Last => {
-                    let last_branch = chain.branches.last().unwrap();
-                    generate_chain_order_after(&last_branch.chain_order)
-                }
-                SortBranch::Before(after_branch) => match chain.before(after_branch) {
-                    None => generate_chain_order_before(&after_branch.chain_order),
-                    Some(before_branch) => generate_chain_order_between(
-                        &before_branch.chain_order,
-                        &after_branch.chain_order,
-                    ),
-                },
-                SortBranch::After(before_branch) => match chain.after(before_branch) {
-                    None => generate_chain_order_after(&before_branch.chain_order),
-                    Some(after_branch) => generate_chain_order_between(
-                        &before_branch.chain_order,
-                        &after_branch.chain_order,
-                    ),
-                },
-            };
+        if !Chain::chain_exists(git_chain, chain_name)? {
+            return Ok(generate_chain_order());
+        }
 
-            match maybe_chain_order {
-                Some(chain_order) => chain_order,
-                None => {
-                    let mut chain_order = generate_chain_order();
-                    // last resort
-                    while chain.has_chain_order(&chain_order) {
-                        chain_order = generate_chain_order();
-                    }
-                    chain_order
-                }
+        // invariant: a chain exists if and only if it has at least one branch.
+        let chain = Chain::get_chain(git_chain, chain_name)?;
+        assert!(!chain.branches.is_empty());
+
+        if let Some(chain_order) = chain.chain_order_for(sort_option) {
+            if chain_order.len() <= MAX_CHAIN_ORDER_LENGTH && !chain.has_chain_order(&chain_order) {
+                return Ok(chain_order);
             }
-        } else {
-            generate_chain_order()
+        }
+
+        // There is no room left between the keys of the neighbouring branches: renumber the
+        // chain, and look up the neighbouring branches again with their new keys.
+        debug_log!("renumbering the sort keys of chain {}", chain_name);
+        chain.renumber(git_chain)?;
+        let chain = Chain::get_chain(git_chain, chain_name)?;
+        let sort_option = match sort_option {
+            SortBranch::First => SortBranch::First,
+            SortBranch::Last => SortBranch::Last,
+            SortBranch::Before(branch) => SortBranch::Before(chain.find_branch(branch)),
+            SortBranch::After(branch) => SortBranch::After(chain.find_branch(branch)),
         };
 
-        Ok(chain_order)
+        match chain.chain_order_for(&sort_option) {
+            Some(chain_order) => Ok(chain_order),
+            None => Err(Error::from_str(&format!(
+                "Unable to generate a sort key for chain {}",
+                chain_name
+            ))),
+        }
     }
 
     fn setup_branch(
@@ -214,22 +577,67 @@ impl Branch {
         branch_name: &str,
         sort_option: &SortBranch,
     ) -> Result<(), Error> {
+        // The branch inherits the scope of the chain it is added to.
+        let scope = if Chain::chain_exists(git_chain, chain_name)? {
+            Chain::get_chain(git_chain, chain_name)?.scope(git_chain)?
+        } else {
+            None
+        };
+
         Branch::delete_all_configs(git_chain, branch_name)?;
 
         let chain_order = Branch::generate_chain_order(git_chain, chain_name, sort_option)?;
         git_chain.set_git_config(&chain_order_key(branch_name), &chain_order)?;
         git_chain.set_git_config(&root_branch_key(branch_name), root_branch)?;
         git_chain.set_git_config(&chain_name_key(branch_name), chain_name)?;
+        if let Some(scope) = scope {
+            git_chain.set_git_config(&chain_scope_key(branch_name), &scope)?;
+        }
 
         Ok(())
     }
 
-    fn display_status(&self, git_chain: &GitChain) -> Result<(), Error> {
+    fn display_status(&self, git_chain: &GitChain, check_conflicts: bool) -> Result<(), Error> {
         let chain = Chain::get_chain(git_chain, &self.chain_name)?;
 
-        let current_branch = git_chain.get_current_branch_name()?;
+        let current_branch = git_chain
+            .get_current_branch_name_if_attached()?
+            .unwrap_or_default();
+
+        chain.display_list_with(
+            git_chain,
+            &current_branch,
+            &ListOptions {
+                check_conflicts,
+                ..Default::default()
+            },
+        )?;
+
+        let pairs = chain.branch_pairs();
+        let unrelated_branches = git_chain.unrelated_branches(&pairs, &(0..pairs.len()))?;
+        if !unrelated_branches.is_empty() {
+            println!();
+            for pair in &unrelated_branches {
+                println!(
+                    "⚠️  Branch {} shares no history with its parent branch {}.",
+                    pair.branch.branch_name.bold(),
+                    pair.parent.bold()
+                );
+            }
+            println!(
+                "To rebase the other branches of the chain, leaving these as they are, run: {} rebase --allow-unrelated",
+                git_chain.executable_name
+            );
+        }
+
+        git_chain.warn_root_behind_upstream(&chain)?;
 
-        chain.display_list(git_chain, &current_branch)?;
+        let limits = git_chain.get_size_limits(None, None)?;
+        let large_branches = git_chain.large_branches(&chain, &limits)?;
+        if !large_branches.is_empty() {
+            println!();
+            git_chain.warn_large_branches(&large_branches);
+        }
 
         Ok(())
     }
@@ -239,6 +647,26 @@ impl Branch {
         Ok(())
     }
 
+    fn record_rebase_base(&self, git_chain: &GitChain, parent_branch: &str) -> Result<(), Error> {
+        // Record the commit of the parent branch that this branch was last rebased onto.
+        let parent_oid = git_chain.get_commit_hash_of_branch(parent_branch)?;
+        git_chain.set_git_config(&chain_base_key(&self.branch_name), &parent_oid)?;
+        Ok(())
+    }
+
+    fn needs_rebase(&self, git_chain: &GitChain, parent_branch: &str) -> Result<bool, Error> {
+        // A branch needs to be rebased if its parent branch moved since the last rebase.
+        // This is detected even if the ahead/behind counts look clean
+        // (e.g. the parent branch was reset after being squashed and merged).
+        match git_chain.get_git_config(&chain_base_key(&self.branch_name))? {
+            None => Ok(false),
+            Some(base_oid) => {
+                let parent_oid = git_chain.get_commit_hash_of_branch(parent_branch)?;
+                Ok(base_oid != parent_oid)
+            }
+        }
+    }
+
     fn move_branch(
         &self,
         git_chain: &GitChain,
@@ -255,18 +683,72 @@ impl Branch {
         Ok(())
     }
 
-    fn backup(&self, git_chain: &GitChain) -> Result<(), Error> {
+    fn backup(&self, git_chain: &GitChain, timestamp: &str) -> Result<(), Error> {
         let (object, _reference) = git_chain.repo.revparse_ext(&self.branch_name)?;
         let commit = git_chain.repo.find_commit(object.id())?;
 
-        let backup_branch = format!("backup-{}/{}", self.chain_name, self.branch_name);
+        let backup_branch = format!(
+            "{}{}/{}",
+            backup_prefix(&self.chain_name),
+            timestamp,
+            self.branch_name
+        );
 
         git_chain.repo.branch(&backup_branch, &commit, true)?;
 
         Ok(())
     }
 
-    fn push(&self, git_chain: &GitChain, force_push: bool) -> Result<bool, Error> {
+    fn force_push_protection(
+        &self,
+        git_chain: &GitChain,
+        upstream: &str,
+    ) -> Result<Option<String>, Error> {
+        // Commits of the upstream without an equivalent commit (by patch-id) in the local branch
+        // were not rewritten by a rebase, e.g. they were pushed by a reviewer.
+        let output = Command::new("git")
+            .arg("cherry")
+            .arg(&self.branch_name)
+            .arg(upstream)
+            .logged_output()
+            .unwrap_or_else(|_| {
+                panic!(
+                    "Unable to run: git cherry {} {}",
+                    self.branch_name, upstream
+                )
+            });
+
+        if output.status.success() {
+            let num_of_missing_commits = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| line.starts_with('+'))
+                .count();
+
+            if num_of_missing_commits > 0 {
+                return Ok(Some(format!(
+                    "{} has {} commits that are not in the local branch",
+                    upstream.bold(),
+                    num_of_missing_commits
+                )));
+            }
+        }
+
+        match git_chain.find_pull_request(&self.branch_name) {
+            Ok(Some(pull_request))
+                if pull_request.state == "OPEN" && pull_request.review_decision == "APPROVED" =>
+            {
+                Ok(Some(format!(
+                    "PR #{} is approved: {}",
+                    pull_request.number, pull_request.url
+                )))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn push(&self, git_chain: &GitChain, options: &PushOptions) -> Result<bool, Error> {
+        let force_push = options.force_push;
+
         // get branch's upstream
 
         let branch = match git_chain
@@ -284,20 +766,59 @@ impl Branch {
         };
 
         match branch.upstream() {
-            Ok(_remote_branch) => {
-                let remote = git_chain
-                    .repo
-                    .branch_upstream_remote(branch.get().name().unwrap())?;
-                let remote = remote.as_str().unwrap();
+            Ok(remote_branch) => {
+                let (remote, remote_ref) = git_chain.push_destination(&self.branch_name)?;
+
+                if options.only_changed {
+                    let local_oid = branch.get().peel_to_commit()?.id();
+                    // The branch may be pushed elsewhere than its upstream branch.
+                    let remote_oid = match git_chain.tracking_ref(&remote, &remote_ref) {
+                        Ok(Some(tracking_ref)) => git_chain.repo.refname_to_id(&tracking_ref).ok(),
+                        Ok(None) | Err(_) => Some(remote_branch.get().peel_to_commit()?.id()),
+                    };
+
+                    if Some(local_oid) == remote_oid {
+                        println!("✅ Already up-to-date {}", self.branch_name.bold());
+                        return Ok(false);
+                    }
+                }
+
+                if force_push && self.has_unknown_remote_commits(git_chain)? {
+                    let upstream = utf8_branch_name(&remote_branch)?;
+                    println!(
+                        "🛑 Not force pushing {}: {} has commits that were pushed since you last fetched or pushed it",
+                        self.branch_name.bold(),
+                        upstream.bold()
+                    );
+                    println!(
+                        "To keep them, run: git fetch, and rebase {} onto {}",
+                        self.branch_name, upstream
+                    );
+                    return Ok(false);
+                }
+
+                if force_push && options.protect_reviewed {
+                    let upstream = utf8_branch_name(&remote_branch)?;
+                    if let Some(reason) = self.force_push_protection(git_chain, &upstream)? {
+                        println!(
+                            "🛑 Not force pushing {}: {}",
+                            self.branch_name.bold(),
+                            reason
+                        );
+                        return Ok(false);
+                    }
+                }
+
+                let refspec = format!("{}:{}", self.branch_name, remote_ref);
 
                 let output = if force_push {
-                    // git push --force-with-lease <remote> <branch>
+                    // git push --force-with-lease <remote> <branch>:<remote_ref>
                     Command::new("git")
                         .arg("push")
                         .arg("--force-with-lease")
-                        .arg(remote)
-                        .arg(&self.branch_name)
-                        .output()
+                        .arg(&remote)
+                        .arg(&refspec)
+                        .logged_output()
                         .unwrap_or_else(|_| {
                             panic!(
                                 "Unable to push branch to their upstream: {}",
@@ -305,12 +826,12 @@ impl Branch {
                             )
                         })
                 } else {
-                    // git push <remote> <branch>
+                    // git push <remote> <branch>:<remote_ref>
                     Command::new("git")
                         .arg("push")
-                        .arg(remote)
-                        .arg(&self.branch_name)
-                        .output()
+                        .arg(&remote)
+                        .arg(&refspec)
+                        .logged_output()
                         .unwrap_or_else(|_| {
                             panic!(
                                 "Unable to push branch to their upstream: {}",
@@ -320,6 +841,12 @@ impl Branch {
                 };
 
                 if output.status.success() {
+                    let local_oid = branch.get().peel_to_commit()?.id();
+                    git_chain.set_git_config(
+                        &chain_remote_oid_key(&self.branch_name),
+                        &local_oid.to_string(),
+                    )?;
+
                     if force_push {
                         println!("✅ Force pushed {}", self.branch_name.bold());
                     } else {
@@ -335,6 +862,9 @@ impl Branch {
                 }
             }
             Err(e) => {
+                if e.code() == ErrorCode::NotFound && options.create_pr {
+                    return Ok(false);
+                }
                 if e.code() == ErrorCode::NotFound {
                     println!(
                         "🛑 Cannot push. Branch has no upstream: {}",
@@ -354,8 +884,14 @@ struct Chain {
     name: String,
     root_branch: String,
     branches: Vec<Branch>,
+    // Branches of the chain that no longer exist locally (e.g. deleted with git update-ref -d).
+    missing_branches: Vec<Branch>,
 }
 
+// The branches of a chain, its missing branches, and the problems found in their metadata.
+type ChainBranches = (Vec<Branch>, Vec<Branch>, Vec<metadata::Problem>);
+type AheadBehindCounts = HashMap<String, Option<(usize, usize)>>;
+
 impl Chain {
     fn get_all_branch_configs(git_chain: &GitChain) -> Result<Vec<(String, String)>, Error> {
         let key_regex = Regex::new(r"^branch\.(?P<branch_name>.+)\.chain-name$".trim()).unwrap();
@@ -381,73 +917,223 @@ impl Chain {
         Ok(list)
     }
 
-    fn get_branches_for_chain(
+    fn get_all_branches_for_chain(
         git_chain: &GitChain,
         chain_name: &str,
-    ) -> Result<Vec<Branch>, Error> {
-        let key_regex = Regex::new(r"^branch\.(?P<branch_name>.+)\.chain-name$".trim()).unwrap();
-        let mut branches: Vec<Branch> = vec![];
+    ) -> Result<ChainBranches, Error> {
+        let key_regex = Regex::new(
+            r"^branch\.(?P<branch_name>.+)\.(?P<key>chain-name|chain-order|root-branch)$",
+        )
+        .unwrap();
 
-        let entries = Chain::get_all_branch_configs(git_chain)?;
-        for (key, value) in entries {
-            if value != chain_name {
-                continue;
+        // A key set several times (e.g. with git config --add) has several entries.
+        let mut values: HashMap<String, Vec<String>> = HashMap::new();
+        let mut branch_names: Vec<String> = vec![];
+        for (key, value) in git_chain.get_git_configs_matching_key(&key_regex)? {
+            let captures = key_regex.captures(&key).unwrap();
+            if &captures["key"] == "chain-name" {
+                branch_names.push(captures["branch_name"].to_string());
+            }
+            values.entry(key).or_default().push(value);
+        }
+        branch_names.sort();
+        branch_names.dedup();
+
+        let mut problems = vec![];
+        let mut value_of = |key: String, branch_name: &str| -> Option<String> {
+            let values = values.get(&key)?;
+            if values.len() > 1 {
+                problems.push(metadata::Problem::MultipleValues {
+                    branch_name: branch_name.to_string(),
+                    key: key.clone(),
+                    values: values.clone(),
+                });
             }
+            values.last().cloned()
+        };
 
-            let captures = key_regex.captures(&key).unwrap();
-            let branch_name = &captures["branch_name"];
+        // (branch name, sort key, root branch) of each branch of the chain.
+        let mut configs: Vec<(String, Option<String>, Option<String>)> = vec![];
+        for branch_name in branch_names {
+            if value_of(chain_name_key(&branch_name), &branch_name).as_deref() != Some(chain_name) {
+                continue;
+            }
+            let chain_order = value_of(chain_order_key(&branch_name), &branch_name);
+            let root_branch = value_of(root_branch_key(&branch_name), &branch_name);
+            configs.push((branch_name, chain_order, root_branch));
+        }
 
-            let results = Branch::get_branch_with_chain(git_chain, branch_name)?;
+        let mut branches: Vec<Branch> = vec![];
+        let mut missing_branches: Vec<Branch> = vec![];
+        for (branch_name, chain_order, root_branch) in &configs {
+            if root_branch.is_none() {
+                problems.push(metadata::Problem::MissingKey {
+                    branch_name: branch_name.clone(),
+                    key: root_branch_key(branch_name),
+                });
+            }
 
-            match results {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    // TODO: could this fail silently?
-                    eprintln!(
-                        "Branch not correctly set up as part of a chain: {}",
-                        branch_name.bold()
-                    );
-                    process::exit(1);
-                }
-                BranchSearchResult::Branch(branch) => {
-                    branches.push(branch);
+            let chain_order = match chain_order {
+                Some(chain_order) => chain_order,
+                None => {
+                    problems.push(metadata::Problem::MissingKey {
+                        branch_name: branch_name.clone(),
+                        key: chain_order_key(branch_name),
+                    });
+                    continue;
                 }
             };
+            if !metadata::is_valid_chain_order(chain_order) {
+                problems.push(metadata::Problem::InvalidChainOrder {
+                    branch_name: branch_name.clone(),
+                    key: chain_order_key(branch_name),
+                    chain_order: chain_order.clone(),
+                });
+            }
+
+            let branch = Branch {
+                branch_name: branch_name.clone(),
+                chain_name: chain_name.to_string(),
+                chain_order: chain_order.clone(),
+                root_branch: root_branch.clone().unwrap_or_default(),
+            };
+            if git_chain.git_local_branch_exists(branch_name)? {
+                branches.push(branch);
+            } else {
+                missing_branches.push(branch);
+            }
+        }
+
+        // Branches sharing a sort key are ordered by name.
+        let sort_key = |branch: &Branch| (branch.chain_order.clone(), branch.branch_name.clone());
+        branches.sort_by_key(sort_key);
+        missing_branches.sort_by_key(sort_key);
+
+        let mut chain_orders: Vec<(&str, &str)> = branches
+            .iter()
+            .chain(missing_branches.iter())
+            .map(|branch| (branch.chain_order.as_str(), branch.branch_name.as_str()))
+            .collect();
+        chain_orders.sort();
+        for group in chain_orders.chunk_by(|a, b| a.0 == b.0) {
+            if group.len() > 1 {
+                problems.push(metadata::Problem::DuplicateChainOrder {
+                    branch_names: group.iter().map(|(_, name)| name.to_string()).collect(),
+                    chain_order: group[0].0.to_string(),
+                });
+            }
+        }
+
+        // Use the first branch with a root branch as the source of the root branch. Without one,
+        // the chain cannot be loaded.
+        let root_branch = branches
+            .iter()
+            .chain(missing_branches.iter())
+            .map(|branch| branch.root_branch.clone())
+            .find(|root_branch| !root_branch.is_empty());
+        let root_branch = match root_branch {
+            Some(root_branch) => root_branch,
+            None => return Ok((vec![], vec![], problems)),
+        };
+        for branch in branches.iter_mut().chain(missing_branches.iter_mut()) {
+            if branch.root_branch.is_empty() {
+                branch.root_branch = root_branch.clone();
+            } else if branch.root_branch != root_branch {
+                problems.push(metadata::Problem::RootMismatch {
+                    branch_name: branch.branch_name.clone(),
+                    key: root_branch_key(&branch.branch_name),
+                    root_branch: branch.root_branch.clone(),
+                    expected: root_branch.clone(),
+                });
+            }
         }
 
-        Ok(branches)
+        Ok((branches, missing_branches, problems))
     }
 
     fn chain_exists(git_chain: &GitChain, chain_name: &str) -> Result<bool, Error> {
-        let branches = Chain::get_branches_for_chain(git_chain, chain_name)?;
-        Ok(!branches.is_empty())
+        let (branches, missing_branches, _problems) =
+            Chain::get_all_branches_for_chain(git_chain, chain_name)?;
+        Ok(!branches.is_empty() || !missing_branches.is_empty())
     }
 
     fn get_chain(git_chain: &GitChain, chain_name: &str) -> Result<Self, Error> {
-        let mut branches = Chain::get_branches_for_chain(git_chain, chain_name)?;
+        let (branches, missing_branches, problems) =
+            Chain::get_all_branches_for_chain(git_chain, chain_name)?;
+
+        if !problems.is_empty() {
+            git_chain.report_metadata_problems(chain_name, &problems);
+        }
 
-        if branches.is_empty() {
+        if branches.is_empty() && missing_branches.is_empty() {
             return Err(Error::from_str(&format!(
                 "Unable to get branches attached to chain: {}",
                 chain_name
             )));
         }
 
-        // TODO: ensure all branches have the same root
-
-        branches.sort_by_key(|b| b.chain_order.clone());
-
-        // use first branch as the source of the root branch
-        let root_branch = branches[0].root_branch.clone();
+        let root_branch = branches
+            .first()
+            .or_else(|| missing_branches.first())
+            .unwrap()
+            .root_branch
+            .clone();
 
         let chain = Chain {
             name: chain_name.to_string(),
             root_branch,
             branches,
+            missing_branches,
         };
 
         Ok(chain)
     }
 
+    // A sort key for a branch inserted at the given position, if there is room for one.
+    fn chain_order_for(&self, sort_option: &SortBranch) -> Option<String> {
+        match sort_option {
+            SortBranch::First => generate_chain_order_before(&self.branches.first()?.chain_order),
+            SortBranch::Last => generate_chain_order_after(&self.branches.last()?.chain_order),
+            SortBranch::Before(after_branch) => match self.before(after_branch) {
+                None => generate_chain_order_before(&after_branch.chain_order),
+                Some(before_branch) => generate_chain_order_between(
+                    &before_branch.chain_order,
+                    &after_branch.chain_order,
+                ),
+            },
+            SortBranch::After(before_branch) => match self.after(before_branch) {
+                None => generate_chain_order_after(&before_branch.chain_order),
+                Some(after_branch) => generate_chain_order_between(
+                    &before_branch.chain_order,
+                    &after_branch.chain_order,
+                ),
+            },
+        }
+    }
+
+    // The branch of the chain with the same name, e.g. after the chain was renumbered.
+    fn find_branch(&self, branch: &Branch) -> Branch {
+        self.branches
+            .iter()
+            .find(|other_branch| other_branch.branch_name == branch.branch_name)
+            .cloned()
+            .unwrap_or_else(|| branch.clone())
+    }
+
+    // Replace the sort keys of the branches with keys spread evenly over the key space, keeping
+    // their order. Whether any key changed.
+    fn renumber(&self, git_chain: &GitChain) -> Result<bool, Error> {
+        let chain_orders = spread_chain_orders(self.branches.len());
+        let mut changed = false;
+        for (branch, chain_order) in self.branches.iter().zip(chain_orders.iter()) {
+            if &branch.chain_order != chain_order {
+                git_chain.set_git_config(&chain_order_key(&branch.branch_name), chain_order)?;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
     fn has_chain_order(&self, chain_order: &str) -> bool {
         for branch in &self.branches {
             if branch.chain_order == chain_order {
@@ -457,20 +1143,48 @@ impl Chain {
         false
     }
 
-    fn display_ahead_behind(
-        &self,
-        git_chain: &GitChain,
-        upstream: &str,
-        branch: &str,
-    ) -> Result<String, Error> {
-        let (upstream_obj, _reference) = git_chain.repo.revparse_ext(upstream)?;
-        let (branch_obj, _reference) = git_chain.repo.revparse_ext(branch)?;
+    // The ahead/behind counts of the existing branches of the chain relative to their parent
+    // branch, keyed by branch name; None for the branches that share no history with their parent.
+    fn ahead_behind_counts(&self, git_chain: &GitChain) -> Result<AheadBehindCounts, Error> {
+        let parent_branches: Vec<String> = self
+            .branches
+            .iter()
+            .map(|branch| self.parent_branch_name(branch))
+            .collect();
+        let pairs: Vec<(&str, &str)> = parent_branches
+            .iter()
+            .zip(self.branches.iter())
+            .map(|(parent_branch, branch)| (parent_branch.as_str(), branch.branch_name.as_str()))
+            .collect();
+
+        let counts = git_chain.ahead_behind_many(&pairs)?;
+        Ok(self
+            .branches
+            .iter()
+            .map(|branch| branch.branch_name.clone())
+            .zip(counts)
+            .collect())
+    }
 
-        let ahead_behind = git_chain
-            .repo
-            .graph_ahead_behind(branch_obj.id(), upstream_obj.id())?;
+    // The number of paths that would conflict when rebasing each branch onto its parent branch.
+    fn predicted_conflicts(&self, git_chain: &GitChain) -> Result<HashMap<String, usize>, Error> {
+        let mut conflicts = HashMap::new();
+        for branch in &self.branches {
+            let parent_branch = self.parent_branch_name(branch);
+            let files = git_chain.predict_conflicts(&parent_branch, &branch.branch_name)?;
+            conflicts.insert(branch.branch_name.clone(), files.len());
+        }
+        Ok(conflicts)
+    }
+
+    fn display_ahead_behind(ahead_behind: Option<(usize, usize)>) -> String {
+        // The counts would include the entire history of both branches.
+        let ahead_behind = match ahead_behind {
+            Some(ahead_behind) => ahead_behind,
+            None => return "unrelated history".red().to_string(),
+        };
 
-        let status = match ahead_behind {
+        match ahead_behind {
             (0, 0) => "".to_string(),
             (ahead, 0) => {
                 format!("{} ahead", ahead)
@@ -481,51 +1195,428 @@ impl Chain {
             (ahead, behind) => {
                 format!("{} ahead ⦁ {} behind", ahead, behind)
             }
-        };
-
-        Ok(status)
+        }
     }
 
-    fn display_list(&self, git_chain: &GitChain, current_branch: &str) -> Result<(), Error> {
-        println!("{}", self.name);
+    fn scope(&self, git_chain: &GitChain) -> Result<Option<String>, Error> {
+        // The scope is stored on every branch of the chain.
+        for branch in &self.branches {
+            if let Some(scope) = git_chain.get_git_config(&chain_scope_key(&branch.branch_name))? {
+                return Ok(Some(scope));
+            }
+        }
+        Ok(None)
+    }
+
+    fn set_scope(&self, git_chain: &GitChain, scope: Option<&str>) -> Result<(), Error> {
+        for branch in &self.branches {
+            match scope {
+                Some(scope) => {
+                    git_chain.set_git_config(&chain_scope_key(&branch.branch_name), scope)?
+                }
+                None => git_chain.delete_git_config(&chain_scope_key(&branch.branch_name))?,
+            }
+        }
+        Ok(())
+    }
 
-        let mut branches = self.branches.clone();
-        branches.reverse();
+    fn display_list(&self, git_chain: &GitChain, current_branch: &str) -> Result<(), Error> {
+        self.display_list_with(git_chain, current_branch, &ListOptions::default())
+    }
+
+    fn display_list_with(
+        &self,
+        git_chain: &GitChain,
+        current_branch: &str,
+        options: &ListOptions,
+    ) -> Result<(), Error> {
+        let counts = self.ahead_behind_counts(git_chain)?;
+        let conflicts = if options.check_conflicts {
+            self.predicted_conflicts(git_chain)?
+        } else {
+            HashMap::new()
+        };
+        let mut header = match self.scope(git_chain)? {
+            Some(scope) => format!("{} (scope: {})", self.name, scope),
+            None => self.name.clone(),
+        };
+        if let Some(landed) = self.landed(git_chain, &counts)?.to_text() {
+            header.push_str(&format!(" ⦁ {}", landed.green()));
+        }
+        let mut last_commits = HashMap::new();
+        if options.verbose {
+            for branch in &self.branches {
+                last_commits.insert(
+                    branch.branch_name.clone(),
+                    git_chain.last_commit(&branch.branch_name)?,
+                );
+            }
+            if let Some(time) = last_commits.values().map(|(time, _author)| *time).max() {
+                header.push_str(&format!(" ⦁ last commit {}", relative_time(time)));
+            }
+        }
+        println!("{}", header);
+
+        for (branch, missing) in self.display_order() {
+            if missing {
+                println!("{:>6}{} {}", "", branch.branch_name, "(missing)".red());
+                continue;
+            }
 
-        for (index, branch) in branches.iter().enumerate() {
             let (marker, branch_name) = if branch.branch_name == current_branch {
                 ("➜ ", branch.branch_name.bold().to_string())
             } else {
                 ("", branch.branch_name.clone())
             };
 
-            let upstream = if index == branches.len() - 1 {
-                &self.root_branch
-            } else {
-                &branches[index + 1].branch_name
-            };
+            let upstream = &self.parent_branch_name(&branch);
 
-            let ahead_behind_status =
-                self.display_ahead_behind(git_chain, upstream, &branch.branch_name)?;
+            let ahead_behind_status = Chain::display_ahead_behind(counts[&branch.branch_name]);
 
-            let status_line = if ahead_behind_status.is_empty() {
+            let mut status_line = if ahead_behind_status.is_empty() {
                 format!("{:>6}{}", marker, branch_name)
             } else {
                 format!("{:>6}{} ⦁ {}", marker, branch_name, ahead_behind_status)
             };
 
+            if let Some((time, author)) = last_commits.get(&branch.branch_name) {
+                let activity = format!("{} by {}", relative_time(*time), author);
+                status_line.push_str(&format!(" ⦁ {}", activity.dimmed()));
+            }
+
+            if branch.needs_rebase(git_chain, upstream)? {
+                status_line.push_str(&format!(" ⦁ {}", "needs rebase".yellow()));
+            }
+
+            if let Some(&num_of_files) = conflicts.get(&branch.branch_name) {
+                if num_of_files > 0 {
+                    let warning = format!("⚠ will conflict in {} files", num_of_files);
+                    status_line.push_str(&format!(" ⦁ {}", warning.red()));
+                }
+            }
+
+            if let Some(note) = branch.note(git_chain)? {
+                status_line.push_str(&format!(" ⦁ 📝 {}", note.italic()));
+            }
+
+            if options.pr_urls {
+                let index = self
+                    .branches
+                    .iter()
+                    .position(|other_branch| other_branch.branch_name == branch.branch_name)
+                    .unwrap();
+                if let Some(url) = git_chain.pull_request_url(self, index)? {
+                    status_line.push_str(&format!(" ⦁ {}", url.cyan()));
+                }
+            }
+
             println!("{}", status_line.trim_end());
         }
 
+        let root_label = match git_chain.fixed_root_kind(&self.root_branch)? {
+            None => "root branch",
+            Some(kind) => kind,
+        };
         if self.root_branch == current_branch {
-            println!("{:>6}{} (root branch)", "➜ ", self.root_branch.bold());
+            println!("{:>6}{} ({})", "➜ ", self.root_branch.bold(), root_label);
         } else {
-            println!("{:>6}{} (root branch)", "", self.root_branch);
+            println!("{:>6}{} ({})", "", self.root_branch, root_label);
         };
 
+        self.display_missing_hint();
+
+        Ok(())
+    }
+
+    fn display_short(
+        &self,
+        git_chain: &GitChain,
+        current_branch: &str,
+        check_conflicts: bool,
+    ) -> Result<(), Error> {
+        let counts = self.ahead_behind_counts(git_chain)?;
+        let conflicts = if check_conflicts {
+            self.predicted_conflicts(git_chain)?
+        } else {
+            HashMap::new()
+        };
+        for (branch, missing) in self.display_order() {
+            if missing {
+                println!("  {} {}", branch.branch_name, "(missing)".red());
+                continue;
+            }
+
+            let marker = if branch.branch_name == current_branch {
+                "➜"
+            } else {
+                " "
+            };
+
+            let upstream = &self.parent_branch_name(&branch);
+
+            let mut status_line = format!("{} {}", marker, branch.branch_name);
+
+            if let Some((ahead, behind)) = counts[&branch.branch_name] {
+                if ahead > 0 {
+                    status_line.push_str(&format!(" +{}", ahead));
+                }
+                if behind > 0 {
+                    status_line.push_str(&format!(" -{}", behind));
+                }
+            } else {
+                status_line.push_str(&format!(" {}", "unrelated".red()));
+            }
+
+            if let Some((ahead, behind)) = git_chain.upstream_ahead_behind(&branch.branch_name)? {
+                if ahead > 0 {
+                    status_line.push_str(&format!(" ↑{}", ahead));
+                }
+                if behind > 0 {
+                    status_line.push_str(&format!(" ↓{}", behind));
+                }
+            }
+
+            // Without gh, or offline, the pull request number is left out.
+            if gh::offline_reason().is_none() {
+                if let Ok(Some(pull_request)) = git_chain.find_pull_request(&branch.branch_name) {
+                    status_line.push_str(&format!(" #{}", pull_request.number));
+                }
+            }
+
+            if branch.needs_rebase(git_chain, upstream)? {
+                status_line.push_str(" ⟳");
+            }
+
+            if let Some(&num_of_files) = conflicts.get(&branch.branch_name) {
+                if num_of_files > 0 {
+                    status_line.push_str(&format!(" ⚠{}", num_of_files));
+                }
+            }
+
+            if let Some(note) = branch.note(git_chain)? {
+                status_line.push_str(&format!(" 📝 {}", note));
+            }
+
+            println!("{}", status_line);
+        }
+
+        self.display_missing_hint();
+
         Ok(())
     }
 
+    fn stats(
+        &self,
+        git_chain: &GitChain,
+        history: &[history::Entry],
+    ) -> Result<stats::ChainStats, Error> {
+        let counts = self.ahead_behind_counts(git_chain)?;
+        let mut branches = vec![];
+        for branch in &self.branches {
+            let parent_branch = self.parent_branch_name(branch);
+            let (last_commit_at, _author) = git_chain.last_commit(&branch.branch_name)?;
+            // Without gh, pull requests are left out.
+            let pull_request = match git_chain.find_pull_request(&branch.branch_name) {
+                Ok(Some(pull_request)) => Some(stats::PullRequestStats {
+                    number: pull_request.number,
+                    url: pull_request.url,
+                    state: pull_request.state,
+                    review_decision: pull_request.review_decision,
+                }),
+                _ => None,
+            };
+
+            branches.push(stats::BranchStats {
+                name: branch.branch_name.clone(),
+                first_commit_at: git_chain.first_commit_at(&parent_branch, &branch.branch_name)?,
+                parent: parent_branch,
+                ahead_behind: counts[&branch.branch_name],
+                last_commit_at,
+                pull_request,
+            });
+        }
+
+        // The newest rebase that moved a branch of the chain.
+        let last_rebased_at = history
+            .iter()
+            .rev()
+            .find(|entry| {
+                entry
+                    .command
+                    .split_whitespace()
+                    .find(|arg| !arg.starts_with('-'))
+                    == Some("rebase")
+                    && entry.changes.iter().any(|(branch_name, _, _)| {
+                        self.branches
+                            .iter()
+                            .chain(&self.missing_branches)
+                            .any(|branch| &branch.branch_name == branch_name)
+                    })
+            })
+            .map(|entry| entry.finished_at);
+
+        Ok(stats::ChainStats {
+            name: self.name.clone(),
+            root_branch: self.root_branch.clone(),
+            branches,
+            missing_branches: self
+                .missing_branches
+                .iter()
+                .map(|branch| branch.branch_name.clone())
+                .collect(),
+            last_rebased_at,
+        })
+    }
+
+    fn summary(&self, git_chain: &GitChain, current_branch: &str) -> Result<ChainSummary, Error> {
+        let counts = self.ahead_behind_counts(git_chain)?;
+        let mut branches = vec![];
+        for (branch, missing) in self.display_order().into_iter().rev() {
+            let mut summary = BranchSummary {
+                current: branch.branch_name == current_branch,
+                missing,
+                ahead: 0,
+                behind: 0,
+                needs_rebase: false,
+                note: branch.note(git_chain)?,
+                pull_request: None,
+                branch_name: branch.branch_name.clone(),
+            };
+
+            if !missing {
+                let upstream = self.parent_branch_name(&branch);
+                let (ahead, behind) = counts[&branch.branch_name].unwrap_or_default();
+                summary.ahead = ahead;
+                summary.behind = behind;
+                summary.needs_rebase = branch.needs_rebase(git_chain, &upstream)?;
+                // Without gh, pull requests are left out.
+                if let Ok(Some(pull_request)) = git_chain.find_pull_request(&branch.branch_name) {
+                    summary.pull_request = Some(PullRequestSummary {
+                        number: pull_request.number,
+                        url: pull_request.url,
+                        state: pull_request.state,
+                    });
+                }
+            }
+
+            branches.push(summary);
+        }
+
+        Ok(ChainSummary {
+            name: self.name.clone(),
+            root_branch: self.root_branch.clone(),
+            scope: self.scope(git_chain)?,
+            landed: self.landed(git_chain, &counts)?,
+            branches,
+        })
+    }
+
+    fn landed(
+        &self,
+        git_chain: &GitChain,
+        counts: &AheadBehindCounts,
+    ) -> Result<LandedSummary, Error> {
+        let mut landed = LandedSummary {
+            total_branches: self.branches.len() + self.missing_branches.len(),
+            ..LandedSummary::default()
+        };
+
+        if !git_chain.git_root_exists(&self.root_branch)? {
+            return Ok(landed);
+        }
+
+        for branch in &self.branches {
+            let parent_branch = self.parent_branch_name(branch);
+            let num_of_commits = match counts[&branch.branch_name] {
+                Some((ahead, _behind)) => ahead,
+                None => continue,
+            };
+            let num_of_landed_commits = git_chain.landed_commits(
+                &self.root_branch,
+                &parent_branch,
+                &branch.branch_name,
+                num_of_commits,
+            )?;
+            landed.total_commits += num_of_commits;
+            landed.commits += num_of_landed_commits;
+            if num_of_commits > 0 && num_of_landed_commits == num_of_commits {
+                landed.branches += 1;
+            }
+        }
+
+        Ok(landed)
+    }
+
+    fn display_order(&self) -> Vec<(Branch, bool)> {
+        // All branches of the chain from the last to the first, flagging the missing branches.
+        let mut branches: Vec<(Branch, bool)> = self
+            .branches
+            .iter()
+            .map(|b| (b.clone(), false))
+            .chain(self.missing_branches.iter().map(|b| (b.clone(), true)))
+            .collect();
+        branches.sort_by(|(a, _), (b, _)| b.chain_order.cmp(&a.chain_order));
+        branches
+    }
+
+    fn display_missing_hint(&self) {
+        if self.missing_branches.is_empty() {
+            return;
+        }
+
+        let exe_name = executable_name();
+        println!();
+        println!("⚠️  Some branches of the chain no longer exist.");
+        println!(
+            "To remove them from the chain, run: {} prune",
+            exe_name.bold()
+        );
+    }
+
+    fn branch_pairs(&self) -> Vec<BranchPair<'_>> {
+        self.branches
+            .iter()
+            .enumerate()
+            .map(|(index, branch)| BranchPair {
+                index,
+                parent: if index == 0 {
+                    &self.root_branch
+                } else {
+                    &self.branches[index - 1].branch_name
+                },
+                branch,
+            })
+            .collect()
+    }
+
+    // The branch that the pull request of the branch at index is based on.
+    fn base_branch_on_remote(
+        &self,
+        git_chain: &GitChain,
+        index: usize,
+        remote_name: &str,
+    ) -> Result<String, Error> {
+        if index == 0 {
+            // The root branch may be a remote-tracking branch, e.g. origin/main
+            let prefix = format!("{}/", remote_name);
+            Ok(self
+                .root_branch
+                .strip_prefix(&prefix)
+                .unwrap_or(&self.root_branch)
+                .to_string())
+        } else {
+            // The parent branch may be pushed to a branch of another name.
+            git_chain.remote_branch_name(&self.branches[index - 1].branch_name)
+        }
+    }
+
+    fn parent_branch_name(&self, branch: &Branch) -> String {
+        match self.before(branch) {
+            Some(parent) => parent.branch_name,
+            None => self.root_branch.clone(),
+        }
+    }
+
     fn before(&self, needle_branch: &Branch) -> Option<Branch> {
         if self.branches.is_empty() {
             return None;
@@ -576,7 +1667,7 @@ impl Chain {
                     "Branch cannot be the root branch: {}",
                     branch.branch_name.bold()
                 );
-                process::exit(1);
+                exit(1);
             }
         }
 
@@ -589,7 +1680,7 @@ impl Chain {
 
     fn delete(self, git_chain: &GitChain) -> Result<Vec<String>, Error> {
         let mut deleted_branches: Vec<String> = vec![];
-        for branch in self.branches {
+        for branch in self.branches.into_iter().chain(self.missing_branches) {
             deleted_branches.push(branch.branch_name.clone());
             branch.remove_from_chain(git_chain)?;
         }
@@ -597,25 +1688,157 @@ impl Chain {
         Ok(deleted_branches)
     }
 
-    fn backup(&self, git_chain: &GitChain) -> Result<(), Error> {
+    // The layout of the chain, as published and as stored with its backups.
+    fn definition(&self, git_chain: &GitChain) -> Result<ChainDefinition, Error> {
+        Ok(ChainDefinition {
+            root_branch: self.root_branch.clone(),
+            scope: self.scope(git_chain)?,
+            branches: self
+                .branches
+                .iter()
+                .map(|branch| branch.branch_name.clone())
+                .collect(),
+        })
+    }
+
+    fn backup(&self, git_chain: &GitChain, include_root: bool) -> Result<(), Error> {
+        let timestamp = git_chain.new_backup_timestamp(&self.name)?;
         for branch in &self.branches {
-            branch.backup(git_chain)?;
+            branch.backup(git_chain, &timestamp)?;
+        }
+
+        if include_root {
+            let commit = git_chain
+                .repo
+                .revparse_single(&self.root_branch)?
+                .peel_to_commit()?;
+            let backup_branch = format!(
+                "{}{}/{}",
+                backup_prefix(&self.name),
+                timestamp,
+                self.root_branch
+            );
+            git_chain.repo.branch(&backup_branch, &commit, true)?;
         }
+
+        // Along with the branches, so that restoring the backup also restores the chain itself.
+        git_chain.write_definition_ref(
+            &publish::backup_ref(&self.name, &timestamp),
+            &self.definition(git_chain)?,
+            &format!("Back up chain {}", self.name),
+            false,
+        )?;
+
         Ok(())
     }
 
-    fn push(&self, git_chain: &GitChain, force_push: bool) -> Result<usize, Error> {
+    // Indices of the branches of the range, or why the range is invalid.
+    fn branch_indices(&self, range: &BranchRange) -> Result<std::ops::Range<usize>, String> {
+        // e.g. all of its branches were deleted.
+        if self.branches.is_empty() {
+            return Err(format!("Chain {} has no branches", self.name.bold()));
+        }
+
+        let index_of = |branch_name: &str| {
+            self.branches
+                .iter()
+                .position(|branch| branch.branch_name == branch_name)
+                .ok_or_else(|| {
+                    format!(
+                        "Branch {} is not part of chain {}",
+                        branch_name.bold(),
+                        self.name.bold()
+                    )
+                })
+        };
+
+        let start = match &range.from {
+            Some(from) => index_of(from)?,
+            None => 0,
+        };
+        let end = match &range.to {
+            Some(to) => index_of(to)? + 1,
+            None => self.branches.len(),
+        };
+
+        if start >= end {
+            return Err(format!(
+                "Branch {} comes after branch {} in chain {}",
+                range.from.as_deref().unwrap_or_default().bold(),
+                range.to.as_deref().unwrap_or_default().bold(),
+                self.name.bold()
+            ));
+        }
+
+        Ok(start..end)
+    }
+
+    // Exits if the range is invalid. Reports the branches of the range if it is not the whole chain.
+    fn resolve_branch_range(&self, range: &BranchRange, operation: &str) -> std::ops::Range<usize> {
+        let indices = match self.branch_indices(range) {
+            Ok(indices) => indices,
+            Err(message) => {
+                eprintln!("🛑 Unable to {} chain: {}", operation, self.name.bold());
+                eprintln!("{}", message);
+                exit(1);
+            }
+        };
+
+        if indices.len() != self.branches.len() {
+            let first_branch = &self.branches[indices.start].branch_name;
+            let last_branch = &self.branches[indices.end - 1].branch_name;
+            if first_branch == last_branch {
+                println!(
+                    "Only branch {} of chain {} is selected.",
+                    first_branch.bold(),
+                    self.name.bold()
+                );
+            } else {
+                println!(
+                    "Only branches {} to {} of chain {} are selected.",
+                    first_branch.bold(),
+                    last_branch.bold(),
+                    self.name.bold()
+                );
+            }
+        }
+
+        indices
+    }
+
+    fn push(
+        &self,
+        git_chain: &GitChain,
+        options: &PushOptions,
+        indices: std::ops::Range<usize>,
+    ) -> Result<usize, Error> {
         let mut num_of_pushes = 0;
-        for branch in &self.branches {
-            if branch.push(git_chain, force_push)? {
+        for branch in &self.branches[indices] {
+            timings::set_branch(Some(&branch.branch_name));
+            if branch.push(git_chain, options)? {
                 num_of_pushes += 1;
             }
         }
+        timings::set_branch(None);
         Ok(num_of_pushes)
     }
 
-    fn prune(&self, git_chain: &GitChain, dry_run: bool) -> Result<Vec<String>, Error> {
+    fn prune(
+        &self,
+        git_chain: &GitChain,
+        dry_run: bool,
+        upstream_gone: bool,
+    ) -> Result<Vec<String>, Error> {
         let mut pruned_branches = vec![];
+
+        // Branches that no longer exist are always pruned.
+        for branch in self.missing_branches.clone() {
+            pruned_branches.push(branch.branch_name.clone());
+            if !dry_run {
+                branch.remove_from_chain(git_chain)?;
+            }
+        }
+
         for branch in self.branches.clone() {
             // branch is an ancestor of the root branch if:
             // - it is the root branch, or
@@ -627,12 +1850,118 @@ impl Chain {
                     branch.remove_from_chain(git_chain)?;
                 }
 
+                pruned_branches.push(branch_name);
+            } else if upstream_gone && git_chain.upstream_gone(&branch.branch_name)? {
+                // The upstream branch was deleted, typically once its pull request was merged,
+                // even if the merge could not be detected (e.g. squashed with other changes).
+                let branch_name = branch.branch_name.clone();
+
+                if !dry_run {
+                    branch.remove_from_chain(git_chain)?;
+                }
+
                 pruned_branches.push(branch_name);
             }
         }
         Ok(pruned_branches)
     }
 
+    fn copy(
+        &self,
+        git_chain: &GitChain,
+        new_chain_name: &str,
+        branch_template: &str,
+    ) -> Result<Vec<String>, Error> {
+        // invariant: new_chain_name chain does not exist
+        assert!(!Chain::chain_exists(git_chain, new_chain_name)?);
+
+        let new_branch_names: Vec<String> = self
+            .branches
+            .iter()
+            .map(|branch| branch_template.replace("{branch}", &branch.branch_name))
+            .collect();
+
+        for new_branch_name in &new_branch_names {
+            if let Some(error) = names::branch_name_error(new_branch_name) {
+                return Err(Error::from_str(&format!(
+                    "{}: {}",
+                    error,
+                    names::display_name(new_branch_name).bold()
+                )));
+            }
+            if git_chain.git_local_branch_exists(new_branch_name)? {
+                return Err(Error::from_str(&format!(
+                    "Branch already exists: {}",
+                    new_branch_name.bold()
+                )));
+            }
+        }
+
+        for (branch, new_branch_name) in self.branches.iter().zip(&new_branch_names) {
+            let (object, _reference) = git_chain.repo.revparse_ext(&branch.branch_name)?;
+            let commit = object.peel_to_commit()?;
+            git_chain.repo.branch(new_branch_name, &commit, false)?;
+
+            Branch::setup_branch(
+                git_chain,
+                new_chain_name,
+                &self.root_branch,
+                new_branch_name,
+                &SortBranch::Last,
+            )?;
+        }
+
+        if let Some(scope) = self.scope(git_chain)? {
+            let new_chain = Chain::get_chain(git_chain, new_chain_name)?;
+            new_chain.set_scope(git_chain, Some(&scope))?;
+        }
+
+        Ok(new_branch_names)
+    }
+
+    // The branches of the chain renamed by rename-prefix, along with their new names.
+    fn renamed_branches(
+        &self,
+        git_chain: &GitChain,
+        renaming: &BranchRenaming,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let renamed_branches: Vec<(String, String)> = self
+            .branches
+            .iter()
+            .filter_map(|branch| {
+                renaming
+                    .apply(&branch.branch_name)
+                    .map(|new_branch_name| (branch.branch_name.clone(), new_branch_name))
+            })
+            .collect();
+
+        let mut new_branch_names = HashSet::new();
+        for (branch_name, new_branch_name) in &renamed_branches {
+            if let Some(error) = names::branch_name_error(new_branch_name) {
+                return Err(Error::from_str(&format!(
+                    "{}: {}",
+                    error,
+                    names::display_name(new_branch_name).bold()
+                )));
+            }
+            if !new_branch_names.insert(new_branch_name) {
+                return Err(Error::from_str(&format!(
+                    "Several branches would be renamed to: {}",
+                    new_branch_name.bold()
+                )));
+            }
+            if git_chain.git_local_branch_exists(new_branch_name)? {
+                return Err(Error::from_str(&format!(
+                    "Unable to rename branch {}. Branch already exists: {}",
+                    branch_name.bold(),
+                    new_branch_name.bold()
+                )));
+            }
+        }
+
+        Ok(renamed_branches)
+    }
+
     fn rename(self, git_chain: &GitChain, new_chain_name: &str) -> Result<(), Error> {
         // invariant: new_chain_name chain does not exist
         assert!(!Chain::chain_exists(git_chain, new_chain_name)?);
@@ -650,34 +1979,506 @@ impl Chain {
     }
 }
 
-struct GitChain {
-    executable_name: String,
-    repo: Repository,
+// A contiguous slice of the branches of a chain, given with --from and --to (both included).
+// Without them, the slice starts at the first branch and ends at the last branch of the chain.
+#[derive(Clone, Default)]
+struct BranchRange {
+    from: Option<String>,
+    to: Option<String>,
 }
 
-impl GitChain {
-    fn init() -> Result<Self, Error> {
-        let name_of_current_executable = executable_name();
-
-        let repo = Repository::discover(".")?;
-
-        if repo.is_bare() {
-            eprintln!(
-                "Cannot run {} on bare git repository.",
-                name_of_current_executable
-            );
-            process::exit(1);
+impl BranchRange {
+    fn from_matches(sub_matches: &ArgMatches) -> BranchRange {
+        BranchRange {
+            from: sub_matches.value_of("from").map(str::to_string),
+            to: sub_matches.value_of("to").map(str::to_string),
         }
+    }
+}
 
-        let git_chain = GitChain {
-            repo,
-            executable_name: name_of_current_executable,
+// How rename-prefix renames the branches of a chain: the prefix or suffix of their names is replaced,
+// or a regex is substituted (with $1 or ${name} for its capture groups).
+enum BranchRenaming {
+    Prefix(String, String),
+    Suffix(String, String),
+    Regex(Regex, String),
+}
+
+impl BranchRenaming {
+    // The new name of a branch, or None if the branch is not renamed.
+    fn apply(&self, branch_name: &str) -> Option<String> {
+        let new_branch_name = match self {
+            BranchRenaming::Prefix(from, to) => branch_name
+                .strip_prefix(from.as_str())
+                .map(|rest| format!("{}{}", to, rest)),
+            BranchRenaming::Suffix(from, to) => branch_name
+                .strip_suffix(from.as_str())
+                .map(|rest| format!("{}{}", rest, to)),
+            BranchRenaming::Regex(regex, replacement) if regex.is_match(branch_name) => {
+                Some(regex.replace(branch_name, replacement.as_str()).to_string())
+            }
+            BranchRenaming::Regex(_, _) => None,
         };
-        Ok(git_chain)
+        new_branch_name.filter(|new_branch_name| new_branch_name != branch_name)
     }
+}
 
-    fn get_current_branch_name(&self) -> Result<String, Error> {
-        let head = match self.repo.head() {
+// What rename-prefix --remote did with the upstream branch of a renamed branch.
+enum RemoteRename {
+    // Renamed on the remote (via gh), along with its pull requests.
+    Renamed(String),
+    // Pushed under its new name to the remote, and deleted under its old name.
+    Pushed(String),
+    // Left as it is, and why.
+    Skipped(String),
+    Failed(String),
+}
+
+struct PushOptions {
+    // Push with --force-with-lease.
+    force_push: bool,
+    // Skip branches that match their upstream.
+    only_changed: bool,
+    // Before force pushing, warn about pull requests that are approved or conflicting.
+    check_pr: bool,
+    // With check_pr, do not push anything if there are warnings.
+    strict: bool,
+    // Do not force push branches with approved pull requests,
+    // or whose upstream has commits that are not in the local branch.
+    protect_reviewed: bool,
+    range: BranchRange,
+    // After pushing, create pull requests for the branches without one. Branches without an
+    // upstream are pushed with --set-upstream before their pull request is created.
+    create_pr: bool,
+    // With create_pr, create draft pull requests.
+    draft: bool,
+}
+
+// Which of the pull requests created by git chain pr are drafts.
+enum DraftMode {
+    None,
+    All,
+    // The pull requests of the branches after this branch, e.g. to have the first branches of the
+    // chain reviewed while the rest is still in progress.
+    After(String),
+}
+
+struct PrOptions {
+    draft_mode: DraftMode,
+    // Label each pull request with its position in the chain, e.g. stack:2/4.
+    stack_order_labels: bool,
+    // Update the base branch of existing pull requests to the parent branch of their branch.
+    update_base: bool,
+    // Open each created or existing pull request in the browser.
+    open: bool,
+    // Exit before pushing any branch if a branch exceeds the size limits.
+    strict: bool,
+}
+
+struct SizeLimits {
+    // Maximum number of commits of a branch, on top of its parent branch.
+    max_commits: Option<usize>,
+    // Maximum number of changed lines (added and deleted) of a branch against its parent branch.
+    max_changed_lines: Option<usize>,
+}
+
+impl SizeLimits {
+    fn is_empty(&self) -> bool {
+        self.max_commits.is_none() && self.max_changed_lines.is_none()
+    }
+
+    fn check(&self, num_of_commits: usize, num_of_changed_lines: usize) -> Vec<String> {
+        let mut violations = vec![];
+        if let Some(max_commits) = self.max_commits {
+            if num_of_commits > max_commits {
+                violations.push(format!("{} commits (max {})", num_of_commits, max_commits));
+            }
+        }
+        if let Some(max_changed_lines) = self.max_changed_lines {
+            if num_of_changed_lines > max_changed_lines {
+                violations.push(format!(
+                    "{} changed lines (max {})",
+                    num_of_changed_lines, max_changed_lines
+                ));
+            }
+        }
+        violations
+    }
+}
+
+struct TestOptions {
+    // Run the command in a temporary worktree per branch, instead of checking out each branch.
+    worktrees: bool,
+    // Number of branches tested at the same time. Requires worktrees when greater than 1.
+    jobs: usize,
+    // Stop at the first branch that fails.
+    fail_fast: bool,
+}
+
+// A single argument is run by the shell, like the command of git rebase --exec.
+fn test_command(command: &[String]) -> Command {
+    if command.len() == 1 {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(&command[0]);
+        shell
+    } else {
+        let mut program = Command::new(&command[0]);
+        program.args(&command[1..]);
+        program
+    }
+}
+
+// Trailers added to the commits that git chain creates or rewrites: Signed-off-by, e.g. for
+// projects that enforce a DCO, and the trailers of chain.trailer (e.g. "Chain: {chain}").
+struct CommitTrailers {
+    trailers: Vec<String>,
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+impl CommitTrailers {
+    // Arguments of git commit. A trailer that the commit already has is not added again, so that
+    // commits rewritten several times do not repeat their trailers.
+    fn commit_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-c".to_string(),
+            "trailer.ifExists=addIfDifferent".to_string(),
+            "commit".to_string(),
+        ];
+        for trailer in &self.trailers {
+            args.push("--trailer".to_string());
+            args.push(trailer.clone());
+        }
+        args
+    }
+
+    fn amend_args(&self) -> Vec<String> {
+        let mut args = self.commit_args();
+        args.extend(
+            ["--amend", "--no-edit", "--no-verify", "--allow-empty"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        );
+        args
+    }
+
+    // Arguments of git rebase; the trailers are added by amending each rewritten commit.
+    fn rebase_args(&self) -> Vec<String> {
+        if self.trailers.is_empty() {
+            return vec![];
+        }
+        let amend_command = std::iter::once("git".to_string())
+            .chain(self.amend_args().iter().map(|arg| shell_quote(arg)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        vec!["--exec".to_string(), amend_command]
+    }
+
+    // Adds the trailers to the last commits of the current branch, e.g. after a cherry-pick.
+    fn add_to_new_commits(&self, num_of_commits: usize) -> Result<(), Error> {
+        if self.trailers.is_empty() || num_of_commits == 0 {
+            return Ok(());
+        }
+
+        // A single commit may be a merge commit, which git rebase would not keep.
+        let args: Vec<String> = if num_of_commits == 1 {
+            self.amend_args()
+        } else {
+            let mut args = vec!["rebase".to_string(), "--keep-empty".to_string()];
+            args.extend(self.rebase_args());
+            args.push(format!("HEAD~{}", num_of_commits));
+            args
+        };
+
+        let command = format!("git {}", args.join(" "));
+        let output = Command::new("git")
+            .args(&args)
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+        if !output.status.success() {
+            eprintln!("Command returned non-zero exit status: {}", command);
+            io::stderr().write_all(&output.stderr).unwrap();
+            exit(1);
+        }
+
+        Ok(())
+    }
+}
+
+// How commits of the upstream of a branch that are not in the branch (e.g. pushed by a reviewer)
+// are handled before the chain is rebased.
+#[derive(Clone, Copy, PartialEq)]
+enum ReconcileMode {
+    // Only warn about them.
+    Off,
+    // Cherry-pick them onto the branch.
+    Rebase,
+    // Merge the upstream into the branch.
+    Merge,
+}
+
+// A branch of a chain, along with the branch it is stacked on: the branch before it in the
+// chain, or the root branch for the first branch.
+struct BranchPair<'a> {
+    index: usize,
+    parent: &'a str,
+    branch: &'a Branch,
+}
+
+// What rebasing a branch onto its parent branch amounts to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RebaseAction {
+    // The first branch is left as is with --ignore-root.
+    SkipRoot,
+    // A branch without a common ancestor with its parent branch is left as is with --allow-unrelated.
+    SkipUnrelated,
+    // The branch was squashed and merged onto its parent branch, and is reset to it.
+    ResetToParent,
+    // git rebase --onto <parent_branch> <fork_point> <branch>
+    Rebase,
+}
+
+struct RebaseStep<'a> {
+    pair: &'a BranchPair<'a>,
+    fork_point: &'a str,
+    action: RebaseAction,
+}
+
+struct RebaseOptions {
+    // Stop at the first rebase.
+    step_rebase: bool,
+    // Do not rebase the first branch of the chain against the root branch.
+    ignore_root: bool,
+    // Passed through to git rebase.
+    committer_date_is_author_date: bool,
+    reset_author_date: bool,
+    // Open the todo list of git rebase --interactive for each branch.
+    interactive: bool,
+    // Offer to remove branches that became empty after the rebase from the chain.
+    drop_empty: bool,
+    // Passed to git rebase as --strategy-option for every branch, instead of the strategy
+    // option of each branch.
+    strategy_option: Option<String>,
+    reconcile: ReconcileMode,
+    // Add Signed-off-by to the rebased commits, in addition to chain.signoff.
+    signoff: bool,
+    // Also delete the branches that are removed from the chain because they became empty.
+    delete_empty: bool,
+    // Rebase in a temporary worktree, and only update the branches once every rebase succeeded.
+    worktree: bool,
+    // Replay each branch onto its current merge-base with its parent branch, instead of onto the
+    // parent branch, so that new commits of the parent branch are not pulled in.
+    keep_base: bool,
+    // Leave the branches without a common ancestor with their parent branch as they are, instead
+    // of refusing to rebase.
+    allow_unrelated: bool,
+    // Fast-forward the root branch to its upstream branch first.
+    update_root: bool,
+    // End on the last rebased branch instead of the original branch.
+    stay: bool,
+    range: BranchRange,
+}
+
+#[derive(Default)]
+struct VerifyOptions {
+    // Only verify these branches, e.g. the branches that were rebased.
+    range: BranchRange,
+    // Do not expect the first branch of the chain to be based on the root branch.
+    ignore_root: bool,
+    // Do not expect any branch to be based on the tip of its parent branch, e.g. after
+    // rebase --keep-base.
+    ignore_parents: bool,
+    // Do not expect the working directory to be clean, e.g. after rebase --worktree.
+    ignore_worktree: bool,
+}
+
+// How chains are displayed by list and status.
+#[derive(Default)]
+struct ListOptions {
+    // Display all chains as markdown tables (list only).
+    markdown: bool,
+    // Display all chains as a tree of their root branches (list only).
+    tree: bool,
+    // Show the URL of the pull request of each branch.
+    pr_urls: bool,
+    // Show when the last commit of each branch was authored and by whom, and in the header the
+    // most recent of these dates, to spot stale branches and abandoned chains.
+    verbose: bool,
+    // Mark the branches that would conflict on the next rebase.
+    check_conflicts: bool,
+}
+
+// The name of a branch, e.g. of an upstream branch; names that are not valid UTF-8 are rejected
+// rather than mangled, as they are passed on to git.
+fn utf8_branch_name(branch: &git2::Branch) -> Result<String, Error> {
+    let name = branch.name_bytes()?;
+    match std::str::from_utf8(name) {
+        Ok(name) => Ok(name.to_string()),
+        Err(_) => Err(Error::from_str(&format!(
+            "{}\n{}",
+            names::non_utf8_branch_error(name),
+            names::BRANCH_NAME_ENCODING
+        ))),
+    }
+}
+
+// The temporary ref that holds the rebased commit of a branch during rebase --worktree, until the
+// branches themselves are updated.
+fn worktree_rebase_ref(branch_name: &str) -> String {
+    format!("refs/chain-rebase/{}", branch_name)
+}
+
+struct GitChain {
+    executable_name: String,
+    repo: Repository,
+    prompt_mode: PromptMode,
+}
+
+// The branch that was checked out when an operation on the branches of a chain (e.g. rebase)
+// started. The operation switches back to it once done, and the guard switches back to it when
+// the operation stops on the way instead, e.g. on an error or a panic. An operation stopped on
+// conflicts is left on the branch to resolve them; the original branch is recorded until the
+// operation is resumed.
+struct OriginalBranch<'a> {
+    git_chain: &'a GitChain,
+    chain_name: String,
+    operation: &'static str,
+    branch_name: String,
+    // With --stay, the operation ends on the last branch that it checked out.
+    stay: bool,
+    // Set once the operation switched back, after which it may e.g. delete the original branch.
+    done: Cell<bool>,
+}
+
+impl<'a> OriginalBranch<'a> {
+    fn new(
+        git_chain: &'a GitChain,
+        chain_name: &str,
+        operation: &'static str,
+        stay: bool,
+    ) -> Result<Self, Error> {
+        let chain_dir = git_chain.chain_dir();
+        let branch_name = match original_branch::read(&chain_dir).ok().flatten() {
+            Some(record)
+                if record.chain_name == chain_name
+                    && record.operation == operation
+                    && git_chain.git_local_branch_exists(&record.branch_name)? =>
+            {
+                record.branch_name
+            }
+            _ => git_chain.get_current_branch_name()?,
+        };
+        let _ = original_branch::clear(&chain_dir);
+
+        Ok(OriginalBranch {
+            git_chain,
+            chain_name: chain_name.to_string(),
+            operation,
+            branch_name,
+            stay,
+            done: Cell::new(false),
+        })
+    }
+
+    // Once the operation is done.
+    fn switch_back(&self) -> Result<(), Error> {
+        self.done.set(true);
+        if self.stay || self.git_chain.get_current_branch_name()? == self.branch_name {
+            return Ok(());
+        }
+
+        println!();
+        println!("Switching back to branch: {}", self.branch_name.bold());
+        self.git_chain.checkout_branch(&self.branch_name)
+    }
+
+    // process::exit skips destructors, so operations stop with this instead.
+    fn exit(&self, code: i32) -> ! {
+        self.restore();
+        exit(code);
+    }
+
+    fn restore(&self) {
+        if self.done.get() {
+            return;
+        }
+
+        if self.git_chain.repo.state() != RepositoryState::Clean {
+            let record = original_branch::Record {
+                chain_name: self.chain_name.clone(),
+                operation: self.operation.to_string(),
+                branch_name: self.branch_name.clone(),
+            };
+            if let Err(err) = original_branch::write(&self.git_chain.chain_dir(), &record) {
+                eprintln!(
+                    "⚠️  Unable to record the original branch {}: {}",
+                    self.branch_name.bold(),
+                    err
+                );
+            }
+            return;
+        }
+
+        if self.stay {
+            return;
+        }
+
+        match self.git_chain.get_current_branch_name() {
+            Ok(current_branch) if current_branch == self.branch_name => {}
+            _ => {
+                eprintln!("Switching back to branch: {}", self.branch_name.bold());
+                if let Err(err) = self.git_chain.checkout_branch(&self.branch_name) {
+                    eprintln!(
+                        "⚠️  Unable to switch back to branch {}: {}",
+                        self.branch_name.bold(),
+                        err.message()
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Drop for OriginalBranch<'_> {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+impl GitChain {
+    fn init(prompt_mode: PromptMode) -> Result<Self, Error> {
+        let name_of_current_executable = executable_name();
+
+        let repo = Repository::discover(".")?;
+
+        if repo.is_bare() {
+            eprintln!(
+                "Cannot run {} on bare git repository.",
+                name_of_current_executable
+            );
+            exit(1);
+        }
+
+        let git_chain = GitChain {
+            repo,
+            executable_name: name_of_current_executable,
+            prompt_mode,
+        };
+        Ok(git_chain)
+    }
+
+    fn get_current_branch_name(&self) -> Result<String, Error> {
+        if self.repo.head_detached()? {
+            return Err(Error::from_str(&format!(
+                "HEAD is detached. Check out a branch first, e.g.: git checkout <branch>\nOr use {} list to display all chains.",
+                self.executable_name
+            )));
+        }
+
+        let head = match self.repo.head() {
             Ok(head) => Some(head),
             Err(ref e)
                 if e.code() == ErrorCode::UnbornBranch || e.code() == ErrorCode::NotFound =>
@@ -687,6 +2488,17 @@ impl GitChain {
             Err(e) => return Err(e),
         };
 
+        if let Some(head) = &head {
+            if head.shorthand().is_none() {
+                return Err(Error::from_str(&format!(
+                    "{}\n{}\nRename the branch to use it with {}, e.g.: git branch -m <new_branch_name>",
+                    names::non_utf8_branch_error(head.shorthand_bytes()),
+                    names::BRANCH_NAME_ENCODING,
+                    self.executable_name
+                )));
+            }
+        }
+
         let head = head.as_ref().and_then(|h| h.shorthand());
 
         match head {
@@ -695,6 +2507,14 @@ impl GitChain {
         }
     }
 
+    fn get_current_branch_name_if_attached(&self) -> Result<Option<String>, Error> {
+        // For read-only output, a detached HEAD means there is no current branch to highlight.
+        if self.repo.head_detached()? {
+            return Ok(None);
+        }
+        Ok(Some(self.get_current_branch_name()?))
+    }
+
     fn get_local_git_config(&self) -> Result<Config, Error> {
         self.repo.config()?.open_level(ConfigLevel::Local)
     }
@@ -712,37 +2532,238 @@ impl GitChain {
         let local_config = self.get_local_git_config()?;
         let mut entries = vec![];
 
-        local_config.entries(None)?.for_each(|entry| {
-            if let Some(key) = entry.name() {
-                if regexp.is_match(key) && entry.has_value() {
-                    let key = key.to_string();
-                    let value = entry.value().unwrap().to_string();
-                    entries.push((key, value));
+        local_config
+            .entries(None)?
+            .for_each(|entry| match (entry.name(), entry.value()) {
+                (Some(key), Some(value)) => {
+                    if regexp.is_match(key) {
+                        entries.push((key.to_string(), value.to_string()));
+                    }
                 }
-            }
-        })?;
+                (Some(key), None) => {
+                    if regexp.is_match(key) && entry.has_value() {
+                        self.report_non_utf8_config(entry.name_bytes());
+                    }
+                }
+                (None, _) => {
+                    let key = String::from_utf8_lossy(entry.name_bytes());
+                    if regexp.is_match(&key) {
+                        self.report_non_utf8_config(entry.name_bytes());
+                    }
+                }
+            })?;
 
         Ok(entries)
     }
 
+    fn get_git_config_setting(&self, key: &str) -> Result<Option<String>, Error> {
+        // Unlike chain metadata, settings may be defined at any config level (e.g. global).
+        let config = self.repo.config()?;
+        match config.get_string(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(ref e) if e.code() == ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn get_setting(&self, env_name: &str, config_key: &str) -> Result<Option<String>, Error> {
+        // Precedence: environment variable > git config
+        if let Some(value) = env_var(env_name) {
+            return Ok(Some(value));
+        }
+        self.get_git_config_setting(config_key)
+    }
+
+    fn verify_enabled(&self, sub_matches: &ArgMatches) -> Result<bool, Error> {
+        // Precedence: --verify > GIT_CHAIN_VERIFY > chain.verify
+        Ok(sub_matches.is_present("verify")
+            || self
+                .get_bool_setting("GIT_CHAIN_VERIFY", "chain.verify")?
+                .unwrap_or(false))
+    }
+
+    fn get_bool_setting(&self, env_name: &str, config_key: &str) -> Result<Option<bool>, Error> {
+        // Precedence: environment variable > git config
+        if let Some(value) = env_bool_setting(env_name)? {
+            return Ok(Some(value));
+        }
+        self.get_git_config_setting(config_key)?
+            .map(|value| parse_bool_setting(config_key, &value))
+            .transpose()
+    }
+
+    fn apply_color_setting(&self) -> Result<(), Error> {
+        // Like git, the output stays colored when it goes to the pager.
+        let auto = || {
+            if pager::in_use() {
+                colored::control::set_override(true);
+            }
+        };
+
+        match self.get_setting("GIT_CHAIN_COLOR", "chain.color")? {
+            None => auto(),
+            Some(value) => match value.as_str() {
+                "always" => colored::control::set_override(true),
+                "never" => colored::control::set_override(false),
+                "auto" => auto(),
+                _ => {
+                    return Err(Error::from_str(&format!(
+                        "Invalid color setting: {}. Expected one of: always, never, auto",
+                        value.bold()
+                    )));
+                }
+            },
+        }
+        Ok(())
+    }
+
+    // Whether long outputs go through the pager. pager.chain is also what git reads to page the
+    // output of git chain itself.
+    fn paging_enabled(&self) -> Result<bool, Error> {
+        // Not a boolean: a pager command for git to run (e.g. pager.chain=less -S).
+        Ok(self.repo.config()?.get_bool("pager.chain").unwrap_or(true))
+    }
+
+    fn apply_offline_setting(&self, offline: bool) -> Result<(), Error> {
+        gh::set_cache_path(self.chain_dir().join("pull-requests"));
+
+        // Precedence: --offline > GIT_CHAIN_OFFLINE > chain.offline
+        if offline
+            || self
+                .get_bool_setting("GIT_CHAIN_OFFLINE", "chain.offline")?
+                .unwrap_or(false)
+        {
+            gh::go_offline("Offline mode is enabled");
+        }
+        Ok(())
+    }
+
+    // Set by administrators (e.g. on shared machines, or by a migration script) to stop every
+    // command but enable and disable.
+    fn ensure_enabled(&self) -> Result<(), Error> {
+        let reason = match self.get_git_config_setting("chain.disabled")? {
+            Some(reason) => reason,
+            None => return Ok(()),
+        };
+        eprintln!(
+            "🛑 {} is disabled in this repository: {}",
+            self.executable_name,
+            if reason.is_empty() {
+                "no reason given"
+            } else {
+                &reason
+            }
+        );
+        eprintln!("To enable it again, run: {} enable", self.executable_name);
+        exit(1);
+    }
+
+    fn disable(&self, reason: &str) -> Result<(), Error> {
+        self.set_git_config("chain.disabled", reason)?;
+        println!(
+            "🛑 Disabled {} in this repository: {}",
+            self.executable_name, reason
+        );
+        println!("To enable it again, run: {} enable", self.executable_name);
+        Ok(())
+    }
+
+    fn enable(&self) -> Result<(), Error> {
+        let entry_level = match self.repo.config()?.get_entry("chain.disabled") {
+            Ok(entry) => entry.level(),
+            Err(ref e) if e.code() == ErrorCode::NotFound => {
+                println!(
+                    "{} is not disabled in this repository.",
+                    self.executable_name
+                );
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        if entry_level != ConfigLevel::Local {
+            eprintln!(
+                "🛑 Unable to enable {}: chain.disabled is set outside of the config of this repository.",
+                self.executable_name
+            );
+            eprintln!("To remove it, run e.g.: git config --global --unset chain.disabled");
+            exit(1);
+        }
+
+        self.delete_git_config("chain.disabled")?;
+        println!("✅ Enabled {} in this repository.", self.executable_name);
+        Ok(())
+    }
+
+    fn apply_gh_host_setting(&self, hostname: Option<&str>) -> Result<(), Error> {
+        // Precedence: --hostname > GH_HOST > chain.ghHost
+        let hostname = match hostname {
+            Some(hostname) => Some(hostname.to_string()),
+            None => self.get_setting("GH_HOST", "chain.ghHost")?,
+        };
+
+        // gh reads the host from GH_HOST, so it is passed on to every gh invocation.
+        if let Some(hostname) = hostname {
+            std::env::set_var("GH_HOST", browse::normalize_hostname(&hostname));
+        }
+        Ok(())
+    }
+
     fn set_git_config(&self, key: &str, value: &str) -> Result<(), Error> {
         let mut local_config = self.get_local_git_config()?;
         local_config.set_str(key, value)?;
+        debug_log!("set git config {} = {}", key, value);
+        Ok(())
+    }
+
+    // Replace all the values of a key that was set several times with a single value.
+    fn replace_git_config(&self, key: &str, value: &str) -> Result<(), Error> {
+        let mut local_config = self.get_local_git_config()?;
+        local_config.remove_multivar(key, ".*")?;
+        local_config.set_str(key, value)?;
+        debug_log!("replaced git config {} = {}", key, value);
         Ok(())
     }
 
     fn delete_git_config(&self, key: &str) -> Result<(), Error> {
         let mut local_config = self.get_local_git_config()?;
         match local_config.remove(key) {
-            Ok(()) => Ok(()),
+            Ok(()) => {
+                debug_log!("deleted git config {}", key);
+                Ok(())
+            }
             Err(ref e) if e.code() == ErrorCode::NotFound => Ok(()),
             Err(e) => Err(e),
         }
     }
 
     fn checkout_branch(&self, branch_name: &str) -> Result<(), Error> {
+        let started_at = Instant::now();
         let (object, reference) = self.repo.revparse_ext(branch_name)?;
 
+        // libgit2 checks out every file of the tree, regardless of the sparse-checkout patterns,
+        // and does not fetch the files missing from a partial clone.
+        if partial::needs_git_for_worktree(&self.repo) {
+            let mut command = Command::new("git");
+            command.args(["checkout", "--quiet"]);
+            if reference.is_none() {
+                command.arg("--detach");
+            }
+            let output = command
+                .arg(branch_name)
+                .logged_output()
+                .map_err(|err| Error::from_str(&format!("Unable to run git checkout: {}", err)))?;
+            if !output.status.success() {
+                return Err(Error::from_str(
+                    String::from_utf8_lossy(&output.stderr).trim(),
+                ));
+            }
+
+            timings::record("checkout", started_at.elapsed());
+            debug_log!("checked out {} with git", branch_name);
+            return Ok(());
+        }
+
         // set working directory
         self.repo.checkout_tree(&object, None)?;
 
@@ -755,6 +2776,8 @@ impl GitChain {
         }
         .unwrap_or_else(|_| panic!("Failed to set HEAD to branch {}", branch_name));
 
+        timings::record("checkout", started_at.elapsed());
+        debug_log!("checked out {}", branch_name);
         Ok(())
     }
 
@@ -763,45 +2786,515 @@ impl GitChain {
             || self.git_remote_branch_exists(branch_name)?)
     }
 
-    fn git_local_branch_exists(&self, branch_name: &str) -> Result<bool, Error> {
-        match self.repo.find_branch(branch_name, BranchType::Local) {
-            Ok(_branch) => Ok(true),
-            Err(ref e) if e.code() == ErrorCode::NotFound => Ok(false),
-            Err(e) => Err(e),
+    fn git_root_exists(&self, root: &str) -> Result<bool, Error> {
+        Ok(self.git_branch_exists(root)? || self.fixed_root_kind(root)?.is_some())
+    }
+
+    fn fixed_root_kind(&self, root: &str) -> Result<Option<&'static str>, Error> {
+        // A chain can be rooted at a tag or a commit instead of a branch, e.g. to stack
+        // a patch series on a release. Such a root is never moved by git chain itself.
+        if self.git_branch_exists(root)? {
+            return Ok(None);
+        }
+        if self
+            .repo
+            .find_reference(&format!("refs/tags/{}", root))
+            .is_ok()
+        {
+            return Ok(Some("root tag"));
+        }
+        match self.repo.revparse_single(root) {
+            Ok(object) if object.peel_to_commit().is_ok() => Ok(Some("root commit")),
+            _ => Ok(None),
         }
     }
 
-    fn git_remote_branch_exists(&self, branch_name: &str) -> Result<bool, Error> {
-        match self.repo.find_branch(branch_name, BranchType::Remote) {
-            Ok(_branch) => Ok(true),
-            Err(ref e) if e.code() == ErrorCode::NotFound => Ok(false),
-            Err(e) => Err(e),
+    fn ensure_root_is_branch(&self, chain: &Chain, action: &str) {
+        if let Ok(Some(kind)) = self.fixed_root_kind(&chain.root_branch) {
+            eprintln!(
+                "🛑 Unable to {} for the chain: {}",
+                action,
+                chain.name.bold()
+            );
+            eprintln!(
+                "The chain is rooted at the {} {}, which is not a branch.",
+                kind.trim_start_matches("root "),
+                chain.root_branch.bold()
+            );
+            exit(1);
         }
     }
 
-    fn display_branch_not_part_of_chain_error(&self, branch_name: &str) {
-        eprintln!("❌ Branch is not part of any chain: {}", branch_name.bold());
-        eprintln!(
-            "To initialize a chain for this branch, run {} init <chain_name> <root_branch>",
-            self.executable_name
+    // The remote-tracking branch of a branch that only exists on a remote, e.g. origin/feature.
+    // When several remotes have it, origin is preferred.
+    fn find_remote_tracking_branch(&self, branch_name: &str) -> Result<Option<String>, Error> {
+        let mut candidates = vec![];
+        for remote in self.repo.remotes()?.iter().flatten() {
+            let candidate = format!("{}/{}", remote, branch_name);
+            if self.git_remote_branch_exists(&candidate)? {
+                candidates.push(candidate);
+            }
+        }
+
+        if candidates.len() == 1 {
+            return Ok(candidates.pop());
+        }
+        Ok(candidates
+            .into_iter()
+            .find(|candidate| candidate.starts_with("origin/")))
+    }
+
+    // Offer to create a local branch tracking the remote-tracking branch of a branch that only
+    // exists on a remote. Whether the branch exists locally afterwards.
+    fn create_local_branch_from_remote(&self, branch_name: &str) -> Result<bool, Error> {
+        let tracking_branch = match self.find_remote_tracking_branch(branch_name)? {
+            Some(tracking_branch) => tracking_branch,
+            None => return Ok(false),
+        };
+
+        let question = format!(
+            "Branch {} only exists as {}. Create a local branch tracking it?",
+            branch_name.bold(),
+            tracking_branch.bold()
+        );
+        if !self.confirm(&question)? {
+            return Ok(false);
+        }
+
+        let commit = self
+            .repo
+            .find_branch(&tracking_branch, BranchType::Remote)?
+            .get()
+            .peel_to_commit()?;
+        let mut branch = self.repo.branch(branch_name, &commit, false)?;
+        branch.set_upstream(Some(&tracking_branch))?;
+
+        println!(
+            "Created branch {} tracking {}",
+            branch_name.bold(),
+            tracking_branch.bold()
         );
+
+        Ok(true)
     }
 
-    fn run_status(&self) -> Result<(), Error> {
-        let branch_name = self.get_current_branch_name()?;
-        println!("On branch: {}", branch_name.bold());
-        println!();
+    fn resolve_root_branch(&self, root_branch: &str) -> Result<Option<String>, Error> {
+        if self.git_branch_exists(root_branch)? {
+            return Ok(Some(root_branch.to_string()));
+        }
 
-        let results = Branch::get_branch_with_chain(self, &branch_name)?;
+        // The root branch may only exist as a remote-tracking branch (e.g. origin/main),
+        // in which case the chain tracks the remote-tracking branch.
+        if let Some(resolved) = &self.find_remote_tracking_branch(root_branch)? {
+            println!(
+                "Using remote-tracking branch {} as the root branch.",
+                resolved.bold()
+            );
+            return Ok(Some(resolved.clone()));
+        }
 
-        match results {
+        // Otherwise, the root may be a tag, or a commit which is stored as its full hash.
+        match self.fixed_root_kind(root_branch)? {
+            Some("root tag") => Ok(Some(root_branch.to_string())),
+            Some(_) => {
+                let commit = self.repo.revparse_single(root_branch)?.peel_to_commit()?;
+                let commit = commit.id().to_string();
+                println!("Using commit {} as the root of the chain.", commit.bold());
+                Ok(Some(commit))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn detect_default_branch(&self) -> Result<Option<String>, Error> {
+        // Prefer the default branch of the origin remote (i.e. refs/remotes/origin/HEAD),
+        // and fall back to init.defaultBranch.
+        match self.repo.find_reference("refs/remotes/origin/HEAD") {
+            Ok(reference) => {
+                if let Some(target) = reference.symbolic_target() {
+                    if let Some(branch_name) = target.strip_prefix("refs/remotes/origin/") {
+                        return Ok(Some(branch_name.to_string()));
+                    }
+                }
+            }
+            Err(ref e) if e.code() == ErrorCode::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        if let Some(default_branch) = self.get_git_config_setting("init.defaultBranch")? {
+            if self.git_local_branch_exists(&default_branch)? {
+                return Ok(Some(default_branch));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn git_local_branch_exists(&self, branch_name: &str) -> Result<bool, Error> {
+        match self.repo.find_branch(branch_name, BranchType::Local) {
+            Ok(_branch) => Ok(true),
+            // Revisions such as HEAD~1 are not valid branch names.
+            Err(ref e) if e.code() == ErrorCode::NotFound || e.code() == ErrorCode::InvalidSpec => {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn git_remote_branch_exists(&self, branch_name: &str) -> Result<bool, Error> {
+        match self.repo.find_branch(branch_name, BranchType::Remote) {
+            Ok(_branch) => Ok(true),
+            Err(ref e) if e.code() == ErrorCode::NotFound || e.code() == ErrorCode::InvalidSpec => {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn confirm(&self, question: &str) -> Result<bool, Error> {
+        prompt::confirm(self.prompt_mode, question)
+    }
+
+    fn confirm_or_proceed(&self, question: &str) -> Result<bool, Error> {
+        prompt::confirm_or_proceed(self.prompt_mode, question)
+    }
+
+    fn resolve_chain_name(&self, chain_name: Option<&str>) -> Result<String, Error> {
+        // Precedence: --chain > GIT_CHAIN_CHAIN > chain of the current branch
+        if let Some(chain_name) = chain_name {
+            return Ok(chain_name.to_string());
+        }
+
+        if let Some(chain_name) = env_var("GIT_CHAIN_CHAIN") {
+            return Ok(chain_name);
+        }
+
+        let branch_name = self.get_current_branch_name()?;
+
+        match Branch::get_branch_with_chain(self, &branch_name)? {
             BranchSearchResult::NotPartOfAnyChain(_) => {
                 self.display_branch_not_part_of_chain_error(&branch_name);
-                process::exit(1);
+                exit(1);
             }
-            BranchSearchResult::Branch(branch) => {
-                branch.display_status(self)?;
+            BranchSearchResult::Branch(branch) => Ok(branch.chain_name),
+        }
+    }
+
+    fn navigation_chain(&self, chain_name: Option<&str>) -> Result<Chain, Error> {
+        // The chain that first, last, next and prev switch between.
+        let chain_name = self.resolve_chain_name(chain_name)?;
+        if !Chain::chain_exists(self, &chain_name)? {
+            eprintln!("Unable to find chain.");
+            eprintln!("Chain does not exist: {}", chain_name.bold());
+            exit(1);
+        }
+        Chain::get_chain(self, &chain_name)
+    }
+
+    fn index_of_current_branch(&self, chain: &Chain) -> Result<usize, Error> {
+        // next and prev move relative to the current branch, which must be part of the chain.
+        let branch_name = self.get_current_branch_name()?;
+        match chain
+            .branches
+            .iter()
+            .position(|branch| branch.branch_name == branch_name)
+        {
+            Some(index) => Ok(index),
+            None => {
+                eprintln!(
+                    "🛑 Branch {} is not part of chain {}.",
+                    branch_name.bold(),
+                    chain.name.bold()
+                );
+                exit(1);
+            }
+        }
+    }
+
+    fn chain_dir(&self) -> std::path::PathBuf {
+        // Holds the lock and the history of git chain.
+        // Shared by all worktrees of the repository, since they share branches.
+        let git_dir = self.repo.path();
+        let common_dir = if self.repo.is_worktree() {
+            match std::fs::read_to_string(git_dir.join("commondir")) {
+                Ok(common_dir) => git_dir.join(common_dir.trim()),
+                Err(_) => git_dir.to_path_buf(),
+            }
+        } else {
+            git_dir.to_path_buf()
+        };
+        common_dir.join("chain")
+    }
+
+    fn lock(&self) -> Result<ChainLock, Error> {
+        let result = lock::acquire(&self.chain_dir())
+            .map_err(|err| Error::from_str(&format!("Unable to acquire lock: {}", err)))?;
+
+        match result {
+            LockResult::Acquired(lock) => Ok(lock),
+            LockResult::Held(pid) => {
+                eprintln!(
+                    "🛑 Another {} command is running on this repository (pid {}).",
+                    self.executable_name, pid
+                );
+                eprintln!(
+                    "If it is no longer running, re-run your command with {} to remove the lock.",
+                    "--force-unlock".bold()
+                );
+                exit(1);
+            }
+        }
+    }
+
+    fn snapshot_chain_branches(&self) -> Result<HashMap<String, Option<String>>, Error> {
+        // The commit of each branch that is part of a chain.
+        let key_regex = Regex::new(r"^branch\.(?P<branch_name>.+)\.chain-name$".trim()).unwrap();
+        let mut snapshot = HashMap::new();
+        for (key, _chain_name) in Chain::get_all_branch_configs(self)? {
+            let captures = key_regex.captures(&key).unwrap();
+            let branch_name = &captures["branch_name"];
+            let commit = if self.git_local_branch_exists(branch_name)? {
+                Some(self.get_commit_hash_of_branch(branch_name)?)
+            } else {
+                None
+            };
+            snapshot.insert(branch_name.to_string(), commit);
+        }
+        Ok(snapshot)
+    }
+
+    fn record_history(
+        &self,
+        started_at: u64,
+        branches_before: HashMap<String, Option<String>>,
+    ) -> Result<(), Error> {
+        let branches_after = self.snapshot_chain_branches()?;
+
+        let mut branch_names: Vec<&String> = branches_before
+            .keys()
+            .chain(branches_after.keys())
+            .collect();
+        branch_names.sort();
+        branch_names.dedup();
+
+        let mut changes = vec![];
+        for branch_name in branch_names {
+            // Branches that are added to or removed from a chain are looked up directly.
+            let lookup = |snapshot: &HashMap<String, Option<String>>| -> Result<_, Error> {
+                match snapshot.get(branch_name) {
+                    Some(commit) => Ok(commit.clone()),
+                    None if self.git_local_branch_exists(branch_name)? => {
+                        Ok(Some(self.get_commit_hash_of_branch(branch_name)?))
+                    }
+                    None => Ok(None),
+                }
+            };
+            let before = lookup(&branches_before)?;
+            let after = lookup(&branches_after)?;
+            if before != after {
+                changes.push((branch_name.clone(), before, after));
+            }
+        }
+
+        let command: Vec<String> = std::env::args().skip(1).collect();
+        let entry = history::Entry {
+            started_at,
+            finished_at: unix_time(),
+            command: command.join(" "),
+            changes,
+        };
+
+        history::append(&self.chain_dir(), &entry)
+            .map_err(|err| Error::from_str(&format!("Unable to record history: {}", err)))
+    }
+
+    fn display_history(&self, limit: Option<usize>) -> Result<(), Error> {
+        let entries = history::read(&self.chain_dir())
+            .map_err(|err| Error::from_str(&format!("Unable to read history: {}", err)))?;
+
+        if entries.is_empty() {
+            println!("No history.");
+            return Ok(());
+        }
+
+        let short = |commit: &Option<String>| match commit {
+            Some(commit) => commit.chars().take(7).collect::<String>(),
+            None => "(none)".to_string(),
+        };
+
+        // newest first
+        let limit = limit.unwrap_or(entries.len());
+        for entry in entries.iter().rev().take(limit) {
+            let (year, month, day, hours, minutes, seconds) = utc_datetime(entry.started_at);
+            println!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC ({}s) {} {}",
+                year,
+                month,
+                day,
+                hours,
+                minutes,
+                seconds,
+                entry.finished_at.saturating_sub(entry.started_at),
+                self.executable_name,
+                entry.command.bold()
+            );
+            for (branch_name, before, after) in &entry.changes {
+                println!("  {} {} → {}", branch_name, short(before), short(after));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn undo(&self) -> Result<(), Error> {
+        let entries = history::read(&self.chain_dir())
+            .map_err(|err| Error::from_str(&format!("Unable to read history: {}", err)))?;
+
+        // Commands that did not move any branch (e.g. note set) are skipped.
+        let entry = match entries.iter().rev().find(|entry| !entry.changes.is_empty()) {
+            Some(entry) => entry,
+            None => {
+                println!("Nothing to undo.");
+                return Ok(());
+            }
+        };
+        let command = format!("{} {}", self.executable_name, entry.command);
+
+        if self.dirty_working_directory()? {
+            eprintln!("🛑 Unable to undo {}", command.bold());
+            eprintln!("You have uncommitted changes in your working directory.");
+            eprintln!("Please commit or stash them.");
+            exit(1);
+        }
+
+        let mut restored_branches = vec![];
+        for (branch_name, before, after) in &entry.changes {
+            let current = if self.git_local_branch_exists(branch_name)? {
+                Some(self.get_commit_hash_of_branch(branch_name)?)
+            } else {
+                None
+            };
+            if &current != after {
+                eprintln!(
+                    "🛑 Unable to undo {}: branch {} moved since.",
+                    command.bold(),
+                    branch_name.bold()
+                );
+                exit(1);
+            }
+
+            match before {
+                Some(before) => restored_branches.push((branch_name, before)),
+                None => println!(
+                    "⚠️  Not deleting branch {}, which did not exist before.",
+                    branch_name.bold()
+                ),
+            }
+        }
+
+        // A checked out branch cannot be moved, so HEAD is detached meanwhile.
+        let current_branch = self.get_current_branch_name_if_attached()?;
+        let checked_out = restored_branches
+            .iter()
+            .any(|(branch_name, _before)| current_branch.as_deref() == Some(branch_name.as_str()));
+        if checked_out {
+            let head = self.repo.head()?.peel_to_commit()?;
+            self.repo.set_head_detached(head.id())?;
+        }
+
+        for (branch_name, before) in &restored_branches {
+            let commit = self.repo.find_commit(git2::Oid::from_str(before)?)?;
+            self.repo.branch(branch_name, &commit, true)?;
+            println!(
+                "Restored branch {} to {}",
+                branch_name.bold(),
+                before.chars().take(7).collect::<String>()
+            );
+        }
+
+        if let (true, Some(current_branch)) = (checked_out, &current_branch) {
+            self.checkout_branch(current_branch)?;
+        }
+
+        println!("✅ Undid {}", command.bold());
+        Ok(())
+    }
+
+    fn force_unlock(&self) -> Result<(), Error> {
+        lock::force_unlock(&self.chain_dir())
+            .map_err(|err| Error::from_str(&format!("Unable to remove lock: {}", err)))
+    }
+
+    // The git operation in progress, if any, and how to finish it.
+    fn operation_in_progress(&self) -> Option<(&'static str, &'static str)> {
+        let in_progress = match self.repo.state() {
+            RepositoryState::Clean => return None,
+            RepositoryState::Merge => ("merge", "git merge --continue or git merge --abort"),
+            RepositoryState::Revert | RepositoryState::RevertSequence => {
+                ("revert", "git revert --continue or git revert --abort")
+            }
+            RepositoryState::CherryPick | RepositoryState::CherryPickSequence => (
+                "cherry-pick",
+                "git cherry-pick --continue or git cherry-pick --abort",
+            ),
+            RepositoryState::Rebase
+            | RepositoryState::RebaseInteractive
+            | RepositoryState::RebaseMerge => {
+                ("rebase", "git rebase --continue or git rebase --abort")
             }
+            RepositoryState::ApplyMailbox | RepositoryState::ApplyMailboxOrRebase => {
+                ("git am", "git am --continue or git am --abort")
+            }
+            RepositoryState::Bisect => ("bisect", "git bisect reset"),
+        };
+        Some(in_progress)
+    }
+
+    fn ensure_no_operation_in_progress(&self) -> Result<(), Error> {
+        // Refuse to stack a chain operation on top of a half-finished git operation.
+        let (operation, resolution) = match self.operation_in_progress() {
+            Some(in_progress) => in_progress,
+            None => return Ok(()),
+        };
+
+        eprintln!("🛑 A {} is in progress.", operation.bold());
+        eprintln!("Finish it with {} before running this command.", resolution);
+        exit(1);
+    }
+
+    fn display_branch_not_part_of_chain_error(&self, branch_name: &str) {
+        eprintln!("❌ Branch is not part of any chain: {}", branch_name.bold());
+        eprintln!(
+            "To initialize a chain for this branch, run {} init <chain_name> <root_branch>",
+            self.executable_name
+        );
+    }
+
+    fn run_status(
+        &self,
+        chain_name: Option<&str>,
+        short: bool,
+        markdown: bool,
+        check_conflicts: bool,
+    ) -> Result<(), Error> {
+        // With --chain, HEAD may be detached.
+        let current_branch = match chain_name {
+            Some(_) => self.get_current_branch_name_if_attached()?,
+            None => Some(self.get_current_branch_name()?),
+        };
+        if let (false, false, Some(current_branch)) = (short, markdown, &current_branch) {
+            println!("On branch: {}", current_branch.bold());
+            println!();
+        }
+
+        let (chain, branch) = self.selected_branch(chain_name, None)?;
+        let current_branch = current_branch.unwrap_or_default();
+        if markdown {
+            println!("{}", chain.summary(self, &current_branch)?.to_markdown());
+        } else if short {
+            chain.display_short(self, &current_branch, check_conflicts)?;
+        } else {
+            branch.display_status(self, check_conflicts)?;
         }
 
         Ok(())
@@ -813,6 +3306,8 @@ impl GitChain {
         root_branch: &str,
         branch_name: &str,
         sort_option: SortBranch,
+        scope: Option<&str>,
+        descendants: &[String],
     ) -> Result<(), Error> {
         let results = Branch::get_branch_with_chain(self, branch_name)?;
 
@@ -820,15 +3315,48 @@ impl GitChain {
             BranchSearchResult::NotPartOfAnyChain(_) => {
                 Branch::setup_branch(self, chain_name, root_branch, branch_name, &sort_option)?;
 
+                // Descendants follow the branch in the chain, in the given order.
+                let mut prev_branch_name = branch_name;
+                for descendant in descendants {
+                    let prev_branch = match Branch::get_branch_with_chain(self, prev_branch_name)? {
+                        BranchSearchResult::Branch(prev_branch) => prev_branch,
+                        BranchSearchResult::NotPartOfAnyChain(_) => {
+                            eprintln!(
+                                "Unable to set up chain for branch: {}",
+                                prev_branch_name.bold()
+                            );
+                            exit(1);
+                        }
+                    };
+                    Branch::setup_branch(
+                        self,
+                        chain_name,
+                        root_branch,
+                        descendant,
+                        &SortBranch::After(prev_branch),
+                    )?;
+                    prev_branch_name = descendant;
+                }
+
+                if let Some(scope) = scope {
+                    Chain::get_chain(self, chain_name)?.set_scope(self, Some(scope))?;
+                }
+
                 match Branch::get_branch_with_chain(self, branch_name)? {
                     BranchSearchResult::NotPartOfAnyChain(_) => {
                         eprintln!("Unable to set up chain for branch: {}", branch_name.bold());
-                        process::exit(1);
+                        exit(1);
                     }
                     BranchSearchResult::Branch(branch) => {
                         println!("🔗 Succesfully set up branch: {}", branch_name.bold());
+                        if !descendants.is_empty() {
+                            println!(
+                                "🔗 Succesfully set up descendant branches: {}",
+                                descendants.join(", ").bold()
+                            );
+                        }
                         println!();
-                        branch.display_status(self)?;
+                        branch.display_status(self, false)?;
                     }
                 };
             }
@@ -838,13 +3366,112 @@ impl GitChain {
                 eprintln!("Branch already part of a chain: {}", branch_name.bold());
                 eprintln!("It is part of the chain: {}", branch.chain_name.bold());
                 eprintln!("With root branch: {}", branch.root_branch.bold());
-                process::exit(1);
+                exit(1);
             }
         };
 
         Ok(())
     }
 
+    fn find_descendant_branches(
+        &self,
+        branch_name: &str,
+        root_branch: &str,
+    ) -> Result<Vec<String>, Error> {
+        // Local branches with commits on top of branch_name, which are not part of any chain,
+        // ordered by their number of commits on top of it.
+        let mut descendants = vec![];
+        for local_branch in self.repo.branches(Some(BranchType::Local))? {
+            let (local_branch, _branch_type) = local_branch?;
+            let candidate = match local_branch.name()? {
+                Some(candidate) => candidate.to_string(),
+                None => continue,
+            };
+
+            if candidate == branch_name || candidate == root_branch {
+                continue;
+            }
+
+            let (ahead, _behind) = self.ahead_behind(branch_name, &candidate)?;
+            if ahead == 0 || !self.is_ancestor(branch_name, &candidate)? {
+                continue;
+            }
+
+            // e.g. branches that already landed on the root branch
+            if self.is_ancestor(&candidate, root_branch)? {
+                continue;
+            }
+
+            if let BranchSearchResult::Branch(branch) =
+                Branch::get_branch_with_chain(self, &candidate)?
+            {
+                println!(
+                    "Skipping branch {}: it is part of the chain {}",
+                    candidate.bold(),
+                    branch.chain_name.bold()
+                );
+                continue;
+            }
+
+            descendants.push((ahead, candidate));
+        }
+
+        descendants.sort();
+        let descendants: Vec<String> = descendants
+            .into_iter()
+            .map(|(_ahead, candidate)| candidate)
+            .collect();
+
+        // A chain is linear: each descendant has to be stacked on the previous one.
+        for pair in descendants.windows(2) {
+            if !self.is_ancestor(&pair[0], &pair[1])? {
+                eprintln!(
+                    "🛑 Unable to add the descendants of {} to the chain.",
+                    branch_name.bold()
+                );
+                eprintln!(
+                    "Branches {} and {} are not stacked on one another.",
+                    pair[0].bold(),
+                    pair[1].bold()
+                );
+                eprintln!(
+                    "To choose the branches of the chain, run: {} setup <chain_name> <root_branch> <branch_1> ... <branch_N>",
+                    self.executable_name
+                );
+                exit(1);
+            }
+        }
+
+        Ok(descendants)
+    }
+
+    // Once the last branch of a chain is removed from it, the chain no longer exists. What is kept
+    // for the chain as a whole, i.e. its layout in refs/chains/<chain_name> (recorded by publish and
+    // subscribe), is deleted as well, so that empty chains do not linger. Its backups are kept.
+    // Returns whether the chain was empty.
+    fn cleanup_empty_chain(&self, chain_name: &str) -> Result<bool, Error> {
+        if Chain::chain_exists(self, chain_name)? {
+            return Ok(false);
+        }
+
+        match self.repo.find_reference(&publish::chain_ref(chain_name)) {
+            Ok(mut reference) => reference.delete()?,
+            Err(ref e) if e.code() == ErrorCode::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(true)
+    }
+
+    fn report_empty_chain(&self, chain_name: &str) -> Result<(), Error> {
+        if self.cleanup_empty_chain(chain_name)? {
+            println!(
+                "Deleted chain {}, as it has no branches left.",
+                chain_name.bold()
+            );
+        }
+        Ok(())
+    }
+
     fn remove_branch_from_chain(&self, branch_name: String) -> Result<(), Error> {
         let results = Branch::get_branch_with_chain(self, &branch_name)?;
 
@@ -869,12 +3496,13 @@ impl GitChain {
                     chain_name.bold()
                 );
                 println!("Its root branch was: {}", root_branch.bold());
+                self.report_empty_chain(&chain_name)?;
             }
         };
         Ok(())
     }
 
-    fn list_chains(&self, current_branch: &str) -> Result<(), Error> {
+    fn list_chains(&self, current_branch: &str, options: &ListOptions) -> Result<(), Error> {
         let list = Chain::get_all_chains(self)?;
 
         if list.is_empty() {
@@ -886,8 +3514,16 @@ impl GitChain {
             return Ok(());
         }
 
+        if options.tree {
+            return self.display_chain_tree(&list, current_branch);
+        }
+
         for (index, chain) in list.iter().enumerate() {
-            chain.display_list(self, current_branch)?;
+            if options.markdown {
+                println!("{}", chain.summary(self, current_branch)?.to_markdown());
+            } else {
+                chain.display_list_with(self, current_branch, options)?;
+            }
 
             if index != list.len() - 1 {
                 println!();
@@ -897,29 +3533,138 @@ impl GitChain {
         Ok(())
     }
 
-    fn move_branch(
-        &self,
-        chain_name: &str,
-        branch_name: &str,
-        sort_option: &SortBranch,
-    ) -> Result<(), Error> {
+    fn display_chain_tree(&self, chains: &[Chain], current_branch: &str) -> Result<(), Error> {
+        // Chains rooted at a branch of another chain are nested under that branch; the other
+        // chains are listed under their root branch.
+        let chain_branches: HashSet<&str> = chains
+            .iter()
+            .flat_map(|chain| chain.branches.iter().chain(&chain.missing_branches))
+            .map(|branch| branch.branch_name.as_str())
+            .collect();
+
+        let mut roots: Vec<&str> = vec![];
+        for chain in chains {
+            let root = chain.root_branch.as_str();
+            if !chain_branches.contains(root) && !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut trees = vec![];
+        for root in roots {
+            let root_label = match self.fixed_root_kind(root)? {
+                None => "root branch",
+                Some(kind) => kind,
+            };
+            let label = if root == current_branch {
+                format!("➜ {} ({})", root.bold(), root_label)
+            } else {
+                format!("{} ({})", root, root_label)
+            };
+            let mut node = TreeNode::new(label);
+            node.children = self.chain_tree_nodes(chains, root, current_branch, &mut visited)?;
+            trees.push(node);
+        }
+
+        // Chains whose roots depend on each other in a cycle are not reachable from a root branch.
+        for chain in chains {
+            if !visited.contains(&chain.name) {
+                let mut node = TreeNode::new(format!("{} (root branch)", chain.root_branch));
+                node.children = self.chain_tree_nodes(
+                    chains,
+                    &chain.root_branch,
+                    current_branch,
+                    &mut visited,
+                )?;
+                trees.push(node);
+            }
+        }
+
+        for (index, tree) in trees.iter().enumerate() {
+            for line in tree.render() {
+                println!("{}", line);
+            }
+
+            if index != trees.len() - 1 {
+                println!();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn chain_tree_nodes(
+        &self,
+        chains: &[Chain],
+        parent_branch: &str,
+        current_branch: &str,
+        visited: &mut HashSet<String>,
+    ) -> Result<Vec<TreeNode>, Error> {
+        let mut nodes = vec![];
+        for chain in chains
+            .iter()
+            .filter(|chain| chain.root_branch == parent_branch)
+        {
+            if !visited.insert(chain.name.clone()) {
+                continue;
+            }
+
+            let counts = chain.ahead_behind_counts(self)?;
+            let mut chain_node = TreeNode::new(chain.name.bold().to_string());
+            for (branch, missing) in chain.display_order().into_iter().rev() {
+                let label = if missing {
+                    format!("{} {}", branch.branch_name, "(missing)".red())
+                } else {
+                    let status = Chain::display_ahead_behind(counts[&branch.branch_name]);
+                    let name = if branch.branch_name == current_branch {
+                        format!("➜ {}", branch.branch_name.bold())
+                    } else {
+                        branch.branch_name.clone()
+                    };
+                    if status.is_empty() {
+                        name
+                    } else {
+                        format!("{} ⦁ {}", name, status)
+                    }
+                };
+
+                let mut branch_node = TreeNode::new(label);
+                branch_node.children =
+                    self.chain_tree_nodes(chains, &branch.branch_name, current_branch, visited)?;
+                chain_node.children.push(branch_node);
+            }
+            nodes.push(chain_node);
+        }
+        Ok(nodes)
+    }
+
+    fn move_branch(
+        &self,
+        chain_name: &str,
+        branch_name: &str,
+        sort_option: &SortBranch,
+    ) -> Result<(), Error> {
         match Branch::get_branch_with_chain(self, branch_name)? {
             BranchSearchResult::NotPartOfAnyChain(_) => {
                 self.display_branch_not_part_of_chain_error(branch_name);
-                process::exit(1);
+                exit(1);
             }
             BranchSearchResult::Branch(branch) => {
+                let old_chain_name = branch.chain_name.clone();
                 branch.move_branch(self, chain_name, sort_option)?;
 
                 match Branch::get_branch_with_chain(self, &branch.branch_name)? {
                     BranchSearchResult::NotPartOfAnyChain(_) => {
                         eprintln!("Unable to move branch: {}", branch.branch_name.bold());
-                        process::exit(1);
+                        exit(1);
                     }
                     BranchSearchResult::Branch(branch) => {
                         println!("🔗 Succesfully moved branch: {}", branch.branch_name.bold());
+                        // The branch may be moved out of its chain into another chain.
+                        self.report_empty_chain(&old_chain_name)?;
                         println!();
-                        branch.display_status(self)?;
+                        branch.display_status(self, false)?;
                     }
                 };
             }
@@ -928,1384 +3673,9877 @@ impl GitChain {
         Ok(())
     }
 
-    fn get_commit_hash_of_head(&self) -> Result<String, Error> {
-        let head = self.repo.head()?;
-        let oid = head.target().unwrap();
-        let commit = self.repo.find_commit(oid).unwrap();
-        Ok(commit.id().to_string())
-    }
-
-    fn get_tree_id_from_branch_name(&self, branch_name: &str) -> Result<String, Error> {
-        // tree_id = git rev-parse branch_name^{tree}
-        // let output = Command::new("git")
-        //     .arg("rev-parse")
-        //     .arg(format!("{}^{{tree}}", branch_name))
-        //     .output()
-        //     .unwrap_or_else(|_| panic!("Unable to get tree id of branch {}", branch_name.bold()));
-
-        // if output.status.success() {
-        //     let raw_output = String::from_utf8(output.stdout).unwrap();
-        //     let tree_id = raw_output.trim().to_string();
-        //     return Ok(tree_id);
-        // }
-
-        // return Err(Error::from_str(&format!(
-        //     "Unable to get tree id of branch {}",
-        //     branch_name.bold()
-        // )));
-
-        match self
-            .repo
-            .revparse_single(&format!("{}^{{tree}}", branch_name))
-        {
-            Ok(tree_object) => {
-                assert_eq!(tree_object.kind().unwrap(), ObjectType::Tree);
-                Ok(tree_object.id().to_string())
-            }
-            Err(_err) => Err(Error::from_str(&format!(
-                "Unable to get tree id of branch {}",
-                branch_name.bold()
-            ))),
+    fn remove_chain(&self, chain_name: &str, dry_run: bool) -> Result<(), Error> {
+        if !Chain::chain_exists(self, chain_name)? {
+            println!(
+                "Unable to delete chain that does not exist: {}",
+                chain_name.bold()
+            );
+            println!("Nothing to do.");
+            return Ok(());
         }
-    }
-
-    fn is_squashed_merged(
-        &self,
-        common_ancestor: &str,
-        parent_branch: &str,
-        current_branch: &str,
-    ) -> Result<bool, Error> {
-        // References:
-        // https://blog.takanabe.tokyo/en/2020/04/remove-squash-merged-local-git-branches/
-        // https://github.com/not-an-aardvark/git-delete-squashed
-
-        // common_ancestor should be pre-computed beforehand, ideally with self.merge_base_fork_point()
-        // common_ancestor is commit sha
-
-        // tree_id = git rev-parse current_branch^{tree}
-        let tree_id = self.get_tree_id_from_branch_name(current_branch)?;
-
-        // dangling_commit_id = git commit-tree tree_id -p common_ancestor -m "Temp commit for checking is_squashed_merged for branch current_branch"
-        let output = Command::new("git")
-            .arg("commit-tree")
-            .arg(&tree_id)
-            .arg("-p")
-            .arg(common_ancestor)
-            .arg("-m")
-            .arg(format!(
-                "Temp commit for checking is_squashed_merged for branch {}",
-                current_branch
-            ))
-            .output()
-            .unwrap_or_else(|_| {
-                panic!(
-                    "Unable to generate commit-tree of branch {}",
-                    current_branch.bold()
-                )
-            });
-
-        let dangling_commit_id = if output.status.success() {
-            let raw_output = String::from_utf8(output.stdout).unwrap();
-            let dangling_commit_id = raw_output.trim().to_string();
-            dangling_commit_id
-        } else {
-            return Err(Error::from_str(&format!(
-                "Unable to generate commit-tree of branch {}",
-                current_branch.bold()
-            )));
-        };
 
-        // output = git cherry parent_branch dangling_commit_id
-        let output = Command::new("git")
-            .arg("cherry")
-            .arg(parent_branch)
-            .arg(&dangling_commit_id)
-            .output()
-            .unwrap_or_else(|_| {
-                panic!(
-                    "Unable to determine if branch {} was squashed and merged into {}",
-                    current_branch.bold(),
-                    parent_branch.bold()
-                )
-            });
+        let chain = Chain::get_chain(self, chain_name)?;
+        let num_of_branches = chain.branches.len() + chain.missing_branches.len();
 
-        let cherry_output = if output.status.success() {
-            let raw_output = String::from_utf8(output.stdout).unwrap();
-            raw_output.trim().to_string()
-        } else {
-            return Err(Error::from_str(&format!(
-                "Unable to determine if branch {} was squashed and merged into {}",
-                current_branch.bold(),
-                parent_branch.bold()
-            )));
-        };
+        println!(
+            "The following branches will be removed from chain {} (the branches themselves are kept):",
+            chain_name.bold()
+        );
+        println!();
+        for branch in &chain.branches {
+            println!("{}", branch.branch_name);
+        }
+        for branch in &chain.missing_branches {
+            println!("{} (missing)", branch.branch_name);
+        }
 
-        let lines: Vec<String> = cherry_output.lines().map(|x| x.to_string()).collect();
-        if lines.is_empty() {
-            return Ok(true);
+        println!();
+        println!("The following chain metadata will be removed from the git config:");
+        println!();
+        for branch in chain.branches.iter().chain(&chain.missing_branches) {
+            for key in Branch::config_keys(&branch.branch_name) {
+                if let Some(value) = self.get_git_config(&key)? {
+                    println!("{} = {}", key, value);
+                }
+            }
         }
+        println!();
 
-        if lines.len() == 1 {
-            // check if output is a single line containing "- dangling_commit_id"
-            let line = &lines[0].trim();
-            let is_squashed_merged = line.starts_with(&format!("- {}", dangling_commit_id));
-            return Ok(is_squashed_merged);
+        if dry_run {
+            println!("{}", "This was a dry-run, no chain deleted!".bold());
+            return Ok(());
         }
 
-        for line in lines {
-            if line.trim().starts_with('-') {
-                continue;
+        let question = format!(
+            "Delete chain {} and remove its {} {} from it?",
+            chain_name.bold(),
+            num_of_branches,
+            if num_of_branches == 1 {
+                "branch"
             } else {
-                return Ok(false);
+                "branches"
             }
+        );
+        if !self.confirm(&question)? {
+            println!("Chain not deleted: {}", chain_name.bold());
+            return Ok(());
         }
 
-        Ok(true)
+        chain.delete(self)?;
+        self.cleanup_empty_chain(chain_name)?;
+        println!("Successfully deleted chain: {}", chain_name.bold());
+
+        Ok(())
     }
 
-    fn rebase(&self, chain_name: &str, step_rebase: bool, ignore_root: bool) -> Result<(), Error> {
-        // invariant: chain_name chain exists
+    fn adopt(&self, chain_name: &str, branch_name: &str) -> Result<(), Error> {
         let chain = Chain::get_chain(self, chain_name)?;
 
-        // ensure root branch exists
-        if !self.git_branch_exists(&chain.root_branch)? {
-            eprintln!("Root branch does not exist: {}", chain.root_branch.bold());
-            process::exit(1);
+        if !self.git_local_branch_exists(branch_name)?
+            && !self.create_local_branch_from_remote(branch_name)?
+        {
+            eprintln!("Branch does not exist: {}", branch_name.bold());
+            exit(1);
         }
 
-        // ensure each branch exists
-        for branch in &chain.branches {
-            if !self.git_local_branch_exists(&branch.branch_name)? {
-                eprintln!("Branch does not exist: {}", branch.branch_name.bold());
-                process::exit(1);
-            }
+        if branch_name == chain.root_branch {
+            eprintln!(
+                "Branch being added to the chain cannot be the root branch: {}",
+                branch_name.bold()
+            );
+            exit(1);
         }
 
-        // ensure repository is in a clean state
-        match self.repo.state() {
-            RepositoryState::Clean => {
-                // go ahead to rebase.
-            }
-            _ => {
-                eprintln!("🛑 Repository needs to be in a clean state before rebasing.");
-                process::exit(1);
-            }
+        if let BranchSearchResult::Branch(branch) =
+            Branch::get_branch_with_chain(self, branch_name)?
+        {
+            eprintln!("🛑 Unable to adopt branch: {}", branch_name.bold());
+            eprintln!(
+                "It is already part of the chain: {}",
+                branch.chain_name.bold()
+            );
+            eprintln!(
+                "To move it to another chain, run: {} move --chain={}",
+                self.executable_name, chain_name
+            );
+            exit(1);
         }
 
-        if self.dirty_working_directory()? {
+        if let Some(branch) = chain.missing_branches.first() {
+            eprintln!("🛑 Branch does not exist: {}", branch.branch_name.bold());
             eprintln!(
-                "🛑 Unable to rebase branches for the chain: {}",
-                chain.name.bold()
+                "To remove the missing branches from the chain, run: {} prune",
+                self.executable_name
             );
-            eprintln!("You have uncommitted changes in your working directory.");
-            eprintln!("Please commit or stash them.");
-            process::exit(1);
+            exit(1);
         }
 
-        let orig_branch = self.get_current_branch_name()?;
-
-        let root_branch = chain.root_branch;
-
-        // List of common ancestors between each branch and its parent branch.
-        // For the first branch, a common ancestor is generated between it and the root branch.
-        //
-        // The following command is used to generate the common ancestors:
-        // git merge-base --fork-point <ancestor_branch> <descendant_branch>
-        let mut common_ancestors = vec![];
+        // A branch that a branch of the chain is stacked on goes right before it.
+        let mut sort_option = None;
+        for branch in &chain.branches {
+            if self.is_ancestor(branch_name, &branch.branch_name)?
+                && !self.is_ancestor(&branch.branch_name, branch_name)?
+            {
+                sort_option = Some(SortBranch::Before(branch.clone()));
+                break;
+            }
+        }
 
-        for (index, branch) in chain.branches.iter().enumerate() {
-            if index == 0 {
-                let common_point = self.smart_merge_base(&root_branch, &branch.branch_name)?;
-                common_ancestors.push(common_point);
-                continue;
+        // Otherwise, it goes after the branch it forked from most recently: the branch of the
+        // chain whose merge-base with it is the latest. On a tie, the earliest branch wins, as the
+        // branches after it only share the commits they are stacked on.
+        let sort_option = match sort_option {
+            Some(sort_option) => sort_option,
+            None => {
+                let mut parent: Option<&Branch> = None;
+                let mut latest_merge_base = match self.merge_base(&chain.root_branch, branch_name) {
+                    Ok(merge_base) => merge_base,
+                    Err(_) => {
+                        eprintln!("🛑 Unable to adopt branch: {}", branch_name.bold());
+                        eprintln!(
+                            "It has no common history with the root branch {} of the chain.",
+                            chain.root_branch.bold()
+                        );
+                        exit(1);
+                    }
+                };
+                for branch in &chain.branches {
+                    let merge_base = self.merge_base(&branch.branch_name, branch_name)?;
+                    if merge_base != latest_merge_base
+                        && self.is_ancestor(&latest_merge_base, &merge_base)?
+                    {
+                        parent = Some(branch);
+                        latest_merge_base = merge_base;
+                    }
+                }
+                match parent {
+                    Some(parent) => SortBranch::After(parent.clone()),
+                    None => SortBranch::First,
+                }
             }
+        };
 
-            let prev_branch = &chain.branches[index - 1];
+        Branch::setup_branch(
+            self,
+            chain_name,
+            &chain.root_branch,
+            branch_name,
+            &sort_option,
+        )?;
 
-            let common_point =
-                self.smart_merge_base(&prev_branch.branch_name, &branch.branch_name)?;
-            common_ancestors.push(common_point);
-        }
+        let position = match &sort_option {
+            SortBranch::Before(branch) => format!("before {}", branch.branch_name.bold()),
+            SortBranch::After(branch) => format!("after {}", branch.branch_name.bold()),
+            _ => "as its first branch".to_string(),
+        };
+        println!(
+            "🔗 Succesfully adopted branch {} into chain {} {}",
+            branch_name.bold(),
+            chain_name.bold(),
+            position
+        );
 
-        assert_eq!(chain.branches.len(), common_ancestors.len());
+        let chain = Chain::get_chain(self, chain_name)?;
+        let parent_branch_name = match Branch::get_branch_with_chain(self, branch_name)? {
+            BranchSearchResult::Branch(branch) => chain.parent_branch_name(&branch),
+            BranchSearchResult::NotPartOfAnyChain(_) => {
+                eprintln!("Unable to adopt branch: {}", branch_name.bold());
+                exit(1);
+            }
+        };
+        if !self.is_ancestor(&parent_branch_name, branch_name)? {
+            println!(
+                "⚠️  Branch {} is not based on the tip of {}. To stack it, run: {} rebase",
+                branch_name.bold(),
+                parent_branch_name.bold(),
+                self.executable_name
+            );
+        }
+        println!();
 
-        let mut num_of_rebase_operations = 0;
-        let mut num_of_branches_visited = 0;
+        let current_branch = self
+            .get_current_branch_name_if_attached()?
+            .unwrap_or_default();
+        chain.display_list(self, &current_branch)?;
 
-        for (index, branch) in chain.branches.iter().enumerate() {
-            if step_rebase && num_of_rebase_operations == 1 {
-                // performed at most one rebase.
-                break;
-            }
+        Ok(())
+    }
 
-            num_of_branches_visited += 1;
+    // Notify the command set in chain.notifyCommand, and the URL set in chain.notifyUrl, of an
+    // event of the chain.
+    fn notify(&self, chain_name: &str, event: notify::Event) -> Result<(), Error> {
+        let command = self.get_setting("GIT_CHAIN_NOTIFY_COMMAND", "chain.notifyCommand")?;
+        let url = self.get_setting("GIT_CHAIN_NOTIFY_URL", "chain.notifyUrl")?;
+        if command.is_none() && url.is_none() {
+            return Ok(());
+        }
 
-            let prev_branch_name = if index == 0 {
-                &root_branch
+        let chain = Chain::get_chain(self, chain_name)?;
+        let mut branches = vec![];
+        for (branch, missing) in chain.display_order().into_iter().rev() {
+            let commit = if missing {
+                None
             } else {
-                &chain.branches[index - 1].branch_name
+                Some(self.get_commit_hash_of_branch(&branch.branch_name)?)
             };
-
-            if index == 0 && ignore_root {
-                // Skip the rebase operation for the first branch of the chain.
-                // Essentially, we do not rebase the first branch against the root branch.
-                println!();
-                println!(
-                    "⚠️  Not rebasing branch {} against root branch {}. Skipping.",
-                    &branch.branch_name.bold(),
-                    prev_branch_name.bold()
+            branches.push(notify::BranchState {
+                parent: chain.parent_branch_name(&branch),
+                name: branch.branch_name,
+                commit,
+            });
+        }
+        let payload = notify::payload(
+            &event,
+            &notify::ChainState {
+                name: chain.name.clone(),
+                root_branch: chain.root_branch.clone(),
+                branches,
+            },
+        );
+
+        if let Some(command) = command {
+            if let Err(message) = notify::run_command(&command, &event, &payload) {
+                eprintln!(
+                    "⚠️  Unable to notify {} of {}: {}",
+                    command.bold(),
+                    event.name(),
+                    message
                 );
-                continue;
             }
+        }
+        if let Some(url) = url {
+            if let Err(message) = notify::post(&url, &payload) {
+                eprintln!(
+                    "⚠️  Unable to notify {} of {}: {}",
+                    url.bold(),
+                    event.name(),
+                    message
+                );
+            }
+        }
 
-            // git rebase --onto <onto> <upstream> <branch>
-            // git rebase --onto parent_branch fork_point branch.name
+        Ok(())
+    }
 
-            self.checkout_branch(&branch.branch_name)?;
+    fn report_non_utf8_config(&self, key: &[u8]) {
+        let key = names::display_bytes(key);
+        if metadata::report_key_once(&key) {
+            eprintln!(
+                "⚠️  Ignoring git config {}: {}",
+                key.bold(),
+                names::BRANCH_NAME_ENCODING
+            );
+        }
+    }
 
-            let before_sha1 = self.get_commit_hash_of_head()?;
+    fn report_metadata_problems(&self, chain_name: &str, problems: &[metadata::Problem]) {
+        if !metadata::report_once(chain_name) {
+            return;
+        }
 
-            let common_point = &common_ancestors[index];
+        eprintln!(
+            "⚠️  Found problems in the metadata of chain {}:",
+            chain_name.bold()
+        );
+        for problem in problems {
+            eprintln!("  - {}", problem.describe());
+        }
+        eprintln!(
+            "To repair them, run: {} doctor --chain {}",
+            self.executable_name, chain_name
+        );
+    }
 
-            // check if current branch is squashed merged to prev_branch_name
-            if self.is_squashed_merged(common_point, prev_branch_name, &branch.branch_name)? {
-                println!();
-                println!(
-                    "⚠️  Branch {} is detected to be squashed and merged onto {}.",
-                    &branch.branch_name.bold(),
-                    prev_branch_name.bold()
-                );
+    // Chains without problems are only mentioned when verbose.
+    fn repair_chains(&self, chain_name: Option<&str>, verbose: bool) -> Result<(), Error> {
+        let mut chain_names: Vec<String> = match chain_name {
+            Some(chain_name) => vec![chain_name.to_string()],
+            None => Chain::get_all_branch_configs(self)?
+                .into_iter()
+                .map(|(_key, chain_name)| chain_name)
+                .collect(),
+        };
+        chain_names.sort();
+        chain_names.dedup();
 
-                let command = format!("git reset --hard {}", &prev_branch_name);
+        if chain_names.is_empty() {
+            println!("No chains to repair.");
+            return Ok(());
+        }
 
-                // git reset --hard <prev_branch_name>
-                let output = Command::new("git")
-                    .arg("reset")
-                    .arg("--hard")
-                    .arg(prev_branch_name)
-                    .output()
-                    .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+        for chain_name in &chain_names {
+            self.repair_chain(chain_name, verbose)?;
+        }
 
-                if !output.status.success() {
-                    eprintln!("Unable to run: {}", &command);
-                    process::exit(1);
-                }
+        Ok(())
+    }
 
-                println!(
-                    "Resetting branch {} to {}",
-                    &branch.branch_name.bold(),
-                    prev_branch_name.bold()
-                );
-                println!("{}", command);
+    fn repair_chain(&self, chain_name: &str, verbose: bool) -> Result<(), Error> {
+        let (branches, missing_branches, problems) =
+            Chain::get_all_branches_for_chain(self, chain_name)?;
 
-                continue;
+        if problems.is_empty() {
+            if verbose {
+                println!("✅ The metadata of chain {} is fine.", chain_name.bold());
             }
+            return Ok(());
+        }
 
-            let command = format!(
-                "git rebase --keep-empty --onto {} {} {}",
-                &prev_branch_name, common_point, &branch.branch_name
-            );
-
-            let output = Command::new("git")
-                .arg("rebase")
-                .arg("--keep-empty")
-                .arg("--onto")
-                .arg(prev_branch_name)
-                .arg(common_point)
-                .arg(&branch.branch_name)
-                .output()
-                .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
-
-            println!();
-            println!("{}", command);
-
-            // ensure repository is in a clean state
-            match self.repo.state() {
-                RepositoryState::Clean => {
-                    if !output.status.success() {
-                        eprintln!("Command returned non-zero exit status: {}", command);
-                        eprintln!("It returned: {}", output.status.code().unwrap());
-                        io::stdout().write_all(&output.stdout).unwrap();
-                        io::stderr().write_all(&output.stderr).unwrap();
-                        process::exit(1);
-                    }
-                    io::stdout().write_all(&output.stdout).unwrap();
-                    io::stderr().write_all(&output.stderr).unwrap();
+        println!(
+            "Found problems in the metadata of chain {}:",
+            chain_name.bold()
+        );
+        for problem in &problems {
+            println!("  - {}", problem.describe());
+        }
 
-                    let after_sha1 = self.get_commit_hash_of_head()?;
+        let root_branch = match branches.first().or_else(|| missing_branches.first()) {
+            Some(branch) => branch.root_branch.clone(),
+            None => {
+                eprintln!(
+                    "🛑 Unable to repair chain {}: none of its branches has a root branch.",
+                    chain_name.bold()
+                );
+                let branch_name = problems[0].branch_names()[0];
+                eprintln!(
+                    "To set it, run: git config {} <root_branch>",
+                    root_branch_key(branch_name)
+                );
+                exit(1);
+            }
+        };
 
-                    if before_sha1 != after_sha1 {
-                        num_of_rebase_operations += 1;
-                    }
-                    // go ahead to rebase next branch.
+        let mut renumber = false;
+        let mut unordered_branches: Vec<String> = vec![];
+        for problem in &problems {
+            match problem {
+                metadata::Problem::MultipleValues { key, values, .. } => {
+                    self.replace_git_config(key, values.last().unwrap())?;
                 }
-                _ => {
-                    print_rebase_error(
-                        &self.executable_name,
-                        &branch.branch_name,
-                        prev_branch_name,
-                    );
-                    process::exit(1);
+                metadata::Problem::MissingKey { branch_name, key }
+                    if key == &chain_order_key(branch_name) =>
+                {
+                    unordered_branches.push(branch_name.clone());
+                    renumber = true;
+                }
+                metadata::Problem::MissingKey { key, .. }
+                | metadata::Problem::RootMismatch { key, .. } => {
+                    self.set_git_config(key, &root_branch)?;
+                }
+                metadata::Problem::InvalidChainOrder { .. }
+                | metadata::Problem::DuplicateChainOrder { .. } => {
+                    renumber = true;
                 }
             }
         }
 
-        let current_branch = self.get_current_branch_name()?;
-
-        if current_branch != orig_branch {
-            println!();
-            println!("Switching back to branch: {}", orig_branch.bold());
-            self.checkout_branch(&orig_branch)?;
+        if renumber {
+            // Branches without a sort key are added back at the end of the chain.
+            let mut ordered_branches: Vec<&Branch> =
+                branches.iter().chain(missing_branches.iter()).collect();
+            ordered_branches
+                .sort_by_key(|branch| (branch.chain_order.clone(), branch.branch_name.clone()));
+            let branch_names: Vec<&str> = ordered_branches
+                .iter()
+                .map(|branch| branch.branch_name.as_str())
+                .chain(unordered_branches.iter().map(String::as_str))
+                .collect();
+            let chain_orders = spread_chain_orders(branch_names.len());
+            for (branch_name, chain_order) in branch_names.iter().zip(chain_orders.iter()) {
+                self.set_git_config(&chain_order_key(branch_name), chain_order)?;
+            }
         }
 
-        println!();
-        if step_rebase
-            && num_of_rebase_operations == 1
-            && num_of_branches_visited != chain.branches.len()
-        {
-            println!("Performed one rebase on branch: {}", current_branch.bold());
-            println!();
+        println!("🔗 Repaired the metadata of chain {}", chain_name.bold());
+        for branch_name in &unordered_branches {
             println!(
-                "To continue rebasing, run {} rebase --step",
+                "Branch {} was added back as the last branch of the chain. To move it, run: {} move",
+                branch_name.bold(),
                 self.executable_name
             );
-
-            return Ok(());
-        }
-
-        if ignore_root {
-            println!(
-                "⚠️ Did not rebase chain against root branch: {}",
-                root_branch.bold()
-            );
-        }
-        if num_of_rebase_operations > 0 {
-            println!("🎉 Successfully rebased chain {}", chain.name.bold());
-        } else {
-            println!("Chain {} is already up-to-date.", chain.name.bold());
         }
 
         Ok(())
     }
 
-    fn dirty_working_directory(&self) -> Result<bool, Error> {
-        // perform equivalent to git diff-index HEAD
-        let obj = self.repo.revparse_single("HEAD")?;
-        let tree = obj.peel(ObjectType::Tree)?;
-
-        // This is used for diff formatting for diff-index. But we're only interested in the diff stats.
-        // let mut opts = DiffOptions::new();
-        // opts.id_abbrev(40);
+    fn renumber_chains(&self, chain_name: Option<&str>) -> Result<(), Error> {
+        let chains = match chain_name {
+            Some(chain_name) => vec![Chain::get_chain(self, chain_name)?],
+            None => Chain::get_all_chains(self)?,
+        };
 
-        let diff = self
-            .repo
-            .diff_tree_to_workdir_with_index(tree.as_tree(), None)?;
+        if chains.is_empty() {
+            println!("No chains to renumber.");
+            return Ok(());
+        }
 
-        let diff_stats = diff.stats()?;
-        let has_changes = diff_stats.files_changed() > 0
-            || diff_stats.insertions() > 0
-            || diff_stats.deletions() > 0;
+        for chain in &chains {
+            if chain.renumber(self)? {
+                println!(
+                    "🔗 Renumbered the sort keys of the {} branches of chain {}",
+                    chain.branches.len(),
+                    chain.name.bold()
+                );
+            } else {
+                println!(
+                    "The sort keys of chain {} are already evenly spread.",
+                    chain.name.bold()
+                );
+            }
+        }
 
-        Ok(has_changes)
+        Ok(())
     }
 
-    fn backup(&self, chain_name: &str) -> Result<(), Error> {
-        if Chain::chain_exists(self, chain_name)? {
-            let chain = Chain::get_chain(self, chain_name)?;
+    fn reorder_topological(&self, chain_name: &str) -> Result<(), Error> {
+        let chain = Chain::get_chain(self, chain_name)?;
 
-            // ensure repository is in a clean state
-            match self.repo.state() {
-                RepositoryState::Clean => {
-                    // go ahead to back up chain.
-                }
-                _ => {
-                    eprintln!(
-                        "🛑 Repository needs to be in a clean state before backing up chain: {}",
-                        chain_name
-                    );
-                    process::exit(1);
+        if let Some(branch) = chain.missing_branches.first() {
+            eprintln!("🛑 Branch does not exist: {}", branch.branch_name.bold());
+            eprintln!(
+                "To remove the missing branches from the chain, run: {} prune",
+                self.executable_name
+            );
+            exit(1);
+        }
+
+        // Each branch is sorted after the branches of the chain that are its ancestors.
+        // Branches pointing at the same commit keep their current order.
+        let mut branches = vec![];
+        for branch in &chain.branches {
+            let mut num_of_ancestors = 0;
+            for other_branch in &chain.branches {
+                if other_branch.branch_name != branch.branch_name
+                    && self.is_ancestor(&other_branch.branch_name, &branch.branch_name)?
+                    && !self.is_ancestor(&branch.branch_name, &other_branch.branch_name)?
+                {
+                    num_of_ancestors += 1;
                 }
             }
+            branches.push((num_of_ancestors, branch.clone()));
+        }
+        branches.sort_by_key(|(num_of_ancestors, _branch)| *num_of_ancestors);
+        let branches: Vec<Branch> = branches.into_iter().map(|(_, branch)| branch).collect();
 
-            if self.dirty_working_directory()? {
+        // A chain is linear: each branch has to be stacked on the previous one.
+        for pair in branches.windows(2) {
+            if !self.is_ancestor(&pair[0].branch_name, &pair[1].branch_name)? {
+                eprintln!("🛑 Unable to reorder chain: {}", chain_name.bold());
                 eprintln!(
-                    "🛑 Unable to back up branches for the chain: {}",
-                    chain.name.bold()
+                    "Branches {} and {} are not stacked on one another.",
+                    pair[0].branch_name.bold(),
+                    pair[1].branch_name.bold()
                 );
-                eprintln!("You have uncommitted changes in your working directory.");
-                eprintln!("Please commit or stash them.");
-                process::exit(1);
+                eprintln!(
+                    "To move a branch of the chain, run: {} move --before=<branch_name> or {} move --after=<branch_name>",
+                    self.executable_name, self.executable_name
+                );
+                exit(1);
             }
+        }
 
-            let orig_branch = self.get_current_branch_name()?;
+        let current_branch = self.get_current_branch_name()?;
 
-            chain.backup(self)?;
+        let unchanged = branches
+            .iter()
+            .zip(chain.branches.iter())
+            .all(|(branch, other_branch)| branch.branch_name == other_branch.branch_name);
+        if unchanged {
+            println!(
+                "Chain {} is already in topological order.",
+                chain_name.bold()
+            );
+            return Ok(());
+        }
 
-            let current_branch = self.get_current_branch_name()?;
+        // The new sort keys are generated from scratch, in order.
+        let chain_orders = spread_chain_orders(branches.len());
+        for (branch, chain_order) in branches.iter().zip(chain_orders.iter()) {
+            self.set_git_config(&chain_order_key(&branch.branch_name), chain_order)?;
+        }
 
-            if current_branch != orig_branch {
-                println!("Switching back to branch: {}", orig_branch.bold());
-                self.checkout_branch(&orig_branch)?;
-            }
+        println!(
+            "🔗 Succesfully reordered chain {} to match the ancestry of its branches.",
+            chain_name.bold()
+        );
+        println!();
+
+        let chain = Chain::get_chain(self, chain_name)?;
+        chain.display_list(self, &current_branch)?;
 
-            println!("🎉 Successfully backed up chain: {}", chain.name.bold());
-        } else {
-            eprintln!("Unable to back up chain.");
-            eprintln!("Chain does not exist: {}", chain_name);
-            process::exit(1);
-        }
         Ok(())
     }
 
-    fn push(&self, chain_name: &str, force_push: bool) -> Result<(), Error> {
-        if Chain::chain_exists(self, chain_name)? {
-            let chain = Chain::get_chain(self, chain_name)?;
+    fn get_commit_hash_of_head(&self) -> Result<String, Error> {
+        let head = self.repo.head()?;
+        let oid = head.target().unwrap();
+        let commit = self.repo.find_commit(oid).unwrap();
+        Ok(commit.id().to_string())
+    }
 
-            let branches_pushed = chain.push(self, force_push)?;
+    fn get_commit_hash_of_branch(&self, branch_name: &str) -> Result<String, Error> {
+        let (object, _reference) = self.repo.revparse_ext(branch_name)?;
+        let commit = object.peel_to_commit()?;
+        Ok(commit.id().to_string())
+    }
 
-            println!("Pushed {} branches.", format!("{}", branches_pushed).bold());
-        } else {
-            eprintln!("Unable to push branches of the chain.");
-            eprintln!("Chain does not exist: {}", chain_name);
-            process::exit(1);
+    fn get_tree_id_from_branch_name(&self, branch_name: &str) -> Result<String, Error> {
+        // tree_id = git rev-parse branch_name^{tree}
+        // let output = Command::new("git")
+        //     .arg("rev-parse")
+        //     .arg(format!("{}^{{tree}}", branch_name))
+        //     .output()
+        //     .unwrap_or_else(|_| panic!("Unable to get tree id of branch {}", branch_name.bold()));
+
+        // if output.status.success() {
+        //     let raw_output = String::from_utf8(output.stdout).unwrap();
+        //     let tree_id = raw_output.trim().to_string();
+        //     return Ok(tree_id);
+        // }
+
+        // return Err(Error::from_str(&format!(
+        //     "Unable to get tree id of branch {}",
+        //     branch_name.bold()
+        // )));
+
+        match self
+            .repo
+            .revparse_single(&format!("{}^{{tree}}", branch_name))
+        {
+            Ok(tree_object) => {
+                assert_eq!(tree_object.kind().unwrap(), ObjectType::Tree);
+                Ok(tree_object.id().to_string())
+            }
+            Err(_err) => Err(Error::from_str(&format!(
+                "Unable to get tree id of branch {}",
+                branch_name.bold()
+            ))),
         }
-        Ok(())
     }
 
-    fn prune(&self, chain_name: &str, dry_run: bool) -> Result<(), Error> {
-        if Chain::chain_exists(self, chain_name)? {
-            let chain = Chain::get_chain(self, chain_name)?;
+    fn is_squashed_merged(
+        &self,
+        common_ancestor: &str,
+        parent_branch: &str,
+        current_branch: &str,
+        limit: Option<&str>,
+    ) -> Result<bool, Error> {
+        if self.is_squashed_merged_by_patch_id(
+            common_ancestor,
+            parent_branch,
+            current_branch,
+            limit,
+        )? {
+            return Ok(true);
+        }
 
-            let pruned_branches = chain.prune(self, dry_run)?;
-            if !pruned_branches.is_empty() {
-                println!(
-                    "Removed the following branches from chain: {}",
-                    chain_name.bold()
-                );
-                println!();
+        // The patch-id of an edited squash commit (e.g. with a fixup or a changelog entry added
+        // when merging) no longer matches the changes of the branch.
+        let detect_edited_squash = self
+            .get_bool_setting("GIT_CHAIN_DETECT_EDITED_SQUASH", "chain.detectEditedSquash")?
+            .unwrap_or(false);
+        if !detect_edited_squash {
+            return Ok(false);
+        }
 
-                for branch in &pruned_branches {
-                    println!("{}", branch);
-                }
+        self.is_squashed_merged_by_cumulative_diff(common_ancestor, parent_branch, current_branch)
+    }
 
-                println!();
-                println!(
-                    "Pruned {} branches.",
-                    format!("{}", pruned_branches.len()).bold()
-                );
+    fn is_squashed_merged_by_cumulative_diff(
+        &self,
+        common_ancestor: &str,
+        parent_branch: &str,
+        current_branch: &str,
+    ) -> Result<bool, Error> {
+        // A branch without changes of its own is not squashed and merged.
+        let tree_id = self.get_tree_id_from_branch_name(current_branch)?;
+        if tree_id == self.get_tree_id_from_branch_name(common_ancestor)? {
+            return Ok(false);
+        }
 
-                if dry_run {
-                    println!();
-                    println!("{}", "This was a dry-run, no branches pruned!".bold());
-                }
-            } else if dry_run {
-                println!(
-                    "This was a dry-run, no branches pruned for chain: {}",
-                    chain_name.bold()
-                );
-            } else {
-                println!("No branches pruned for chain: {}", chain_name.bold());
-            }
-        } else {
-            eprintln!("Unable to prune branches of the chain.");
-            eprintln!("Chain does not exist: {}", chain_name);
-            process::exit(1);
+        // Merging the branch into its parent branch changes nothing if all of the changes of the
+        // branch are already in the parent branch, whatever else the squash commit changed.
+        // git merge-tree --write-tree parent_branch current_branch
+        let output = Command::new("git")
+            .arg("merge-tree")
+            .arg("--write-tree")
+            .arg(parent_branch)
+            .arg(current_branch)
+            .logged_output()
+            .unwrap_or_else(|_| {
+                panic!(
+                    "Unable to run: git merge-tree --write-tree {} {}",
+                    parent_branch, current_branch
+                )
+            });
+
+        // Conflicts (exit status 1), or a version of git without --write-tree.
+        if !output.status.success() {
+            debug_log!(
+                "git merge-tree of {} into {} did not merge cleanly",
+                current_branch,
+                parent_branch
+            );
+            return Ok(false);
         }
-        Ok(())
+
+        let merged_tree_id = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        Ok(merged_tree_id == self.get_tree_id_from_branch_name(parent_branch)?)
     }
 
-    fn smart_merge_base(
+    fn is_squashed_merged_by_patch_id(
         &self,
-        ancestor_branch: &str,
-        descendant_branch: &str,
-    ) -> Result<String, Error> {
-        if self.is_ancestor(ancestor_branch, descendant_branch)? {
-            // Can "fast forward" from ancestor_branch to descendant_branch
-            return self.merge_base(ancestor_branch, descendant_branch);
-        }
-        self.merge_base_fork_point(ancestor_branch, descendant_branch)
-    }
+        common_ancestor: &str,
+        parent_branch: &str,
+        current_branch: &str,
+        limit: Option<&str>,
+    ) -> Result<bool, Error> {
+        // References:
+        // https://blog.takanabe.tokyo/en/2020/04/remove-squash-merged-local-git-branches/
+        // https://github.com/not-an-aardvark/git-delete-squashed
 
-    fn merge_base(&self, ancestor_branch: &str, descendant_branch: &str) -> Result<String, Error> {
-        // git merge-base <ancestor_branch> <descendant_branch>
+        // common_ancestor should be pre-computed beforehand, ideally with self.merge_base_fork_point()
+        // common_ancestor is commit sha
+
+        // tree_id = git rev-parse current_branch^{tree}
+        let tree_id = self.get_tree_id_from_branch_name(current_branch)?;
 
+        // dangling_commit_id = git commit-tree tree_id -p common_ancestor -m "Temp commit for checking is_squashed_merged for branch current_branch"
         let output = Command::new("git")
-            .arg("merge-base")
-            .arg(ancestor_branch)
-            .arg(descendant_branch)
-            .output()
+            .arg("commit-tree")
+            .arg(&tree_id)
+            .arg("-p")
+            .arg(common_ancestor)
+            .arg("-m")
+            .arg(format!(
+                "Temp commit for checking is_squashed_merged for branch {}",
+                current_branch
+            ))
+            .logged_output()
             .unwrap_or_else(|_| {
                 panic!(
-                    "Unable to run: git merge-base {} {}",
-                    ancestor_branch.bold(),
-                    descendant_branch.bold()
+                    "Unable to generate commit-tree of branch {}",
+                    current_branch.bold()
                 )
             });
 
-        if output.status.success() {
+        let dangling_commit_id = if output.status.success() {
             let raw_output = String::from_utf8(output.stdout).unwrap();
-            let common_point = raw_output.trim().to_string();
-            return Ok(common_point);
-        }
-        Err(Error::from_str(&format!(
-            "Unable to get common ancestor of {} and {}",
-            ancestor_branch.bold(),
-            descendant_branch.bold()
-        )))
-    }
-
-    fn merge_base_fork_point(
-        &self,
-        ancestor_branch: &str,
-        descendant_branch: &str,
-    ) -> Result<String, Error> {
-        // git merge-base --fork-point <ancestor_branch> <descendant_branch>
+            let dangling_commit_id = raw_output.trim().to_string();
+            dangling_commit_id
+        } else {
+            return Err(Error::from_str(&format!(
+                "Unable to generate commit-tree of branch {}",
+                current_branch.bold()
+            )));
+        };
 
+        // output = git cherry parent_branch dangling_commit_id [limit]
         let output = Command::new("git")
-            .arg("merge-base")
-            .arg("--fork-point")
-            .arg(ancestor_branch)
-            .arg(descendant_branch)
-            .output()
+            .arg("cherry")
+            .arg(parent_branch)
+            .arg(&dangling_commit_id)
+            .args(limit)
+            .logged_output()
             .unwrap_or_else(|_| {
                 panic!(
-                    "Unable to run: git merge-base --fork-point {} {}",
-                    ancestor_branch.bold(),
-                    descendant_branch.bold()
+                    "Unable to determine if branch {} was squashed and merged into {}",
+                    current_branch.bold(),
+                    parent_branch.bold()
                 )
             });
 
-        if output.status.success() {
+        let cherry_output = if output.status.success() {
             let raw_output = String::from_utf8(output.stdout).unwrap();
-            let common_point = raw_output.trim().to_string();
-            return Ok(common_point);
-        }
-        if output.status.code().unwrap() == 1 {
-            // fork-point not found, try git merge-base
-            return self.merge_base(ancestor_branch, descendant_branch);
-        }
+            raw_output.trim().to_string()
+        } else {
+            return Err(Error::from_str(&format!(
+                "Unable to determine if branch {} was squashed and merged into {}",
+                current_branch.bold(),
+                parent_branch.bold()
+            )));
+        };
 
-        Err(Error::from_str(&format!(
-            "Unable to get forkpoint of {} and {}",
-            ancestor_branch.bold(),
-            descendant_branch.bold()
-        )))
-    }
+        let lines: Vec<String> = cherry_output.lines().map(|x| x.to_string()).collect();
+        if lines.is_empty() {
+            return Ok(true);
+        }
 
-    fn is_ancestor(&self, ancestor_branch: &str, descendant_branch: &str) -> Result<bool, Error> {
-        let (ancestor_object, _reference) = self.repo.revparse_ext(ancestor_branch)?;
-        let (descendant_object, _reference) = self.repo.revparse_ext(descendant_branch)?;
+        if lines.len() == 1 {
+            // check if output is a single line containing "- dangling_commit_id"
+            let line = &lines[0].trim();
+            let is_squashed_merged = line.starts_with(&format!("- {}", dangling_commit_id));
+            return Ok(is_squashed_merged);
+        }
 
-        let common_point = self
-            .repo
-            .merge_base(ancestor_object.id(), descendant_object.id())?;
+        for line in lines {
+            if line.trim().starts_with('-') {
+                continue;
+            } else {
+                return Ok(false);
+            }
+        }
 
-        Ok(common_point == ancestor_object.id())
+        Ok(true)
     }
-}
 
-fn parse_sort_option(
-    git_chain: &GitChain,
-    chain_name: &str,
-    before_branch: Option<&str>,
-    after_branch: Option<&str>,
-) -> Result<SortBranch, Error> {
-    if let Some(before_branch) = before_branch {
-        if !git_chain.git_local_branch_exists(before_branch)? {
-            return Err(Error::from_str(&format!(
-                "Branch does not exist: {}",
-                before_branch.bold()
-            )));
+    fn commit_trailers(&self, chain_name: &str, signoff: bool) -> Result<CommitTrailers, Error> {
+        // Precedence: --signoff > GIT_CHAIN_SIGNOFF > chain.signoff
+        let signoff = signoff
+            || self
+                .get_bool_setting("GIT_CHAIN_SIGNOFF", "chain.signoff")?
+                .unwrap_or(false);
+
+        let mut trailers = vec![];
+        if signoff {
+            let signature = self.repo.signature()?;
+            trailers.push(format!(
+                "Signed-off-by: {} <{}>",
+                signature.name().unwrap_or_default(),
+                signature.email().unwrap_or_default()
+            ));
         }
 
-        let before_branch = match Branch::get_branch_with_chain(git_chain, before_branch)? {
-            BranchSearchResult::NotPartOfAnyChain(_) => {
-                git_chain.display_branch_not_part_of_chain_error(before_branch);
-                process::exit(1);
-            }
-            BranchSearchResult::Branch(before_branch) => {
-                if before_branch.chain_name != chain_name {
-                    return Err(Error::from_str(&format!(
-                        "Branch {} is not part of chain {}",
-                        before_branch.branch_name.bold(),
-                        chain_name.bold()
-                    )));
+        // chain.trailer may be given several times; {chain} is replaced by the name of the chain.
+        let config = self.repo.config()?;
+        let mut entries = config.multivar("chain.trailer", None)?;
+        while let Some(entry) = entries.next() {
+            if let Some(trailer) = entry?.value() {
+                if !trailer.trim().is_empty() {
+                    trailers.push(trailer.trim().replace("{chain}", chain_name));
                 }
-                before_branch
             }
-        };
+        }
 
-        Ok(SortBranch::Before(before_branch))
-    } else if let Some(after_branch) = after_branch {
-        if !git_chain.git_local_branch_exists(after_branch)? {
-            return Err(Error::from_str(&format!(
-                "Branch does not exist: {}",
-                after_branch.bold()
-            )));
+        Ok(CommitTrailers { trailers })
+    }
+
+    fn reconcile_mode(&self, reconcile: Option<&str>) -> Result<ReconcileMode, Error> {
+        // Precedence: --reconcile > GIT_CHAIN_RECONCILE > chain.reconcile
+        let value = match reconcile {
+            Some(reconcile) => Some(reconcile.to_string()),
+            None => self.get_setting("GIT_CHAIN_RECONCILE", "chain.reconcile")?,
+        };
+        match value.as_deref() {
+            None | Some("off") => Ok(ReconcileMode::Off),
+            Some("rebase") => Ok(ReconcileMode::Rebase),
+            Some("merge") => Ok(ReconcileMode::Merge),
+            Some(value) => Err(Error::from_str(&format!(
+                "Invalid reconcile setting: {}. Expected one of: off, rebase, merge",
+                value.bold()
+            ))),
         }
+    }
 
-        let after_branch = match Branch::get_branch_with_chain(git_chain, after_branch)? {
-            BranchSearchResult::NotPartOfAnyChain(_) => {
-                git_chain.display_branch_not_part_of_chain_error(after_branch);
-                process::exit(1);
-            }
-            BranchSearchResult::Branch(after_branch) => {
-                if after_branch.chain_name != chain_name {
-                    return Err(Error::from_str(&format!(
-                        "Branch {} is not part of chain {}",
-                        after_branch.branch_name.bold(),
-                        chain_name.bold()
-                    )));
-                }
-                after_branch
+    fn reconcile_upstreams(
+        &self,
+        branches: &[Branch],
+        mode: ReconcileMode,
+        trailers: &CommitTrailers,
+    ) -> Result<(), Error> {
+        // Commits of the upstream of a branch without an equivalent commit in the branch would be
+        // discarded by the next force push, so they are brought into the branch before the rebase.
+        for branch in branches {
+            let local_branch = self
+                .repo
+                .find_branch(&branch.branch_name, BranchType::Local)?;
+            let upstream = match local_branch.upstream() {
+                Ok(upstream) => utf8_branch_name(&upstream)?,
+                Err(ref e) if e.code() == ErrorCode::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+
+            let commits = self.commits_to_cherry_pick(&upstream, &branch.branch_name, None)?;
+            if commits.is_empty() {
+                continue;
             }
-        };
 
-        Ok(SortBranch::After(after_branch))
-    } else {
-        Ok(SortBranch::Last)
-    }
-}
+            if mode == ReconcileMode::Off {
+                println!(
+                    "⚠️  {} has {} commits that are not in branch {} (e.g. pushed by a reviewer).",
+                    upstream.bold(),
+                    commits.len(),
+                    branch.branch_name.bold()
+                );
+                println!(
+                    "To include them before rebasing, run: {} rebase --reconcile=rebase",
+                    self.executable_name
+                );
+                continue;
+            }
 
-fn run(arg_matches: ArgMatches) -> Result<(), Error> {
-    let git_chain = GitChain::init()?;
+            self.checkout_branch(&branch.branch_name)?;
 
-    match arg_matches.subcommand() {
-        ("init", Some(sub_matches)) => {
-            // Initialize the current branch to a chain.
+            // The cherry-picked commits are rewritten by the rebase that follows, which adds the
+            // trailers; only a merge commit needs them now.
+            let mut args = vec![];
+            let mut merge_commit = false;
+            let operation = if self.is_ancestor(&branch.branch_name, &upstream)? {
+                args.extend(["merge", "--ff-only", &upstream]);
+                "merge"
+            } else if mode == ReconcileMode::Rebase {
+                args.extend(["cherry-pick", "--allow-empty"]);
+                args.extend(commits.iter().map(String::as_str));
+                "cherry-pick"
+            } else {
+                args.extend(["merge", "--no-edit", &upstream]);
+                merge_commit = true;
+                "merge"
+            };
 
-            let chain_name = sub_matches.value_of("chain_name").unwrap().to_string();
-            let root_branch = sub_matches.value_of("root_branch");
+            let command = format!("git {}", args.join(" "));
+            let output = Command::new("git")
+                .args(&args)
+                .logged_output()
+                .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
 
-            let before_branch = sub_matches.value_of("before");
-            let after_branch = sub_matches.value_of("after");
+            println!();
+            println!("{}", command);
 
-            let branch_name = git_chain.get_current_branch_name()?;
+            if !output.status.success() || self.repo.state() != RepositoryState::Clean {
+                io::stdout().write_all(&output.stdout).unwrap();
+                io::stderr().write_all(&output.stderr).unwrap();
+                eprintln!(
+                    "🛑 Unable to include the commits of {} into {}",
+                    upstream.bold(),
+                    branch.branch_name.bold()
+                );
+                eprintln!(
+                    "⚠️  Resolve any conflicts, run git {} --continue, and then run {} rebase",
+                    operation, self.executable_name
+                );
+                exit(1);
+            }
 
-            let root_branch = if Chain::chain_exists(&git_chain, &chain_name)? {
-                // Derive root branch from an existing chain
-                let chain = Chain::get_chain(&git_chain, &chain_name)?;
+            if merge_commit {
+                trailers.add_to_new_commits(1)?;
+            }
 
-                if let Some(user_provided_root_branch) = root_branch {
-                    if user_provided_root_branch != chain.root_branch {
-                        println!(
-                            "Using root branch {} of chain {} instead of {}",
-                            chain.root_branch.bold(),
-                            chain_name.bold(),
-                            user_provided_root_branch.bold()
-                        );
-                    }
-                }
+            println!(
+                "✅ Included {} commits of {} into {}",
+                commits.len(),
+                upstream.bold(),
+                branch.branch_name.bold()
+            );
+        }
 
-                chain.root_branch
-            } else if let Some(root_branch) = root_branch {
-                root_branch.to_string()
-            } else {
-                eprintln!("Please provide the root branch.");
-                process::exit(1);
-            };
+        Ok(())
+    }
 
-            if !git_chain.git_branch_exists(&root_branch)? {
-                eprintln!("Root branch does not exist: {}", root_branch.bold());
-                process::exit(1);
+    // Walk the branches of the chain within indices, in order, and hand each of them to on_step
+    // along with the action that rebasing it onto its parent branch amounts to. The action of a
+    // branch is only decided once on_step returned for the branches before it, since squash
+    // merges are detected against the parent branch as rewritten by the previous steps.
+    fn rebase_steps<F>(
+        &self,
+        pairs: &[BranchPair],
+        fork_points: &[String],
+        indices: &std::ops::Range<usize>,
+        options: &RebaseOptions,
+        mut on_step: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&RebaseStep) -> Result<ControlFlow<()>, Error>,
+    {
+        for pair in pairs {
+            if !indices.contains(&pair.index) {
+                continue;
             }
 
-            if root_branch == branch_name {
-                eprintln!(
-                    "Current branch cannot be the root branch: {}",
-                    branch_name.bold()
-                );
-                process::exit(1);
-            }
+            timings::set_branch(Some(&pair.branch.branch_name));
 
-            let sort_option = if sub_matches.is_present("first") {
-                SortBranch::First
-            } else {
-                parse_sort_option(&git_chain, &chain_name, before_branch, after_branch)?
+            let fork_point = &fork_points[pair.index];
+            let action = self.rebase_action(pair, fork_point, options)?;
+            let step = RebaseStep {
+                pair,
+                fork_point,
+                action,
             };
-
-            git_chain.init_chain(&chain_name, &root_branch, &branch_name, sort_option)?
+            if on_step(&step)?.is_break() {
+                break;
+            }
         }
-        ("remove", Some(sub_matches)) => {
-            // Remove current branch from its chain.
 
-            let chain_name = sub_matches.value_of("chain_name");
+        Ok(())
+    }
 
-            let branch_name = git_chain.get_current_branch_name()?;
+    fn rebase_action(
+        &self,
+        pair: &BranchPair,
+        fork_point: &str,
+        options: &RebaseOptions,
+    ) -> Result<RebaseAction, Error> {
+        if pair.index == 0 && options.ignore_root {
+            return Ok(RebaseAction::SkipRoot);
+        }
 
-            if let Some(chain_name) = chain_name {
-                // Only delete a specific chain
-                if Chain::chain_exists(&git_chain, chain_name)? {
-                    let chain = Chain::get_chain(&git_chain, chain_name)?;
-                    let deleted_branches = chain.delete(&git_chain)?;
-
-                    if !deleted_branches.is_empty() {
-                        println!("Removed the following branches from their chains:");
-                        for branch_name in deleted_branches {
-                            println!("{}", branch_name)
-                        }
-                    }
-                    println!("Successfully deleted chain: {}", chain_name.bold());
-                    return Ok(());
-                }
+        if options.allow_unrelated && !self.shares_history(pair.parent, &pair.branch.branch_name)? {
+            return Ok(RebaseAction::SkipUnrelated);
+        }
 
-                println!(
-                    "Unable to delete chain that does not exist: {}",
-                    chain_name.bold()
-                );
-                println!("Nothing to do.");
+        // The parent branch is not merged in, so there is nothing to detect.
+        if options.keep_base {
+            return Ok(RebaseAction::Rebase);
+        }
 
-                return Ok(());
-            }
+        // check if the branch is squashed merged to its parent branch
+        let is_squashed_merged =
+            self.is_squashed_merged(fork_point, pair.parent, &pair.branch.branch_name, None)?;
+        debug_log!(
+            "squash and merge of {} onto {} detected: {}",
+            pair.branch.branch_name,
+            pair.parent,
+            is_squashed_merged
+        );
 
-            git_chain.remove_branch_from_chain(branch_name)?
+        if is_squashed_merged {
+            Ok(RebaseAction::ResetToParent)
+        } else {
+            Ok(RebaseAction::Rebase)
         }
-        ("list", Some(_sub_matches)) => {
-            // List all chains.
-            let current_branch = git_chain.get_current_branch_name()?;
-            git_chain.list_chains(&current_branch)?
+    }
+
+    fn ensure_rebase_branches_exist(&self, chain: &Chain) -> Result<(), Error> {
+        // ensure root branch exists
+        if !self.git_root_exists(&chain.root_branch)? {
+            eprintln!("Root branch does not exist: {}", chain.root_branch.bold());
+            exit(1);
         }
-        ("move", Some(sub_matches)) => {
-            // Move current branch or chain.
 
-            let before_branch = sub_matches.value_of("before");
-            let after_branch = sub_matches.value_of("after");
-            let root_branch = sub_matches.value_of("root");
-            let chain_name = sub_matches.value_of("chain_name");
+        // ensure each branch exists
+        if let Some(branch) = chain.missing_branches.first() {
+            eprintln!("Branch does not exist: {}", branch.branch_name.bold());
+            eprintln!(
+                "To remove missing branches from the chain, run: {} prune",
+                executable_name().bold()
+            );
+            exit(1);
+        }
 
-            let branch_name = git_chain.get_current_branch_name()?;
+        Ok(())
+    }
 
-            let branch = match Branch::get_branch_with_chain(&git_chain, &branch_name)? {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
-                    process::exit(1);
-                }
-                BranchSearchResult::Branch(branch) => branch,
-            };
+    // List of common ancestors between each branch and its parent branch.
+    // For the first branch, a common ancestor is generated between it and the root branch.
+    //
+    // The following command is used to generate the common ancestors:
+    // git merge-base --fork-point <ancestor_branch> <descendant_branch>
+    fn rebase_fork_points(
+        &self,
+        pairs: &[BranchPair],
+        indices: &std::ops::Range<usize>,
+    ) -> Result<Vec<String>, Error> {
+        let mut common_ancestors = vec![];
 
-            if let Some(root_branch) = root_branch {
-                // invariant: chain_name is None
-                // clap ensures this invariant
-                assert!(chain_name.is_none());
+        for pair in pairs {
+            timings::set_branch(Some(&pair.branch.branch_name));
 
-                if !git_chain.git_branch_exists(root_branch)? {
-                    eprintln!("Root branch does not exist: {}", root_branch.bold());
-                    process::exit(1);
-                }
+            // Before the branch is rewritten, as its remote tip is no longer included afterwards.
+            if indices.contains(&pair.index) {
+                self.record_included_remote_oid(&pair.branch.branch_name)?;
+            }
 
-                if root_branch == branch_name {
-                    eprintln!(
-                        "Current branch cannot be the root branch: {}",
-                        branch_name.bold()
-                    );
-                    process::exit(1);
-                }
+            // Unused, as branches without a common ancestor with their parent branch are skipped.
+            if !self.shares_history(pair.parent, &pair.branch.branch_name)? {
+                common_ancestors.push(self.get_commit_hash_of_branch(&pair.branch.branch_name)?);
+                continue;
+            }
 
-                let chain = Chain::get_chain(&git_chain, &branch.chain_name)?;
+            let common_point = self.smart_merge_base(pair.parent, &pair.branch.branch_name)?;
+            common_ancestors.push(common_point);
+        }
 
-                let old_root_branch = chain.root_branch.clone();
+        assert_eq!(pairs.len(), common_ancestors.len());
+        timings::set_branch(None);
 
-                chain.change_root_branch(&git_chain, root_branch)?;
+        Ok(common_ancestors)
+    }
 
-                println!(
-                    "Changed root branch for the chain {} from {} to {}",
-                    chain.name.bold(),
-                    old_root_branch.bold(),
-                    root_branch.bold()
-                );
+    // Branches that have commits of their own before the rebase, so that branches that
+    // become empty can be told apart from branches that did not have any commits yet.
+    fn non_empty_branches(
+        &self,
+        pairs: &[BranchPair],
+        indices: &std::ops::Range<usize>,
+    ) -> Result<Vec<String>, Error> {
+        let mut non_empty_branches = vec![];
+        for pair in pairs {
+            if !indices.contains(&pair.index) {
+                continue;
+            }
+            let (ahead, _behind) = self.ahead_behind(pair.parent, &pair.branch.branch_name)?;
+            if ahead > 0 {
+                non_empty_branches.push(pair.branch.branch_name.clone());
             }
+        }
+        Ok(non_empty_branches)
+    }
 
-            match chain_name {
-                None => {
-                    let chain_name = branch.chain_name;
-                    if before_branch.is_some() || after_branch.is_some() {
-                        let sort_option = parse_sort_option(
-                            &git_chain,
-                            &chain_name,
-                            before_branch,
-                            after_branch,
-                        )?;
-                        git_chain.move_branch(&chain_name, &branch_name, &sort_option)?
-                    } else {
-                        // nothing to do
-                        println!("Nothing to do. ☕");
-                    }
-                }
-                Some(new_chain_name) => {
-                    let old_chain_name = branch.chain_name;
-                    if before_branch.is_some()
-                        || after_branch.is_some()
-                        || new_chain_name != old_chain_name
-                    {
-                        let sort_option = parse_sort_option(
-                            &git_chain,
-                            new_chain_name,
-                            before_branch,
-                            after_branch,
-                        )?;
-                        git_chain.move_branch(new_chain_name, &branch_name, &sort_option)?
-                    } else {
-                        // nothing to do
-                        println!("Nothing to do. ☕");
-                    }
-                }
-            };
+    fn rebase(&self, chain_name: &str, options: &RebaseOptions) -> Result<(), Error> {
+        let chain = Chain::get_chain(self, chain_name)?;
+        if options.update_root {
+            self.update_root(&chain)?;
+        } else if !options.ignore_root {
+            self.warn_root_behind_upstream(&chain)?;
         }
-        ("rebase", Some(sub_matches)) => {
-            // Rebase all branches for the current chain.
-            let branch_name = git_chain.get_current_branch_name()?;
 
-            let branch = match Branch::get_branch_with_chain(&git_chain, &branch_name)? {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
-                    process::exit(1);
-                }
-                BranchSearchResult::Branch(branch) => branch,
-            };
+        if options.worktree {
+            return self.rebase_in_worktree(chain_name, options);
+        }
 
-            if Chain::chain_exists(&git_chain, &branch.chain_name)? {
-                let step_rebase = sub_matches.is_present("step");
-                let ignore_root = sub_matches.is_present("ignore_root");
-                git_chain.rebase(&branch.chain_name, step_rebase, ignore_root)?;
-            } else {
-                eprintln!("Unable to rebase chain.");
-                eprintln!("Chain does not exist: {}", branch.chain_name.bold());
-                process::exit(1);
-            }
+        // invariant: chain_name chain exists
+        let chain = Chain::get_chain(self, chain_name)?;
+        self.ensure_rebase_branches_exist(&chain)?;
+
+        if self.dirty_working_directory()? {
+            eprintln!(
+                "🛑 Unable to rebase branches for the chain: {}",
+                chain.name.bold()
+            );
+            eprintln!("You have uncommitted changes in your working directory.");
+            eprintln!("Please commit or stash them.");
+            exit(1);
         }
-        ("backup", Some(_sub_matches)) => {
-            // Back up all branches of the current chain.
 
-            let branch_name = git_chain.get_current_branch_name()?;
+        let indices = chain.resolve_branch_range(&options.range, "rebase");
+        self.ensure_related_histories(&chain, &indices, options.allow_unrelated, "rebase")?;
 
-            let branch = match Branch::get_branch_with_chain(&git_chain, &branch_name)? {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
-                    process::exit(1);
-                }
-                BranchSearchResult::Branch(branch) => branch,
-            };
+        let original_branch = OriginalBranch::new(self, &chain.name, "rebase", options.stay)?;
 
-            git_chain.backup(&branch.chain_name)?;
-        }
-        ("push", Some(sub_matches)) => {
-            // Push all branches of the current chain to their upstreams.
+        let trailers = self.commit_trailers(&chain.name, options.signoff)?;
+        self.reconcile_upstreams(
+            &chain.branches[indices.clone()],
+            options.reconcile,
+            &trailers,
+        )?;
 
-            let branch_name = git_chain.get_current_branch_name()?;
+        let root_branch = chain.root_branch.clone();
+        let pairs = chain.branch_pairs();
+        let common_ancestors = self.rebase_fork_points(&pairs, &indices)?;
+        let non_empty_branches = self.non_empty_branches(&pairs, &indices)?;
+        // The tips of the branches before they are rewritten, for --keep-base.
+        let orig_tips = pairs
+            .iter()
+            .map(|pair| self.get_commit_hash_of_branch(&pair.branch.branch_name))
+            .collect::<Result<Vec<String>, Error>>()?;
 
-            let branch = match Branch::get_branch_with_chain(&git_chain, &branch_name)? {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
-                    process::exit(1);
-                }
-                BranchSearchResult::Branch(branch) => branch,
-            };
+        let mut num_of_rebase_operations = 0;
+        let mut num_of_branches_visited = 0;
 
-            let force_push = sub_matches.is_present("force");
-            git_chain.push(&branch.chain_name, force_push)?;
-        }
-        ("prune", Some(sub_matches)) => {
-            // Prune any branches of the current chain.
+        let mut report = RebaseReport {
+            chain_name: chain.name.clone(),
+            entries: pairs[indices.clone()]
+                .iter()
+                .map(|pair| RebaseEntry {
+                    branch_name: pair.branch.branch_name.clone(),
+                    parent: pair.parent.to_string(),
+                    outcome: RebaseOutcome::Pending,
+                })
+                .collect(),
+        };
 
-            let branch_name = git_chain.get_current_branch_name()?;
+        self.rebase_steps(&pairs, &common_ancestors, &indices, options, |step| {
+            let branch = step.pair.branch;
+            let prev_branch_name = step.pair.parent;
+            let outcome = &mut report.entries[step.pair.index - indices.start].outcome;
 
-            let branch = match Branch::get_branch_with_chain(&git_chain, &branch_name)? {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
-                    process::exit(1);
+            num_of_branches_visited += 1;
+
+            match step.action {
+                RebaseAction::SkipRoot => {
+                    // Skip the rebase operation for the first branch of the chain.
+                    // Essentially, we do not rebase the first branch against the root branch.
+                    println!();
+                    println!(
+                        "⚠️  Not rebasing branch {} against root branch {}. Skipping.",
+                        &branch.branch_name.bold(),
+                        prev_branch_name.bold()
+                    );
+                    *outcome = RebaseOutcome::Skipped;
+                    return Ok(ControlFlow::Continue(()));
                 }
-                BranchSearchResult::Branch(branch) => branch,
-            };
+                RebaseAction::SkipUnrelated => {
+                    println!();
+                    println!(
+                        "⚠️  Branch {} shares no history with {}. Skipping.",
+                        &branch.branch_name.bold(),
+                        prev_branch_name.bold()
+                    );
+                    *outcome = RebaseOutcome::Skipped;
+                    return Ok(ControlFlow::Continue(()));
+                }
+                RebaseAction::ResetToParent => {
+                    self.checkout_branch(&branch.branch_name)?;
 
-            let dry_run = sub_matches.is_present("dry_run");
+                    println!();
+                    println!(
+                        "⚠️  Branch {} is detected to be squashed and merged onto {}.",
+                        &branch.branch_name.bold(),
+                        prev_branch_name.bold()
+                    );
 
-            git_chain.prune(&branch.chain_name, dry_run)?;
-        }
-        ("rename", Some(sub_matches)) => {
-            // Rename current chain.
+                    let command = format!("git reset --hard {}", &prev_branch_name);
 
-            let new_chain_name = sub_matches.value_of("chain_name").unwrap().to_string();
+                    // git reset --hard <prev_branch_name>
+                    let output = Command::new("git")
+                        .arg("reset")
+                        .arg("--hard")
+                        .arg(prev_branch_name)
+                        .logged_output()
+                        .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
 
-            let branch_name = git_chain.get_current_branch_name()?;
+                    if !output.status.success() {
+                        eprintln!("Unable to run: {}", &command);
+                        original_branch.exit(1);
+                    }
 
-            let branch = match Branch::get_branch_with_chain(&git_chain, &branch_name)? {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
-                    process::exit(1);
-                }
-                BranchSearchResult::Branch(branch) => branch,
-            };
+                    println!(
+                        "Resetting branch {} to {}",
+                        &branch.branch_name.bold(),
+                        prev_branch_name.bold()
+                    );
+                    println!("{}", command);
 
-            if Chain::chain_exists(&git_chain, &new_chain_name)? {
-                eprintln!(
-                    "Unable to rename chain {} to {}",
-                    branch.chain_name.bold(),
-                    new_chain_name.bold()
-                );
-                eprintln!("Chain already exists: {}", branch.chain_name.bold());
-                process::exit(1);
+                    branch.record_rebase_base(self, prev_branch_name)?;
+                    *outcome = RebaseOutcome::Reset;
+
+                    return Ok(ControlFlow::Continue(()));
+                }
+                RebaseAction::Rebase => {}
             }
 
-            if Chain::chain_exists(&git_chain, &branch.chain_name)? {
-                let chain = Chain::get_chain(&git_chain, &branch.chain_name)?;
-                let old_chain_name = chain.name.clone();
-                chain.rename(&git_chain, &new_chain_name)?;
-                println!(
-                    "Renamed chain from {} to {}",
-                    old_chain_name.bold(),
-                    new_chain_name.bold()
-                );
+            // git rebase --onto <onto> <upstream> <branch>
+            // git rebase --onto parent_branch fork_point branch.name
+            //
+            // With --keep-base, the branch is replayed onto its fork point instead, unless it was
+            // stacked on the tip of its parent branch, which may have been rewritten since.
+            let onto = if options.keep_base
+                && (step.pair.index == 0 || orig_tips[step.pair.index - 1] != step.fork_point)
+            {
+                step.fork_point
             } else {
-                eprintln!("Unable to rename chain.");
-                eprintln!("Chain does not exist: {}", new_chain_name.bold());
-                process::exit(1);
+                prev_branch_name
+            };
+
+            self.checkout_branch(&branch.branch_name)?;
+
+            let before_sha1 = self.get_commit_hash_of_head()?;
+            let (num_of_commits, _behind) =
+                self.ahead_behind(step.fork_point, &branch.branch_name)?;
+
+            let strategy_option_args =
+                branch.strategy_option_args(self, options.strategy_option.as_deref())?;
+            let trailer_args = trailers.rebase_args();
+            let mut rebase_args = vec!["rebase", "--keep-empty"];
+            rebase_args.extend(strategy_option_args.iter().map(String::as_str));
+            rebase_args.extend(trailer_args.iter().map(String::as_str));
+            if options.committer_date_is_author_date {
+                rebase_args.push("--committer-date-is-author-date");
             }
-        }
-        ("setup", Some(sub_matches)) => {
-            // Set up a chain.
+            if options.reset_author_date {
+                rebase_args.push("--reset-author-date");
+            }
+            if options.interactive {
+                rebase_args.push("--interactive");
+            }
+            rebase_args.extend(["--onto", onto, step.fork_point, &branch.branch_name]);
 
-            let chain_name = sub_matches.value_of("chain_name").unwrap().to_string();
-            let root_branch = sub_matches.value_of("root_branch").unwrap().to_string();
+            let command = format!("git {}", rebase_args.join(" "));
 
-            let branches: Vec<String> = sub_matches
-                .values_of("branch")
-                .unwrap()
-                .map(|x| x.to_string())
-                .collect();
-
-            // ensure root branch exists
-            if !git_chain.git_branch_exists(&root_branch)? {
-                eprintln!("Root branch does not exist: {}", root_branch.bold());
-                process::exit(1);
-            }
+            let output = if options.interactive {
+                // git takes over the terminal to edit the todo list of this branch;
+                // the next branch is rebased once the todo list is closed.
+                println!();
+                println!("{}", command);
 
-            let mut visited_branches = HashSet::new();
+                let status = Command::new("git")
+                    .args(&rebase_args)
+                    .logged_status()
+                    .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
 
-            for branch_name in &branches {
-                if branch_name == &root_branch {
-                    eprintln!(
-                        "Branch being added to the chain cannot be the root branch: {}",
-                        branch_name.bold()
-                    );
-                    process::exit(1);
+                Output {
+                    status,
+                    stdout: vec![],
+                    stderr: vec![],
                 }
+            } else {
+                let output = Command::new("git")
+                    .args(&rebase_args)
+                    .logged_output()
+                    .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
 
-                if !git_chain.git_local_branch_exists(branch_name)? {
-                    eprintln!("Branch does not exist: {}", branch_name.bold());
-                    process::exit(1);
-                }
+                println!();
+                println!("{}", command);
 
-                let results = Branch::get_branch_with_chain(&git_chain, branch_name)?;
+                output
+            };
 
-                match results {
-                    BranchSearchResult::Branch(branch) => {
-                        eprintln!("❌ Unable to initialize branch to a chain.");
-                        eprintln!();
-                        eprintln!("Branch already part of a chain: {}", branch_name.bold());
-                        eprintln!("It is part of the chain: {}", branch.chain_name.bold());
-                        eprintln!("With root branch: {}", branch.root_branch.bold());
-                        process::exit(1);
+            // ensure repository is in a clean state
+            match self.repo.state() {
+                RepositoryState::Clean => {
+                    if !output.status.success() {
+                        eprintln!("Command returned non-zero exit status: {}", command);
+                        eprintln!("It returned: {}", output.status.code().unwrap());
+                        io::stdout().write_all(&output.stdout).unwrap();
+                        io::stderr().write_all(&output.stderr).unwrap();
+                        original_branch.exit(1);
                     }
-                    BranchSearchResult::NotPartOfAnyChain(_) => {}
-                }
+                    io::stdout().write_all(&output.stdout).unwrap();
+                    io::stderr().write_all(&output.stderr).unwrap();
 
-                if visited_branches.contains(branch_name) {
-                    eprintln!(
-                        "Branch defined on the chain at least twice: {}",
-                        branch_name.bold()
+                    let after_sha1 = self.get_commit_hash_of_head()?;
+
+                    if before_sha1 != after_sha1 {
+                        num_of_rebase_operations += 1;
+                        *outcome = RebaseOutcome::Rebased {
+                            commits: num_of_commits,
+                        };
+                    } else {
+                        *outcome = RebaseOutcome::UpToDate;
+                    }
+
+                    // A branch kept on its fork point still needs to be rebased onto its parent.
+                    if onto == prev_branch_name {
+                        branch.record_rebase_base(self, prev_branch_name)?;
+                    }
+                }
+                _ => {
+                    *outcome = RebaseOutcome::Conflict {
+                        commits: num_of_commits,
+                        files: self.conflict_owners(
+                            &pairs,
+                            step.pair.index,
+                            self.conflicting_files()?,
+                        )?,
+                    };
+                    let resume_command = format!("{} rebase", self.executable_name);
+                    if let Some(text) = report.to_text(Some(&resume_command)) {
+                        println!();
+                        println!("{}", text);
+                    }
+                    self.notify(
+                        &chain.name,
+                        notify::Event::RebaseConflict {
+                            branch: branch.branch_name.clone(),
+                            parent: prev_branch_name.to_string(),
+                        },
+                    )?;
+                    print_rebase_error(
+                        &self.executable_name,
+                        &branch.branch_name,
+                        prev_branch_name,
                     );
-                    eprintln!("Branches should be unique when setting up a new chain.");
-                    process::exit(1);
+                    original_branch.exit(1);
                 }
-                visited_branches.insert(branch_name);
             }
 
-            for branch_name in &branches {
-                Branch::setup_branch(
-                    &git_chain,
-                    &chain_name,
-                    &root_branch,
-                    branch_name,
-                    &SortBranch::Last,
-                )?;
+            if options.step_rebase && num_of_rebase_operations == 1 {
+                // performed at most one rebase.
+                return Ok(ControlFlow::Break(()));
             }
 
-            println!("🔗 Succesfully set up chain: {}", chain_name.bold());
+            // go ahead to rebase next branch.
+            Ok(ControlFlow::Continue(()))
+        })?;
+
+        timings::set_branch(None);
+
+        let current_branch = self.get_current_branch_name()?;
+        original_branch.switch_back()?;
+
+        if options.drop_empty {
+            self.drop_empty_branches(
+                &chain.name,
+                &root_branch,
+                &chain.branches,
+                &non_empty_branches,
+                options.delete_empty,
+            )?;
+        }
+
+        println!();
+        if let Some(text) = report.to_text(None) {
+            println!("{}", text);
+            println!();
+        }
+        if options.step_rebase
+            && num_of_rebase_operations == 1
+            && num_of_branches_visited != indices.len()
+        {
+            println!("Performed one rebase on branch: {}", current_branch.bold());
             println!();
+            println!(
+                "To continue rebasing, run {} rebase --step",
+                self.executable_name
+            );
 
-            let chain = Chain::get_chain(&git_chain, &chain_name)?;
-            let current_branch = git_chain.get_current_branch_name()?;
-            chain.display_list(&git_chain, &current_branch)?;
+            return Ok(());
         }
-        ("first", Some(_sub_matches)) => {
-            // Switch to the first branch of the chain.
 
-            let branch_name = git_chain.get_current_branch_name()?;
+        if options.ignore_root {
+            println!(
+                "⚠️ Did not rebase chain against root branch: {}",
+                root_branch.bold()
+            );
+        }
+        if num_of_rebase_operations > 0 {
+            println!("🎉 Successfully rebased chain {}", chain.name.bold());
+            self.notify(&chain.name, notify::Event::RebaseCompleted)?;
+        } else {
+            println!("Chain {} is already up-to-date.", chain.name.bold());
+        }
 
-            let current_branch = match Branch::get_branch_with_chain(&git_chain, &branch_name)? {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
-                    process::exit(1);
-                }
-                BranchSearchResult::Branch(branch) => branch,
-            };
+        Ok(())
+    }
 
-            if Chain::chain_exists(&git_chain, &current_branch.chain_name)? {
-                let chain = Chain::get_chain(&git_chain, &current_branch.chain_name)?;
-                let first_branch = chain.branches.first().unwrap();
+    // Rebase the branches of the chain in a temporary worktree, so that the working directory, the
+    // index and the checked out branch are left alone. The rebased commits are kept in temporary
+    // refs, and the branches are only updated once every branch was rebased.
+    fn rebase_in_worktree(&self, chain_name: &str, options: &RebaseOptions) -> Result<(), Error> {
+        // invariant: chain_name chain exists
+        let chain = Chain::get_chain(self, chain_name)?;
+        self.ensure_rebase_branches_exist(&chain)?;
+
+        let indices = chain.resolve_branch_range(&options.range, "rebase");
+        self.ensure_related_histories(&chain, &indices, options.allow_unrelated, "rebase")?;
+        let orig_branch = self.get_current_branch_name_if_attached()?;
+
+        // Bringing the commits of the upstreams into the branches requires checking them out.
+        let trailers = self.commit_trailers(&chain.name, options.signoff)?;
+        self.reconcile_upstreams(
+            &chain.branches[indices.clone()],
+            ReconcileMode::Off,
+            &trailers,
+        )?;
 
-                if current_branch.branch_name == first_branch.branch_name {
-                    println!(
-                        "Already on the first branch of the chain {}",
-                        current_branch.chain_name.bold()
-                    );
-                    return Ok(());
-                }
+        let root_branch = chain.root_branch.clone();
+        let pairs = chain.branch_pairs();
+        let common_ancestors = self.rebase_fork_points(&pairs, &indices)?;
+        let non_empty_branches = self.non_empty_branches(&pairs, &indices)?;
 
-                git_chain.checkout_branch(&first_branch.branch_name)?;
+        let mut orig_oids = HashMap::new();
+        for branch in &chain.branches {
+            let oid = self.get_commit_hash_of_branch(&branch.branch_name)?;
+            self.repo.reference(
+                &worktree_rebase_ref(&branch.branch_name),
+                git2::Oid::from_str(&oid)?,
+                true,
+                "git chain rebase --worktree",
+            )?;
+            orig_oids.insert(branch.branch_name.clone(), oid);
+        }
 
-                println!("Switched to branch: {}", first_branch.branch_name.bold());
-            } else {
-                eprintln!("Unable to find chain.");
-                eprintln!("Chain does not exist: {}", current_branch.chain_name.bold());
-                process::exit(1);
-            }
+        // Each branch is rebased onto the rebased commit of its parent branch.
+        let parents: Vec<String> = pairs
+            .iter()
+            .map(|pair| {
+                if pair.index == 0 {
+                    pair.parent.to_string()
+                } else {
+                    worktree_rebase_ref(pair.parent)
+                }
+            })
+            .collect();
+        let worktree_pairs: Vec<BranchPair> = pairs
+            .iter()
+            .map(|pair| BranchPair {
+                index: pair.index,
+                parent: &parents[pair.index],
+                branch: pair.branch,
+            })
+            .collect();
+
+        let worktree_path =
+            std::env::temp_dir().join(format!("git-chain-rebase-{}", process::id()));
+        let output = Command::new("git")
+            .arg("worktree")
+            .arg("add")
+            .arg("--detach")
+            .arg(&worktree_path)
+            .arg(worktree_rebase_ref(
+                &chain.branches[indices.start].branch_name,
+            ))
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: git worktree add"));
+
+        if !output.status.success() {
+            self.cleanup_worktree_rebase(&chain, None);
+            eprintln!("🛑 Unable to create a temporary worktree for the rebase.");
+            io::stderr().write_all(&output.stderr).unwrap();
+            exit(1);
         }
-        ("last", Some(_sub_matches)) => {
-            // Switch to the last branch of the chain.
 
-            let branch_name = git_chain.get_current_branch_name()?;
+        let mut num_of_rebase_operations = 0;
+        let mut rebased_onto = vec![];
+
+        let mut report = RebaseReport {
+            chain_name: chain.name.clone(),
+            entries: pairs[indices.clone()]
+                .iter()
+                .map(|pair| RebaseEntry {
+                    branch_name: pair.branch.branch_name.clone(),
+                    parent: pair.parent.to_string(),
+                    outcome: RebaseOutcome::Pending,
+                })
+                .collect(),
+        };
 
-            let current_branch = match Branch::get_branch_with_chain(&git_chain, &branch_name)? {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
-                    process::exit(1);
+        self.rebase_steps(
+            &worktree_pairs,
+            &common_ancestors,
+            &indices,
+            options,
+            |step| {
+                let branch = step.pair.branch;
+                let parent_ref = step.pair.parent;
+                let prev_branch_name = pairs[step.pair.index].parent;
+                let branch_ref = worktree_rebase_ref(&branch.branch_name);
+                let outcome = &mut report.entries[step.pair.index - indices.start].outcome;
+
+                match step.action {
+                    RebaseAction::SkipRoot => {
+                        println!();
+                        println!(
+                            "⚠️  Not rebasing branch {} against root branch {}. Skipping.",
+                            &branch.branch_name.bold(),
+                            prev_branch_name.bold()
+                        );
+                        *outcome = RebaseOutcome::Skipped;
+                        return Ok(ControlFlow::Continue(()));
+                    }
+                    RebaseAction::SkipUnrelated => {
+                        println!();
+                        println!(
+                            "⚠️  Branch {} shares no history with {}. Skipping.",
+                            &branch.branch_name.bold(),
+                            prev_branch_name.bold()
+                        );
+                        *outcome = RebaseOutcome::Skipped;
+                        return Ok(ControlFlow::Continue(()));
+                    }
+                    RebaseAction::ResetToParent => {
+                        println!();
+                        println!(
+                            "⚠️  Branch {} is detected to be squashed and merged onto {}.",
+                            &branch.branch_name.bold(),
+                            prev_branch_name.bold()
+                        );
+
+                        let parent_oid = self.get_commit_hash_of_branch(parent_ref)?;
+                        self.repo.reference(
+                            &branch_ref,
+                            git2::Oid::from_str(&parent_oid)?,
+                            true,
+                            "git chain rebase --worktree",
+                        )?;
+
+                        println!(
+                            "Resetting branch {} to {}",
+                            &branch.branch_name.bold(),
+                            prev_branch_name.bold()
+                        );
+
+                        rebased_onto.push(step.pair.index);
+                        *outcome = RebaseOutcome::Reset;
+                        return Ok(ControlFlow::Continue(()));
+                    }
+                    RebaseAction::Rebase => {}
                 }
-                BranchSearchResult::Branch(branch) => branch,
-            };
 
-            if Chain::chain_exists(&git_chain, &current_branch.chain_name)? {
-                let chain = Chain::get_chain(&git_chain, &current_branch.chain_name)?;
-                let last_branch = chain.branches.last().unwrap();
+                let before_sha1 = self.get_commit_hash_of_branch(&branch_ref)?;
+                let (num_of_commits, _behind) = self.ahead_behind(step.fork_point, &branch_ref)?;
 
-                if current_branch.branch_name == last_branch.branch_name {
-                    println!(
-                        "Already on the last branch of the chain {}",
-                        current_branch.chain_name.bold()
+                let output = Command::new("git")
+                    .arg("checkout")
+                    .arg("--quiet")
+                    .arg("--detach")
+                    .arg(&branch_ref)
+                    .current_dir(&worktree_path)
+                    .logged_output()
+                    .unwrap_or_else(|_| {
+                        panic!("Unable to run: git checkout --detach {}", branch_ref)
+                    });
+                if !output.status.success() {
+                    self.cleanup_worktree_rebase(&chain, Some(&worktree_path));
+                    eprintln!(
+                        "🛑 Unable to check out branch {} in the temporary worktree.",
+                        branch.branch_name.bold()
                     );
-                    return Ok(());
+                    io::stderr().write_all(&output.stderr).unwrap();
+                    exit(1);
                 }
 
-                git_chain.checkout_branch(&last_branch.branch_name)?;
+                // git rebase --onto <onto> <upstream>, on the detached rebased commit of the branch
+                let strategy_option_args =
+                    branch.strategy_option_args(self, options.strategy_option.as_deref())?;
+                let trailer_args = trailers.rebase_args();
+                let mut rebase_args = vec!["rebase", "--keep-empty"];
+                rebase_args.extend(strategy_option_args.iter().map(String::as_str));
+                rebase_args.extend(trailer_args.iter().map(String::as_str));
+                if options.committer_date_is_author_date {
+                    rebase_args.push("--committer-date-is-author-date");
+                }
+                if options.reset_author_date {
+                    rebase_args.push("--reset-author-date");
+                }
+                rebase_args.extend(["--onto", parent_ref, step.fork_point]);
 
-                println!("Switched to branch: {}", last_branch.branch_name.bold());
-            } else {
-                eprintln!("Unable to find chain.");
-                eprintln!("Chain does not exist: {}", current_branch.chain_name.bold());
-                process::exit(1);
-            }
-        }
-        ("next", Some(_sub_matches)) => {
-            // Switch to the next branch of the chain.
+                let command = format!(
+                    "git {} {}",
+                    rebase_args.join(" ").replace(parent_ref, prev_branch_name),
+                    branch.branch_name
+                );
 
-            let branch_name = git_chain.get_current_branch_name()?;
+                let output = Command::new("git")
+                    .args(&rebase_args)
+                    .current_dir(&worktree_path)
+                    .logged_output()
+                    .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
 
-            let current_branch = match Branch::get_branch_with_chain(&git_chain, &branch_name)? {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
-                    process::exit(1);
-                }
-                BranchSearchResult::Branch(branch) => branch,
-            };
+                println!();
+                println!("{}", command);
 
-            if Chain::chain_exists(&git_chain, &current_branch.chain_name)? {
-                let chain = Chain::get_chain(&git_chain, &current_branch.chain_name)?;
-                let index_of_branch = chain
-                    .branches
-                    .iter()
-                    .position(|b| b == &current_branch)
-                    .unwrap();
+                if !output.status.success() {
+                    let conflicts = Command::new("git")
+                        .arg("diff")
+                        .arg("--name-only")
+                        .arg("--diff-filter=U")
+                        .current_dir(&worktree_path)
+                        .logged_output()
+                        .map(|output| {
+                            String::from_utf8_lossy(&output.stdout)
+                                .lines()
+                                .map(str::to_string)
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    let _ = Command::new("git")
+                        .arg("rebase")
+                        .arg("--abort")
+                        .current_dir(&worktree_path)
+                        .logged_output();
+                    self.cleanup_worktree_rebase(&chain, Some(&worktree_path));
 
-                let index_of_next_branch = index_of_branch + 1;
+                    io::stdout().write_all(&output.stdout).unwrap();
+                    io::stderr().write_all(&output.stderr).unwrap();
 
-                if index_of_next_branch == chain.branches.len() {
-                    eprintln!("There is no next branch of the chain.");
-                    process::exit(1);
+                    *outcome = RebaseOutcome::Conflict {
+                        commits: num_of_commits,
+                        files: self.conflict_owners(&pairs, step.pair.index, conflicts)?,
+                    };
+                    if let Some(text) = report.to_text(None) {
+                        println!();
+                        println!("{}", text);
+                    }
+                    self.notify(
+                        &chain.name,
+                        notify::Event::RebaseConflict {
+                            branch: branch.branch_name.clone(),
+                            parent: prev_branch_name.to_string(),
+                        },
+                    )?;
+                    eprintln!();
+                    eprintln!(
+                        "🛑 Unable to rebase branch {} onto {} in a temporary worktree.",
+                        branch.branch_name.bold(),
+                        prev_branch_name.bold()
+                    );
+                    eprintln!(
+                        "No branches were updated, and your working directory was left alone."
+                    );
+                    eprintln!(
+                        "To resolve the conflicts, run {} rebase without --worktree.",
+                        self.executable_name
+                    );
+                    exit(1);
                 }
 
-                let next_branch = &chain.branches[index_of_next_branch];
+                let output = Command::new("git")
+                    .arg("rev-parse")
+                    .arg("HEAD")
+                    .current_dir(&worktree_path)
+                    .logged_output()
+                    .unwrap_or_else(|_| panic!("Unable to run: git rev-parse HEAD"));
+                let after_sha1 = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                self.repo.reference(
+                    &branch_ref,
+                    git2::Oid::from_str(&after_sha1)?,
+                    true,
+                    "git chain rebase --worktree",
+                )?;
 
-                if current_branch.branch_name == next_branch.branch_name {
-                    println!(
-                        "Already on the branch {}",
-                        current_branch.branch_name.bold()
-                    );
-                    return Ok(());
+                if before_sha1 != after_sha1 {
+                    num_of_rebase_operations += 1;
+                    *outcome = RebaseOutcome::Rebased {
+                        commits: num_of_commits,
+                    };
+                } else {
+                    *outcome = RebaseOutcome::UpToDate;
                 }
 
-                git_chain.checkout_branch(&next_branch.branch_name)?;
+                rebased_onto.push(step.pair.index);
+                Ok(ControlFlow::Continue(()))
+            },
+        )?;
 
-                println!("Switched to branch: {}", next_branch.branch_name.bold());
-            } else {
-                eprintln!("Unable to find chain.");
-                eprintln!("Chain does not exist: {}", current_branch.chain_name.bold());
-                process::exit(1);
-            }
+        timings::set_branch(None);
+
+        let output = Command::new("git")
+            .arg("worktree")
+            .arg("remove")
+            .arg("--force")
+            .arg(&worktree_path)
+            .logged_output();
+        if !matches!(&output, Ok(output) if output.status.success()) {
+            eprintln!(
+                "⚠️  Unable to remove the worktree: {}",
+                worktree_path.display().to_string().bold()
+            );
         }
-        ("prev", Some(_sub_matches)) => {
-            // Switch to the previous branch of the chain.
 
-            let branch_name = git_chain.get_current_branch_name()?;
+        let mut updates = vec![];
+        for pair in &pairs[indices.clone()] {
+            let branch_name = &pair.branch.branch_name;
+            let new_oid = self.get_commit_hash_of_branch(&worktree_rebase_ref(branch_name))?;
+            if new_oid != orig_oids[branch_name] {
+                updates.push((branch_name.clone(), new_oid));
+            }
+        }
 
-            let current_branch = match Branch::get_branch_with_chain(&git_chain, &branch_name)? {
-                BranchSearchResult::NotPartOfAnyChain(_) => {
-                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
-                    process::exit(1);
+        // The checked out branch is updated with git reset --keep, which keeps uncommitted
+        // changes, so that the working directory matches it. It goes first, as it fails if the
+        // uncommitted changes touch files that the rebase changed.
+        if let Some(orig_branch) = &orig_branch {
+            if let Some((_, new_oid)) = updates.iter().find(|(name, _)| name == orig_branch) {
+                let output = Command::new("git")
+                    .arg("reset")
+                    .arg("--keep")
+                    .arg(new_oid)
+                    .logged_output()
+                    .unwrap_or_else(|_| panic!("Unable to run: git reset --keep {}", new_oid));
+                if !output.status.success() {
+                    self.cleanup_worktree_rebase(&chain, None);
+                    eprintln!(
+                        "🛑 Unable to update the checked out branch: {}",
+                        orig_branch.bold()
+                    );
+                    io::stderr().write_all(&output.stderr).unwrap();
+                    eprintln!("Your uncommitted changes touch files that the rebase changed.");
+                    eprintln!("No branches were updated. Please commit or stash them.");
+                    exit(1);
                 }
-                BranchSearchResult::Branch(branch) => branch,
-            };
-
-            if Chain::chain_exists(&git_chain, &current_branch.chain_name)? {
-                let chain = Chain::get_chain(&git_chain, &current_branch.chain_name)?;
-                let index_of_branch = chain
-                    .branches
-                    .iter()
-                    .position(|b| b == &current_branch)
-                    .unwrap();
+            }
+        }
 
-                if index_of_branch == 0 {
-                    eprintln!("There is no previous branch of the chain.");
-                    process::exit(1);
-                }
+        // git update-ref --stdin updates the other branches in a single transaction, provided
+        // that they did not move in the meantime.
+        let stdin: String = updates
+            .iter()
+            .filter(|(name, _)| Some(name) != orig_branch.as_ref())
+            .map(|(name, new_oid)| {
+                format!(
+                    "update refs/heads/{} {} {}\n",
+                    name, new_oid, orig_oids[name]
+                )
+            })
+            .collect();
+        if !stdin.is_empty() {
+            let mut child = Command::new("git")
+                .arg("update-ref")
+                .arg("-m")
+                .arg("git chain rebase --worktree")
+                .arg("--stdin")
+                .stdin(process::Stdio::piped())
+                .stderr(process::Stdio::piped())
+                .spawn()
+                .unwrap_or_else(|_| panic!("Unable to run: git update-ref --stdin"));
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(stdin.as_bytes())
+                .unwrap();
+            let output = child.wait_with_output().unwrap();
+            if !output.status.success() {
+                self.cleanup_worktree_rebase(&chain, None);
+                eprintln!(
+                    "🛑 Unable to update the branches of chain {}",
+                    chain.name.bold()
+                );
+                io::stderr().write_all(&output.stderr).unwrap();
+                exit(1);
+            }
+        }
 
-                let index_of_prev_branch = index_of_branch - 1;
-                let prev_branch = &chain.branches[index_of_prev_branch];
+        for index in rebased_onto {
+            chain.branches[index].record_rebase_base(self, &parents[index])?;
+        }
+        self.cleanup_worktree_rebase(&chain, None);
+
+        if options.drop_empty {
+            self.drop_empty_branches(
+                &chain.name,
+                &root_branch,
+                &chain.branches,
+                &non_empty_branches,
+                options.delete_empty,
+            )?;
+        }
 
-                if current_branch.branch_name == prev_branch.branch_name {
-                    println!(
-                        "Already on the branch {}",
-                        current_branch.branch_name.bold()
-                    );
-                    return Ok(());
-                }
+        println!();
+        if let Some(text) = report.to_text(None) {
+            println!("{}", text);
+            println!();
+        }
+        if options.ignore_root {
+            println!(
+                "⚠️ Did not rebase chain against root branch: {}",
+                root_branch.bold()
+            );
+        }
+        if num_of_rebase_operations > 0 {
+            println!("🎉 Successfully rebased chain {}", chain.name.bold());
+            self.notify(&chain.name, notify::Event::RebaseCompleted)?;
+        } else {
+            println!("Chain {} is already up-to-date.", chain.name.bold());
+        }
 
-                git_chain.checkout_branch(&prev_branch.branch_name)?;
+        Ok(())
+    }
 
-                println!("Switched to branch: {}", prev_branch.branch_name.bold());
-            } else {
-                eprintln!("Unable to find chain.");
-                eprintln!("Chain does not exist: {}", current_branch.chain_name.bold());
-                process::exit(1);
-            }
+    fn cleanup_worktree_rebase(&self, chain: &Chain, worktree_path: Option<&std::path::Path>) {
+        if let Some(worktree_path) = worktree_path {
+            let _ = Command::new("git")
+                .arg("worktree")
+                .arg("remove")
+                .arg("--force")
+                .arg(worktree_path)
+                .logged_output();
         }
-        _ => {
-            git_chain.run_status()?;
+        for branch in &chain.branches {
+            if let Ok(mut reference) = self
+                .repo
+                .find_reference(&worktree_rebase_ref(&branch.branch_name))
+            {
+                let _ = reference.delete();
+            }
+        }
+    }
+
+    // Paths of the files with unresolved conflicts in the index.
+    fn conflicting_files(&self) -> Result<Vec<String>, Error> {
+        let mut index = self.repo.index()?;
+        index.read(true)?;
+
+        let mut files = vec![];
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+            if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+                files.push(String::from_utf8_lossy(&entry.path).to_string());
+            }
+        }
+        Ok(files)
+    }
+
+    // Which branch the other side of each conflict of the branch at index comes from: the closest
+    // branch before it in the chain that changed the file, or else the root branch, if it changed
+    // the file since the branch forked from it.
+    fn conflict_owners(
+        &self,
+        pairs: &[BranchPair],
+        index: usize,
+        paths: Vec<String>,
+    ) -> Result<Vec<ConflictingFile>, Error> {
+        let mut changed_paths = vec![];
+        for pair in pairs[..index].iter().rev() {
+            changed_paths.push((
+                pair.branch.branch_name.as_str(),
+                self.changed_paths(pair.parent, &pair.branch.branch_name)?,
+            ));
+        }
+        let root_branch = pairs[0].parent;
+        changed_paths.push((
+            root_branch,
+            self.changed_paths(&pairs[index].branch.branch_name, root_branch)?,
+        ));
+
+        Ok(paths
+            .into_iter()
+            .map(|path| {
+                let last_changed_by = changed_paths
+                    .iter()
+                    .find(|(_branch_name, changed_paths)| changed_paths.contains(&path))
+                    .map(|(branch_name, _changed_paths)| branch_name.to_string());
+                ConflictingFile {
+                    path,
+                    last_changed_by,
+                }
+            })
+            .collect())
+    }
+
+    // The paths changed by branch since it forked from base, i.e. git diff --name-only base...branch
+    fn changed_paths(&self, base: &str, branch: &str) -> Result<HashSet<String>, Error> {
+        let branch_commit = self.repo.revparse_single(branch)?.peel_to_commit()?;
+        let base_oid = self.repo.revparse_single(base)?.peel_to_commit()?.id();
+        let merge_base = match self.repo.merge_base(base_oid, branch_commit.id()) {
+            Ok(merge_base) => merge_base,
+            Err(ref e) if e.code() == ErrorCode::NotFound => return Ok(HashSet::new()),
+            Err(e) => return Err(e),
+        };
+        let base_tree = self.repo.find_commit(merge_base)?.tree()?;
+        let diff =
+            self.repo
+                .diff_tree_to_tree(Some(&base_tree), Some(&branch_commit.tree()?), None)?;
+
+        let mut paths = HashSet::new();
+        for delta in diff.deltas() {
+            for file in [delta.old_file(), delta.new_file()] {
+                // Lossy, like the paths of conflicting_files, which these are compared to.
+                if let Some(path) = file.path_bytes() {
+                    paths.insert(String::from_utf8_lossy(path).to_string());
+                }
+            }
+        }
+        Ok(paths)
+    }
+
+    fn commits_to_cherry_pick(
+        &self,
+        upstream: &str,
+        branch: &str,
+        limit: Option<&str>,
+    ) -> Result<Vec<String>, Error> {
+        // Commits of upstream (after limit, if given) that have no equivalent change in branch,
+        // oldest first.
+        // git cherry <branch> <upstream> [<limit>]
+        let mut cherry_args = vec!["cherry", branch, upstream];
+        cherry_args.extend(limit);
+        let command = format!("git {}", cherry_args.join(" "));
+        let output = Command::new("git")
+            .args(&cherry_args)
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+        if !output.status.success() {
+            eprintln!("Unable to run: {}", &command);
+            exit(1);
+        }
+
+        let commits = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("+ "))
+            .map(|commit| commit.trim().to_string())
+            .collect();
+
+        Ok(commits)
+    }
+
+    fn cherry(
+        &self,
+        chain_name: &str,
+        signoff: bool,
+        allow_unrelated: bool,
+        stay: bool,
+    ) -> Result<(), Error> {
+        // invariant: chain_name chain exists
+        let chain = Chain::get_chain(self, chain_name)?;
+        let trailers = self.commit_trailers(chain_name, signoff)?;
+
+        // ensure root branch exists
+        if !self.git_root_exists(&chain.root_branch)? {
+            eprintln!("Root branch does not exist: {}", chain.root_branch.bold());
+            exit(1);
+        }
+
+        // ensure each branch exists
+        if let Some(branch) = chain.missing_branches.first() {
+            eprintln!("Branch does not exist: {}", branch.branch_name.bold());
+            eprintln!(
+                "To remove missing branches from the chain, run: {} prune",
+                executable_name().bold()
+            );
+            exit(1);
+        }
+
+        let indices = 0..chain.branches.len();
+        self.ensure_related_histories(
+            &chain,
+            &indices,
+            allow_unrelated,
+            "cherry-pick commits for",
+        )?;
+
+        if self.dirty_working_directory()? {
+            eprintln!(
+                "🛑 Unable to cherry-pick commits for the chain: {}",
+                chain.name.bold()
+            );
+            eprintln!("You have uncommitted changes in your working directory.");
+            eprintln!("Please commit or stash them.");
+            exit(1);
+        }
+
+        let original_branch = OriginalBranch::new(self, &chain.name, "cherry", stay)?;
+
+        let mut num_of_commits = 0;
+
+        // Unlike rebase, the existing commits of each branch are kept as they are;
+        // the new commits of its parent branch are cherry-picked on top of the branch.
+        for branch in &chain.branches {
+            let parent_branch = chain.parent_branch_name(branch);
+
+            if !self.shares_history(&parent_branch, &branch.branch_name)? {
+                println!();
+                println!(
+                    "⚠️  Branch {} shares no history with {}. Skipping.",
+                    branch.branch_name.bold(),
+                    parent_branch.bold()
+                );
+                continue;
+            }
+
+            let commits = self.commits_to_cherry_pick(&parent_branch, &branch.branch_name, None)?;
+            if commits.is_empty() {
+                continue;
+            }
+
+            self.checkout_branch(&branch.branch_name)?;
+
+            let strategy_option_args = branch.strategy_option_args(self, None)?;
+            let mut cherry_pick_args = vec!["cherry-pick", "--allow-empty"];
+            cherry_pick_args.extend(strategy_option_args.iter().map(String::as_str));
+            cherry_pick_args.extend(commits.iter().map(|commit| commit.as_str()));
+
+            let command = format!("git {}", cherry_pick_args.join(" "));
+            let output = Command::new("git")
+                .args(&cherry_pick_args)
+                .logged_output()
+                .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+            println!();
+            println!(
+                "Cherry-picking {} commits of {} onto {}",
+                commits.len(),
+                parent_branch.bold(),
+                branch.branch_name.bold()
+            );
+
+            match self.repo.state() {
+                RepositoryState::Clean => {
+                    if !output.status.success() {
+                        eprintln!("Command returned non-zero exit status: {}", command);
+                        eprintln!("It returned: {}", output.status.code().unwrap());
+                        io::stdout().write_all(&output.stdout).unwrap();
+                        io::stderr().write_all(&output.stderr).unwrap();
+                        original_branch.exit(1);
+                    }
+                    io::stdout().write_all(&output.stdout).unwrap();
+                    io::stderr().write_all(&output.stderr).unwrap();
+
+                    trailers.add_to_new_commits(commits.len())?;
+                    num_of_commits += commits.len();
+                }
+                _ => {
+                    print_cherry_pick_error(
+                        &self.executable_name,
+                        &branch.branch_name,
+                        &parent_branch,
+                    );
+                    original_branch.exit(1);
+                }
+            }
+        }
+
+        original_branch.switch_back()?;
+
+        println!();
+        if num_of_commits > 0 {
+            println!(
+                "🎉 Successfully cherry-picked {} commits onto the branches of chain {}",
+                num_of_commits,
+                chain.name.bold()
+            );
+        } else {
+            println!("Chain {} is already up-to-date.", chain.name.bold());
+        }
+
+        Ok(())
+    }
+
+    fn squash_branch(
+        &self,
+        chain_name: Option<&str>,
+        message: Option<&str>,
+        signoff: bool,
+    ) -> Result<(), Error> {
+        let (chain, branch) = self.selected_branch(chain_name, None)?;
+        self.ensure_checked_out(&chain, &branch, "squash the branch")?;
+        let trailers = self.commit_trailers(&chain.name, signoff)?;
+        let commit_args = trailers.commit_args();
+        let trailer_args = trailers.rebase_args();
+
+        // ensure each branch exists
+        if let Some(missing_branch) = chain.missing_branches.first() {
+            eprintln!(
+                "Branch does not exist: {}",
+                missing_branch.branch_name.bold()
+            );
+            eprintln!(
+                "To remove missing branches from the chain, run: {} prune",
+                executable_name().bold()
+            );
+            exit(1);
         }
+
+        if self.dirty_working_directory()? {
+            eprintln!("🛑 Unable to squash branch: {}", branch.branch_name.bold());
+            eprintln!("You have uncommitted changes in your working directory.");
+            eprintln!("Please commit or stash them.");
+            exit(1);
+        }
+
+        let parent_branch = chain.parent_branch_name(&branch);
+        let common_point = self.smart_merge_base(&parent_branch, &branch.branch_name)?;
+
+        // commits unique to the branch, oldest first
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+        revwalk.push_ref(&format!("refs/heads/{}", branch.branch_name))?;
+        revwalk.hide(self.repo.revparse_single(&common_point)?.id())?;
+
+        let mut messages = vec![];
+        for oid in revwalk {
+            let commit = self.repo.find_commit(oid?)?;
+            let message = String::from_utf8_lossy(commit.message_bytes()).to_string();
+            messages.push(message.trim().to_string());
+        }
+
+        if messages.len() < 2 {
+            println!(
+                "Branch {} has {} commits of its own. Nothing to squash.",
+                branch.branch_name.bold(),
+                messages.len()
+            );
+            return Ok(());
+        }
+
+        let old_head = self.get_commit_hash_of_branch(&branch.branch_name)?;
+
+        // git reset --soft <common_point>
+        let output = Command::new("git")
+            .arg("reset")
+            .arg("--soft")
+            .arg(&common_point)
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: git reset --soft {}", &common_point));
+        if !output.status.success() {
+            eprintln!("Unable to run: git reset --soft {}", &common_point);
+            exit(1);
+        }
+
+        let status = match message {
+            Some(message) => Command::new("git")
+                .args(&commit_args)
+                .arg("--message")
+                .arg(message)
+                .logged_output()
+                .map(|output| output.status),
+            // git takes over the terminal to edit the combined commit message.
+            None => Command::new("git")
+                .args(&commit_args)
+                .arg("--edit")
+                .arg("--message")
+                .arg(messages.join("\n\n"))
+                .logged_status(),
+        }
+        .unwrap_or_else(|_| panic!("Unable to run: git commit"));
+
+        if !status.success() {
+            // e.g. the commit message was left empty; restore the commits of the branch.
+            let _ = Command::new("git")
+                .arg("reset")
+                .arg("--soft")
+                .arg(&old_head)
+                .logged_output();
+            eprintln!("🛑 Unable to squash branch: {}", branch.branch_name.bold());
+            eprintln!("The commit was aborted. The branch was left as it was.");
+            exit(1);
+        }
+
+        println!(
+            "Squashed {} commits of branch {} into one.",
+            messages.len(),
+            branch.branch_name.bold()
+        );
+
+        // Restack the dependent branches: the commits of each branch since the old head of
+        // its parent branch are rebased onto the new head of its parent branch.
+        let dependent_branches: Vec<&Branch> = chain
+            .branches
+            .iter()
+            .skip_while(|other| other.branch_name != branch.branch_name)
+            .skip(1)
+            .collect();
+
+        let original_branch = OriginalBranch::new(self, &chain.name, "squash-branch", false)?;
+
+        let mut prev_branch_name = &branch.branch_name;
+        let mut prev_old_head = old_head;
+        for dependent_branch in &dependent_branches {
+            let old_head = self.get_commit_hash_of_branch(&dependent_branch.branch_name)?;
+
+            let strategy_option_args = dependent_branch.strategy_option_args(self, None)?;
+            let mut rebase_args = vec!["rebase", "--keep-empty"];
+            rebase_args.extend(strategy_option_args.iter().map(String::as_str));
+            rebase_args.extend(trailer_args.iter().map(String::as_str));
+            rebase_args.extend([
+                "--onto",
+                prev_branch_name,
+                &prev_old_head,
+                &dependent_branch.branch_name,
+            ]);
+            let command = format!("git {}", rebase_args.join(" "));
+            let output = Command::new("git")
+                .args(rebase_args)
+                .logged_output()
+                .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+            println!();
+            println!("{}", command);
+
+            match self.repo.state() {
+                RepositoryState::Clean => {
+                    if !output.status.success() {
+                        eprintln!("Command returned non-zero exit status: {}", command);
+                        eprintln!("It returned: {}", output.status.code().unwrap());
+                        io::stdout().write_all(&output.stdout).unwrap();
+                        io::stderr().write_all(&output.stderr).unwrap();
+                        original_branch.exit(1);
+                    }
+                    io::stdout().write_all(&output.stdout).unwrap();
+                    io::stderr().write_all(&output.stderr).unwrap();
+
+                    dependent_branch.record_rebase_base(self, prev_branch_name)?;
+                }
+                _ => {
+                    // Leave the conflict in place to be resolved.
+                    print_rebase_error(
+                        &self.executable_name,
+                        &dependent_branch.branch_name,
+                        prev_branch_name,
+                    );
+                    exit(1);
+                }
+            }
+
+            prev_branch_name = &dependent_branch.branch_name;
+            prev_old_head = old_head;
+        }
+
+        original_branch.switch_back()?;
+
+        println!();
+        println!(
+            "🎉 Successfully squashed branch {} and rebased {} dependent branches.",
+            branch.branch_name.bold(),
+            dependent_branches.len()
+        );
+
+        Ok(())
+    }
+
+    fn new_stack(&self, name: &str, template: &str, root: Option<&str>) -> Result<(), Error> {
+        // Templates are defined in git config, e.g.
+        // chain.template.feature.branches = "{name}/api {name}/ui {name}/tests"
+        // chain.template.feature.root = main
+        let branches_key = format!("chain.template.{}.branches", template);
+        let branch_names: Vec<String> = match self.get_git_config_setting(&branches_key)? {
+            Some(branches) => branches
+                .split_whitespace()
+                .map(|branch| branch.replace("{name}", name))
+                .collect(),
+            None => vec![],
+        };
+
+        if branch_names.is_empty() {
+            eprintln!("🛑 Template is not defined: {}", template.bold());
+            eprintln!(
+                "To define it, run: git config {} \"{{name}}/api {{name}}/ui\"",
+                branches_key
+            );
+            exit(1);
+        }
+
+        validate_chain_name(name);
+
+        if Chain::chain_exists(self, name)? {
+            eprintln!("🛑 Chain already exists: {}", name.bold());
+            exit(1);
+        }
+
+        // Precedence: --root > chain.template.<template>.root > default branch
+        let root_branch = match root {
+            Some(root) => root.to_string(),
+            None => {
+                match self.get_git_config_setting(&format!("chain.template.{}.root", template))? {
+                    Some(root) => root,
+                    None => match self.detect_default_branch()? {
+                        Some(default_branch) => default_branch,
+                        None => {
+                            eprintln!("Unable to detect the default branch of the repository.");
+                            eprintln!("Please provide the root branch with --root.");
+                            exit(1);
+                        }
+                    },
+                }
+            }
+        };
+
+        let root_branch = match self.resolve_root_branch(&root_branch)? {
+            Some(root_branch) => root_branch,
+            None => {
+                eprintln!("Root branch does not exist: {}", root_branch.bold());
+                exit(1);
+            }
+        };
+
+        let mut visited_branches = HashSet::new();
+        for branch_name in &branch_names {
+            validate_branch_name(branch_name);
+            if self.git_local_branch_exists(branch_name)? {
+                eprintln!("🛑 Branch already exists: {}", branch_name.bold());
+                exit(1);
+            }
+            if !visited_branches.insert(branch_name) {
+                eprintln!(
+                    "Branch defined on the template at least twice: {}",
+                    branch_name.bold()
+                );
+                exit(1);
+            }
+        }
+
+        if self.dirty_working_directory()? {
+            eprintln!("🛑 Unable to create the chain: {}", name.bold());
+            eprintln!("You have uncommitted changes in your working directory.");
+            eprintln!("Please commit or stash them.");
+            exit(1);
+        }
+
+        // All branches start at the root; each one is filled in on top of the previous one.
+        let root_commit = self.repo.revparse_single(&root_branch)?.peel_to_commit()?;
+        for branch_name in &branch_names {
+            self.repo.branch(branch_name, &root_commit, false)?;
+            Branch::setup_branch(self, name, &root_branch, branch_name, &SortBranch::Last)?;
+        }
+
+        self.checkout_branch(&branch_names[0])?;
+
+        println!(
+            "🔗 Succesfully created chain {} from template {}",
+            name.bold(),
+            template.bold()
+        );
+        println!();
+
+        let chain = Chain::get_chain(self, name)?;
+        chain.display_list(self, &branch_names[0])?;
+
+        Ok(())
     }
 
-    Ok(())
-}
+    fn backport(
+        &self,
+        chain_name: &str,
+        revision: &str,
+        signoff: bool,
+        stay: bool,
+    ) -> Result<(), Error> {
+        // invariant: chain_name chain exists
+        let chain = Chain::get_chain(self, chain_name)?;
+        let trailers = self.commit_trailers(chain_name, signoff)?;
+
+        // ensure each branch exists
+        if let Some(branch) = chain.missing_branches.first() {
+            eprintln!("Branch does not exist: {}", branch.branch_name.bold());
+            eprintln!(
+                "To remove missing branches from the chain, run: {} prune",
+                executable_name().bold()
+            );
+            exit(1);
+        }
+
+        if self.dirty_working_directory()? {
+            eprintln!(
+                "🛑 Unable to backport {} to the chain: {}",
+                revision.bold(),
+                chain.name.bold()
+            );
+            eprintln!("You have uncommitted changes in your working directory.");
+            eprintln!("Please commit or stash them.");
+            exit(1);
+        }
+
+        // A branch (e.g. a hotfix branch) contributes the commits since it forked off the
+        // root branch; any other revision is a single commit.
+        let commit = match self.repo.revparse_single(revision) {
+            Ok(object) => object.peel_to_commit()?,
+            Err(_) => {
+                eprintln!(
+                    "🛑 Unable to find the branch or commit: {}",
+                    revision.bold()
+                );
+                exit(1);
+            }
+        };
+        let limit = if self.git_branch_exists(revision)? {
+            self.merge_base(&chain.root_branch, revision)?
+        } else if commit.parent_count() > 0 {
+            commit.parent_id(0)?.to_string()
+        } else {
+            eprintln!("🛑 Unable to backport the root commit: {}", revision.bold());
+            exit(1);
+        };
+        let commit = commit.id().to_string();
+
+        let original_branch = OriginalBranch::new(self, &chain.name, "cherry", stay)?;
+
+        println!(
+            "Backporting {} to chain {}",
+            revision.bold(),
+            chain.name.bold()
+        );
+        println!();
+
+        // Unlike cherry, each branch gets the commits on its own; a branch with conflicts is
+        // skipped, so that the other branches are still updated.
+        let mut num_of_updated_branches = 0;
+        let mut failed_branches = vec![];
+        for branch in &chain.branches {
+            let commits =
+                self.commits_to_cherry_pick(&commit, &branch.branch_name, Some(&limit))?;
+            if commits.is_empty() {
+                println!(
+                    "✅ {}: already contains the changes",
+                    branch.branch_name.bold()
+                );
+                continue;
+            }
+
+            self.checkout_branch(&branch.branch_name)?;
+
+            let strategy_option_args = branch.strategy_option_args(self, None)?;
+            let mut cherry_pick_args = vec!["cherry-pick", "--allow-empty"];
+            cherry_pick_args.extend(strategy_option_args.iter().map(String::as_str));
+            cherry_pick_args.extend(commits.iter().map(|commit| commit.as_str()));
+
+            let command = format!("git {}", cherry_pick_args.join(" "));
+            let output = Command::new("git")
+                .args(&cherry_pick_args)
+                .logged_output()
+                .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+            if output.status.success() && self.repo.state() == RepositoryState::Clean {
+                trailers.add_to_new_commits(commits.len())?;
+                println!(
+                    "✅ {}: cherry-picked {} commits",
+                    branch.branch_name.bold(),
+                    commits.len()
+                );
+                num_of_updated_branches += 1;
+                continue;
+            }
+
+            // git cherry-pick --abort
+            if self.repo.state() != RepositoryState::Clean {
+                let output = Command::new("git")
+                    .arg("cherry-pick")
+                    .arg("--abort")
+                    .logged_output()
+                    .unwrap_or_else(|_| panic!("Unable to run: git cherry-pick --abort"));
+                if !output.status.success() {
+                    print_cherry_pick_error(&self.executable_name, &branch.branch_name, revision);
+                    original_branch.exit(1);
+                }
+            }
+
+            println!(
+                "🛑 {}: unable to cherry-pick the commits; the branch was left as it was",
+                branch.branch_name.bold()
+            );
+            failed_branches.push(branch.branch_name.clone());
+        }
+
+        original_branch.switch_back()?;
+
+        println!();
+        if failed_branches.is_empty() {
+            println!(
+                "🎉 Successfully backported {} to {} branches of chain {}",
+                revision.bold(),
+                num_of_updated_branches,
+                chain.name.bold()
+            );
+            return Ok(());
+        }
+
+        eprintln!(
+            "🛑 Unable to backport {} to {} branches of chain {}: {}",
+            revision.bold(),
+            failed_branches.len(),
+            chain.name.bold(),
+            failed_branches.join(", ")
+        );
+        eprintln!("Cherry-pick the commits onto these branches manually, resolving any conflicts.");
+        original_branch.exit(1);
+    }
+
+    fn drop_empty_branches(
+        &self,
+        chain_name: &str,
+        root_branch: &str,
+        branches: &[Branch],
+        non_empty_branches: &[String],
+        delete: bool,
+    ) -> Result<(), Error> {
+        let mut empty_branches: Vec<(Branch, String)> = vec![];
+        for (index, branch) in branches.iter().enumerate() {
+            if !non_empty_branches.contains(&branch.branch_name) {
+                continue;
+            }
+
+            let prev_branch_name = if index == 0 {
+                root_branch
+            } else {
+                &branches[index - 1].branch_name
+            };
+            let (ahead, _behind) = self.ahead_behind(prev_branch_name, &branch.branch_name)?;
+            if ahead == 0 {
+                empty_branches.push((branch.clone(), prev_branch_name.to_string()));
+            }
+        }
+
+        if empty_branches.is_empty() {
+            return Ok(());
+        }
+
+        println!();
+        for (branch, prev_branch_name) in &empty_branches {
+            println!(
+                "⚠️  Branch {} has no commits after the rebase. Its changes are already in {}.",
+                branch.branch_name.bold(),
+                prev_branch_name.bold()
+            );
+        }
+
+        if !prompt::can_confirm(self.prompt_mode) {
+            println!(
+                "To remove a branch from the chain, check it out and run: {} remove",
+                self.executable_name
+            );
+            return Ok(());
+        }
+
+        let branch_names: Vec<String> = empty_branches
+            .iter()
+            .map(|(branch, _)| branch.branch_name.clone())
+            .collect();
+        let question = if delete {
+            format!(
+                "Remove and delete {} empty branches of chain {}? ({})",
+                branch_names.len(),
+                chain_name.bold(),
+                branch_names.join(", ")
+            )
+        } else {
+            format!(
+                "Remove {} empty branches from chain {}? ({})",
+                branch_names.len(),
+                chain_name.bold(),
+                branch_names.join(", ")
+            )
+        };
+        if !self.confirm(&question)? {
+            return Ok(());
+        }
+
+        let current_branch = self.get_current_branch_name()?;
+        for (branch, prev_branch_name) in empty_branches {
+            let branch_name = branch.branch_name.clone();
+            branch.remove_from_chain(self)?;
+            println!(
+                "Removed branch {} from chain {}",
+                branch_name.bold(),
+                chain_name.bold()
+            );
+
+            if delete {
+                if branch_name == current_branch {
+                    // The branch has the same commit as its parent branch,
+                    // unless the parent branch was deleted as well.
+                    let switch_to = if self.git_local_branch_exists(&prev_branch_name)? {
+                        prev_branch_name
+                    } else {
+                        root_branch.to_string()
+                    };
+                    println!("Switching to branch: {}", switch_to.bold());
+                    self.checkout_branch(&switch_to)?;
+                }
+                self.repo
+                    .find_branch(&branch_name, BranchType::Local)?
+                    .delete()?;
+                println!("Deleted branch {}", branch_name.bold());
+            }
+        }
+        self.report_empty_chain(chain_name)?;
+
+        Ok(())
+    }
+
+    fn stashes(&self) -> Result<Vec<ChainStash>, Error> {
+        // git stash list --format=%gd%x09%gs
+        let output = Command::new("git")
+            .arg("stash")
+            .arg("list")
+            .arg("--format=%gd%x09%gs")
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: git stash list"));
+
+        if !output.status.success() {
+            return Err(Error::from_str("Unable to run: git stash list"));
+        }
+
+        Ok(stash::parse_stash_list(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    fn chain_stashes(&self, chain: &Chain) -> Result<Vec<ChainStash>, Error> {
+        // Stashes made with git chain stash push, or made with git stash on a branch of the chain.
+        Ok(self
+            .stashes()?
+            .into_iter()
+            .filter(|stash| match &stash.chain_name {
+                Some(chain_name) => chain_name == &chain.name,
+                None => chain
+                    .branches
+                    .iter()
+                    .any(|branch| branch.branch_name == stash.branch_name),
+            })
+            .collect())
+    }
+
+    fn stash_push(
+        &self,
+        chain_name: Option<&str>,
+        message: Option<&str>,
+        include_untracked: bool,
+    ) -> Result<(), Error> {
+        let (chain, branch) = self.selected_branch(chain_name, None)?;
+        self.ensure_checked_out(&chain, &branch, "stash the changes")?;
+        let branch_name = branch.branch_name.clone();
+
+        let stash_message = stash::stash_message(&branch.chain_name, message);
+        let mut args = vec!["stash", "push", "--message", &stash_message];
+        if include_untracked {
+            args.push("--include-untracked");
+        }
+
+        let command = format!("git {}", args.join(" "));
+        let output = Command::new("git")
+            .args(&args)
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+        if !output.status.success() {
+            eprintln!(
+                "🛑 Unable to stash the changes of branch {}",
+                branch_name.bold()
+            );
+            io::stderr().write_all(&output.stderr).unwrap();
+            exit(1);
+        }
+
+        if String::from_utf8_lossy(&output.stdout).contains("No local changes to save") {
+            println!("No local changes to stash.");
+            return Ok(());
+        }
+
+        println!(
+            "✅ Stashed the changes of branch {} of chain {}: {}",
+            branch_name.bold(),
+            branch.chain_name.bold(),
+            message.unwrap_or("WIP")
+        );
+
+        Ok(())
+    }
+
+    fn stash_pop(
+        &self,
+        chain_name: Option<&str>,
+        reference: Option<&str>,
+        here: bool,
+    ) -> Result<(), Error> {
+        let chain_name = self.resolve_chain_name(chain_name)?;
+        if !Chain::chain_exists(self, &chain_name)? {
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+        let chain = Chain::get_chain(self, &chain_name)?;
+
+        let stash = match reference {
+            Some(reference) => self
+                .stashes()?
+                .into_iter()
+                .find(|stash| stash.reference == reference),
+            // The most recent stash of the chain.
+            None => self.chain_stashes(&chain)?.into_iter().next(),
+        };
+        let stash = match stash {
+            Some(stash) => stash,
+            None => {
+                match reference {
+                    Some(reference) => eprintln!("🛑 Stash does not exist: {}", reference.bold()),
+                    None => eprintln!("🛑 Chain {} has no stashes.", chain_name.bold()),
+                }
+                exit(1);
+            }
+        };
+
+        let current_branch = self.get_current_branch_name()?;
+
+        if stash.branch_name != current_branch {
+            if here {
+                println!(
+                    "⚠️  Stash {} was made on branch {}; popping it onto branch {}.",
+                    stash.reference,
+                    stash.branch_name.bold(),
+                    current_branch.bold()
+                );
+            } else {
+                if !self.git_local_branch_exists(&stash.branch_name)? {
+                    eprintln!(
+                        "🛑 Stash {} was made on branch {}, which no longer exists.",
+                        stash.reference,
+                        stash.branch_name.bold()
+                    );
+                    eprintln!(
+                        "To pop it onto the current branch, run: {} stash pop --here {}",
+                        self.executable_name, stash.reference
+                    );
+                    exit(1);
+                }
+
+                if self.dirty_working_directory()? {
+                    eprintln!(
+                        "🛑 Unable to switch to branch {}, where stash {} was made.",
+                        stash.branch_name.bold(),
+                        stash.reference
+                    );
+                    eprintln!("You have uncommitted changes in your working directory.");
+                    eprintln!(
+                        "To pop it onto the current branch instead, run: {} stash pop --here {}",
+                        self.executable_name, stash.reference
+                    );
+                    exit(1);
+                }
+
+                println!(
+                    "Switching to branch {}, where stash {} was made.",
+                    stash.branch_name.bold(),
+                    stash.reference
+                );
+                self.checkout_branch(&stash.branch_name)?;
+            }
+        }
+
+        let command = format!("git stash pop {}", stash.reference);
+        let output = Command::new("git")
+            .arg("stash")
+            .arg("pop")
+            .arg(&stash.reference)
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+        if !output.status.success() {
+            io::stdout().write_all(&output.stdout).unwrap();
+            io::stderr().write_all(&output.stderr).unwrap();
+            eprintln!("🛑 Unable to pop stash {}", stash.reference);
+            eprintln!(
+                "The stash is kept. Resolve any conflicts, then run: git stash drop {}",
+                stash.reference
+            );
+            exit(1);
+        }
+
+        println!(
+            "✅ Popped stash {} onto branch {}: {}",
+            stash.reference,
+            self.get_current_branch_name()?.bold(),
+            stash.message
+        );
+
+        Ok(())
+    }
+
+    fn stash_list(&self, chain_name: Option<&str>) -> Result<(), Error> {
+        let chain_name = self.resolve_chain_name(chain_name)?;
+        if !Chain::chain_exists(self, &chain_name)? {
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+        let chain = Chain::get_chain(self, &chain_name)?;
+
+        let stashes = self.chain_stashes(&chain)?;
+        if stashes.is_empty() {
+            println!("Chain {} has no stashes.", chain_name.bold());
+            return Ok(());
+        }
+
+        for stash in stashes {
+            println!(
+                "{} {}: {}",
+                stash.reference,
+                stash.branch_name.bold(),
+                stash.message
+            );
+        }
+
+        Ok(())
+    }
+
+    fn dirty_working_directory(&self) -> Result<bool, Error> {
+        // libgit2 reports the files outside of the sparse-checkout patterns as deleted, and needs
+        // the contents of the files missing from a partial clone to compare them.
+        if partial::needs_git_for_worktree(&self.repo) {
+            let output = Command::new("git")
+                .args(["status", "--porcelain", "--untracked-files=no"])
+                .logged_output()
+                .map_err(|err| Error::from_str(&format!("Unable to run git status: {}", err)))?;
+            if !output.status.success() {
+                return Err(Error::from_str(
+                    String::from_utf8_lossy(&output.stderr).trim(),
+                ));
+            }
+            return Ok(!output.stdout.is_empty());
+        }
+
+        // perform equivalent to git diff-index HEAD
+        let obj = self.repo.revparse_single("HEAD")?;
+        let tree = obj.peel(ObjectType::Tree)?;
+
+        // This is used for diff formatting for diff-index. But we're only interested in the diff stats.
+        // let mut opts = DiffOptions::new();
+        // opts.id_abbrev(40);
+
+        let diff = self
+            .repo
+            .diff_tree_to_workdir_with_index(tree.as_tree(), None)?;
+
+        let diff_stats = diff.stats()?;
+        let has_changes = diff_stats.files_changed() > 0
+            || diff_stats.insertions() > 0
+            || diff_stats.deletions() > 0;
+
+        Ok(has_changes)
+    }
+
+    fn backup(&self, chain_name: &str, include_root: bool) -> Result<(), Error> {
+        if Chain::chain_exists(self, chain_name)? {
+            let chain = Chain::get_chain(self, chain_name)?;
+
+            if self.dirty_working_directory()? {
+                eprintln!(
+                    "🛑 Unable to back up branches for the chain: {}",
+                    chain.name.bold()
+                );
+                eprintln!("You have uncommitted changes in your working directory.");
+                eprintln!("Please commit or stash them.");
+                exit(1);
+            }
+
+            let orig_branch = self.get_current_branch_name_if_attached()?;
+
+            chain.backup(self, include_root)?;
+
+            if let Some(orig_branch) = orig_branch {
+                let current_branch = self.get_current_branch_name()?;
+
+                if current_branch != orig_branch {
+                    println!("Switching back to branch: {}", orig_branch.bold());
+                    self.checkout_branch(&orig_branch)?;
+                }
+            }
+
+            println!("🎉 Successfully backed up chain: {}", chain.name.bold());
+
+            if let Some(retention) = self.get_backup_retention()? {
+                let pruned_backups = self.prune_backups(chain_name, retention, false)?;
+                if !pruned_backups.is_empty() {
+                    println!(
+                        "Removed {} old backups of chain: {}",
+                        pruned_backups.len(),
+                        chain_name.bold()
+                    );
+                }
+            }
+        } else {
+            eprintln!("Unable to back up chain.");
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+        Ok(())
+    }
+
+    fn get_backup_retention(&self) -> Result<Option<usize>, Error> {
+        match self.get_git_config_setting("chain.backupRetention")? {
+            None => Ok(None),
+            Some(value) => match value.parse::<usize>() {
+                Ok(retention) => Ok(Some(retention)),
+                Err(_) => Err(Error::from_str(&format!(
+                    "Invalid value {} for {}. Expected the number of backups to keep.",
+                    value.bold(),
+                    "chain.backupRetention".bold()
+                ))),
+            },
+        }
+    }
+
+    fn get_backups(&self, chain_name: &str) -> Result<Vec<(String, Vec<String>)>, Error> {
+        // Backups of a chain, from oldest to newest, with their backup branches.
+        let prefix = backup_prefix(chain_name);
+        let timestamp_regex = Regex::new(r"^\d{8}T\d{6}(-\d+)?$").unwrap();
+
+        let mut backups: HashMap<String, Vec<String>> = HashMap::new();
+        for branch in self.repo.branches(Some(BranchType::Local))? {
+            let (branch, _branch_type) = branch?;
+            let branch_name = match branch.name()? {
+                Some(branch_name) => branch_name,
+                None => continue,
+            };
+
+            let rest = match branch_name.strip_prefix(&prefix) {
+                Some(rest) => rest,
+                None => continue,
+            };
+
+            if let Some((timestamp, _branch)) = rest.split_once('/') {
+                if timestamp_regex.is_match(timestamp) {
+                    backups
+                        .entry(timestamp.to_string())
+                        .or_default()
+                        .push(branch_name.to_string());
+                }
+            }
+        }
+
+        let mut backups: Vec<(String, Vec<String>)> = backups.into_iter().collect();
+        backups.sort_by_key(|(timestamp, _branches)| backup_sort_key(timestamp));
+        Ok(backups)
+    }
+
+    // The name of a new backup of the chain, which does not overwrite an earlier backup made
+    // within the same second.
+    fn new_backup_timestamp(&self, chain_name: &str) -> Result<String, Error> {
+        let timestamp = backup_timestamp();
+        let backups = self.get_backups(chain_name)?;
+        let is_taken = |candidate: &str| -> Result<bool, Error> {
+            Ok(backups
+                .iter()
+                .any(|(backup_timestamp, _branches)| backup_timestamp == candidate)
+                || self
+                    .read_backup_definition(chain_name, candidate)?
+                    .is_some())
+        };
+
+        if !is_taken(&timestamp)? {
+            return Ok(timestamp);
+        }
+        let mut counter = 2;
+        loop {
+            let candidate = format!("{}-{}", timestamp, counter);
+            if !is_taken(&candidate)? {
+                return Ok(candidate);
+            }
+            counter += 1;
+        }
+    }
+
+    fn list_backups(&self, chain_name: &str) -> Result<(), Error> {
+        let backups = self.get_backups(chain_name)?;
+
+        if backups.is_empty() {
+            println!("No backups of chain: {}", chain_name.bold());
+            return Ok(());
+        }
+
+        println!("Backups of chain: {}", chain_name.bold());
+        for (timestamp, branches) in backups.iter().rev() {
+            // Backups made with --include-root also hold the root branch, as named by the chain
+            // definition of the backup.
+            let root_backup = match self.read_backup_definition(chain_name, timestamp)? {
+                Some(definition) => format!(
+                    "{}{}/{}",
+                    backup_prefix(chain_name),
+                    timestamp,
+                    definition.root_branch
+                ),
+                None => String::new(),
+            };
+
+            println!();
+            println!("{}", timestamp.bold());
+            for branch in branches {
+                if branch == &root_backup {
+                    println!("    {} (root branch)", branch);
+                } else {
+                    println!("    {}", branch);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // The chain definition stored with a backup; backups made by older versions have none.
+    fn read_backup_definition(
+        &self,
+        chain_name: &str,
+        timestamp: &str,
+    ) -> Result<Option<ChainDefinition>, Error> {
+        let reference_name = publish::backup_ref(chain_name, timestamp);
+        match self.repo.find_reference(&reference_name) {
+            Ok(_) => Ok(Some(self.read_definition_ref(&reference_name)?)),
+            Err(ref e) if e.code() == ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn restore_backup(
+        &self,
+        chain_name: &str,
+        timestamp: &str,
+        include_root: bool,
+    ) -> Result<(), Error> {
+        let backup_branches = match self
+            .get_backups(chain_name)?
+            .into_iter()
+            .find(|(backup_timestamp, _branches)| backup_timestamp == timestamp)
+        {
+            Some((_timestamp, branches)) => branches,
+            None => {
+                eprintln!(
+                    "🛑 Chain {} has no backup {}",
+                    chain_name.bold(),
+                    timestamp.bold()
+                );
+                eprintln!(
+                    "To list the backups of the chain, run: {} backup --list --chain {}",
+                    self.executable_name, chain_name
+                );
+                exit(1);
+            }
+        };
+
+        let definition = match self.read_backup_definition(chain_name, timestamp)? {
+            Some(definition) => definition,
+            None => {
+                eprintln!(
+                    "🛑 Backup {} of chain {} has no chain definition, as it was made by an older version.",
+                    timestamp.bold(),
+                    chain_name.bold()
+                );
+                eprintln!(
+                    "To restore a branch from it, run: git branch --force <branch> <backup_branch>"
+                );
+                exit(1);
+            }
+        };
+
+        if self.dirty_working_directory()? {
+            eprintln!(
+                "🛑 Unable to restore chain {} from backup {}",
+                chain_name.bold(),
+                timestamp.bold()
+            );
+            eprintln!("You have uncommitted changes in your working directory.");
+            eprintln!("Please commit or stash them.");
+            exit(1);
+        }
+
+        // The branches of the backup, by the name of the branch that they are restored to.
+        let prefix = format!("{}{}/", backup_prefix(chain_name), timestamp);
+        let mut restored_branches = vec![];
+        for backup_branch in &backup_branches {
+            let branch_name = backup_branch.strip_prefix(&prefix).unwrap().to_string();
+            if branch_name == definition.root_branch {
+                if !include_root {
+                    continue;
+                }
+                // e.g. origin/main, which is updated by fetching.
+                if !self.git_local_branch_exists(&branch_name)?
+                    && self.git_remote_branch_exists(&branch_name)?
+                {
+                    eprintln!(
+                        "⚠️  Not restoring root branch {}: it is a remote-tracking branch.",
+                        branch_name.bold()
+                    );
+                    continue;
+                }
+            } else if !definition.branches.contains(&branch_name) {
+                continue;
+            }
+            restored_branches.push((branch_name, backup_branch.clone()));
+        }
+
+        for branch_name in &definition.branches {
+            if !restored_branches
+                .iter()
+                .any(|(name, _)| name == branch_name)
+            {
+                eprintln!(
+                    "🛑 Backup {} has no backup branch of branch {}",
+                    timestamp.bold(),
+                    branch_name.bold()
+                );
+                exit(1);
+            }
+            if let BranchSearchResult::Branch(branch) =
+                Branch::get_branch_with_chain(self, branch_name)?
+            {
+                if branch.chain_name != chain_name {
+                    eprintln!(
+                        "🛑 Branch {} is part of chain {} since the backup.",
+                        branch_name.bold(),
+                        branch.chain_name.bold()
+                    );
+                    eprintln!(
+                        "To restore it, remove it from that chain first: {} remove --chain {}",
+                        self.executable_name, branch.chain_name
+                    );
+                    exit(1);
+                }
+            }
+        }
+
+        // A checked out branch cannot be moved, so HEAD is detached meanwhile.
+        let current_branch = self.get_current_branch_name_if_attached()?;
+        let checked_out = restored_branches
+            .iter()
+            .any(|(branch_name, _backup)| current_branch.as_deref() == Some(branch_name.as_str()));
+        if checked_out {
+            let head = self.repo.head()?.peel_to_commit()?;
+            self.repo.set_head_detached(head.id())?;
+        }
+
+        for (branch_name, backup_branch) in &restored_branches {
+            let commit = self.repo.revparse_single(backup_branch)?.peel_to_commit()?;
+            self.repo.branch(branch_name, &commit, true)?;
+            println!(
+                "Restored branch {} from {}",
+                branch_name.bold(),
+                backup_branch
+            );
+        }
+
+        if let (true, Some(current_branch)) = (checked_out, &current_branch) {
+            self.checkout_branch(current_branch)?;
+        }
+
+        // The membership of the branches; their other metadata (e.g. notes) is kept.
+        if Chain::chain_exists(self, chain_name)? {
+            let chain = Chain::get_chain(self, chain_name)?;
+            for branch in chain.branches.into_iter().chain(chain.missing_branches) {
+                if !definition.branches.contains(&branch.branch_name) {
+                    println!(
+                        "Removed branch {} from the chain",
+                        branch.branch_name.bold()
+                    );
+                    branch.remove_from_chain(self)?;
+                }
+            }
+        }
+        let chain_orders = spread_chain_orders(definition.branches.len());
+        for (branch_name, chain_order) in definition.branches.iter().zip(chain_orders) {
+            self.set_git_config(&chain_order_key(branch_name), &chain_order)?;
+            self.set_git_config(&root_branch_key(branch_name), &definition.root_branch)?;
+            self.set_git_config(&chain_name_key(branch_name), chain_name)?;
+        }
+        let chain = Chain::get_chain(self, chain_name)?;
+        chain.set_scope(self, definition.scope.as_deref())?;
+
+        println!(
+            "🔗 Succesfully restored chain {} from backup {}",
+            chain_name.bold(),
+            timestamp.bold()
+        );
+        println!();
+
+        let current_branch = self
+            .get_current_branch_name_if_attached()?
+            .unwrap_or_default();
+        chain.display_list(self, &current_branch)?;
+
+        Ok(())
+    }
+
+    fn prune_backups(
+        &self,
+        chain_name: &str,
+        keep: usize,
+        dry_run: bool,
+    ) -> Result<Vec<String>, Error> {
+        let backups = self.get_backups(chain_name)?;
+        let num_to_prune = backups.len().saturating_sub(keep);
+
+        let mut pruned_backups = vec![];
+        for (timestamp, branches) in backups.into_iter().take(num_to_prune) {
+            if dry_run {
+                pruned_backups.push(timestamp);
+                continue;
+            }
+            for branch_name in branches {
+                let mut branch = self.repo.find_branch(&branch_name, BranchType::Local)?;
+                branch.delete()?;
+            }
+            match self
+                .repo
+                .find_reference(&publish::backup_ref(chain_name, &timestamp))
+            {
+                Ok(mut reference) => reference.delete()?,
+                Err(ref e) if e.code() == ErrorCode::NotFound => {}
+                Err(e) => return Err(e),
+            }
+            pruned_backups.push(timestamp);
+        }
+
+        Ok(pruned_backups)
+    }
+
+    // Check the invariants that rewriting the branches of a chain (e.g. rebase or cherry) must keep:
+    // each branch is based on its parent branch, no branch lost commits compared to the last
+    // backup of the chain, and the working directory is clean.
+    fn verify(&self, chain_name: &str, options: &VerifyOptions) -> Result<(), Error> {
+        let chain = Chain::get_chain(self, chain_name)?;
+        let indices = chain
+            .branch_indices(&options.range)
+            .unwrap_or(0..chain.branches.len());
+        let backup_timestamp = self
+            .get_backups(chain_name)?
+            .pop()
+            .map(|(timestamp, _branches)| timestamp);
+        let backup_of = |branch_name: &str| -> Result<Option<String>, Error> {
+            let timestamp = match &backup_timestamp {
+                Some(timestamp) => timestamp,
+                None => return Ok(None),
+            };
+            let backup_branch =
+                format!("{}{}/{}", backup_prefix(chain_name), timestamp, branch_name);
+            if self.git_local_branch_exists(&backup_branch)? {
+                Ok(Some(backup_branch))
+            } else {
+                Ok(None)
+            }
+        };
+
+        // Each problem, with the commits involved.
+        let mut problems: Vec<(String, Vec<String>)> = vec![];
+
+        let root_exists = self.git_root_exists(&chain.root_branch)?;
+        if !root_exists {
+            problems.push((
+                format!("Root branch does not exist: {}", chain.root_branch.bold()),
+                vec![],
+            ));
+        }
+        for branch in &chain.missing_branches {
+            problems.push((
+                format!("Branch does not exist: {}", branch.branch_name.bold()),
+                vec![],
+            ));
+        }
+
+        let pairs = chain.branch_pairs();
+        for pair in &pairs[indices] {
+            let branch_name = &pair.branch.branch_name;
+            let check_parent = if pair.index == 0 {
+                root_exists && !options.ignore_root && !options.ignore_parents
+            } else {
+                !options.ignore_parents
+            };
+            if check_parent && !self.is_ancestor(pair.parent, branch_name)? {
+                problems.push((
+                    format!(
+                        "Branch {} is not based on its parent branch {}",
+                        branch_name.bold(),
+                        pair.parent.bold()
+                    ),
+                    vec![],
+                ));
+            }
+
+            // The commits of the branch when it was backed up, which are after the commits of its
+            // parent branch when it was backed up.
+            if pair.index == 0 && !root_exists {
+                continue;
+            }
+            let backup_branch = match backup_of(branch_name)? {
+                Some(backup_branch) => backup_branch,
+                None => continue,
+            };
+            let backup_parent = if pair.index == 0 {
+                None
+            } else {
+                backup_of(pair.parent)?
+            };
+            let limit = backup_parent.as_deref().unwrap_or(pair.parent);
+
+            // Commits without an equivalent change in the branch are lost, unless they landed on
+            // the root branch.
+            let mut lost_commits =
+                self.commits_to_cherry_pick(&backup_branch, branch_name, Some(limit))?;
+            if root_exists && !lost_commits.is_empty() {
+                let not_landed =
+                    self.commits_to_cherry_pick(&backup_branch, &chain.root_branch, Some(limit))?;
+                lost_commits.retain(|commit| not_landed.contains(commit));
+            }
+            if !lost_commits.is_empty() {
+                let commits = lost_commits
+                    .iter()
+                    .map(|commit| {
+                        let commit = self.repo.revparse_single(commit)?.peel_to_commit()?;
+                        let short_id = commit.as_object().short_id()?;
+                        Ok(format!(
+                            "{} {}",
+                            short_id.as_str().unwrap_or_default().yellow(),
+                            commit.summary().unwrap_or_default()
+                        ))
+                    })
+                    .collect::<Result<Vec<String>, Error>>()?;
+                problems.push((
+                    format!(
+                        "Branch {} lost {} commits of its backup {}",
+                        branch_name.bold(),
+                        lost_commits.len(),
+                        backup_branch.bold()
+                    ),
+                    commits,
+                ));
+            }
+        }
+
+        if !options.ignore_worktree {
+            if let Some((operation, _resolution)) = self.operation_in_progress() {
+                problems.push((format!("A {} is in progress.", operation.bold()), vec![]));
+            }
+            if self.dirty_working_directory()? {
+                problems.push((
+                    "You have uncommitted changes in your working directory.".to_string(),
+                    vec![],
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            match &backup_timestamp {
+                Some(timestamp) => println!(
+                    "✅ Verified chain {}: every branch is based on its parent branch, and no commits were lost since backup {}.",
+                    chain.name.bold(),
+                    timestamp.bold()
+                ),
+                None => {
+                    println!(
+                        "✅ Verified chain {}: every branch is based on its parent branch.",
+                        chain.name.bold()
+                    );
+                    println!(
+                        "The chain has no backups to detect lost commits. To back it up, run: {} backup",
+                        self.executable_name
+                    );
+                }
+            }
+            return Ok(());
+        }
+
+        eprintln!("🛑 Chain {} failed verification:", chain.name.bold());
+        for (problem, commits) in &problems {
+            eprintln!("  - {}", problem);
+            for commit in commits {
+                eprintln!("      {}", commit);
+            }
+        }
+        eprintln!();
+
+        let timestamp = match &backup_timestamp {
+            Some(timestamp) => timestamp,
+            None => {
+                eprintln!("The chain has no backups to restore.");
+                eprintln!("To find the previous commits of a branch, run: git reflog <branch>");
+                exit(1);
+            }
+        };
+        let restored_branches = chain
+            .branches
+            .iter()
+            .map(|branch| {
+                Ok(backup_of(&branch.branch_name)?.map(|backup| (&branch.branch_name, backup)))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let current_branch = self.get_current_branch_name_if_attached()?;
+        let checked_out = restored_branches
+            .iter()
+            .flatten()
+            .any(|(branch_name, _backup)| current_branch.as_deref() == Some(branch_name.as_str()));
+
+        eprintln!(
+            "To restore the branches of the chain from backup {}, run:",
+            timestamp.bold()
+        );
+        if let Some((_operation, resolution)) = self.operation_in_progress() {
+            // e.g. git rebase --continue or git rebase --abort
+            let abort = resolution.rsplit(" or ").next().unwrap_or(resolution);
+            eprintln!("    {}", abort);
+        }
+        if checked_out {
+            eprintln!("    git checkout --detach");
+        }
+        for (branch_name, backup) in restored_branches.iter().flatten() {
+            eprintln!("    git branch --force {} {}", branch_name, backup);
+        }
+        if let (true, Some(current_branch)) = (checked_out, &current_branch) {
+            eprintln!("    git checkout {}", current_branch);
+        }
+        exit(1);
+    }
+
+    fn push(&self, chain_name: &str, options: &PushOptions) -> Result<(), Error> {
+        if Chain::chain_exists(self, chain_name)? {
+            let chain = Chain::get_chain(self, chain_name)?;
+            let indices = chain.resolve_branch_range(&options.range, "push");
+
+            // Fail before pushing anything.
+            if options.create_pr {
+                self.ensure_gh_available("create pull requests");
+            }
+
+            if options.force_push && options.check_pr {
+                self.check_pull_requests_before_force_push(
+                    &chain,
+                    &chain.branches[indices.clone()],
+                    options.strict,
+                )?;
+            }
+
+            let branches_pushed = chain.push(self, options, indices)?;
+
+            println!("Pushed {} branches.", format!("{}", branches_pushed).bold());
+
+            if options.create_pr {
+                println!();
+                let draft_mode = if options.draft {
+                    DraftMode::All
+                } else {
+                    DraftMode::None
+                };
+                let pr_options = PrOptions {
+                    draft_mode,
+                    stack_order_labels: false,
+                    update_base: false,
+                    open: false,
+                    strict: false,
+                };
+                self.pr(chain_name, &pr_options)?;
+            }
+        } else {
+            eprintln!("Unable to push branches of the chain.");
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+        Ok(())
+    }
+
+    fn check_pull_requests_before_force_push(
+        &self,
+        chain: &Chain,
+        branches: &[Branch],
+        strict: bool,
+    ) -> Result<(), Error> {
+        let mut num_of_issues = 0;
+        // Pull requests whose review state could not be checked.
+        let mut num_of_unknown = 0;
+
+        for branch in branches {
+            let pull_request = match self.find_pull_request(&branch.branch_name) {
+                Ok(Some(pull_request)) => pull_request,
+                Ok(None) => continue,
+                Err(GhError::Unavailable) => {
+                    eprintln!("⚠️  Unable to check pull requests: gh is not installed.");
+                    if strict {
+                        self.exit_review_state_unknown(chain);
+                    }
+                    return Ok(());
+                }
+                Err(GhError::Offline) => {
+                    num_of_unknown += 1;
+                    continue;
+                }
+                Err(GhError::Failed(message)) => {
+                    eprintln!(
+                        "⚠️  Unable to check the pull request of branch {}: {}",
+                        branch.branch_name.bold(),
+                        message
+                    );
+                    num_of_unknown += 1;
+                    continue;
+                }
+            };
+
+            if pull_request.state != "OPEN" {
+                continue;
+            }
+
+            if pull_request.review_decision == "APPROVED" {
+                num_of_issues += 1;
+                eprintln!(
+                    "⚠️  Force pushing {} may invalidate the approved reviews of PR #{}: {}",
+                    branch.branch_name.bold(),
+                    pull_request.number,
+                    pull_request.url
+                );
+            }
+
+            if pull_request.mergeable == "CONFLICTING" {
+                num_of_issues += 1;
+                eprintln!(
+                    "⚠️  PR #{} of branch {} has merge conflicts that will not be resolved automatically: {}",
+                    pull_request.number,
+                    branch.branch_name.bold(),
+                    pull_request.url
+                );
+            }
+        }
+
+        // Offline, pull requests are read from the cache, which may be out of date.
+        if strict && (num_of_unknown > 0 || gh::offline_reason().is_some()) {
+            self.exit_review_state_unknown(chain);
+        }
+
+        if strict && num_of_issues > 0 {
+            eprintln!(
+                "🛑 Not force pushing branches of the chain: {}",
+                chain.name.bold()
+            );
+            eprintln!("Re-run without --strict to push anyway.");
+            exit(1);
+        }
+
+        Ok(())
+    }
+
+    fn exit_review_state_unknown(&self, chain: &Chain) -> ! {
+        eprintln!(
+            "🛑 Not force pushing branches of the chain: {}",
+            chain.name.bold()
+        );
+        eprintln!("The review state of the pull requests could not be checked with gh.");
+        eprintln!("Re-run without --strict to push anyway.");
+        exit(1);
+    }
+
+    fn prune(&self, chain_name: &str, dry_run: bool) -> Result<(), Error> {
+        if Chain::chain_exists(self, chain_name)? {
+            let chain = Chain::get_chain(self, chain_name)?;
+
+            if !dry_run {
+                let candidates = chain.prune(self, true, true)?;
+                if !candidates.is_empty() {
+                    // Their commits are not on the root branch: they may not have landed.
+                    let mut warned = false;
+                    for branch_name in &candidates {
+                        if self.upstream_gone(branch_name)?
+                            && !self.is_ancestor(branch_name, &chain.root_branch)?
+                        {
+                            println!(
+                                "⚠️  The upstream branch of {} is gone, but its commits are not on {}.",
+                                branch_name.bold(),
+                                chain.root_branch.bold()
+                            );
+                            warned = true;
+                        }
+                    }
+                    if warned {
+                        println!();
+                    }
+                    let question = format!(
+                        "Remove {} {} from chain {}? ({})",
+                        candidates.len(),
+                        if candidates.len() == 1 {
+                            "branch"
+                        } else {
+                            "branches"
+                        },
+                        chain_name.bold(),
+                        candidates.join(", ")
+                    );
+                    // Without a terminal, prune only proceeds if no branch may be unlanded.
+                    let confirmed = if warned {
+                        self.confirm(&question)?
+                    } else {
+                        self.confirm_or_proceed(&question)?
+                    };
+                    if !confirmed {
+                        println!("No branches pruned for chain: {}", chain_name.bold());
+                        return Ok(());
+                    }
+                }
+            }
+
+            let pruned_branches = chain.prune(self, dry_run, true)?;
+            if !pruned_branches.is_empty() {
+                println!(
+                    "Removed the following branches from chain: {}",
+                    chain_name.bold()
+                );
+                println!();
+
+                for branch in &pruned_branches {
+                    println!("{}", branch);
+                }
+
+                println!();
+                println!(
+                    "Pruned {} branches.",
+                    format!("{}", pruned_branches.len()).bold()
+                );
+
+                if dry_run {
+                    println!();
+                    println!("{}", "This was a dry-run, no branches pruned!".bold());
+                } else {
+                    self.restack_children_of_pruned_branches(&chain, &pruned_branches)?;
+                    self.report_empty_chain(chain_name)?;
+                }
+            } else if dry_run {
+                println!(
+                    "This was a dry-run, no branches pruned for chain: {}",
+                    chain_name.bold()
+                );
+            } else {
+                println!("No branches pruned for chain: {}", chain_name.bold());
+            }
+        } else {
+            eprintln!("Unable to prune branches of the chain.");
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+        Ok(())
+    }
+
+    fn rename_prefix(
+        &self,
+        chain_name: &str,
+        renaming: &BranchRenaming,
+        remote: bool,
+        dry_run: bool,
+    ) -> Result<(), Error> {
+        let chain = Chain::get_chain(self, chain_name)?;
+        let renamed_branches = match chain.renamed_branches(self, renaming) {
+            Ok(renamed_branches) => renamed_branches,
+            Err(e) => {
+                eprintln!("🛑 {}", e.message());
+                exit(1);
+            }
+        };
+
+        if renamed_branches.is_empty() {
+            eprintln!(
+                "🛑 No branches of chain {} would be renamed.",
+                chain_name.bold()
+            );
+            exit(1);
+        }
+
+        if dry_run {
+            for (branch_name, new_branch_name) in &renamed_branches {
+                println!(
+                    "Would rename branch {} to {}",
+                    branch_name.bold(),
+                    new_branch_name.bold()
+                );
+            }
+            println!();
+            println!("{}", "This was a dry-run, no branches renamed!".bold());
+            return Ok(());
+        }
+
+        // Chains rooted at a renamed branch follow it.
+        let root_branch_regex = Regex::new(r"^branch\.(?P<branch_name>.+)\.root-branch$").unwrap();
+        let root_branch_configs = self.get_git_configs_matching_key(&root_branch_regex)?;
+
+        for (branch_name, new_branch_name) in &renamed_branches {
+            // git branch -m also moves the chain metadata and the upstream of the branch, which
+            // are stored under branch.<branch_name> in the git config, and updates HEAD.
+            let output = Command::new("git")
+                .arg("branch")
+                .arg("-m")
+                .arg(branch_name)
+                .arg(new_branch_name)
+                .logged_output()
+                .unwrap_or_else(|_| panic!("Unable to rename branch: {}", branch_name.bold()));
+            if !output.status.success() {
+                io::stderr().write_all(&output.stderr).unwrap();
+                eprintln!("🛑 Unable to rename branch: {}", branch_name.bold());
+                exit(1);
+            }
+
+            for (key, root_branch) in &root_branch_configs {
+                if root_branch == branch_name {
+                    self.set_git_config(key, new_branch_name)?;
+                }
+            }
+
+            println!(
+                "Renamed branch {} to {}",
+                branch_name.bold(),
+                new_branch_name.bold()
+            );
+        }
+
+        let mut failed = false;
+        if remote {
+            let mut warned_gh_unavailable = false;
+            for (branch_name, new_branch_name) in &renamed_branches {
+                match self.rename_remote_branch(branch_name, new_branch_name)? {
+                    RemoteRename::Renamed(remote_name) => {
+                        println!(
+                            "Renamed branch {} to {} on remote {}",
+                            branch_name.bold(),
+                            new_branch_name.bold(),
+                            remote_name.bold()
+                        );
+                    }
+                    RemoteRename::Pushed(remote_name) => {
+                        if !warned_gh_unavailable {
+                            warned_gh_unavailable = true;
+                            eprintln!("⚠️  gh is not installed: the pull requests of the renamed branches are not moved to their new names.");
+                        }
+                        println!(
+                            "Pushed branch {} to remote {}, and deleted {}",
+                            new_branch_name.bold(),
+                            remote_name.bold(),
+                            branch_name.bold()
+                        );
+                    }
+                    RemoteRename::Skipped(reason) => {
+                        println!(
+                            "Did not rename branch {} on the remote: {}",
+                            branch_name.bold(),
+                            reason
+                        );
+                    }
+                    RemoteRename::Failed(message) => {
+                        failed = true;
+                        eprintln!(
+                            "🛑 Unable to rename branch {} on the remote: {}",
+                            branch_name.bold(),
+                            message
+                        );
+                    }
+                }
+            }
+        }
+
+        println!(
+            "🔗 Succesfully renamed {} branches of chain {}",
+            renamed_branches.len(),
+            chain_name.bold()
+        );
+        println!();
+        let current_branch = self
+            .get_current_branch_name_if_attached()?
+            .unwrap_or_default();
+        Chain::get_chain(self, chain_name)?.display_list(self, &current_branch)?;
+
+        if failed {
+            exit(1);
+        }
+        Ok(())
+    }
+
+    // Renames the upstream branch of a renamed branch, if it has the old name of the branch: on
+    // GitHub (via gh), which moves its pull requests along, or else by pushing the branch under its
+    // new name and deleting the old one. The branch then tracks the renamed upstream branch.
+    fn rename_remote_branch(
+        &self,
+        branch_name: &str,
+        new_branch_name: &str,
+    ) -> Result<RemoteRename, Error> {
+        let (remote_name, remote_ref, tracking_ref) = match self.upstream_ref(new_branch_name)? {
+            Some(upstream) => upstream,
+            None => return Ok(RemoteRename::Skipped("it has no upstream".to_string())),
+        };
+        if self
+            .get_git_config(&chain_push_ref_key(new_branch_name))?
+            .is_some()
+        {
+            return Ok(RemoteRename::Skipped(
+                "it is pushed to the ref set with git chain push-ref".to_string(),
+            ));
+        }
+        if remote_ref != format!("refs/heads/{}", branch_name) {
+            return Ok(RemoteRename::Skipped(format!(
+                "its upstream branch has another name: {}",
+                remote_ref
+            )));
+        }
+        let new_remote_ref = format!("refs/heads/{}", new_branch_name);
+
+        match gh::rename_branch(branch_name, new_branch_name) {
+            Ok(()) => {
+                self.set_git_config(
+                    &format!("branch.{}.merge", new_branch_name),
+                    &new_remote_ref,
+                )?;
+                // The remote-tracking branch follows, without fetching.
+                if let Some(new_tracking_ref) = self.tracking_ref(&remote_name, &new_remote_ref)? {
+                    if let Ok(mut reference) = self.repo.find_reference(&tracking_ref) {
+                        reference.rename(&new_tracking_ref, true, "git chain rename-prefix")?;
+                    }
+                }
+                Ok(RemoteRename::Renamed(remote_name))
+            }
+            Err(GhError::Unavailable) => {
+                let refspec = format!("{}:{}", new_branch_name, new_remote_ref);
+                let pushed = Command::new("git")
+                    .arg("push")
+                    .arg("--quiet")
+                    .arg("--set-upstream")
+                    .arg(&remote_name)
+                    .arg(&refspec)
+                    .logged_output()
+                    .unwrap_or_else(|_| panic!("Unable to push branch: {}", new_branch_name));
+                if !pushed.status.success() {
+                    return Ok(RemoteRename::Failed(
+                        String::from_utf8_lossy(&pushed.stderr).trim().to_string(),
+                    ));
+                }
+                let deleted = Command::new("git")
+                    .arg("push")
+                    .arg("--quiet")
+                    .arg("--delete")
+                    .arg(&remote_name)
+                    .arg(&remote_ref)
+                    .logged_output()
+                    .unwrap_or_else(|_| panic!("Unable to delete remote branch: {}", branch_name));
+                if !deleted.status.success() {
+                    return Ok(RemoteRename::Failed(
+                        String::from_utf8_lossy(&deleted.stderr).trim().to_string(),
+                    ));
+                }
+                Ok(RemoteRename::Pushed(remote_name))
+            }
+            Err(GhError::Failed(message)) => Ok(RemoteRename::Failed(message)),
+            Err(GhError::Offline) => {
+                Ok(RemoteRename::Failed("GitHub cannot be reached".to_string()))
+            }
+        }
+    }
+
+    fn restack_children_of_pruned_branches(
+        &self,
+        chain: &Chain,
+        pruned_branches: &[String],
+    ) -> Result<(), Error> {
+        if !Chain::chain_exists(self, &chain.name)? {
+            return Ok(());
+        }
+
+        // Branches that were stacked on a pruned branch now depend on the parent of the
+        // pruned branch; they need to be rebased if they are not based on it yet.
+        let pruned_chain = Chain::get_chain(self, &chain.name)?;
+        let mut children = vec![];
+        for branch in &pruned_chain.branches {
+            let old_parent = chain.parent_branch_name(branch);
+            if !pruned_branches.contains(&old_parent) {
+                continue;
+            }
+            let new_parent = pruned_chain.parent_branch_name(branch);
+            if !self.is_ancestor(&new_parent, &branch.branch_name)? {
+                children.push((branch.branch_name.clone(), old_parent, new_parent));
+            }
+        }
+
+        if children.is_empty() {
+            return Ok(());
+        }
+
+        println!();
+        for (branch_name, old_parent, new_parent) in &children {
+            println!(
+                "⚠️  Branch {} was stacked on the pruned branch {}. It needs to be rebased onto {}.",
+                branch_name.bold(),
+                old_parent.bold(),
+                new_parent.bold()
+            );
+        }
+
+        // Without a terminal, prune does not go on to rebase the chain.
+        let question = format!("Rebase chain {} onto the new parents?", chain.name.bold());
+        if !prompt::can_confirm(self.prompt_mode) || !self.confirm(&question)? {
+            println!(
+                "To rebase the chain later, run: {} rebase",
+                self.executable_name
+            );
+            return Ok(());
+        }
+
+        let options = RebaseOptions {
+            step_rebase: false,
+            ignore_root: false,
+            committer_date_is_author_date: false,
+            reset_author_date: false,
+            interactive: false,
+            drop_empty: false,
+            delete_empty: false,
+            worktree: false,
+            keep_base: false,
+            allow_unrelated: false,
+            strategy_option: None,
+            reconcile: ReconcileMode::Off,
+            signoff: false,
+            update_root: false,
+            stay: false,
+            range: BranchRange::default(),
+        };
+        self.rebase(&chain.name, &options)
+    }
+
+    fn get_size_limits(
+        &self,
+        max_commits: Option<&str>,
+        max_changed_lines: Option<&str>,
+    ) -> Result<SizeLimits, Error> {
+        // Precedence: command-line flag > git config
+        let parse = |value: Option<&str>, key: &str| -> Result<Option<usize>, Error> {
+            let value = match value {
+                Some(value) => Some(value.to_string()),
+                None => self.get_git_config_setting(key)?,
+            };
+            match value {
+                // 0 disables the limit.
+                Some(value) => match value.parse::<usize>() {
+                    Ok(0) => Ok(None),
+                    Ok(limit) => Ok(Some(limit)),
+                    Err(_) => Err(Error::from_str(&format!(
+                        "Invalid value for {}: {}",
+                        key, value
+                    ))),
+                },
+                None => Ok(None),
+            }
+        };
+
+        Ok(SizeLimits {
+            max_commits: parse(max_commits, "chain.maxCommits")?,
+            max_changed_lines: parse(max_changed_lines, "chain.maxChangedLines")?,
+        })
+    }
+
+    fn branch_size(&self, parent_branch: &str, branch_name: &str) -> Result<(usize, usize), Error> {
+        // Number of commits and changed lines of the branch on top of its parent branch,
+        // i.e. git diff --shortstat parent_branch...branch
+        let (num_of_commits, _behind) = self.ahead_behind(parent_branch, branch_name)?;
+
+        let (branch_obj, _reference) = self.repo.revparse_ext(branch_name)?;
+        let (parent_obj, _reference) = self.repo.revparse_ext(parent_branch)?;
+        let branch_commit = branch_obj.peel_to_commit()?;
+        let merge_base = self
+            .repo
+            .merge_base(branch_commit.id(), parent_obj.peel_to_commit()?.id())?;
+
+        let base_tree = self.repo.find_commit(merge_base)?.tree()?;
+        let diff =
+            self.repo
+                .diff_tree_to_tree(Some(&base_tree), Some(&branch_commit.tree()?), None)?;
+        // The changed lines are counted from the contents of the changed files.
+        let odb = self.repo.odb()?;
+        let missing_blobs: Vec<String> = diff
+            .deltas()
+            .flat_map(|delta| [delta.old_file().id(), delta.new_file().id()])
+            .filter(|oid| !oid.is_zero() && !odb.exists(*oid))
+            .map(|oid| oid.to_string())
+            .collect();
+        self.fetch_missing_objects(&missing_blobs)?;
+        let stats = diff.stats()?;
+
+        Ok((num_of_commits, stats.insertions() + stats.deletions()))
+    }
+
+    // The paths that would conflict when rebasing a branch onto its parent branch, predicted with an
+    // in-memory merge of both branches, without touching the working directory. This is quick but
+    // approximate: a rebase applies the commits one by one, which may conflict on intermediate
+    // versions of the files, or not conflict on changes that a later commit reverts.
+    fn predict_conflicts(
+        &self,
+        parent_branch: &str,
+        branch_name: &str,
+    ) -> Result<Vec<String>, Error> {
+        let (parent_object, _reference) = self.repo.revparse_ext(parent_branch)?;
+        let (branch_object, _reference) = self.repo.revparse_ext(branch_name)?;
+        let parent_commit = parent_object.peel_to_commit()?;
+        let branch_commit = branch_object.peel_to_commit()?;
+
+        // Branches based on their parent branch, or unrelated to it, are not rebased onto it.
+        let merge_base = match self.repo.merge_base(parent_commit.id(), branch_commit.id()) {
+            Ok(merge_base) if merge_base == parent_commit.id() => return Ok(vec![]),
+            Ok(merge_base) => merge_base,
+            Err(ref e) if e.code() == ErrorCode::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        // The merge reads the files changed on either side.
+        if partial::promisor_remote(&self.repo).is_some() {
+            let base_tree = self.repo.find_commit(merge_base)?.tree()?;
+            let odb = self.repo.odb()?;
+            let mut missing_blobs = vec![];
+            for commit in [&parent_commit, &branch_commit] {
+                let diff =
+                    self.repo
+                        .diff_tree_to_tree(Some(&base_tree), Some(&commit.tree()?), None)?;
+                missing_blobs.extend(
+                    diff.deltas()
+                        .flat_map(|delta| [delta.old_file().id(), delta.new_file().id()])
+                        .filter(|oid| !oid.is_zero() && !odb.exists(*oid))
+                        .map(|oid| oid.to_string()),
+                );
+            }
+            self.fetch_missing_objects(&missing_blobs)?;
+        }
+
+        let index = self
+            .repo
+            .merge_commits(&parent_commit, &branch_commit, None)?;
+        let mut files = vec![];
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+            if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+                files.push(String::from_utf8_lossy(&entry.path).to_string());
+            }
+        }
+        Ok(files)
+    }
+
+    fn fetch_missing_objects(&self, oids: &[String]) -> Result<(), Error> {
+        // libgit2 does not fetch the objects missing from a partial clone on demand.
+        match partial::promisor_remote(&self.repo) {
+            Some(remote_name) => partial::fetch_objects(&remote_name, oids).map_err(|err| {
+                Error::from_str(&format!(
+                    "Unable to fetch missing objects from {}: {}",
+                    remote_name, err
+                ))
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn upstream_ref(&self, branch_name: &str) -> Result<Option<(String, String, String)>, Error> {
+        // The remote, the ref on the remote, and the remote-tracking ref of a branch:
+        // either the upstream of a local branch, or the remote-tracking branch itself.
+        let (remote_name, remote_ref) = if self.git_local_branch_exists(branch_name)? {
+            let remote_name = self.get_git_config(&format!("branch.{}.remote", branch_name))?;
+            let remote_ref = self.get_git_config(&format!("branch.{}.merge", branch_name))?;
+            match (remote_name, remote_ref) {
+                (Some(remote_name), Some(remote_ref)) if remote_name != "." => {
+                    (remote_name, remote_ref)
+                }
+                _ => return Ok(None),
+            }
+        } else if self.git_remote_branch_exists(branch_name)? {
+            let tracking_ref = format!("refs/remotes/{}", branch_name);
+            let remote_name = self.repo.branch_remote_name(&tracking_ref)?;
+            let remote_name = remote_name.as_str().unwrap_or_default().to_string();
+            let prefix = format!("{}/", remote_name);
+            let remote_ref = match branch_name.strip_prefix(&prefix) {
+                Some(name) => format!("refs/heads/{}", name),
+                None => return Ok(None),
+            };
+            (remote_name, remote_ref)
+        } else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .tracking_ref(&remote_name, &remote_ref)?
+            .map(|tracking_ref| (remote_name, remote_ref, tracking_ref)))
+    }
+
+    // The remote-tracking ref that a ref on the remote is fetched into, e.g. refs/remotes/origin/main
+    // for refs/heads/main; None if the fetch refspecs of the remote do not cover it.
+    fn tracking_ref(&self, remote_name: &str, remote_ref: &str) -> Result<Option<String>, Error> {
+        let remote = self.repo.find_remote(remote_name)?;
+        for refspec in remote.refspecs() {
+            if refspec.direction() == git2::Direction::Fetch && refspec.src_matches(remote_ref) {
+                let tracking_ref = refspec.transform(remote_ref)?;
+                return Ok(Some(tracking_ref.as_str().unwrap_or_default().to_string()));
+            }
+        }
+        Ok(None)
+    }
+
+    // Where push sends a branch: the remote, and the ref on the remote.
+    // Like git push, the remote is branch.<branch>.pushRemote, remote.pushDefault, or else the remote
+    // of the upstream branch. The ref is the one set with git chain push-ref, or else the upstream
+    // branch with push.default=upstream, or else the remote.<remote>.push refspecs applied to the
+    // branch, or else the branch of the same name.
+    fn push_destination(&self, branch_name: &str) -> Result<(String, String), Error> {
+        let upstream_remote = self
+            .get_git_config_setting(&format!("branch.{}.remote", branch_name))?
+            .filter(|remote_name| remote_name != ".");
+        let remote_name =
+            match self.get_git_config_setting(&format!("branch.{}.pushRemote", branch_name))? {
+                Some(remote_name) => remote_name,
+                None => match self.get_git_config_setting("remote.pushDefault")? {
+                    Some(remote_name) => remote_name,
+                    None => upstream_remote
+                        .clone()
+                        .unwrap_or_else(|| "origin".to_string()),
+                },
+            };
+
+        if let Some(push_ref) = self.get_git_config(&chain_push_ref_key(branch_name))? {
+            return Ok((remote_name, qualify_push_ref(&push_ref)));
+        }
+
+        let push_default = self.get_git_config_setting("push.default")?;
+        if matches!(push_default.as_deref(), Some("upstream" | "tracking"))
+            && upstream_remote.as_deref() == Some(remote_name.as_str())
+        {
+            if let Some(merge) =
+                self.get_git_config_setting(&format!("branch.{}.merge", branch_name))?
+            {
+                return Ok((remote_name, merge));
+            }
+        }
+
+        let local_ref = format!("refs/heads/{}", branch_name);
+        if let Ok(remote) = self.repo.find_remote(&remote_name) {
+            for refspec in remote.refspecs() {
+                if refspec.direction() == git2::Direction::Push && refspec.src_matches(&local_ref) {
+                    let remote_ref = refspec.transform(&local_ref)?;
+                    if let Some(remote_ref) = remote_ref.as_str() {
+                        return Ok((remote_name, remote_ref.to_string()));
+                    }
+                }
+            }
+        }
+
+        Ok((remote_name, local_ref))
+    }
+
+    // The name of the branch on the remote, e.g. for pull requests. Branches pushed to refs that are
+    // not branches (e.g. refs/for/main on Gerrit) keep their local name.
+    fn remote_branch_name(&self, branch_name: &str) -> Result<String, Error> {
+        let (_remote_name, remote_ref) = self.push_destination(branch_name)?;
+        Ok(remote_ref
+            .strip_prefix("refs/heads/")
+            .unwrap_or(branch_name)
+            .to_string())
+    }
+
+    // The pull request of a branch, looked up by the name of the branch on the remote.
+    fn find_pull_request(&self, branch_name: &str) -> Result<Option<gh::PullRequest>, GhError> {
+        let remote_branch_name = self
+            .remote_branch_name(branch_name)
+            .unwrap_or_else(|_| branch_name.to_string());
+        gh::find_pull_request(&remote_branch_name)
+    }
+
+    fn upstream_gone(&self, branch_name: &str) -> Result<bool, Error> {
+        // The branch has an upstream branch, whose remote-tracking branch no longer exists,
+        // i.e. [gone] in git branch -vv: it was deleted on the remote and pruned by a fetch.
+        if !self.git_local_branch_exists(branch_name)? {
+            return Ok(false);
+        }
+        // Branches pushed with git chain push-ref are gone once the ref they are pushed to is.
+        let tracking_ref = if self
+            .get_git_config(&chain_push_ref_key(branch_name))?
+            .is_some()
+        {
+            let (remote_name, remote_ref) = self.push_destination(branch_name)?;
+            self.tracking_ref(&remote_name, &remote_ref)
+        } else {
+            self.upstream_ref(branch_name).map(|upstream| {
+                upstream.map(|(_remote_name, _remote_ref, tracking_ref)| tracking_ref)
+            })
+        };
+        let tracking_ref = match tracking_ref {
+            Ok(Some(tracking_ref)) => tracking_ref,
+            // e.g. the remote itself was removed
+            Ok(None) | Err(_) => return Ok(false),
+        };
+        match self.repo.find_reference(&tracking_ref) {
+            Ok(_) => Ok(false),
+            Err(ref e) if e.code() == ErrorCode::NotFound => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn record_included_remote_oid(&self, branch_name: &str) -> Result<(), Error> {
+        // Records the tip of the remote-tracking branch, if the branch includes it, for the
+        // safety check of push --force. Commits that were fetched but not included in the branch
+        // are not recorded, so that force pushing does not silently drop them.
+        let tracking_ref = match self.upstream_ref(branch_name)? {
+            Some((_remote_name, _remote_ref, tracking_ref)) => tracking_ref,
+            None => return Ok(()),
+        };
+        let remote_oid = match self.repo.refname_to_id(&tracking_ref) {
+            Ok(remote_oid) => remote_oid,
+            Err(_) => return Ok(()),
+        };
+
+        let local_oid = self.repo.revparse_single(branch_name)?.id();
+        if remote_oid == local_oid || self.repo.graph_descendant_of(local_oid, remote_oid)? {
+            self.set_git_config(&chain_remote_oid_key(branch_name), &remote_oid.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    fn remote_ref_oid(&self, remote_name: &str, remote_ref: &str) -> Result<Option<String>, Error> {
+        // git ls-remote <remote> <ref>
+        let command = format!("git ls-remote {} {}", remote_name, remote_ref);
+        let output = Command::new("git")
+            .arg("ls-remote")
+            .arg(remote_name)
+            .arg(remote_ref)
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+        if !output.status.success() {
+            eprintln!("Command returned non-zero exit status: {}", command);
+            io::stderr().write_all(&output.stderr).unwrap();
+            exit(1);
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .find(|(_oid, name)| name.trim() == remote_ref)
+            .map(|(oid, _name)| oid.trim().to_string()))
+    }
+
+    fn fetch(&self, chain_name: &str) -> Result<(), Error> {
+        if !Chain::chain_exists(self, chain_name)? {
+            eprintln!("Unable to fetch the remote branches of the chain.");
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+
+        let chain = Chain::get_chain(self, chain_name)?;
+
+        // remote -> [(ref on the remote, remote-tracking ref)]
+        let mut targets: Vec<(String, Vec<(String, String)>)> = vec![];
+        let branch_names = std::iter::once(&chain.root_branch)
+            .chain(chain.branches.iter().map(|branch| &branch.branch_name));
+        for branch_name in branch_names {
+            let (remote_name, remote_ref, tracking_ref) = match self.upstream_ref(branch_name)? {
+                Some(upstream) => upstream,
+                None => continue,
+            };
+            match targets.iter_mut().find(|(name, _)| *name == remote_name) {
+                Some((_, refs)) => refs.push((remote_ref, tracking_ref)),
+                None => targets.push((remote_name, vec![(remote_ref, tracking_ref)])),
+            }
+        }
+
+        if targets.is_empty() {
+            println!(
+                "None of the branches of chain {} have an upstream branch.",
+                chain.name.bold()
+            );
+            return Ok(());
+        }
+
+        let short = |oid: &str| oid.chars().take(7).collect::<String>();
+        let mut num_of_updates = 0;
+
+        for (remote_name, refs) in targets {
+            // git ls-remote <remote> <refs>
+            // Only the refs that moved on the remote are fetched.
+            let mut ls_remote_args = vec!["ls-remote".to_string(), remote_name.clone()];
+            ls_remote_args.extend(refs.iter().map(|(remote_ref, _)| remote_ref.clone()));
+
+            let command = format!("git {}", ls_remote_args.join(" "));
+            let output = Command::new("git")
+                .args(&ls_remote_args)
+                .logged_output()
+                .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+            if !output.status.success() {
+                eprintln!("Command returned non-zero exit status: {}", command);
+                io::stderr().write_all(&output.stderr).unwrap();
+                exit(1);
+            }
+
+            let remote_oids: HashMap<String, String> = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| {
+                    let (oid, name) = line.split_once('\t')?;
+                    Some((name.trim().to_string(), oid.trim().to_string()))
+                })
+                .collect();
+
+            let mut refspecs = vec![];
+            let mut updates = vec![];
+            for (remote_ref, tracking_ref) in refs {
+                let local_oid = self
+                    .repo
+                    .refname_to_id(&tracking_ref)
+                    .ok()
+                    .map(|oid| oid.to_string());
+                let name = tracking_ref
+                    .strip_prefix("refs/remotes/")
+                    .unwrap_or(&tracking_ref)
+                    .to_string();
+
+                match (remote_oids.get(&remote_ref), local_oid) {
+                    (Some(remote_oid), Some(local_oid)) if *remote_oid == local_oid => {}
+                    (Some(remote_oid), local_oid) => {
+                        refspecs.push(format!("+{}:{}", remote_ref, tracking_ref));
+                        let status = match local_oid {
+                            Some(local_oid) => {
+                                format!("{}..{}", short(&local_oid), short(remote_oid))
+                            }
+                            None => "(new)".to_string(),
+                        };
+                        updates.push((name, status));
+                    }
+                    (None, Some(_local_oid)) => {
+                        // git fetch --prune
+                        self.repo.find_reference(&tracking_ref)?.delete()?;
+                        updates.push((name, "(deleted on the remote)".to_string()));
+                    }
+                    (None, None) => {}
+                }
+            }
+
+            if !refspecs.is_empty() {
+                let mut fetch_args = vec!["fetch".to_string(), remote_name.clone()];
+                fetch_args.extend(refspecs);
+
+                let command = format!("git {}", fetch_args.join(" "));
+                let output = Command::new("git")
+                    .args(&fetch_args)
+                    .logged_output()
+                    .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+                if !output.status.success() {
+                    eprintln!("Command returned non-zero exit status: {}", command);
+                    io::stderr().write_all(&output.stderr).unwrap();
+                    exit(1);
+                }
+            }
+
+            for (name, status) in updates {
+                println!("{} {}", name.bold(), status);
+                num_of_updates += 1;
+            }
+        }
+
+        for branch in &chain.branches {
+            self.record_included_remote_oid(&branch.branch_name)?;
+        }
+
+        if num_of_updates > 0 {
+            println!();
+            println!(
+                "✅ Updated {} remote branches of chain {}",
+                num_of_updates,
+                chain.name.bold()
+            );
+        } else {
+            println!(
+                "Remote branches of chain {} are already up-to-date.",
+                chain.name.bold()
+            );
+        }
+
+        Ok(())
+    }
+
+    // The branches that exceed the limits, with the limits that they exceed.
+    fn large_branches(
+        &self,
+        chain: &Chain,
+        limits: &SizeLimits,
+    ) -> Result<Vec<(String, Vec<String>)>, Error> {
+        let mut large_branches = vec![];
+        if limits.is_empty() {
+            return Ok(large_branches);
+        }
+
+        for branch in &chain.branches {
+            let parent_branch = chain.parent_branch_name(branch);
+            let (num_of_commits, num_of_changed_lines) =
+                self.branch_size(&parent_branch, &branch.branch_name)?;
+            let violations = limits.check(num_of_commits, num_of_changed_lines);
+            if !violations.is_empty() {
+                large_branches.push((branch.branch_name.clone(), violations));
+            }
+        }
+
+        Ok(large_branches)
+    }
+
+    fn warn_large_branches(&self, large_branches: &[(String, Vec<String>)]) {
+        for (branch_name, violations) in large_branches {
+            println!(
+                "⚠️  Branch {} is too large: {}",
+                branch_name.bold(),
+                violations.join(", ")
+            );
+        }
+    }
+
+    fn size_guard(&self, chain_name: &str, limits: &SizeLimits, strict: bool) -> Result<(), Error> {
+        if !Chain::chain_exists(self, chain_name)? {
+            eprintln!("Unable to check the size of the branches of the chain.");
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+
+        let chain = Chain::get_chain(self, chain_name)?;
+        let mut num_of_large_branches = 0;
+
+        for branch in &chain.branches {
+            let parent_branch = chain.parent_branch_name(branch);
+            let (num_of_commits, num_of_changed_lines) =
+                self.branch_size(&parent_branch, &branch.branch_name)?;
+
+            println!(
+                "{} ⦁ {} commits ⦁ {} changed lines",
+                branch.branch_name.bold(),
+                num_of_commits,
+                num_of_changed_lines
+            );
+
+            let violations = limits.check(num_of_commits, num_of_changed_lines);
+            if !violations.is_empty() {
+                num_of_large_branches += 1;
+                println!("  ⚠️  Branch is too large: {}", violations.join(", "));
+            }
+        }
+
+        if num_of_large_branches > 0 {
+            println!();
+            println!(
+                "⚠️  {} branches of chain {} are too large. Consider splitting them into smaller branches.",
+                num_of_large_branches,
+                chain.name.bold()
+            );
+            if strict {
+                exit(1);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn lint(&self, chain_name: &str, rules: &LintRules, fix: bool) -> Result<(), Error> {
+        if !Chain::chain_exists(self, chain_name)? {
+            eprintln!("Unable to lint the chain.");
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+
+        let num_of_metadata_issues = self.lint_chain_metadata(chain_name, fix)?;
+        if !Chain::chain_exists(self, chain_name)? {
+            return self.report_empty_chain(chain_name);
+        }
+
+        let chain = Chain::get_chain(self, chain_name)?;
+        let mut num_of_violations = 0;
+
+        for (index, branch) in chain.branches.iter().enumerate() {
+            let parent_branch = if index == 0 {
+                &chain.root_branch
+            } else {
+                &chain.branches[index - 1].branch_name
+            };
+
+            let (branch_obj, _reference) = self.repo.revparse_ext(&branch.branch_name)?;
+            let (parent_obj, _reference) = self.repo.revparse_ext(parent_branch)?;
+
+            // commits unique to the branch, i.e. git log parent_branch..branch
+            let mut revwalk = self.repo.revwalk()?;
+            revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+            revwalk.push(branch_obj.peel_to_commit()?.id())?;
+            revwalk.hide(parent_obj.peel_to_commit()?.id())?;
+
+            println!("{}", branch.branch_name.bold());
+
+            let mut branch_violations = 0;
+            for oid in revwalk {
+                let commit = self.repo.find_commit(oid?)?;
+                let message = String::from_utf8_lossy(commit.message_bytes()).to_string();
+                let violations = rules.check(&message);
+
+                if violations.is_empty() {
+                    continue;
+                }
+
+                branch_violations += violations.len();
+                let short_id = commit.as_object().short_id()?;
+                println!(
+                    "  {} {}",
+                    short_id.as_str().unwrap_or_default().yellow(),
+                    message.lines().next().unwrap_or("")
+                );
+                for violation in violations {
+                    println!("    ⚠️  {}", violation);
+                }
+            }
+
+            if branch_violations == 0 {
+                println!("  ✅ No issues");
+            }
+            num_of_violations += branch_violations;
+        }
+
+        println!();
+        if num_of_metadata_issues > 0 {
+            eprintln!(
+                "🛑 Found {} issues in the metadata of chain: {}",
+                num_of_metadata_issues,
+                chain.name.bold()
+            );
+            eprintln!(
+                "To repair them, run: {} lint --fix --chain {}",
+                self.executable_name, chain.name
+            );
+        }
+        if num_of_violations > 0 {
+            eprintln!(
+                "🛑 Found {} commit message issues in chain: {}",
+                num_of_violations,
+                chain.name.bold()
+            );
+            if fix {
+                eprintln!("Commit messages are not reworded by --fix. To reword them, run: git rebase --interactive");
+            }
+        }
+        if num_of_metadata_issues > 0 || num_of_violations > 0 {
+            exit(1);
+        }
+
+        println!(
+            "🎉 All commit messages in chain {} passed",
+            chain.name.bold()
+        );
+        Ok(())
+    }
+
+    // Checks the structure of the chain: branches that no longer exist (the parent of the next
+    // branch is missing), and branches that are left out of the order of the chain or share their
+    // place in it. With fix, they are repaired; returns the number of issues left.
+    fn lint_chain_metadata(&self, chain_name: &str, fix: bool) -> Result<usize, Error> {
+        let (branches, missing_branches, problems) =
+            Chain::get_all_branches_for_chain(self, chain_name)?;
+        if missing_branches.is_empty() && problems.is_empty() {
+            return Ok(0);
+        }
+        // The problems are listed below rather than when loading the chain.
+        metadata::report_once(chain_name);
+
+        let mut ordered_branches: Vec<(&Branch, bool)> = branches
+            .iter()
+            .map(|branch| (branch, false))
+            .chain(missing_branches.iter().map(|branch| (branch, true)))
+            .collect();
+        ordered_branches
+            .sort_by_key(|(branch, _)| (branch.chain_order.clone(), branch.branch_name.clone()));
+
+        println!("{}", "Chain metadata".bold());
+        for (index, (branch, missing)) in ordered_branches.iter().enumerate() {
+            if !missing {
+                continue;
+            }
+            match ordered_branches[index + 1..]
+                .iter()
+                .find(|(_branch, missing)| !missing)
+            {
+                Some((child, _)) => println!(
+                    "  ⚠️  Branch {} does not exist: it is the parent of branch {}",
+                    branch.branch_name.bold(),
+                    child.branch_name.bold()
+                ),
+                None => println!("  ⚠️  Branch {} does not exist", branch.branch_name.bold()),
+            }
+        }
+        for problem in &problems {
+            println!("  ⚠️  {}", problem.describe());
+        }
+
+        if !fix {
+            return Ok(missing_branches.len() + problems.len());
+        }
+
+        println!();
+        self.repair_chain(chain_name, false)?;
+        for branch in missing_branches {
+            println!(
+                "Removed branch {} from the chain, as it does not exist",
+                branch.branch_name.bold()
+            );
+            branch.remove_from_chain(self)?;
+        }
+        println!();
+
+        Ok(0)
+    }
+
+    fn test(
+        &self,
+        chain_name: &str,
+        command: &[String],
+        options: &TestOptions,
+    ) -> Result<(), Error> {
+        if !Chain::chain_exists(self, chain_name)? {
+            eprintln!("Unable to test the chain.");
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+
+        let chain = Chain::get_chain(self, chain_name)?;
+        let mut entries = vec![];
+        for branch in &chain.branches {
+            let outcome = if self.git_local_branch_exists(&branch.branch_name)? {
+                TestOutcome::Pending
+            } else {
+                TestOutcome::Missing
+            };
+            entries.push(TestEntry {
+                branch_name: branch.branch_name.clone(),
+                outcome,
+            });
+        }
+
+        if options.worktrees {
+            self.test_in_worktrees(command, options, &mut entries)?;
+        } else {
+            self.test_in_place(&chain, command, options, &mut entries)?;
+        }
+
+        let report = TestReport {
+            chain_name: chain.name.clone(),
+            entries,
+        };
+
+        println!();
+        println!("{}", report.to_text());
+        println!();
+
+        let num_of_failures = report.num_of_failures();
+        if num_of_failures > 0 {
+            eprintln!(
+                "🛑 {} of {} branches of chain {} failed: {}",
+                num_of_failures,
+                report.entries.len(),
+                chain.name.bold(),
+                command.join(" ")
+            );
+            exit(1);
+        }
+
+        println!(
+            "🎉 All branches of chain {} passed: {}",
+            chain.name.bold(),
+            command.join(" ")
+        );
+        Ok(())
+    }
+
+    // Check out each branch of the chain in turn, and run the command in the working directory.
+    fn test_in_place(
+        &self,
+        chain: &Chain,
+        command: &[String],
+        options: &TestOptions,
+        entries: &mut [TestEntry],
+    ) -> Result<(), Error> {
+        if self.dirty_working_directory()? {
+            eprintln!(
+                "🛑 Unable to test the branches of the chain: {}",
+                chain.name.bold()
+            );
+            eprintln!("You have uncommitted changes in your working directory.");
+            eprintln!("Please commit or stash them, or run with --worktrees.");
+            exit(1);
+        }
+
+        let original_branch = OriginalBranch::new(self, &chain.name, "test", false)?;
+        let mut num_of_tested_branches = 0;
+
+        for entry in entries.iter_mut() {
+            if matches!(entry.outcome, TestOutcome::Missing) {
+                continue;
+            }
+
+            if num_of_tested_branches > 0 {
+                println!();
+            }
+            num_of_tested_branches += 1;
+            println!("Testing branch: {}", entry.branch_name.bold());
+            self.checkout_branch(&entry.branch_name)?;
+
+            let status = test_command(command)
+                .env("GIT_CHAIN_TEST_BRANCH", &entry.branch_name)
+                .logged_status();
+            let status = match status {
+                Ok(status) => status,
+                Err(err) => {
+                    eprintln!("🛑 Unable to run: {}", command.join(" "));
+                    eprintln!("{}", err);
+                    original_branch.exit(1);
+                }
+            };
+
+            entry.outcome = if status.success() {
+                TestOutcome::Passed
+            } else {
+                TestOutcome::Failed {
+                    code: status.code(),
+                }
+            };
+
+            if options.fail_fast && !status.success() {
+                break;
+            }
+        }
+
+        original_branch.switch_back()?;
+
+        Ok(())
+    }
+
+    // Run the command in a temporary worktree per branch, up to options.jobs at the same time.
+    // The output of each branch is printed once all of them are done, in the order of the chain.
+    fn test_in_worktrees(
+        &self,
+        command: &[String],
+        options: &TestOptions,
+        entries: &mut [TestEntry],
+    ) -> Result<(), Error> {
+        let base_path = std::env::temp_dir();
+        let mut worktrees = vec![];
+
+        // git worktree add updates the administrative files of the repository, so the worktrees
+        // are created one at a time before running anything.
+        for (index, entry) in entries.iter().enumerate() {
+            if matches!(entry.outcome, TestOutcome::Missing) {
+                continue;
+            }
+
+            let path = base_path.join(format!("git-chain-test-{}-{}", process::id(), index));
+            let output = Command::new("git")
+                .arg("worktree")
+                .arg("add")
+                .arg("--detach")
+                .arg(&path)
+                .arg(&entry.branch_name)
+                .logged_output()
+                .unwrap_or_else(|_| {
+                    panic!("Unable to create a worktree for {}", entry.branch_name)
+                });
+
+            if !output.status.success() {
+                self.remove_test_worktrees(&worktrees);
+                eprintln!(
+                    "🛑 Unable to create a worktree for branch: {}",
+                    entry.branch_name.bold()
+                );
+                io::stderr().write_all(&output.stderr).unwrap();
+                exit(1);
+            }
+
+            worktrees.push((index, path));
+        }
+
+        let next_worktree = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let outputs: Mutex<HashMap<usize, io::Result<Output>>> = Mutex::new(HashMap::new());
+
+        thread::scope(|scope| {
+            for _ in 0..options.jobs.max(1) {
+                scope.spawn(|| loop {
+                    if options.fail_fast && failed.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Some((index, path)) =
+                        worktrees.get(next_worktree.fetch_add(1, Ordering::SeqCst))
+                    else {
+                        break;
+                    };
+
+                    let output = test_command(command)
+                        .current_dir(path)
+                        .env("GIT_CHAIN_TEST_BRANCH", &entries[*index].branch_name)
+                        .logged_output();
+                    if !matches!(&output, Ok(output) if output.status.success()) {
+                        failed.store(true, Ordering::SeqCst);
+                    }
+                    outputs.lock().unwrap().insert(*index, output);
+                });
+            }
+        });
+
+        self.remove_test_worktrees(&worktrees);
+
+        let mut outputs = outputs.into_inner().unwrap();
+        let mut num_of_tested_branches = 0;
+        for (index, entry) in entries.iter_mut().enumerate() {
+            let Some(output) = outputs.remove(&index) else {
+                continue;
+            };
+
+            let output = match output {
+                Ok(output) => output,
+                Err(err) => {
+                    eprintln!("🛑 Unable to run: {}", command.join(" "));
+                    eprintln!("{}", err);
+                    exit(1);
+                }
+            };
+
+            if num_of_tested_branches > 0 {
+                println!();
+            }
+            num_of_tested_branches += 1;
+            println!("Testing branch: {}", entry.branch_name.bold());
+            io::stdout().write_all(&output.stdout).unwrap();
+            io::stdout().flush().unwrap();
+            io::stderr().write_all(&output.stderr).unwrap();
+
+            entry.outcome = if output.status.success() {
+                TestOutcome::Passed
+            } else {
+                TestOutcome::Failed {
+                    code: output.status.code(),
+                }
+            };
+        }
+
+        Ok(())
+    }
+
+    fn remove_test_worktrees(&self, worktrees: &[(usize, std::path::PathBuf)]) {
+        for (_index, path) in worktrees {
+            let output = Command::new("git")
+                .arg("worktree")
+                .arg("remove")
+                .arg("--force")
+                .arg(path)
+                .logged_output();
+            if !matches!(&output, Ok(output) if output.status.success()) {
+                eprintln!(
+                    "⚠️  Unable to remove the worktree: {}",
+                    path.display().to_string().bold()
+                );
+            }
+        }
+    }
+
+    fn branch_web_url(&self, chain: &Chain, index: usize) -> Result<String, Error> {
+        // The URL of the pull request of the branch, or else its compare page.
+        let branch = &chain.branches[index];
+
+        match self.find_pull_request(&branch.branch_name) {
+            Ok(Some(pull_request)) => return Ok(pull_request.url),
+            Ok(None) | Err(GhError::Unavailable) | Err(GhError::Offline) => {}
+            Err(GhError::Failed(message)) => {
+                eprintln!(
+                    "⚠️  Unable to find the pull request of branch {}: {}",
+                    branch.branch_name.bold(),
+                    message
+                );
+            }
+        }
+
+        let (remote_name, remote_url) = self.branch_remote_url(&branch.branch_name)?;
+        let base = chain.base_branch_on_remote(self, index, &remote_name)?;
+        let head = self.remote_branch_name(&branch.branch_name)?;
+
+        if let Some(template) = self.pr_url_template()? {
+            return match browse::render_url_template(&template, &remote_url, &base, &head) {
+                Some(url) => Ok(url),
+                None => Err(Error::from_str(&format!(
+                    "Unable to parse the URL of remote: {}",
+                    remote_name.bold()
+                ))),
+            };
+        }
+
+        let gh_host = env_var("GH_HOST");
+        let web_url = match browse::web_url_from_remote(&remote_url, gh_host.as_deref()) {
+            Some(web_url) => web_url,
+            None => {
+                return Err(Error::from_str(&format!(
+                    "Unable to determine the web URL of remote: {}",
+                    remote_name.bold()
+                )));
+            }
+        };
+
+        Ok(browse::compare_url(&web_url, &base, &head))
+    }
+
+    // The remote of a branch (the remote of its upstream, or else origin), along with its URL.
+    fn branch_remote_url(&self, branch_name: &str) -> Result<(String, String), Error> {
+        let remote_name = match self
+            .repo
+            .branch_upstream_remote(&format!("refs/heads/{}", branch_name))
+        {
+            Ok(remote_name) => remote_name.as_str().unwrap_or("origin").to_string(),
+            Err(_) => "origin".to_string(),
+        };
+
+        let remote = match self.repo.find_remote(&remote_name) {
+            Ok(remote) => remote,
+            Err(ref e) if e.code() == ErrorCode::NotFound => {
+                return Err(Error::from_str(&format!(
+                    "Remote does not exist: {}",
+                    remote_name.bold()
+                )));
+            }
+            Err(e) => return Err(e),
+        };
+
+        match remote.url() {
+            Some(url) => Ok((remote_name, url.to_string())),
+            None => Err(Error::from_str(&format!(
+                "Unable to determine the URL of remote: {}",
+                remote_name.bold()
+            ))),
+        }
+    }
+
+    fn pr_url_template(&self) -> Result<Option<String>, Error> {
+        self.get_setting("GIT_CHAIN_PR_URL_TEMPLATE", "chain.prUrlTemplate")
+    }
+
+    // The URL of the pull request of the branch: from gh, or else rendered from
+    // chain.prUrlTemplate. None if neither is available.
+    fn pull_request_url(&self, chain: &Chain, index: usize) -> Result<Option<String>, Error> {
+        let branch = &chain.branches[index];
+        if let Ok(Some(pull_request)) = self.find_pull_request(&branch.branch_name) {
+            return Ok(Some(pull_request.url));
+        }
+
+        let template = match self.pr_url_template()? {
+            Some(template) => template,
+            None => return Ok(None),
+        };
+        let (remote_name, remote_url) = match self.branch_remote_url(&branch.branch_name) {
+            Ok(remote) => remote,
+            Err(_) => return Ok(None),
+        };
+        let base = chain.base_branch_on_remote(self, index, &remote_name)?;
+        let head = self.remote_branch_name(&branch.branch_name)?;
+        Ok(browse::render_url_template(
+            &template,
+            &remote_url,
+            &base,
+            &head,
+        ))
+    }
+
+    fn browse(&self, chain_name: Option<&str>, all: bool, print_only: bool) -> Result<(), Error> {
+        let (chain, branch) = self.selected_branch(chain_name, None)?;
+
+        for (index, chain_branch) in chain.branches.iter().enumerate() {
+            if !all && chain_branch.branch_name != branch.branch_name {
+                continue;
+            }
+
+            let url = self.branch_web_url(&chain, index)?;
+
+            if print_only {
+                println!("{}", url);
+                continue;
+            }
+
+            println!("Opening {}", url);
+            if let Err(e) = browse::open_url(&url) {
+                eprintln!("🛑 Unable to open the browser: {}", e);
+                exit(1);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn diff(&self, chain_name: Option<&str>, all: bool, stat: bool) -> Result<(), Error> {
+        let (chain, branch) = self.selected_branch(chain_name, None)?;
+
+        // The three-dot range diffs against the merge base, so that changes on the base
+        // that are not part of the chain (e.g. new commits on the root branch) are excluded.
+        let range = if all {
+            // What the chain would look like if it was fully landed onto the root branch.
+            let last_branch = chain.branches.last().unwrap();
+            format!("{}...{}", chain.root_branch, last_branch.branch_name)
+        } else {
+            let parent_branch = chain.parent_branch_name(&branch);
+            format!("{}...{}", parent_branch, branch.branch_name)
+        };
+
+        let mut diff_args = vec!["diff"];
+        if stat {
+            diff_args.push("--stat");
+        }
+        // The range is never read as a path, e.g. of a file named like it.
+        diff_args.push(&range);
+        diff_args.push("--");
+
+        let command = format!("git {}", diff_args.join(" "));
+        let status = Command::new("git")
+            .args(&diff_args)
+            .logged_status()
+            .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+        if !status.success() {
+            eprintln!("Command returned non-zero exit status: {}", command);
+            exit(1);
+        }
+
+        Ok(())
+    }
+
+    // Annotate each line of a file, as of the current branch, with the branch of the chain that
+    // introduced it. Lines that come from the root branch are annotated with the root branch.
+    fn blame(&self, chain_name: Option<&str>, path: &str) -> Result<(), Error> {
+        let (chain, branch) = self.selected_branch(chain_name, None)?;
+
+        // git blame takes paths relative to the current directory, git2 relative to the workdir.
+        let workdir = self.repo.workdir().unwrap().canonicalize().unwrap();
+        let current_dir = std::env::current_dir().unwrap().canonicalize().unwrap();
+        let prefix = current_dir
+            .strip_prefix(&workdir)
+            .unwrap_or(std::path::Path::new(""));
+        let repo_path = prefix.join(path);
+
+        let tip = self
+            .repo
+            .revparse_single(&branch.branch_name)?
+            .peel_to_commit()?;
+        let blob = match tip.tree()?.get_path(&repo_path) {
+            Ok(entry) => entry.to_object(&self.repo)?.peel_to_blob()?,
+            Err(ref e) if e.code() == ErrorCode::NotFound => {
+                eprintln!(
+                    "🛑 File does not exist on branch {}: {}",
+                    branch.branch_name.bold(),
+                    path
+                );
+                exit(1);
+            }
+            Err(e) => return Err(e),
+        };
+
+        // The commits of each branch, i.e. git log parent_branch..branch, up to the current branch.
+        let mut commit_branches: HashMap<git2::Oid, &str> = HashMap::new();
+        for pair in chain.branch_pairs() {
+            let (branch_obj, _reference) = self.repo.revparse_ext(&pair.branch.branch_name)?;
+            let (parent_obj, _reference) = self.repo.revparse_ext(pair.parent)?;
+
+            let mut revwalk = self.repo.revwalk()?;
+            revwalk.push(branch_obj.peel_to_commit()?.id())?;
+            revwalk.hide(parent_obj.peel_to_commit()?.id())?;
+            for oid in revwalk {
+                commit_branches.insert(oid?, &pair.branch.branch_name);
+            }
+
+            if pair.branch.branch_name == branch.branch_name {
+                break;
+            }
+        }
+
+        // Blame reads every version of the file, i.e. git rev-list --objects tip -- path
+        if partial::promisor_remote(&self.repo).is_some() {
+            let missing_blobs =
+                partial::missing_objects(&[tip.id().to_string()], &[&repo_path.to_string_lossy()])
+                    .map_err(|err| Error::from_str(&err))?;
+            self.fetch_missing_objects(&missing_blobs)?;
+        }
+
+        let mut options = git2::BlameOptions::new();
+        options.newest_commit(tip.id());
+        let blame = self.repo.blame_file(&repo_path, Some(&mut options))?;
+
+        let contents = String::from_utf8_lossy(blob.content()).to_string();
+        let lines: Vec<&str> = contents.lines().collect();
+
+        let mut annotations = vec![];
+        for line_number in 1..=lines.len() {
+            let (branch_name, short_id) = match blame.get_line(line_number) {
+                Some(hunk) => {
+                    let commit_id = hunk.final_commit_id();
+                    let branch_name = commit_branches
+                        .get(&commit_id)
+                        .copied()
+                        .unwrap_or(&chain.root_branch);
+                    let commit = self.repo.find_commit(commit_id)?;
+                    let short_id = commit.as_object().short_id()?;
+                    (
+                        branch_name,
+                        short_id.as_str().unwrap_or_default().to_string(),
+                    )
+                }
+                None => (chain.root_branch.as_str(), "".to_string()),
+            };
+            annotations.push((branch_name, short_id));
+        }
+
+        let branch_width = annotations
+            .iter()
+            .map(|(branch_name, _short_id)| branch_name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let line_number_width = lines.len().to_string().len();
+
+        for (index, line) in lines.iter().enumerate() {
+            let (branch_name, short_id) = &annotations[index];
+            let padded = format!("{:width$}", branch_name, width = branch_width);
+            let padded = if *branch_name == chain.root_branch {
+                padded.dimmed()
+            } else {
+                padded.bold()
+            };
+            println!(
+                "{} {} {:>width$}) {}",
+                padded,
+                short_id.yellow(),
+                index + 1,
+                line,
+                width = line_number_width
+            );
+        }
+
+        Ok(())
+    }
+
+    fn get_branch_of_chain(&self, branch_name: Option<&str>) -> Result<Branch, Error> {
+        let branch_name = match branch_name {
+            Some(branch_name) => branch_name.to_string(),
+            None => self.get_current_branch_name()?,
+        };
+
+        match Branch::get_branch_with_chain(self, &branch_name)? {
+            BranchSearchResult::NotPartOfAnyChain(_) => {
+                self.display_branch_not_part_of_chain_error(&branch_name);
+                exit(1);
+            }
+            BranchSearchResult::Branch(branch) => Ok(branch),
+        }
+    }
+
+    // The branch that commands of a single branch (e.g. diff, note) act on: the given branch, or
+    // else the current branch if it is part of the selected chain (--chain, GIT_CHAIN_CHAIN, or else
+    // the chain of the current branch), or else the last branch of that chain.
+    fn selected_branch(
+        &self,
+        chain_name: Option<&str>,
+        branch_name: Option<&str>,
+    ) -> Result<(Chain, Branch), Error> {
+        if let Some(branch_name) = branch_name {
+            let branch = self.get_branch_of_chain(Some(branch_name))?;
+            if let Some(chain_name) = chain_name {
+                if !Chain::chain_exists(self, chain_name)? {
+                    eprintln!("Chain does not exist: {}", chain_name);
+                    exit(1);
+                }
+                if branch.chain_name != chain_name {
+                    eprintln!(
+                        "Branch {} is not part of chain {}",
+                        branch.branch_name, chain_name
+                    );
+                    exit(1);
+                }
+            }
+            let chain = Chain::get_chain(self, &branch.chain_name)?;
+            return Ok((chain, branch));
+        }
+
+        let chain_name = self.resolve_chain_name(chain_name)?;
+        if !Chain::chain_exists(self, &chain_name)? {
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+        let chain = Chain::get_chain(self, &chain_name)?;
+
+        let current_branch = self.get_current_branch_name_if_attached()?;
+        let branch = chain
+            .branches
+            .iter()
+            .find(|branch| current_branch.as_deref() == Some(branch.branch_name.as_str()))
+            .or_else(|| chain.branches.last())
+            .cloned();
+        match branch {
+            Some(branch) => Ok((chain, branch)),
+            None => {
+                eprintln!("🛑 Chain {} has no branches.", chain_name.bold());
+                exit(1);
+            }
+        }
+    }
+
+    // Commands that change the working directory (e.g. squash-branch) need the selected branch to
+    // be checked out.
+    fn ensure_checked_out(
+        &self,
+        chain: &Chain,
+        branch: &Branch,
+        action: &str,
+    ) -> Result<(), Error> {
+        if self.get_current_branch_name_if_attached()?.as_deref() == Some(&branch.branch_name) {
+            return Ok(());
+        }
+        eprintln!(
+            "🛑 Unable to {}: the current branch is not part of chain {}.",
+            action,
+            chain.name.bold()
+        );
+        eprintln!(
+            "Check out one of its branches first, e.g.: git checkout {}",
+            branch.branch_name
+        );
+        exit(1);
+    }
+
+    fn print_root(&self, chain_name: Option<&str>, branch_name: Option<&str>) -> Result<(), Error> {
+        // Only the branch name is printed, so that the output can be used in scripts.
+        let chain_name = match (chain_name, branch_name) {
+            (None, Some(branch_name)) => self.get_branch_of_chain(Some(branch_name))?.chain_name,
+            (chain_name, _) => self.resolve_chain_name(chain_name)?,
+        };
+
+        if !Chain::chain_exists(self, &chain_name)? {
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+
+        let chain = Chain::get_chain(self, &chain_name)?;
+        println!("{}", chain.root_branch);
+        Ok(())
+    }
+
+    fn print_parent(
+        &self,
+        chain_name: Option<&str>,
+        branch_name: Option<&str>,
+    ) -> Result<(), Error> {
+        // Only the branch name is printed, so that the output can be used in scripts.
+        let (chain, branch) = self.selected_branch(chain_name, branch_name)?;
+        println!("{}", chain.parent_branch_name(&branch));
+        Ok(())
+    }
+
+    fn changes(&self, chain_name: &str) -> Result<(), Error> {
+        if !Chain::chain_exists(self, chain_name)? {
+            eprintln!("Unable to check the changes of the chain.");
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+
+        let chain = Chain::get_chain(self, chain_name)?;
+        let scope = match chain.scope(self)? {
+            Some(scope) => scope,
+            None => {
+                eprintln!("Chain has no scope: {}", chain.name.bold());
+                eprintln!(
+                    "To set a scope, run: {} scope <directory>",
+                    self.executable_name
+                );
+                exit(1);
+            }
+        };
+
+        let mut num_of_files_out_of_scope = 0;
+
+        for (index, branch) in chain.branches.iter().enumerate() {
+            let parent_branch = if index == 0 {
+                &chain.root_branch
+            } else {
+                &chain.branches[index - 1].branch_name
+            };
+
+            let (branch_obj, _reference) = self.repo.revparse_ext(&branch.branch_name)?;
+            let (parent_obj, _reference) = self.repo.revparse_ext(parent_branch)?;
+            let branch_commit = branch_obj.peel_to_commit()?;
+            let base_oid = self
+                .repo
+                .merge_base(parent_obj.peel_to_commit()?.id(), branch_commit.id())?;
+            let base_tree = self.repo.find_commit(base_oid)?.tree()?;
+
+            let diff = self.repo.diff_tree_to_tree(
+                Some(&base_tree),
+                Some(&branch_commit.tree()?),
+                None,
+            )?;
+
+            let mut files_out_of_scope = vec![];
+            for delta in diff.deltas() {
+                for file in [delta.old_file(), delta.new_file()] {
+                    if let Some(path) = file.path().and_then(|path| path.to_str()) {
+                        if !path_in_scope(path, &scope)
+                            && !files_out_of_scope.contains(&path.to_string())
+                        {
+                            files_out_of_scope.push(path.to_string());
+                        }
+                    }
+                }
+            }
+
+            println!("{}", branch.branch_name.bold());
+            if files_out_of_scope.is_empty() {
+                println!("  ✅ No changes outside of scope");
+            }
+            for path in &files_out_of_scope {
+                println!("  ⚠️  {}", path);
+            }
+            num_of_files_out_of_scope += files_out_of_scope.len();
+        }
+
+        println!();
+        if num_of_files_out_of_scope > 0 {
+            println!(
+                "⚠️  {} files changed outside of scope {} in chain: {}",
+                num_of_files_out_of_scope,
+                scope.bold(),
+                chain.name.bold()
+            );
+        } else {
+            println!(
+                "🎉 All changes of chain {} are within scope: {}",
+                chain.name.bold(),
+                scope.bold()
+            );
+        }
+
+        Ok(())
+    }
+
+    fn remote_branch_names(&self, remote_name: &str) -> Result<HashSet<String>, Error> {
+        // git ls-remote --heads <remote>
+        let command = format!("git ls-remote --heads {}", remote_name);
+        let output = Command::new("git")
+            .arg("ls-remote")
+            .arg("--heads")
+            .arg(remote_name)
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+        if !output.status.success() {
+            eprintln!("Command returned non-zero exit status: {}", command);
+            io::stderr().write_all(&output.stderr).unwrap();
+            exit(1);
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .filter_map(|(_oid, name)| name.trim().strip_prefix("refs/heads/"))
+            .map(|name| name.to_string())
+            .collect())
+    }
+
+    fn remote_default_branch(&self, remote_name: &str) -> Result<Option<String>, Error> {
+        // git ls-remote --symref <remote> HEAD
+        // The default branch is given by a line like: ref: refs/heads/main	HEAD
+        let command = format!("git ls-remote --symref {} HEAD", remote_name);
+        let output = Command::new("git")
+            .arg("ls-remote")
+            .arg("--symref")
+            .arg(remote_name)
+            .arg("HEAD")
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+        if !output.status.success() {
+            eprintln!("Command returned non-zero exit status: {}", command);
+            io::stderr().write_all(&output.stderr).unwrap();
+            exit(1);
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("ref: refs/heads/"))
+            .filter_map(|line| line.strip_suffix("\tHEAD"))
+            .map(|name| name.to_string())
+            .next())
+    }
+
+    fn migrate_root(
+        &self,
+        remote_name: &str,
+        from: Option<&str>,
+        dry_run: bool,
+    ) -> Result<(), Error> {
+        let new_default = match self.remote_default_branch(remote_name)? {
+            Some(new_default) => new_default,
+            None => {
+                eprintln!(
+                    "🛑 Unable to detect the default branch of remote {}",
+                    remote_name.bold()
+                );
+                exit(1);
+            }
+        };
+
+        // The previous default branch is the one that refs/remotes/<remote>/HEAD still points
+        // to, unless given with --from.
+        let tracking_prefix = format!("refs/remotes/{}/", remote_name);
+        let old_default = match from {
+            Some(from) => Some(from.to_string()),
+            None => match self
+                .repo
+                .find_reference(&format!("{}HEAD", tracking_prefix))
+            {
+                Ok(reference) => reference
+                    .symbolic_target()
+                    .and_then(|target| target.strip_prefix(&tracking_prefix))
+                    .map(|name| name.to_string()),
+                Err(ref e) if e.code() == ErrorCode::NotFound => None,
+                Err(e) => return Err(e),
+            },
+        };
+
+        let old_default = match old_default {
+            Some(old_default) if old_default != new_default => old_default,
+            Some(_) => {
+                println!(
+                    "✅ The default branch of remote {} is still {}.",
+                    remote_name.bold(),
+                    new_default.bold()
+                );
+                println!(
+                    "To update chains rooted at another branch, run: {} migrate-root --from <branch>",
+                    self.executable_name
+                );
+                return Ok(());
+            }
+            None => {
+                eprintln!(
+                    "🛑 Unable to tell the previous default branch of remote {}.",
+                    remote_name.bold()
+                );
+                eprintln!(
+                    "To give it, run: {} migrate-root --from <branch>",
+                    self.executable_name
+                );
+                exit(1);
+            }
+        };
+
+        // A root branch that is a remote-tracking branch is migrated to a remote-tracking branch.
+        let old_tracking = format!("{}/{}", remote_name, old_default);
+        let new_tracking = format!("{}/{}", remote_name, new_default);
+        let mut migrations = vec![];
+        for chain in Chain::get_all_chains(self)? {
+            let new_root = if chain.root_branch == old_default {
+                if self.git_local_branch_exists(&new_default)? {
+                    new_default.clone()
+                } else {
+                    new_tracking.clone()
+                }
+            } else if chain.root_branch == old_tracking {
+                new_tracking.clone()
+            } else {
+                continue;
+            };
+
+            if !self.git_branch_exists(&new_root)? {
+                eprintln!("🛑 Branch does not exist: {}", new_root.bold());
+                eprintln!("To fetch it, run: git fetch {}", remote_name);
+                exit(1);
+            }
+            migrations.push((chain, new_root));
+        }
+
+        println!(
+            "The default branch of remote {} changed from {} to {}.",
+            remote_name.bold(),
+            old_default.bold(),
+            new_default.bold()
+        );
+
+        if migrations.is_empty() {
+            println!("No chains are rooted at {}.", old_default.bold());
+        } else {
+            println!();
+            for (chain, new_root) in &migrations {
+                println!(
+                    "{}: {} ➜ {}",
+                    chain.name.bold(),
+                    chain.root_branch,
+                    new_root
+                );
+            }
+
+            if dry_run {
+                println!();
+                println!("{}", "This was a dry-run, no chains were updated!".bold());
+                return Ok(());
+            }
+
+            println!();
+            let question = format!("Update the root branch of {} chains?", migrations.len());
+            if !self.confirm(&question)? {
+                println!("No chains were updated.");
+                return Ok(());
+            }
+
+            for (chain, new_root) in &migrations {
+                chain.change_root_branch(self, new_root)?;
+            }
+            println!("✅ Updated the root branch of {} chains.", migrations.len());
+        }
+
+        // git remote set-head <remote> <new_default>
+        if !dry_run && from.is_none() && self.git_remote_branch_exists(&new_tracking)? {
+            let output = Command::new("git")
+                .arg("remote")
+                .arg("set-head")
+                .arg(remote_name)
+                .arg(&new_default)
+                .logged_output()
+                .unwrap_or_else(|_| panic!("Unable to run: git remote set-head"));
+            if !output.status.success() {
+                io::stderr().write_all(&output.stderr).unwrap();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_chain_definition(
+        &self,
+        chain_name: &str,
+        definition: &ChainDefinition,
+    ) -> Result<bool, Error> {
+        // Each publication is a commit on top of the previous one, so that the history of the
+        // chain layout is kept.
+        self.write_definition_ref(
+            &publish::chain_ref(chain_name),
+            definition,
+            &format!("Publish chain {}", chain_name),
+            true,
+        )
+    }
+
+    // Commit the definition to the ref, on top of its current commit if keep_history is set.
+    // Returns false if the definition is unchanged.
+    fn write_definition_ref(
+        &self,
+        reference_name: &str,
+        definition: &ChainDefinition,
+        message: &str,
+        keep_history: bool,
+    ) -> Result<bool, Error> {
+        let blob = self.repo.blob(definition.to_text().as_bytes())?;
+        let mut tree_builder = self.repo.treebuilder(None)?;
+        tree_builder.insert(publish::DEFINITION_FILE, blob, 0o100644)?;
+        let tree = self.repo.find_tree(tree_builder.write()?)?;
+
+        let parent = match self.repo.find_reference(reference_name) {
+            Ok(reference) => Some(reference.peel_to_commit()?),
+            Err(ref e) if e.code() == ErrorCode::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        if let Some(parent) = &parent {
+            if parent.tree_id() == tree.id() {
+                return Ok(false);
+            }
+        }
+
+        let signature = match self.repo.signature() {
+            Ok(signature) => signature,
+            Err(_) => git2::Signature::now("git-chain", "git-chain@localhost")?,
+        };
+        let parents: Vec<&git2::Commit> = parent.iter().filter(|_| keep_history).collect();
+        let commit = self
+            .repo
+            .commit(None, &signature, &signature, message, &tree, &parents)?;
+        self.repo.reference(reference_name, commit, true, message)?;
+
+        Ok(true)
+    }
+
+    fn read_chain_definition(&self, chain_name: &str) -> Result<ChainDefinition, Error> {
+        self.read_definition_ref(&publish::chain_ref(chain_name))
+    }
+
+    fn read_definition_ref(&self, reference_name: &str) -> Result<ChainDefinition, Error> {
+        let commit = self.repo.find_reference(reference_name)?.peel_to_commit()?;
+        let entry = match commit.tree()?.get_name(publish::DEFINITION_FILE) {
+            Some(entry) => entry.to_object(&self.repo)?,
+            None => {
+                eprintln!(
+                    "🛑 Invalid chain definition in {}: missing file {}",
+                    reference_name.bold(),
+                    publish::DEFINITION_FILE
+                );
+                exit(1);
+            }
+        };
+
+        let text = match entry.as_blob() {
+            Some(blob) => String::from_utf8_lossy(blob.content()).to_string(),
+            None => String::new(),
+        };
+
+        match ChainDefinition::from_text(&text) {
+            Ok(definition) => Ok(definition),
+            Err(err) => {
+                eprintln!(
+                    "🛑 Invalid chain definition in {}: {}",
+                    reference_name.bold(),
+                    err
+                );
+                exit(1);
+            }
+        }
+    }
+
+    fn run_git_remote_command(&self, args: &[&str], failure: &str) {
+        let command = format!("git {}", args.join(" "));
+        let output = Command::new("git")
+            .args(args)
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+        if !output.status.success() {
+            eprintln!("🛑 {}", failure);
+            eprintln!("Command returned non-zero exit status: {}", command);
+            io::stderr().write_all(&output.stderr).unwrap();
+            exit(1);
+        }
+    }
+
+    fn publish(&self, chain_name: &str, remote_name: &str) -> Result<(), Error> {
+        if !Chain::chain_exists(self, chain_name)? {
+            eprintln!("🛑 Unable to publish the chain.");
+            eprintln!("Chain does not exist: {}", chain_name.bold());
+            exit(1);
+        }
+
+        let chain = Chain::get_chain(self, chain_name)?;
+
+        if let Some(branch) = chain.missing_branches.first() {
+            eprintln!("Branch does not exist: {}", branch.branch_name.bold());
+            eprintln!(
+                "To remove missing branches from the chain, run: {} prune",
+                self.executable_name.bold()
+            );
+            exit(1);
+        }
+
+        let definition = chain.definition(self)?;
+
+        let reference_name = publish::chain_ref(chain_name);
+        if !self.write_chain_definition(chain_name, &definition)? {
+            println!(
+                "The layout of chain {} is unchanged since it was last published.",
+                chain_name.bold()
+            );
+        }
+
+        // git push <remote> +refs/chains/<chain_name>:refs/chains/<chain_name>
+        let refspec = format!("+{}:{}", reference_name, reference_name);
+        self.run_git_remote_command(
+            &["push", "--quiet", remote_name, &refspec],
+            &format!(
+                "Unable to publish chain {} to remote {}",
+                chain_name, remote_name
+            ),
+        );
+
+        println!(
+            "✅ Published chain {} to {} as {}",
+            chain_name.bold(),
+            remote_name.bold(),
+            reference_name
+        );
+        println!(
+            "To set it up in another clone, run: {} subscribe {}",
+            self.executable_name, chain_name
+        );
+
+        Ok(())
+    }
+
+    fn subscribe(&self, chain_name: &str, remote_name: &str) -> Result<(), Error> {
+        validate_chain_name(chain_name);
+
+        if Chain::chain_exists(self, chain_name)? {
+            eprintln!("🛑 Chain already exists: {}", chain_name.bold());
+            eprintln!(
+                "To replace it, remove it first: {} remove --chain {}",
+                self.executable_name, chain_name
+            );
+            exit(1);
+        }
+
+        // Fetch the chain definition, and the branches it refers to.
+        let reference_name = publish::chain_ref(chain_name);
+        let refspec = format!("+{}:{}", reference_name, reference_name);
+        let failure = format!(
+            "Unable to fetch chain {} from remote {}",
+            chain_name, remote_name
+        );
+        self.run_git_remote_command(&["fetch", "--quiet", remote_name, &refspec], &failure);
+        self.run_git_remote_command(&["fetch", "--quiet", remote_name], &failure);
+
+        let definition = self.read_chain_definition(chain_name)?;
+
+        let root_branch = match self.resolve_root_branch(&definition.root_branch)? {
+            Some(root_branch) => root_branch,
+            None => {
+                eprintln!(
+                    "Root branch does not exist: {}",
+                    definition.root_branch.bold()
+                );
+                exit(1);
+            }
+        };
+
+        // Branches that only exist on the remote are created from their remote-tracking branch.
+        let mut branches_to_create = vec![];
+        for branch_name in &definition.branches {
+            validate_branch_name(branch_name);
+
+            if self.git_local_branch_exists(branch_name)? {
+                if let BranchSearchResult::Branch(branch) =
+                    Branch::get_branch_with_chain(self, branch_name)?
+                {
+                    eprintln!(
+                        "🛑 Branch {} is already part of the chain {}",
+                        branch_name.bold(),
+                        branch.chain_name.bold()
+                    );
+                    exit(1);
+                }
+                continue;
+            }
+
+            let remote_branch = format!("{}/{}", remote_name, branch_name);
+            if !self.git_remote_branch_exists(&remote_branch)? {
+                eprintln!(
+                    "🛑 Branch {} does not exist locally or on remote {}",
+                    branch_name.bold(),
+                    remote_name.bold()
+                );
+                eprintln!(
+                    "It may not have been pushed yet; ask its author to run: {} push",
+                    self.executable_name
+                );
+                exit(1);
+            }
+            branches_to_create.push((branch_name, remote_branch));
+        }
+
+        for (branch_name, remote_branch) in &branches_to_create {
+            let commit = self.repo.revparse_single(remote_branch)?.peel_to_commit()?;
+            let mut branch = self.repo.branch(branch_name, &commit, false)?;
+            branch.set_upstream(Some(remote_branch))?;
+            println!(
+                "Created branch {} from {}",
+                branch_name.bold(),
+                remote_branch.bold()
+            );
+        }
+
+        for branch_name in &definition.branches {
+            Branch::setup_branch(
+                self,
+                chain_name,
+                &root_branch,
+                branch_name,
+                &SortBranch::Last,
+            )?;
+        }
+
+        let chain = Chain::get_chain(self, chain_name)?;
+        if let Some(scope) = &definition.scope {
+            chain.set_scope(self, Some(scope))?;
+        }
+
+        println!(
+            "🔗 Succesfully subscribed to chain {} from {}",
+            chain_name.bold(),
+            remote_name.bold()
+        );
+        println!();
+
+        let current_branch = self
+            .get_current_branch_name_if_attached()?
+            .unwrap_or_default();
+        chain.display_list(self, &current_branch)?;
+
+        Ok(())
+    }
+
+    // Set up a chain from a stack of pull requests, i.e. pull requests whose base branch is the head
+    // branch of another pull request, starting from the pull request of selector (a number, URL or
+    // branch) or of the current branch.
+    fn import_from_prs(
+        &self,
+        selector: Option<&str>,
+        chain_name: Option<&str>,
+        remote_name: &str,
+    ) -> Result<(), Error> {
+        let exit_on_gh_error = |err: GhError| -> ! {
+            eprintln!("🛑 Unable to import the pull requests: {}", err);
+            exit(1);
+        };
+
+        let selector = match selector {
+            Some(selector) => selector.to_string(),
+            None => self.get_current_branch_name()?,
+        };
+
+        let pull_request = match gh::find_pull_request_refs(&selector) {
+            Ok(Some(pull_request)) => pull_request,
+            Ok(None) => {
+                eprintln!("🛑 No pull request found for: {}", selector.bold());
+                exit(1);
+            }
+            Err(err) => exit_on_gh_error(err),
+        };
+
+        // Follow the base branches down to a branch without an open pull request, which is the
+        // root branch of the chain.
+        let mut visited = HashSet::new();
+        visited.insert(pull_request.head.clone());
+        let mut stack = vec![pull_request];
+        loop {
+            let base = stack[0].base.clone();
+            if visited.contains(&base) {
+                break;
+            }
+            match gh::find_pull_request_refs(&base) {
+                Ok(Some(pull_request)) if pull_request.state == "OPEN" => {
+                    visited.insert(pull_request.head.clone());
+                    stack.insert(0, pull_request);
+                }
+                Ok(_) => break,
+                Err(err) => exit_on_gh_error(err),
+            }
+        }
+
+        // Then the pull requests into the head branch of the last pull request, up to the top of
+        // the stack.
+        loop {
+            let head = stack.last().unwrap().head.clone();
+            let mut pull_requests = match gh::pull_requests_into(&head) {
+                Ok(pull_requests) => pull_requests,
+                Err(err) => exit_on_gh_error(err),
+            };
+            pull_requests.retain(|pull_request| !visited.contains(&pull_request.head));
+            if pull_requests.len() > 1 {
+                let numbers: Vec<String> = pull_requests
+                    .iter()
+                    .map(|pull_request| format!("#{}", pull_request.number))
+                    .collect();
+                println!(
+                    "⚠️  Several pull requests are based on branch {}: {}. The chain ends at {}.",
+                    head.bold(),
+                    numbers.join(", "),
+                    head.bold()
+                );
+                break;
+            }
+            match pull_requests.pop() {
+                Some(pull_request) => {
+                    visited.insert(pull_request.head.clone());
+                    stack.push(pull_request);
+                }
+                None => break,
+            }
+        }
+
+        let root_branch = stack[0].base.clone();
+        let chain_name = match chain_name {
+            Some(chain_name) => chain_name.to_string(),
+            None => stack[0].head.clone(),
+        };
+        validate_chain_name(&chain_name);
+
+        if Chain::chain_exists(self, &chain_name)? {
+            eprintln!("🛑 Chain already exists: {}", chain_name.bold());
+            eprintln!(
+                "To replace it, remove it first: {} remove --chain {}",
+                self.executable_name, chain_name
+            );
+            exit(1);
+        }
+
+        println!(
+            "Found {} pull requests into {}: {}",
+            stack.len(),
+            root_branch.bold(),
+            stack
+                .iter()
+                .map(|pull_request| format!("#{} {}", pull_request.number, pull_request.head))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+
+        let failure = format!("Unable to fetch the branches from remote {}", remote_name);
+        self.run_git_remote_command(&["fetch", "--quiet", remote_name], &failure);
+
+        let root_branch = match self.resolve_root_branch(&root_branch)? {
+            Some(root_branch) => root_branch,
+            None => {
+                eprintln!("Root branch does not exist: {}", root_branch.bold());
+                exit(1);
+            }
+        };
+
+        // Branches that only exist on the remote are created from their remote-tracking branch.
+        let mut branches_to_create = vec![];
+        for pull_request in &stack {
+            let branch_name = &pull_request.head;
+            validate_branch_name(branch_name);
+
+            let remote_branch = format!("{}/{}", remote_name, branch_name);
+            let remote_branch_exists = self.git_remote_branch_exists(&remote_branch)?;
+
+            if self.git_local_branch_exists(branch_name)? {
+                if let BranchSearchResult::Branch(branch) =
+                    Branch::get_branch_with_chain(self, branch_name)?
+                {
+                    eprintln!(
+                        "🛑 Branch {} is already part of the chain {}",
+                        branch_name.bold(),
+                        branch.chain_name.bold()
+                    );
+                    exit(1);
+                }
+                if remote_branch_exists
+                    && self.get_commit_hash_of_branch(branch_name)?
+                        != self.get_commit_hash_of_branch(&remote_branch)?
+                {
+                    println!(
+                        "⚠️  Branch {} differs from {}. Keeping the local branch.",
+                        branch_name.bold(),
+                        remote_branch.bold()
+                    );
+                }
+                continue;
+            }
+
+            if !remote_branch_exists {
+                eprintln!(
+                    "🛑 Branch {} of pull request #{} does not exist on remote {}",
+                    branch_name.bold(),
+                    pull_request.number,
+                    remote_name.bold()
+                );
+                eprintln!("Pull requests from forks cannot be imported.");
+                exit(1);
+            }
+            branches_to_create.push((branch_name, remote_branch));
+        }
+
+        for (branch_name, remote_branch) in &branches_to_create {
+            let commit = self.repo.revparse_single(remote_branch)?.peel_to_commit()?;
+            let mut branch = self.repo.branch(branch_name, &commit, false)?;
+            branch.set_upstream(Some(remote_branch))?;
+            println!(
+                "Created branch {} from {}",
+                branch_name.bold(),
+                remote_branch.bold()
+            );
+        }
+
+        for pull_request in &stack {
+            Branch::setup_branch(
+                self,
+                &chain_name,
+                &root_branch,
+                &pull_request.head,
+                &SortBranch::Last,
+            )?;
+        }
+
+        println!(
+            "🔗 Succesfully imported chain {} from {} pull requests",
+            chain_name.bold(),
+            stack.len()
+        );
+        println!();
+
+        let chain = Chain::get_chain(self, &chain_name)?;
+        let current_branch = self
+            .get_current_branch_name_if_attached()?
+            .unwrap_or_default();
+        chain.display_list(self, &current_branch)?;
+
+        Ok(())
+    }
+
+    // Once the branches of a chain are reordered (e.g. with move), the pull request of a branch may
+    // still be into its former parent branch, and show the changes of other branches.
+    fn update_pull_request_base(&self, branch_name: &str, head: &str, base: &str) {
+        let result = gh::find_pull_request_refs(head).and_then(|refs| match refs {
+            Some(refs) if refs.base != base => {
+                gh::edit_pull_request_base(head, base).map(|_| Some(refs.base))
+            }
+            _ => Ok(None),
+        });
+
+        match result {
+            Ok(Some(former_base)) => println!(
+                "✅ Updated the base branch of the pull request of branch {} from {} to {}",
+                branch_name.bold(),
+                former_base.bold(),
+                base.bold()
+            ),
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!(
+                    "🛑 Unable to update the base branch of the pull request of branch {}: {}",
+                    branch_name.bold(),
+                    err
+                );
+                exit(1);
+            }
+        }
+    }
+
+    fn update_stack_order_label(
+        &self,
+        branch_name: &str,
+        label: &str,
+        current_labels: &[String],
+    ) -> Result<(), Error> {
+        // Ordering labels of a previous position in the stack, or of a stack of another size.
+        let stack_order_label = Regex::new(r"^stack:\d+/\d+$").unwrap();
+        let stale_labels: Vec<String> = current_labels
+            .iter()
+            .filter(|current_label| {
+                stack_order_label.is_match(current_label) && *current_label != label
+            })
+            .cloned()
+            .collect();
+        let add_labels: Vec<String> = if current_labels.iter().any(|current| current == label) {
+            vec![]
+        } else {
+            vec![label.to_string()]
+        };
+
+        if add_labels.is_empty() && stale_labels.is_empty() {
+            return Ok(());
+        }
+
+        let remote_branch_name = self.remote_branch_name(branch_name)?;
+        let result = add_labels
+            .iter()
+            .try_for_each(|label| {
+                gh::create_label(label, "Position of the pull request in its stack")
+            })
+            .and_then(|_| {
+                gh::edit_pull_request_labels(&remote_branch_name, &add_labels, &stale_labels)
+            });
+
+        match result {
+            Ok(()) => {
+                println!(
+                    "🏷️  Labeled pull request of branch {}: {}",
+                    branch_name.bold(),
+                    label
+                );
+            }
+            Err(err) => {
+                eprintln!(
+                    "⚠️  Unable to label the pull request of branch {}: {}",
+                    branch_name.bold(),
+                    err
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn ensure_gh_available(&self, action: &str) {
+        match gh::check_available() {
+            Ok(()) => {}
+            Err(GhError::Unavailable) => {
+                eprintln!("🛑 Unable to {}: gh is not installed.", action);
+                eprintln!("See: https://cli.github.com");
+                exit(1);
+            }
+            Err(GhError::Failed(message)) => {
+                eprintln!("🛑 Unable to {}: {}", action, message);
+                exit(1);
+            }
+            Err(GhError::Offline) => {
+                eprintln!(
+                    "🛑 Unable to {}: {}.",
+                    action,
+                    gh::offline_reason().unwrap_or_default()
+                );
+                eprintln!(
+                    "Pull requests can only be changed while GitHub can be reached (without --offline, GIT_CHAIN_OFFLINE or chain.offline)."
+                );
+                exit(1);
+            }
+        }
+    }
+
+    fn pr(&self, chain_name: &str, options: &PrOptions) -> Result<(), Error> {
+        if !Chain::chain_exists(self, chain_name)? {
+            eprintln!("Unable to create pull requests for the chain.");
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+
+        let chain = Chain::get_chain(self, chain_name)?;
+
+        // Pull requests can only be opened against branches.
+        self.ensure_root_is_branch(&chain, "create pull requests");
+
+        if let Some(branch) = chain.missing_branches.first() {
+            eprintln!("Branch does not exist: {}", branch.branch_name.bold());
+            eprintln!(
+                "To remove missing branches from the chain, run: {} prune",
+                self.executable_name.bold()
+            );
+            exit(1);
+        }
+
+        // Checked before any branch is pushed, as large pull requests are hard to review.
+        let limits = self.get_size_limits(None, None)?;
+        let large_branches = self.large_branches(&chain, &limits)?;
+        if !large_branches.is_empty() {
+            self.warn_large_branches(&large_branches);
+            if options.strict {
+                eprintln!(
+                    "🛑 Not creating pull requests for chain {}: {} {} too large.",
+                    chain.name.bold(),
+                    large_branches.len(),
+                    if large_branches.len() == 1 {
+                        "branch is"
+                    } else {
+                        "branches are"
+                    }
+                );
+                eprintln!("Split them into smaller branches, or re-run without --strict.");
+                exit(1);
+            }
+            println!();
+        }
+
+        // The pull requests of the first branches are ready for review, the others are drafts.
+        let num_of_ready_branches = match &options.draft_mode {
+            DraftMode::None => chain.branches.len(),
+            DraftMode::All => 0,
+            DraftMode::After(branch_name) => {
+                match chain
+                    .branches
+                    .iter()
+                    .position(|branch| &branch.branch_name == branch_name)
+                {
+                    Some(index) => index + 1,
+                    None => {
+                        eprintln!(
+                            "🛑 Branch {} is not part of chain {}.",
+                            branch_name.bold(),
+                            chain.name.bold()
+                        );
+                        exit(1);
+                    }
+                }
+            }
+        };
+
+        self.ensure_gh_available("create pull requests");
+
+        let remote_name = chain
+            .branches
+            .iter()
+            .find_map(|branch| {
+                self.repo
+                    .branch_upstream_remote(&format!("refs/heads/{}", branch.branch_name))
+                    .ok()
+                    .and_then(|remote_name| remote_name.as_str().map(|name| name.to_string()))
+            })
+            .unwrap_or_else(|| "origin".to_string());
+
+        let mut remote_branches = self.remote_branch_names(&remote_name)?;
+
+        // The root branch may be a remote-tracking branch, e.g. origin/main
+        let prefix = format!("{}/", remote_name);
+        let root_branch = chain
+            .root_branch
+            .strip_prefix(&prefix)
+            .unwrap_or(&chain.root_branch)
+            .to_string();
+
+        if !remote_branches.contains(&root_branch) {
+            eprintln!(
+                "🛑 Root branch {} does not exist on remote {}.",
+                root_branch.bold(),
+                remote_name.bold()
+            );
+            eprintln!("Push it first, so that pull requests can be opened against it.");
+            exit(1);
+        }
+
+        // The URLs of the created and existing pull requests, to open with --open.
+        let mut urls = vec![];
+
+        // Pull requests are created from the first to the last branch, so that the base
+        // branch of each pull request exists on the remote before it is used.
+        for (index, branch) in chain.branches.iter().enumerate() {
+            // Pull requests are between the branches on the remote, which may be named differently.
+            let head = self.remote_branch_name(&branch.branch_name)?;
+            let base = if index == 0 {
+                root_branch.clone()
+            } else {
+                self.remote_branch_name(&chain.branches[index - 1].branch_name)?
+            };
+            let stack_order_label = format!("stack:{}/{}", index + 1, chain.branches.len());
+
+            if !remote_branches.contains(&head) {
+                println!(
+                    "Pushing branch {} to remote {}",
+                    branch.branch_name.bold(),
+                    remote_name.bold()
+                );
+
+                // git push --set-upstream <remote> <branch>:<remote_ref>
+                let (_remote_name, remote_ref) = self.push_destination(&branch.branch_name)?;
+                let refspec = format!("{}:{}", branch.branch_name, remote_ref);
+                let command = format!("git push --set-upstream {} {}", remote_name, refspec);
+                let output = Command::new("git")
+                    .arg("push")
+                    .arg("--set-upstream")
+                    .arg(&remote_name)
+                    .arg(&refspec)
+                    .logged_output()
+                    .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+                if !output.status.success() {
+                    eprintln!(
+                        "🛑 Unable to push branch {}, which is needed for its pull request and the pull requests of the branches after it.",
+                        branch.branch_name.bold()
+                    );
+                    io::stderr().write_all(&output.stderr).unwrap();
+                    exit(1);
+                }
+
+                remote_branches.insert(head.clone());
+            }
+
+            match gh::find_pull_request(&head) {
+                Ok(Some(pull_request)) if pull_request.state == "OPEN" => {
+                    println!(
+                        "✅ Pull request of branch {} already exists: {}",
+                        branch.branch_name.bold(),
+                        pull_request.url
+                    );
+                    if options.stack_order_labels {
+                        // Without the current labels, the stale ones cannot be told apart.
+                        match gh::pull_request_labels(&head) {
+                            Ok(labels) => self.update_stack_order_label(
+                                &branch.branch_name,
+                                &stack_order_label,
+                                &labels,
+                            )?,
+                            Err(_) => eprintln!(
+                                "⚠️  Unable to get the labels of the pull request of branch {}",
+                                branch.branch_name.bold()
+                            ),
+                        }
+                    }
+                    if options.update_base {
+                        self.update_pull_request_base(&branch.branch_name, &head, &base);
+                    }
+                    urls.push(pull_request.url);
+                    continue;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    eprintln!(
+                        "🛑 Unable to find the pull request of branch {}: {}",
+                        branch.branch_name.bold(),
+                        err
+                    );
+                    exit(1);
+                }
+            }
+
+            let draft = index >= num_of_ready_branches;
+            match gh::create_pull_request(&head, &base, draft) {
+                Ok(url) => {
+                    println!(
+                        "🎉 Created pull request of branch {} into {}: {}",
+                        branch.branch_name.bold(),
+                        base.bold(),
+                        url
+                    );
+                    self.notify(
+                        &chain.name,
+                        notify::Event::PullRequestCreated {
+                            branch: branch.branch_name.clone(),
+                            base: base.clone(),
+                            url: url.clone(),
+                        },
+                    )?;
+                    if options.stack_order_labels {
+                        self.update_stack_order_label(
+                            &branch.branch_name,
+                            &stack_order_label,
+                            &[],
+                        )?;
+                    }
+                    urls.push(url);
+                }
+                Err(err) => {
+                    eprintln!(
+                        "🛑 Unable to create the pull request of branch {}: {}",
+                        branch.branch_name.bold(),
+                        err
+                    );
+                    exit(1);
+                }
+            }
+        }
+
+        if options.open {
+            for url in &urls {
+                println!("Opening {}", url);
+                if let Err(e) = browse::open_url(url) {
+                    eprintln!("🛑 Unable to open the browser: {}", e);
+                    exit(1);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn after_land(&self, chain_name: &str, push: bool) -> Result<(), Error> {
+        if !Chain::chain_exists(self, chain_name)? {
+            eprintln!("Unable to clean up the chain.");
+            eprintln!("Chain does not exist: {}", chain_name);
+            exit(1);
+        }
+
+        // Landed branches are detected against a root that moves as branches are merged.
+        let chain = Chain::get_chain(self, chain_name)?;
+        self.ensure_root_is_branch(&chain, "clean up landed branches");
+
+        // Rebasing first lets the rebase reset squashed and merged branches onto the root
+        // branch, while their children are rebased without replaying the landed commits.
+        let options = RebaseOptions {
+            step_rebase: false,
+            ignore_root: false,
+            committer_date_is_author_date: false,
+            reset_author_date: false,
+            interactive: false,
+            // Landed branches are pruned below.
+            drop_empty: false,
+            delete_empty: false,
+            worktree: false,
+            keep_base: false,
+            allow_unrelated: false,
+            strategy_option: None,
+            reconcile: ReconcileMode::Off,
+            signoff: false,
+            update_root: false,
+            stay: false,
+            range: BranchRange::default(),
+        };
+        self.rebase(chain_name, &options)?;
+
+        let chain = Chain::get_chain(self, chain_name)?;
+        // Branches whose upstream branch is gone are left to prune, which confirms them first.
+        let landed_branches = chain.prune(self, false, false)?;
+
+        println!();
+        if landed_branches.is_empty() {
+            println!("No landed branches found for chain: {}", chain_name.bold());
+        } else {
+            println!(
+                "Removed the following landed branches from chain: {}",
+                chain_name.bold()
+            );
+            println!();
+            for branch in &landed_branches {
+                println!("{}", branch);
+            }
+            self.report_empty_chain(chain_name)?;
+        }
+
+        // Pull requests merged upstream whose changes are not on the root branch yet,
+        // e.g. the local root branch is out of date.
+        for branch in &chain.branches {
+            if landed_branches.contains(&branch.branch_name) {
+                continue;
+            }
+            if let Ok(Some(pull_request)) = self.find_pull_request(&branch.branch_name) {
+                if pull_request.state == "MERGED" {
+                    eprintln!(
+                        "⚠️  PR #{} of branch {} is merged, but its changes are not on {} yet.",
+                        pull_request.number,
+                        branch.branch_name.bold(),
+                        chain.root_branch.bold()
+                    );
+                    eprintln!(
+                        "Update {} and re-run this command.",
+                        chain.root_branch.bold()
+                    );
+                }
+            }
+        }
+
+        if push && Chain::chain_exists(self, chain_name)? {
+            println!();
+            let chain = Chain::get_chain(self, chain_name)?;
+            let options = PushOptions {
+                force_push: true,
+                only_changed: true,
+                check_pr: false,
+                strict: false,
+                protect_reviewed: self
+                    .get_bool_setting("GIT_CHAIN_PROTECT_REVIEWED", "chain.protectReviewed")?
+                    .unwrap_or(false),
+                range: BranchRange::default(),
+                create_pr: false,
+                draft: false,
+            };
+            let branches_pushed = chain.push(self, &options, 0..chain.branches.len())?;
+            println!("Pushed {} branches.", format!("{}", branches_pushed).bold());
+        }
+
+        Ok(())
+    }
+
+    fn smart_merge_base(
+        &self,
+        ancestor_branch: &str,
+        descendant_branch: &str,
+    ) -> Result<String, Error> {
+        if self.is_ancestor(ancestor_branch, descendant_branch)? {
+            // Can "fast forward" from ancestor_branch to descendant_branch
+            let common_point = self.merge_base(ancestor_branch, descendant_branch)?;
+            debug_log!(
+                "{} is an ancestor of {}; using merge base {}",
+                ancestor_branch,
+                descendant_branch,
+                common_point
+            );
+            return Ok(common_point);
+        }
+        let common_point = self.merge_base_fork_point(ancestor_branch, descendant_branch)?;
+        debug_log!(
+            "using fork point {} of {} and {}",
+            common_point,
+            ancestor_branch,
+            descendant_branch
+        );
+        Ok(common_point)
+    }
+
+    fn merge_base(&self, ancestor_branch: &str, descendant_branch: &str) -> Result<String, Error> {
+        // git merge-base <ancestor_branch> <descendant_branch>
+
+        let output = Command::new("git")
+            .arg("merge-base")
+            .arg(ancestor_branch)
+            .arg(descendant_branch)
+            .logged_output()
+            .unwrap_or_else(|_| {
+                panic!(
+                    "Unable to run: git merge-base {} {}",
+                    ancestor_branch.bold(),
+                    descendant_branch.bold()
+                )
+            });
+
+        if output.status.success() {
+            let raw_output = String::from_utf8(output.stdout).unwrap();
+            let common_point = raw_output.trim().to_string();
+            return Ok(common_point);
+        }
+        Err(Error::from_str(&format!(
+            "Unable to get common ancestor of {} and {}",
+            ancestor_branch.bold(),
+            descendant_branch.bold()
+        )))
+    }
+
+    fn merge_base_fork_point(
+        &self,
+        ancestor_branch: &str,
+        descendant_branch: &str,
+    ) -> Result<String, Error> {
+        // git merge-base --fork-point <ancestor_branch> <descendant_branch>
+
+        let output = Command::new("git")
+            .arg("merge-base")
+            .arg("--fork-point")
+            .arg(ancestor_branch)
+            .arg(descendant_branch)
+            .logged_output()
+            .unwrap_or_else(|_| {
+                panic!(
+                    "Unable to run: git merge-base --fork-point {} {}",
+                    ancestor_branch.bold(),
+                    descendant_branch.bold()
+                )
+            });
+
+        if output.status.success() {
+            let raw_output = String::from_utf8(output.stdout).unwrap();
+            let common_point = raw_output.trim().to_string();
+            return Ok(common_point);
+        }
+        if output.status.code().unwrap() == 1 {
+            // fork-point not found, try git merge-base
+            debug_log!(
+                "no fork point of {} and {} in the reflog; falling back to merge base",
+                ancestor_branch,
+                descendant_branch
+            );
+            return self.merge_base(ancestor_branch, descendant_branch);
+        }
+
+        Err(Error::from_str(&format!(
+            "Unable to get forkpoint of {} and {}",
+            ancestor_branch.bold(),
+            descendant_branch.bold()
+        )))
+    }
+
+    fn ahead_behind(&self, upstream: &str, branch: &str) -> Result<(usize, usize), Error> {
+        let (upstream_obj, _reference) = self.repo.revparse_ext(upstream)?;
+        let (branch_obj, _reference) = self.repo.revparse_ext(branch)?;
+
+        self.repo
+            .graph_ahead_behind(branch_obj.id(), upstream_obj.id())
+    }
+
+    // The ahead/behind counts of many (upstream, branch) pairs at once; see counts.rs.
+    fn ahead_behind_many(
+        &self,
+        pairs: &[(&str, &str)],
+    ) -> Result<Vec<Option<(usize, usize)>>, Error> {
+        let mut oids = vec![];
+        for (upstream, branch) in pairs {
+            let upstream_oid = self.repo.revparse_single(upstream)?.peel_to_commit()?.id();
+            let branch_oid = self.repo.revparse_single(branch)?.peel_to_commit()?.id();
+            oids.push((upstream_oid, branch_oid));
+        }
+
+        counts::ahead_behind_many(&self.repo, &oids)
+    }
+
+    // The upstream of the root branch, and the ahead/behind counts of the root branch relative to
+    // it; None if the root is not a local branch with an upstream.
+    fn root_upstream_status(
+        &self,
+        root_branch: &str,
+    ) -> Result<Option<(String, usize, usize)>, Error> {
+        if !self.git_local_branch_exists(root_branch)? {
+            return Ok(None);
+        }
+        let branch = self.repo.find_branch(root_branch, BranchType::Local)?;
+        let upstream = match branch.upstream() {
+            Ok(upstream) => upstream,
+            Err(ref e) if e.code() == ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let upstream_name = utf8_branch_name(&upstream)?;
+
+        let local_oid = branch.get().peel_to_commit()?.id();
+        let upstream_oid = upstream.get().peel_to_commit()?.id();
+        let (ahead, behind) = self.repo.graph_ahead_behind(local_oid, upstream_oid)?;
+        Ok(Some((upstream_name, ahead, behind)))
+    }
+
+    // Branches rebased onto a root branch that is behind its upstream are based on outdated
+    // commits, and their pull requests keep conflicting with the upstream root branch.
+    fn warn_root_behind_upstream(&self, chain: &Chain) -> Result<(), Error> {
+        let (upstream, ahead, behind) = match self.root_upstream_status(&chain.root_branch)? {
+            Some((upstream, ahead, behind)) if behind > 0 => (upstream, ahead, behind),
+            _ => return Ok(()),
+        };
+
+        println!();
+        if ahead > 0 {
+            println!(
+                "⚠️  Root branch {} has diverged from its upstream branch {}: {} ahead ⦁ {} behind.",
+                chain.root_branch.bold(),
+                upstream.bold(),
+                ahead,
+                behind
+            );
+            println!(
+                "The branches of chain {} are not based on the latest commits of {}. To update the root branch, merge or rebase it onto {}.",
+                chain.name.bold(),
+                upstream,
+                upstream
+            );
+        } else {
+            println!(
+                "⚠️  Root branch {} is {} {} behind its upstream branch {}.",
+                chain.root_branch.bold(),
+                behind,
+                if behind == 1 { "commit" } else { "commits" },
+                upstream.bold()
+            );
+            println!(
+                "The branches of chain {} are not based on the latest commits of {}.",
+                chain.name.bold(),
+                upstream
+            );
+            println!(
+                "To update the root branch, and rebase the chain onto it, run: {} rebase --update-root",
+                self.executable_name
+            );
+        }
+        Ok(())
+    }
+
+    // Fast-forward the root branch of the chain to its upstream, before rebasing onto it.
+    fn update_root(&self, chain: &Chain) -> Result<(), Error> {
+        let (upstream, ahead, behind) = match self.root_upstream_status(&chain.root_branch)? {
+            Some(status) => status,
+            None => {
+                eprintln!(
+                    "🛑 Unable to update root branch {}: it is not a local branch with an upstream branch.",
+                    chain.root_branch.bold()
+                );
+                exit(1);
+            }
+        };
+
+        if behind == 0 {
+            return Ok(());
+        }
+
+        if ahead > 0 {
+            eprintln!(
+                "🛑 Unable to update root branch {}: it has diverged from its upstream branch {}.",
+                chain.root_branch.bold(),
+                upstream.bold()
+            );
+            eprintln!("Merge or rebase it onto {}, then run again.", upstream);
+            exit(1);
+        }
+
+        if self.get_current_branch_name_if_attached()?.as_deref() == Some(&chain.root_branch) {
+            // The working directory is updated along with the checked out branch.
+            self.run_git_remote_command(
+                &["merge", "--ff-only", "--quiet", &upstream],
+                &format!("Unable to update root branch {}", chain.root_branch),
+            );
+        } else {
+            let upstream_oid = self.repo.revparse_single(&upstream)?.peel_to_commit()?.id();
+            self.repo
+                .find_branch(&chain.root_branch, BranchType::Local)?
+                .get_mut()
+                .set_target(
+                    upstream_oid,
+                    &format!(
+                        "git chain rebase --update-root: fast-forward to {}",
+                        upstream
+                    ),
+                )?;
+        }
+
+        println!(
+            "✅ Updated root branch {} to {} ({} new {}).",
+            chain.root_branch.bold(),
+            upstream.bold(),
+            behind,
+            if behind == 1 { "commit" } else { "commits" }
+        );
+        Ok(())
+    }
+
+    // The author date (as a UNIX timestamp) and the author name of the last commit of a branch.
+    // The author date is kept when a branch is rebased, unlike the committer date, so a stale
+    // branch still looks stale after the chain is rebased.
+    fn first_commit_at(
+        &self,
+        parent_branch: &str,
+        branch_name: &str,
+    ) -> Result<Option<i64>, Error> {
+        // The author date of the oldest commit of the branch, i.e. of git log parent_branch..branch
+        let (branch_obj, _reference) = self.repo.revparse_ext(branch_name)?;
+        let (parent_obj, _reference) = self.repo.revparse_ext(parent_branch)?;
+
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(branch_obj.peel_to_commit()?.id())?;
+        revwalk.hide(parent_obj.peel_to_commit()?.id())?;
+
+        let mut first_commit_at = None;
+        for oid in revwalk {
+            let author_time = self.repo.find_commit(oid?)?.author().when().seconds();
+            first_commit_at =
+                Some(first_commit_at.map_or(author_time, |first: i64| first.min(author_time)));
+        }
+        Ok(first_commit_at)
+    }
+
+    fn display_stats(&self, chain_names: &[String], json: bool) -> Result<(), Error> {
+        let history = history::read(&self.chain_dir())
+            .map_err(|err| Error::from_str(&format!("Unable to read history: {}", err)))?;
+
+        let mut chain_stats = vec![];
+        for chain_name in chain_names {
+            if !Chain::chain_exists(self, chain_name)? {
+                eprintln!("Unable to display the statistics of the chain.");
+                eprintln!("Chain does not exist: {}", chain_name.bold());
+                exit(1);
+            }
+            let chain = Chain::get_chain(self, chain_name)?;
+            chain_stats.push(chain.stats(self, &history)?);
+        }
+
+        if json {
+            println!("{}", stats::to_json(unix_time(), &chain_stats));
+            return Ok(());
+        }
+
+        if chain_stats.is_empty() {
+            println!("No chains to list.");
+            return Ok(());
+        }
+        for (index, chain) in chain_stats.iter().enumerate() {
+            println!("{}", chain.to_text());
+            if index != chain_stats.len() - 1 {
+                println!();
+            }
+        }
+        Ok(())
+    }
+
+    fn last_commit(&self, branch_name: &str) -> Result<(i64, String), Error> {
+        let commit = self.repo.revparse_single(branch_name)?.peel_to_commit()?;
+        let author = commit.author();
+        Ok((
+            author.when().seconds(),
+            String::from_utf8_lossy(author.name_bytes()).to_string(),
+        ))
+    }
+
+    fn upstream_ahead_behind(&self, branch_name: &str) -> Result<Option<(usize, usize)>, Error> {
+        let branch = self.repo.find_branch(branch_name, BranchType::Local)?;
+
+        let upstream = match branch.upstream() {
+            Ok(upstream) => upstream,
+            Err(ref e) if e.code() == ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let local_oid = branch.get().peel_to_commit()?.id();
+        let upstream_oid = upstream.get().peel_to_commit()?.id();
+
+        Ok(Some(self.repo.graph_ahead_behind(local_oid, upstream_oid)?))
+    }
+
+    // How many of the num_of_commits commits of branch on top of parent_branch already landed in
+    // root_branch: merged, rebased (by patch-id) or squashed and merged.
+    fn landed_commits(
+        &self,
+        root_branch: &str,
+        parent_branch: &str,
+        branch: &str,
+        num_of_commits: usize,
+    ) -> Result<usize, Error> {
+        if num_of_commits == 0 {
+            return Ok(0);
+        }
+
+        if self.is_ancestor(branch, root_branch)? {
+            return Ok(num_of_commits);
+        }
+
+        let fork_point = self.merge_base(parent_branch, branch)?;
+
+        // git cherry <root_branch> <branch> <fork_point>
+        let command = format!("git cherry {} {} {}", root_branch, branch, fork_point);
+        let output = Command::new("git")
+            .arg("cherry")
+            .arg(root_branch)
+            .arg(branch)
+            .arg(&fork_point)
+            .logged_output()
+            .unwrap_or_else(|_| panic!("Unable to run: {}", &command));
+
+        if !output.status.success() {
+            return Err(Error::from_str(&format!("Unable to run: {}", &command)));
+        }
+
+        let num_of_landed_commits = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| line.starts_with('-'))
+            .count();
+        if num_of_landed_commits == num_of_commits {
+            return Ok(num_of_commits);
+        }
+
+        if self.is_squashed_merged(&fork_point, root_branch, branch, Some(&fork_point))? {
+            return Ok(num_of_commits);
+        }
+
+        Ok(num_of_landed_commits)
+    }
+
+    // Whether the two branches have a common ancestor. A branch created with git checkout --orphan,
+    // or fetched from another repository, may have none.
+    fn shares_history(&self, branch: &str, other_branch: &str) -> Result<bool, Error> {
+        let branch_oid = self.repo.revparse_single(branch)?.peel_to_commit()?.id();
+        let other_oid = self
+            .repo
+            .revparse_single(other_branch)?
+            .peel_to_commit()?
+            .id();
+        match self.repo.merge_base(branch_oid, other_oid) {
+            Ok(_) => Ok(true),
+            Err(ref e) if e.code() == ErrorCode::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    // The branches within indices that share no history with their parent branch.
+    fn unrelated_branches<'a>(
+        &self,
+        pairs: &'a [BranchPair],
+        indices: &std::ops::Range<usize>,
+    ) -> Result<Vec<&'a BranchPair<'a>>, Error> {
+        let mut unrelated_branches = vec![];
+        for pair in &pairs[indices.clone()] {
+            if !self.shares_history(pair.parent, &pair.branch.branch_name)? {
+                unrelated_branches.push(pair);
+            }
+        }
+        Ok(unrelated_branches)
+    }
+
+    // Commits of a branch without a common ancestor with its parent branch cannot be told apart from
+    // the commits of the parent branch, so their entire history would be replayed.
+    fn ensure_related_histories(
+        &self,
+        chain: &Chain,
+        indices: &std::ops::Range<usize>,
+        allow_unrelated: bool,
+        action: &str,
+    ) -> Result<(), Error> {
+        if allow_unrelated {
+            return Ok(());
+        }
+
+        let pairs = chain.branch_pairs();
+        let unrelated_branches = self.unrelated_branches(&pairs, indices)?;
+        if unrelated_branches.is_empty() {
+            return Ok(());
+        }
+
+        eprintln!(
+            "🛑 Unable to {} chain {}: some of its branches share no history with their parent branch.",
+            action,
+            chain.name.bold()
+        );
+        for pair in unrelated_branches {
+            eprintln!(
+                "  - {} (parent branch: {})",
+                pair.branch.branch_name.bold(),
+                pair.parent
+            );
+        }
+        eprintln!(
+            "To leave these branches as they are, and {} the other branches, run again with --allow-unrelated.",
+            action
+        );
+        exit(1);
+    }
+
+    fn is_ancestor(&self, ancestor_branch: &str, descendant_branch: &str) -> Result<bool, Error> {
+        let (ancestor_object, _reference) = self.repo.revparse_ext(ancestor_branch)?;
+        let (descendant_object, _reference) = self.repo.revparse_ext(descendant_branch)?;
+
+        // Branches without a common ancestor are not ancestors of one another.
+        let common_point = match self
+            .repo
+            .merge_base(ancestor_object.id(), descendant_object.id())
+        {
+            Ok(common_point) => common_point,
+            Err(ref e) if e.code() == ErrorCode::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        Ok(common_point == ancestor_object.id())
+    }
+}
+
+fn parse_scope_option(scope: Option<&str>) -> Option<String> {
+    let scope = scope?;
+    match normalize_scope(scope) {
+        Some(scope) => Some(scope),
+        None => {
+            eprintln!("Invalid scope: {}", scope.bold());
+            eprintln!("A scope is a directory relative to the root of the repository.");
+            exit(1);
+        }
+    }
+}
+
+fn validate_chain_name(chain_name: &str) {
+    if let Some(error) = names::chain_name_error(chain_name) {
+        eprintln!(
+            "🛑 Invalid chain name: {}",
+            names::display_name(chain_name).bold()
+        );
+        eprintln!("{}.", error);
+        eprintln!("{}", names::CHAIN_NAME_CHARSET);
+        exit(1);
+    }
+}
+
+fn validate_branch_name(branch_name: &str) {
+    if let Some(error) = names::branch_name_error(branch_name) {
+        eprintln!(
+            "🛑 Invalid branch name: {}",
+            names::display_name(branch_name).bold()
+        );
+        eprintln!("{}.", error);
+        eprintln!("Branch names must follow the rules of git check-ref-format.");
+        exit(1);
+    }
+}
+
+fn parse_sort_option(
+    git_chain: &GitChain,
+    chain_name: &str,
+    before_branch: Option<&str>,
+    after_branch: Option<&str>,
+) -> Result<SortBranch, Error> {
+    if let Some(before_branch) = before_branch {
+        if !git_chain.git_local_branch_exists(before_branch)? {
+            return Err(Error::from_str(&format!(
+                "Branch does not exist: {}",
+                before_branch.bold()
+            )));
+        }
+
+        let before_branch = match Branch::get_branch_with_chain(git_chain, before_branch)? {
+            BranchSearchResult::NotPartOfAnyChain(_) => {
+                git_chain.display_branch_not_part_of_chain_error(before_branch);
+                exit(1);
+            }
+            BranchSearchResult::Branch(before_branch) => {
+                if before_branch.chain_name != chain_name {
+                    return Err(Error::from_str(&format!(
+                        "Branch {} is not part of chain {}",
+                        before_branch.branch_name.bold(),
+                        chain_name.bold()
+                    )));
+                }
+                before_branch
+            }
+        };
+
+        Ok(SortBranch::Before(before_branch))
+    } else if let Some(after_branch) = after_branch {
+        if !git_chain.git_local_branch_exists(after_branch)? {
+            return Err(Error::from_str(&format!(
+                "Branch does not exist: {}",
+                after_branch.bold()
+            )));
+        }
+
+        let after_branch = match Branch::get_branch_with_chain(git_chain, after_branch)? {
+            BranchSearchResult::NotPartOfAnyChain(_) => {
+                git_chain.display_branch_not_part_of_chain_error(after_branch);
+                exit(1);
+            }
+            BranchSearchResult::Branch(after_branch) => {
+                if after_branch.chain_name != chain_name {
+                    return Err(Error::from_str(&format!(
+                        "Branch {} is not part of chain {}",
+                        after_branch.branch_name.bold(),
+                        chain_name.bold()
+                    )));
+                }
+                after_branch
+            }
+        };
+
+        Ok(SortBranch::After(after_branch))
+    } else {
+        Ok(SortBranch::Last)
+    }
+}
+
+fn test_options(sub_matches: &ArgMatches) -> Result<TestOptions, Error> {
+    let jobs = match sub_matches.value_of("jobs") {
+        Some(jobs) => match jobs.parse::<usize>() {
+            Ok(jobs) if jobs > 0 => jobs,
+            _ => {
+                return Err(Error::from_str(&format!(
+                    "Invalid number of jobs: {}",
+                    jobs
+                )));
+            }
+        },
+        None => 1,
+    };
+
+    Ok(TestOptions {
+        worktrees: sub_matches.is_present("worktrees") || sub_matches.is_present("jobs"),
+        jobs,
+        fail_fast: sub_matches.is_present("fail_fast"),
+    })
+}
+
+fn print_version(json: bool) {
+    let version = env!("CARGO_PKG_VERSION");
+
+    if !json {
+        println!("git-chain {}", version);
+        return;
+    }
+
+    let (major, minor, rev) = git2::Version::get().libgit2_version();
+    // Optional cargo features compiled into this build.
+    #[allow(unused_mut)]
+    let mut features: Vec<&str> = vec![];
+    #[cfg(feature = "webhook")]
+    features.push("webhook");
+
+    println!("{{");
+    println!(r#"  "version": "{}","#, version);
+    println!(
+        r#"  "commit": "{}","#,
+        option_env!("GIT_CHAIN_BUILD_COMMIT").unwrap_or("unknown")
+    );
+    println!(r#"  "libgit2": "{}.{}.{}","#, major, minor, rev);
+    println!(
+        r#"  "features": [{}]"#,
+        features
+            .iter()
+            .map(|feature| format!(r#""{}""#, feature))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    println!("}}");
+}
+
+fn run(arg_matches: ArgMatches) -> Result<(), Error> {
+    if arg_matches.is_present("version") {
+        print_version(arg_matches.is_present("json"));
+        return Ok(());
+    }
+
+    if arg_matches.is_present("debug") || arg_matches.is_present("debug_file") {
+        let debug_file = arg_matches.value_of("debug_file");
+        if let Err(err) = debug::enable(debug_file) {
+            eprintln!(
+                "🛑 Unable to open the debug file {}: {}",
+                debug_file.unwrap_or_default().bold(),
+                err
+            );
+            exit(1);
+        }
+        debug_log!(
+            "git chain {}",
+            std::env::args().skip(1).collect::<Vec<_>>().join(" ")
+        );
+    }
+
+    // Guides are displayed outside of repositories too.
+    if let ("help", Some(sub_matches)) = arg_matches.subcommand() {
+        display_help(
+            sub_matches.value_of("topic"),
+            arg_matches.is_present("no_pager"),
+        );
+        return Ok(());
+    }
+
+    // Precedence: --yes / --no-input > GIT_CHAIN_YES
+    let prompt_mode = if arg_matches.is_present("yes") {
+        PromptMode::AssumeYes
+    } else if arg_matches.is_present("no_input") {
+        PromptMode::NoInput
+    } else if env_bool_setting("GIT_CHAIN_YES")?.unwrap_or(false) {
+        PromptMode::AssumeYes
+    } else {
+        PromptMode::Interactive
+    };
+
+    let git_chain = GitChain::init(prompt_mode)?;
+
+    git_chain.apply_color_setting()?;
+
+    if !matches!(arg_matches.subcommand_name(), Some("enable" | "disable")) {
+        git_chain.ensure_enabled()?;
+    }
+
+    // Long outputs go through the pager of git, unless --no-pager is given or pager.chain is false.
+    if pager::in_use() {
+        pager::exit_quietly_on_broken_pipe();
+    } else if matches!(
+        arg_matches.subcommand_name(),
+        Some("list" | "history" | "blame" | "diff")
+    ) && !arg_matches.is_present("no_pager")
+        && git_chain.paging_enabled()?
+    {
+        debug_log!("paging the output");
+        pager::page(std::env::args_os().skip(1).collect());
+    }
+
+    git_chain.apply_gh_host_setting(arg_matches.value_of("hostname"))?;
+    git_chain.apply_offline_setting(arg_matches.is_present("offline"))?;
+
+    let is_mutating_subcommand = matches!(
+        arg_matches.subcommand_name(),
+        Some(
+            "init"
+                | "remove"
+                | "move"
+                | "reorder"
+                | "doctor"
+                | "adopt"
+                | "rebase"
+                | "cherry"
+                | "squash-branch"
+                | "push"
+                | "prune"
+                | "after-land"
+                | "pr"
+                | "setup"
+                | "new-stack"
+                | "migrate-root"
+                | "publish"
+                | "subscribe"
+                | "import"
+                | "rename"
+                | "rename-prefix"
+                | "copy"
+                | "undo"
+                | "fetch"
+                | "first"
+                | "last"
+                | "next"
+                | "prev"
+        )
+    ) || matches!(
+        arg_matches.subcommand(),
+        ("note", Some(sub_matches)) | ("strategy", Some(sub_matches)) | ("push-ref", Some(sub_matches)) if matches!(sub_matches.subcommand_name(), Some("set") | Some("clear"))
+    ) || matches!(
+        arg_matches.subcommand(),
+        // With worktrees, the branches are not checked out in the working directory.
+        ("test", Some(sub_matches)) if !sub_matches.is_present("worktrees") && !sub_matches.is_present("jobs")
+    ) || matches!(
+        arg_matches.subcommand(),
+        ("stash", Some(sub_matches)) if matches!(sub_matches.subcommand_name(), Some("push") | Some("pop"))
+    ) || matches!(
+        arg_matches.subcommand(),
+        ("lint", Some(sub_matches)) if sub_matches.is_present("fix")
+    ) || matches!(
+        arg_matches.subcommand(),
+        ("scope", Some(sub_matches)) if sub_matches.is_present("clear") || sub_matches.is_present("scope")
+    ) || matches!(
+        arg_matches.subcommand(),
+        ("backup", Some(sub_matches)) if !(sub_matches.is_present("list") || (sub_matches.is_present("prune") && sub_matches.is_present("dry_run")))
+    );
+
+    // Precedence: --read-only > GIT_CHAIN_READ_ONLY > chain.readOnly
+    if is_mutating_subcommand
+        && (arg_matches.is_present("read_only")
+            || git_chain
+                .get_bool_setting("GIT_CHAIN_READ_ONLY", "chain.readOnly")?
+                .unwrap_or(false))
+    {
+        let subcommand_name = arg_matches.subcommand_name().unwrap_or_default();
+        eprintln!(
+            "🛑 Unable to run {} {}: the repository is in read-only mode.",
+            git_chain.executable_name,
+            subcommand_name.bold()
+        );
+        eprintln!(
+            "Read-only mode is enabled by --read-only, GIT_CHAIN_READ_ONLY or chain.readOnly. Commands that display chains (e.g. status, list) still work."
+        );
+        exit(1);
+    }
+
+    if arg_matches.is_present("force_unlock") {
+        git_chain.force_unlock()?;
+    }
+
+    // The lock is released when the command returns or exits early (e.g. on a rebase conflict).
+    // A command that was killed leaves a stale lock behind, which is detected on the next run.
+    let _lock = if is_mutating_subcommand {
+        git_chain.ensure_no_operation_in_progress()?;
+        Some(git_chain.lock()?)
+    } else {
+        None
+    };
+
+    if is_mutating_subcommand {
+        let branches_before = git_chain.snapshot_chain_branches()?;
+        *PENDING_HISTORY
+            .lock()
+            .unwrap_or_else(|err| err.into_inner()) = Some((unix_time(), branches_before));
+    }
+
+    let result = run_subcommand(&git_chain, &arg_matches);
+
+    let pending_history = PENDING_HISTORY
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .take();
+    if let Some((started_at, branches_before)) = pending_history {
+        git_chain.record_history(started_at, branches_before)?;
+    }
+    result?;
+
+    Ok(())
+}
+
+fn run_subcommand(git_chain: &GitChain, arg_matches: &ArgMatches) -> Result<(), Error> {
+    match arg_matches.subcommand() {
+        ("init", Some(sub_matches)) => {
+            // Initialize the current branch to a chain.
+
+            let chain_name = sub_matches.value_of("chain_name").unwrap().to_string();
+            validate_chain_name(&chain_name);
+            let root_branch = sub_matches.value_of("root_branch");
+
+            let before_branch = sub_matches.value_of("before");
+            let after_branch = sub_matches.value_of("after");
+            let scope = parse_scope_option(sub_matches.value_of("scope"));
+
+            let branch_name = git_chain.get_current_branch_name()?;
+
+            let root_branch = if Chain::chain_exists(git_chain, &chain_name)? {
+                // Derive root branch from an existing chain
+                let chain = Chain::get_chain(git_chain, &chain_name)?;
+
+                if let Some(user_provided_root_branch) = root_branch {
+                    if user_provided_root_branch != chain.root_branch {
+                        println!(
+                            "Using root branch {} of chain {} instead of {}",
+                            chain.root_branch.bold(),
+                            chain_name.bold(),
+                            user_provided_root_branch.bold()
+                        );
+                    }
+                }
+
+                chain.root_branch
+            } else if let Some(root_branch) = root_branch {
+                root_branch.to_string()
+            } else if let Some(default_branch) = git_chain.detect_default_branch()? {
+                println!(
+                    "Using default branch {} as the root branch.",
+                    default_branch.bold()
+                );
+                default_branch
+            } else {
+                eprintln!("Unable to detect the default branch of the repository.");
+                eprintln!("Please provide the root branch.");
+                exit(1);
+            };
+
+            let root_branch = match git_chain.resolve_root_branch(&root_branch)? {
+                Some(root_branch) => root_branch,
+                None => {
+                    eprintln!("Root branch does not exist: {}", root_branch.bold());
+                    exit(1);
+                }
+            };
+
+            if root_branch == branch_name {
+                eprintln!(
+                    "Current branch cannot be the root branch: {}",
+                    branch_name.bold()
+                );
+                exit(1);
+            }
+
+            let sort_option = if sub_matches.is_present("first") {
+                SortBranch::First
+            } else {
+                parse_sort_option(git_chain, &chain_name, before_branch, after_branch)?
+            };
+
+            let descendants = if sub_matches.is_present("all_descendants") {
+                git_chain.find_descendant_branches(&branch_name, &root_branch)?
+            } else {
+                vec![]
+            };
+
+            git_chain.init_chain(
+                &chain_name,
+                &root_branch,
+                &branch_name,
+                sort_option,
+                scope.as_deref(),
+                &descendants,
+            )?
+        }
+        ("remove", Some(sub_matches)) => {
+            // Remove current branch from its chain.
+
+            let chain_name = sub_matches.value_of("chain_name");
+
+            let branch_name = git_chain.get_current_branch_name()?;
+
+            if let Some(chain_name) = chain_name {
+                // Only delete a specific chain
+                let dry_run = sub_matches.is_present("dry_run");
+                git_chain.remove_chain(chain_name, dry_run)?;
+                return Ok(());
+            }
+
+            git_chain.remove_branch_from_chain(branch_name)?
+        }
+        ("list", Some(sub_matches)) => {
+            // List all chains.
+            let current_branch = git_chain
+                .get_current_branch_name_if_attached()?
+                .unwrap_or_default();
+            git_chain.list_chains(
+                &current_branch,
+                &ListOptions {
+                    markdown: sub_matches.value_of("format") == Some("markdown"),
+                    tree: sub_matches.is_present("tree"),
+                    pr_urls: sub_matches.is_present("pr"),
+                    verbose: sub_matches.is_present("verbose"),
+                    check_conflicts: false,
+                },
+            )?
+        }
+        ("reorder", Some(sub_matches)) => {
+            // Reorder the branches of a chain to match their git ancestry.
+            // clap ensures that --topological is given.
+            assert!(sub_matches.is_present("topological"));
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            if !Chain::chain_exists(git_chain, &chain_name)? {
+                eprintln!("Unable to reorder chain.");
+                eprintln!("Chain does not exist: {}", chain_name.bold());
+                exit(1);
+            }
+            git_chain.reorder_topological(&chain_name)?;
+        }
+        ("doctor", Some(sub_matches)) => {
+            // Repair the metadata of chains.
+            let chain_name = sub_matches.value_of("chain_name");
+            if let Some(chain_name) = chain_name {
+                let chain_names = Chain::get_all_branch_configs(git_chain)?;
+                if !chain_names.iter().any(|(_key, value)| value == chain_name) {
+                    eprintln!("Unable to repair chain.");
+                    eprintln!("Chain does not exist: {}", chain_name.bold());
+                    exit(1);
+                }
+            }
+            let renumber = sub_matches.is_present("renumber");
+            git_chain.repair_chains(chain_name, !renumber)?;
+            if renumber {
+                git_chain.renumber_chains(chain_name)?;
+            }
+        }
+        ("adopt", Some(sub_matches)) => {
+            // Add an existing branch to a chain, at the position given by its ancestry.
+            let branch_name = sub_matches.value_of("branch").unwrap();
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            if !Chain::chain_exists(git_chain, &chain_name)? {
+                eprintln!("Unable to adopt branch: {}", branch_name.bold());
+                eprintln!("Chain does not exist: {}", chain_name.bold());
+                exit(1);
+            }
+            git_chain.adopt(&chain_name, branch_name)?;
+        }
+        ("move", Some(sub_matches)) => {
+            // Move current branch or chain.
+
+            let before_branch = sub_matches.value_of("before");
+            let after_branch = sub_matches.value_of("after");
+            let root_branch = sub_matches.value_of("root");
+            let chain_name = sub_matches.value_of("chain_name");
+
+            let branch_name = git_chain.get_current_branch_name()?;
+
+            let branch = match Branch::get_branch_with_chain(git_chain, &branch_name)? {
+                BranchSearchResult::NotPartOfAnyChain(_) => {
+                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
+                    exit(1);
+                }
+                BranchSearchResult::Branch(branch) => branch,
+            };
+
+            if let Some(root_branch) = root_branch {
+                // invariant: chain_name is None
+                // clap ensures this invariant
+                assert!(chain_name.is_none());
+
+                let root_branch = match git_chain.resolve_root_branch(root_branch)? {
+                    Some(root_branch) => root_branch,
+                    None => {
+                        eprintln!("Root branch does not exist: {}", root_branch.bold());
+                        exit(1);
+                    }
+                };
+
+                if root_branch == branch_name {
+                    eprintln!(
+                        "Current branch cannot be the root branch: {}",
+                        branch_name.bold()
+                    );
+                    exit(1);
+                }
+
+                let chain = Chain::get_chain(git_chain, &branch.chain_name)?;
+
+                let old_root_branch = chain.root_branch.clone();
+
+                chain.change_root_branch(git_chain, &root_branch)?;
+
+                println!(
+                    "Changed root branch for the chain {} from {} to {}",
+                    chain.name.bold(),
+                    old_root_branch.bold(),
+                    root_branch.bold()
+                );
+            }
+
+            match chain_name {
+                None => {
+                    let chain_name = branch.chain_name;
+                    if before_branch.is_some() || after_branch.is_some() {
+                        let sort_option =
+                            parse_sort_option(git_chain, &chain_name, before_branch, after_branch)?;
+                        git_chain.move_branch(&chain_name, &branch_name, &sort_option)?
+                    } else {
+                        // nothing to do
+                        println!("Nothing to do. ☕");
+                    }
+                }
+                Some(new_chain_name) => {
+                    validate_chain_name(new_chain_name);
+                    let old_chain_name = branch.chain_name;
+                    if before_branch.is_some()
+                        || after_branch.is_some()
+                        || new_chain_name != old_chain_name
+                    {
+                        let sort_option = parse_sort_option(
+                            git_chain,
+                            new_chain_name,
+                            before_branch,
+                            after_branch,
+                        )?;
+                        git_chain.move_branch(new_chain_name, &branch_name, &sort_option)?
+                    } else {
+                        // nothing to do
+                        println!("Nothing to do. ☕");
+                    }
+                }
+            };
+        }
+        ("rebase", Some(sub_matches)) => {
+            // Rebase all branches for the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+
+            let interactive = sub_matches.is_present("interactive");
+            if interactive && !io::stdin().is_terminal() && env_var("GIT_SEQUENCE_EDITOR").is_none()
+            {
+                eprintln!("🛑 Unable to rebase interactively: stdin is not a terminal.");
+                eprintln!("Set GIT_SEQUENCE_EDITOR to edit the todo lists non-interactively.");
+                exit(1);
+            }
+
+            // Precedence: --worktree > GIT_CHAIN_REBASE_IN_WORKTREE > chain.rebaseInWorktree
+            // --step, --interactive and --keep-base need the branches checked out in the working
+            // directory.
+            let worktree = sub_matches.is_present("worktree")
+                || (!sub_matches.is_present("step")
+                    && !interactive
+                    && !sub_matches.is_present("keep_base")
+                    && git_chain
+                        .get_bool_setting("GIT_CHAIN_REBASE_IN_WORKTREE", "chain.rebaseInWorktree")?
+                        .unwrap_or(false));
+
+            if Chain::chain_exists(git_chain, &chain_name)? {
+                let options = RebaseOptions {
+                    step_rebase: sub_matches.is_present("step"),
+                    ignore_root: sub_matches.is_present("ignore_root"),
+                    committer_date_is_author_date: sub_matches
+                        .is_present("committer_date_is_author_date"),
+                    reset_author_date: sub_matches.is_present("reset_author_date"),
+                    interactive,
+                    drop_empty: true,
+                    delete_empty: sub_matches.is_present("delete_empty"),
+                    worktree,
+                    keep_base: sub_matches.is_present("keep_base"),
+                    allow_unrelated: sub_matches.is_present("allow_unrelated"),
+                    update_root: sub_matches.is_present("update_root"),
+                    strategy_option: sub_matches.value_of("strategy_option").map(str::to_string),
+                    reconcile: git_chain.reconcile_mode(sub_matches.value_of("reconcile"))?,
+                    signoff: sub_matches.is_present("signoff"),
+                    stay: sub_matches.is_present("stay"),
+                    range: BranchRange::from_matches(sub_matches),
+                };
+                if sub_matches.is_present("timings") {
+                    timings::enable();
+                }
+                git_chain.rebase(&chain_name, &options)?;
+                timings::report();
+
+                // A rebase stopped by --step is verified once it is done.
+                if !options.step_rebase && git_chain.verify_enabled(sub_matches)? {
+                    println!();
+                    git_chain.verify(
+                        &chain_name,
+                        &VerifyOptions {
+                            range: BranchRange::from_matches(sub_matches),
+                            ignore_root: options.ignore_root,
+                            ignore_parents: options.keep_base,
+                            ignore_worktree: options.worktree,
+                        },
+                    )?;
+                }
+            } else {
+                eprintln!("Unable to rebase chain.");
+                eprintln!("Chain does not exist: {}", chain_name.bold());
+                exit(1);
+            }
+        }
+        ("cherry", Some(sub_matches)) => {
+            // Cherry-pick the new commits of each parent branch onto the branches of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            let signoff = sub_matches.is_present("signoff");
+            let stay = sub_matches.is_present("stay");
+
+            if Chain::chain_exists(git_chain, &chain_name)? {
+                match sub_matches.value_of("backport") {
+                    Some(revision) => git_chain.backport(&chain_name, revision, signoff, stay)?,
+                    None => git_chain.cherry(
+                        &chain_name,
+                        signoff,
+                        sub_matches.is_present("allow_unrelated"),
+                        stay,
+                    )?,
+                }
+
+                if git_chain.verify_enabled(sub_matches)? {
+                    println!();
+                    git_chain.verify(&chain_name, &VerifyOptions::default())?;
+                }
+            } else {
+                eprintln!("Unable to cherry-pick commits for the chain.");
+                eprintln!("Chain does not exist: {}", chain_name.bold());
+                exit(1);
+            }
+        }
+        ("backup", Some(sub_matches)) => {
+            // Back up all branches of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+
+            if sub_matches.is_present("list") {
+                git_chain.list_backups(&chain_name)?;
+            } else if sub_matches.is_present("prune") {
+                let keep = match sub_matches.value_of("keep") {
+                    Some(keep) => match keep.parse::<usize>() {
+                        Ok(keep) => keep,
+                        Err(_) => {
+                            eprintln!("Invalid number of backups to keep: {}", keep.bold());
+                            exit(1);
+                        }
+                    },
+                    None => match git_chain.get_backup_retention()? {
+                        Some(retention) => retention,
+                        None => {
+                            eprintln!("Please provide the number of backups to keep with --keep,");
+                            eprintln!("or set it with: git config chain.backupRetention <N>");
+                            exit(1);
+                        }
+                    },
+                };
+
+                let dry_run = sub_matches.is_present("dry_run");
+                let pruned_backups = git_chain.prune_backups(&chain_name, keep, dry_run)?;
+                if pruned_backups.is_empty() {
+                    println!("No backups pruned for chain: {}", chain_name.bold());
+                } else {
+                    println!(
+                        "{} {} old backups of chain: {}",
+                        if dry_run { "Would remove" } else { "Removed" },
+                        pruned_backups.len(),
+                        chain_name.bold()
+                    );
+                    for timestamp in pruned_backups {
+                        println!("{}", timestamp);
+                    }
+                }
+            } else if let Some(timestamp) = sub_matches.value_of("restore") {
+                git_chain.restore_backup(
+                    &chain_name,
+                    timestamp,
+                    sub_matches.is_present("include_root"),
+                )?;
+            } else {
+                git_chain.backup(&chain_name, sub_matches.is_present("include_root"))?;
+            }
+        }
+        ("push", Some(sub_matches)) => {
+            // Push all branches of the current chain to their upstreams.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+
+            // Precedence: --force / --no-force-push > GIT_CHAIN_FORCE_PUSH > chain.forcePush
+            let force_push = if sub_matches.is_present("force") {
+                true
+            } else if sub_matches.is_present("no_force_push") {
+                false
+            } else {
+                git_chain
+                    .get_bool_setting("GIT_CHAIN_FORCE_PUSH", "chain.forcePush")?
+                    .unwrap_or(false)
+            };
+            let options = PushOptions {
+                force_push,
+                only_changed: sub_matches.is_present("only_changed"),
+                check_pr: sub_matches.is_present("check_pr"),
+                strict: sub_matches.is_present("strict"),
+                // Precedence: --protect-reviewed > GIT_CHAIN_PROTECT_REVIEWED > chain.protectReviewed
+                protect_reviewed: sub_matches.is_present("protect_reviewed")
+                    || git_chain
+                        .get_bool_setting("GIT_CHAIN_PROTECT_REVIEWED", "chain.protectReviewed")?
+                        .unwrap_or(false),
+                range: BranchRange::from_matches(sub_matches),
+                create_pr: sub_matches.is_present("create_pr"),
+                draft: sub_matches.is_present("draft"),
+            };
+            if sub_matches.is_present("timings") {
+                timings::enable();
+            }
+            git_chain.push(&chain_name, &options)?;
+            timings::report();
+        }
+        ("prune", Some(sub_matches)) => {
+            // Prune any branches of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+
+            let dry_run = sub_matches.is_present("dry_run");
+
+            git_chain.prune(&chain_name, dry_run)?;
+        }
+        ("scope", Some(sub_matches)) => {
+            // Display or update the scope of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            let chain = Chain::get_chain(git_chain, &chain_name)?;
+
+            if sub_matches.is_present("clear") {
+                chain.set_scope(git_chain, None)?;
+                println!("Cleared the scope of chain: {}", chain_name.bold());
+            } else if let Some(scope) = parse_scope_option(sub_matches.value_of("scope")) {
+                chain.set_scope(git_chain, Some(&scope))?;
+                println!(
+                    "Set the scope of chain {} to: {}",
+                    chain_name.bold(),
+                    scope.bold()
+                );
+            } else {
+                match chain.scope(git_chain)? {
+                    Some(scope) => println!("{}", scope),
+                    None => println!("Chain has no scope: {}", chain_name.bold()),
+                }
+            }
+        }
+        ("stash", Some(stash_matches)) => {
+            // Stash changes per chain, and pop them back onto the branch they were made on.
+            // The chain may be given before or after the subcommand of stash.
+            let chain_of = |sub_matches: Option<&ArgMatches>| {
+                sub_matches
+                    .and_then(|sub_matches| sub_matches.value_of("chain_name"))
+                    .or_else(|| stash_matches.value_of("chain_name"))
+                    .map(|chain_name| chain_name.to_string())
+            };
+            match stash_matches.subcommand() {
+                ("push", Some(sub_matches)) => git_chain.stash_push(
+                    chain_of(Some(sub_matches)).as_deref(),
+                    sub_matches.value_of("message"),
+                    sub_matches.is_present("include_untracked"),
+                )?,
+                ("pop", Some(sub_matches)) => git_chain.stash_pop(
+                    chain_of(Some(sub_matches)).as_deref(),
+                    sub_matches.value_of("stash"),
+                    sub_matches.is_present("here"),
+                )?,
+                (_, sub_matches) => git_chain.stash_list(chain_of(sub_matches).as_deref())?,
+            }
+        }
+        ("note", Some(note_matches)) => {
+            // Display, set or clear the note of a branch of a chain.
+            // The branch and the chain may be given before or after the subcommand of note.
+            let branch_of = |sub_matches: Option<&ArgMatches>| -> Result<Branch, Error> {
+                let value_of = |name: &str| {
+                    sub_matches
+                        .and_then(|sub_matches| sub_matches.value_of(name))
+                        .or_else(|| note_matches.value_of(name))
+                        .map(|value| value.to_string())
+                };
+                let chain_name = value_of("chain_name");
+                let branch_name = value_of("branch");
+                let (_chain, branch) =
+                    git_chain.selected_branch(chain_name.as_deref(), branch_name.as_deref())?;
+                Ok(branch)
+            };
+            match note_matches.subcommand() {
+                ("set", Some(sub_matches)) => {
+                    let branch = branch_of(Some(sub_matches))?;
+                    let note: Vec<&str> = sub_matches.values_of("note").unwrap().collect();
+                    let note = note.join(" ");
+                    // Notes are displayed on a single line.
+                    let note = note.split_whitespace().collect::<Vec<_>>().join(" ");
+                    git_chain.set_git_config(&chain_note_key(&branch.branch_name), &note)?;
+                    println!(
+                        "Set the note of branch {} to: {}",
+                        branch.branch_name.bold(),
+                        note
+                    );
+                }
+                ("clear", Some(sub_matches)) => {
+                    let branch = branch_of(Some(sub_matches))?;
+                    git_chain.delete_git_config(&chain_note_key(&branch.branch_name))?;
+                    println!("Cleared the note of branch: {}", branch.branch_name.bold());
+                }
+                (_, sub_matches) => {
+                    let branch = branch_of(sub_matches)?;
+                    match branch.note(git_chain)? {
+                        Some(note) => println!("{}", note),
+                        None => println!("Branch has no note: {}", branch.branch_name.bold()),
+                    }
+                }
+            }
+        }
+        ("strategy", Some(strategy_matches)) => {
+            // Display, set or clear the strategy option of a branch of a chain.
+            // The branch may be given before or after the subcommand of strategy.
+            let branch_of = |sub_matches: Option<&ArgMatches>| {
+                sub_matches
+                    .and_then(|sub_matches| sub_matches.value_of("branch"))
+                    .or_else(|| strategy_matches.value_of("branch"))
+                    .map(|branch_name| branch_name.to_string())
+            };
+            match strategy_matches.subcommand() {
+                ("set", Some(sub_matches)) => {
+                    let branch_name = branch_of(Some(sub_matches));
+                    let branch = git_chain.get_branch_of_chain(branch_name.as_deref())?;
+                    let options: Vec<&str> = sub_matches.values_of("option").unwrap().collect();
+                    let options = options.join(" ");
+                    git_chain.set_git_config(
+                        &chain_strategy_option_key(&branch.branch_name),
+                        &options,
+                    )?;
+                    println!(
+                        "Set the strategy option of branch {} to: {}",
+                        branch.branch_name.bold(),
+                        options
+                    );
+                }
+                ("clear", Some(sub_matches)) => {
+                    let branch_name = branch_of(Some(sub_matches));
+                    let branch = git_chain.get_branch_of_chain(branch_name.as_deref())?;
+                    git_chain.delete_git_config(&chain_strategy_option_key(&branch.branch_name))?;
+                    println!(
+                        "Cleared the strategy option of branch: {}",
+                        branch.branch_name.bold()
+                    );
+                }
+                (_, sub_matches) => {
+                    let branch_name = branch_of(sub_matches);
+                    let branch = git_chain.get_branch_of_chain(branch_name.as_deref())?;
+                    let options = branch.strategy_options(git_chain)?;
+                    if options.is_empty() {
+                        println!(
+                            "Branch has no strategy option: {}",
+                            branch.branch_name.bold()
+                        );
+                    } else {
+                        println!("{}", options.join(" "));
+                    }
+                }
+            }
+        }
+        ("push-ref", Some(push_ref_matches)) => {
+            // Display, set or clear the ref on the remote that a branch of a chain is pushed to.
+            let branch_of = |sub_matches: Option<&ArgMatches>| {
+                sub_matches
+                    .and_then(|sub_matches| sub_matches.value_of("branch"))
+                    .or_else(|| push_ref_matches.value_of("branch"))
+                    .map(|branch_name| branch_name.to_string())
+            };
+            match push_ref_matches.subcommand() {
+                ("set", Some(sub_matches)) => {
+                    let branch_name = branch_of(Some(sub_matches));
+                    let branch = git_chain.get_branch_of_chain(branch_name.as_deref())?;
+                    let push_ref = sub_matches.value_of("ref").unwrap();
+                    git_chain.set_git_config(&chain_push_ref_key(&branch.branch_name), push_ref)?;
+                    println!(
+                        "Set the push ref of branch {} to: {}",
+                        branch.branch_name.bold(),
+                        qualify_push_ref(push_ref)
+                    );
+                }
+                ("clear", Some(sub_matches)) => {
+                    let branch_name = branch_of(Some(sub_matches));
+                    let branch = git_chain.get_branch_of_chain(branch_name.as_deref())?;
+                    git_chain.delete_git_config(&chain_push_ref_key(&branch.branch_name))?;
+                    println!(
+                        "Cleared the push ref of branch: {}",
+                        branch.branch_name.bold()
+                    );
+                }
+                (_, sub_matches) => {
+                    let branch_name = branch_of(sub_matches);
+                    let branch = git_chain.get_branch_of_chain(branch_name.as_deref())?;
+                    let (remote_name, remote_ref) =
+                        git_chain.push_destination(&branch.branch_name)?;
+                    println!("{} {}", remote_name, remote_ref);
+                }
+            }
+        }
+        ("browse", Some(sub_matches)) => {
+            // Open the pull request or compare page of the current branch.
+            let all = sub_matches.is_present("all");
+            let print_only = sub_matches.is_present("print");
+            git_chain.browse(sub_matches.value_of("chain_name"), all, print_only)?;
+        }
+        ("pr", Some(sub_matches)) => {
+            // Create pull requests for the branches of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            let draft_mode = match sub_matches.value_of("draft_until") {
+                Some(branch_name) => DraftMode::After(branch_name.to_string()),
+                None if sub_matches.is_present("draft") => DraftMode::All,
+                None => DraftMode::None,
+            };
+            let options = PrOptions {
+                draft_mode,
+                stack_order_labels: sub_matches.is_present("stack_order_labels"),
+                update_base: sub_matches.is_present("update_base_on_reorder"),
+                open: sub_matches.is_present("open"),
+                strict: sub_matches.is_present("strict"),
+            };
+            git_chain.pr(&chain_name, &options)?;
+        }
+        ("after-land", Some(sub_matches)) => {
+            // Clean up the current chain after some of its branches landed on the root branch.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            let push = sub_matches.is_present("push");
+            git_chain.after_land(&chain_name, push)?;
+        }
+        ("diff", Some(sub_matches)) => {
+            // Display the changes of the current branch, or of the entire chain.
+            let all = sub_matches.is_present("all");
+            let stat = sub_matches.is_present("stat");
+            git_chain.diff(sub_matches.value_of("chain_name"), all, stat)?;
+        }
+        ("blame", Some(sub_matches)) => {
+            // Annotate the lines of a file with the branches of the current chain.
+            git_chain.blame(
+                sub_matches.value_of("chain_name"),
+                sub_matches.value_of("path").unwrap(),
+            )?;
+        }
+        ("root", Some(sub_matches)) => {
+            // Print the root branch of the current chain.
+            let chain_name = sub_matches.value_of("chain_name");
+            let branch_name = sub_matches.value_of("branch");
+            git_chain.print_root(chain_name, branch_name)?;
+        }
+        ("parent", Some(sub_matches)) => {
+            // Print the parent branch of the current branch.
+            let chain_name = sub_matches.value_of("chain_name");
+            let branch_name = sub_matches.value_of("branch");
+            git_chain.print_parent(chain_name, branch_name)?;
+        }
+        ("changes", Some(sub_matches)) => {
+            // Warn about changes of the current chain outside of its scope.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            git_chain.changes(&chain_name)?;
+        }
+        ("squash-branch", Some(sub_matches)) => {
+            // Squash the commits of the current branch into one, and restack its dependent branches.
+            let message = sub_matches.value_of("message");
+            let signoff = sub_matches.is_present("signoff");
+            git_chain.squash_branch(sub_matches.value_of("chain_name"), message, signoff)?;
+        }
+        ("disable", Some(sub_matches)) => {
+            // Stop every command but enable and disable in this repository.
+            let reason: Vec<&str> = sub_matches.values_of("reason").unwrap().collect();
+            git_chain.disable(&reason.join(" "))?;
+        }
+        ("enable", Some(_sub_matches)) => {
+            git_chain.enable()?;
+        }
+        ("fetch", Some(sub_matches)) => {
+            // Fetch the upstream branches of the root branch and the branches of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            git_chain.fetch(&chain_name)?;
+        }
+        ("verify", Some(sub_matches)) => {
+            // Check the invariants of the branches of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+
+            if Chain::chain_exists(git_chain, &chain_name)? {
+                git_chain.verify(&chain_name, &VerifyOptions::default())?;
+            } else {
+                eprintln!("Unable to verify chain.");
+                eprintln!("Chain does not exist: {}", chain_name.bold());
+                exit(1);
+            }
+        }
+        ("stats", Some(sub_matches)) => {
+            // Display the metrics of the current chain, or of all chains.
+            let chain_names = if sub_matches.is_present("all") {
+                Chain::get_all_chains(git_chain)?
+                    .into_iter()
+                    .map(|chain| chain.name)
+                    .collect()
+            } else {
+                vec![git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?]
+            };
+            let json = sub_matches.value_of("format") == Some("json");
+            git_chain.display_stats(&chain_names, json)?;
+        }
+        ("history", Some(sub_matches)) => {
+            // Display the commands that modified branches or chains, newest first.
+            let limit = match sub_matches.value_of("limit") {
+                Some(limit) => match limit.parse::<usize>() {
+                    Ok(limit) => Some(limit),
+                    Err(_) => {
+                        eprintln!("Invalid number of entries: {}", limit.bold());
+                        exit(1);
+                    }
+                },
+                None => None,
+            };
+            git_chain.display_history(limit)?;
+        }
+        ("undo", Some(_sub_matches)) => {
+            // Restore the branches moved by the last command that moved branches.
+            git_chain.undo()?;
+        }
+        ("size-guard", Some(sub_matches)) => {
+            // Check the size of the branches of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            let limits = git_chain.get_size_limits(
+                sub_matches.value_of("max_commits"),
+                sub_matches.value_of("max_changed_lines"),
+            )?;
+            git_chain.size_guard(&chain_name, &limits, sub_matches.is_present("strict"))?;
+        }
+        ("test", Some(sub_matches)) => {
+            // Run a command on each branch of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            let command: Vec<String> = sub_matches
+                .values_of("command")
+                .unwrap()
+                .map(|arg| arg.to_string())
+                .collect();
+            let options = test_options(sub_matches)?;
+            git_chain.test(&chain_name, &command, &options)?;
+        }
+        ("lint", Some(sub_matches)) => {
+            // Lint the commit messages of the branches of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+
+            let pattern = match sub_matches.value_of("pattern") {
+                Some(pattern) => Some(pattern.to_string()),
+                None => git_chain.get_git_config_setting("chain.lintPattern")?,
+            };
+            let ticket_pattern = match sub_matches.value_of("ticket_pattern") {
+                Some(ticket_pattern) => Some(ticket_pattern.to_string()),
+                None => git_chain.get_git_config_setting("chain.lintTicketPattern")?,
+            };
+            let max_subject_length = match sub_matches.value_of("max_subject_length") {
+                Some(max_subject_length) => Some(max_subject_length.to_string()),
+                None => git_chain.get_git_config_setting("chain.lintMaxSubjectLength")?,
+            };
+
+            let compile = |pattern: Option<String>| -> Result<Option<Regex>, Error> {
+                match pattern {
+                    Some(pattern) => match Regex::new(&pattern) {
+                        Ok(regex) => Ok(Some(regex)),
+                        Err(_) => Err(Error::from_str(&format!(
+                            "Invalid lint pattern: {}",
+                            pattern
+                        ))),
+                    },
+                    None => Ok(None),
+                }
+            };
+
+            let max_subject_length = match max_subject_length {
+                Some(max_subject_length) => match max_subject_length.parse::<usize>() {
+                    Ok(max_subject_length) => max_subject_length,
+                    Err(_) => {
+                        return Err(Error::from_str(&format!(
+                            "Invalid max subject length: {}",
+                            max_subject_length
+                        )));
+                    }
+                },
+                None => lint::DEFAULT_MAX_SUBJECT_LENGTH,
+            };
+
+            let rules = LintRules {
+                pattern: compile(pattern)?,
+                conventional: sub_matches.is_present("conventional")
+                    || match git_chain.get_git_config_setting("chain.lintConventional")? {
+                        Some(value) => Config::parse_bool(&value)?,
+                        None => false,
+                    },
+                max_subject_length,
+                ticket_pattern: compile(ticket_pattern)?,
+            };
+
+            git_chain.lint(&chain_name, &rules, sub_matches.is_present("fix"))?;
+        }
+        ("rename", Some(sub_matches)) => {
+            // Rename current chain.
+
+            let new_chain_name = sub_matches.value_of("chain_name").unwrap().to_string();
+            validate_chain_name(&new_chain_name);
+
+            let branch_name = git_chain.get_current_branch_name()?;
+
+            let branch = match Branch::get_branch_with_chain(git_chain, &branch_name)? {
+                BranchSearchResult::NotPartOfAnyChain(_) => {
+                    git_chain.display_branch_not_part_of_chain_error(&branch_name);
+                    exit(1);
+                }
+                BranchSearchResult::Branch(branch) => branch,
+            };
+
+            if Chain::chain_exists(git_chain, &new_chain_name)? {
+                eprintln!(
+                    "Unable to rename chain {} to {}",
+                    branch.chain_name.bold(),
+                    new_chain_name.bold()
+                );
+                eprintln!("Chain already exists: {}", branch.chain_name.bold());
+                exit(1);
+            }
+
+            if Chain::chain_exists(git_chain, &branch.chain_name)? {
+                let chain = Chain::get_chain(git_chain, &branch.chain_name)?;
+                let old_chain_name = chain.name.clone();
+                chain.rename(git_chain, &new_chain_name)?;
+                println!(
+                    "Renamed chain from {} to {}",
+                    old_chain_name.bold(),
+                    new_chain_name.bold()
+                );
+            } else {
+                eprintln!("Unable to rename chain.");
+                eprintln!("Chain does not exist: {}", new_chain_name.bold());
+                exit(1);
+            }
+        }
+        ("rename-prefix", Some(sub_matches)) => {
+            // Rename the branches of the current chain.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            let from = sub_matches.value_of("from").unwrap();
+            let to = sub_matches.value_of("to").unwrap();
+
+            let renaming = if sub_matches.is_present("regex") {
+                match Regex::new(from) {
+                    Ok(regex) => BranchRenaming::Regex(regex, to.to_string()),
+                    Err(e) => {
+                        eprintln!("🛑 Invalid regex {}: {}", from.bold(), e);
+                        exit(1);
+                    }
+                }
+            } else if sub_matches.is_present("suffix") {
+                // e.g. *-wip, as in a glob
+                let from = from.strip_prefix('*').unwrap_or(from);
+                let to = to.strip_prefix('*').unwrap_or(to);
+                BranchRenaming::Suffix(from.to_string(), to.to_string())
+            } else {
+                // e.g. user/foo/*, as in a glob
+                let from = from.strip_suffix('*').unwrap_or(from);
+                let to = to.strip_suffix('*').unwrap_or(to);
+                BranchRenaming::Prefix(from.to_string(), to.to_string())
+            };
+
+            git_chain.rename_prefix(
+                &chain_name,
+                &renaming,
+                sub_matches.is_present("remote"),
+                sub_matches.is_present("dry_run"),
+            )?;
+        }
+        ("copy", Some(sub_matches)) => {
+            // Copy a chain and its branches into a new chain.
+
+            let chain_name = sub_matches.value_of("chain_name").unwrap().to_string();
+            let new_chain_name = sub_matches.value_of("new_chain_name").unwrap().to_string();
+            validate_chain_name(&new_chain_name);
+            let branch_template = sub_matches.value_of("template").unwrap_or("{branch}-v2");
+
+            if !branch_template.contains("{branch}") {
+                eprintln!(
+                    "Branch name template must contain {}: {}",
+                    "{branch}".bold(),
+                    branch_template.bold()
+                );
+                exit(1);
+            }
+
+            if !Chain::chain_exists(git_chain, &chain_name)? {
+                eprintln!("Unable to copy chain.");
+                eprintln!("Chain does not exist: {}", chain_name.bold());
+                exit(1);
+            }
+
+            if Chain::chain_exists(git_chain, &new_chain_name)? {
+                eprintln!(
+                    "Unable to copy chain {} to {}",
+                    chain_name.bold(),
+                    new_chain_name.bold()
+                );
+                eprintln!("Chain already exists: {}", new_chain_name.bold());
+                exit(1);
+            }
+
+            let chain = Chain::get_chain(git_chain, &chain_name)?;
+            chain.copy(git_chain, &new_chain_name, branch_template)?;
+
+            println!(
+                "🔗 Succesfully copied chain {} to {}",
+                chain_name.bold(),
+                new_chain_name.bold()
+            );
+            println!();
+
+            let new_chain = Chain::get_chain(git_chain, &new_chain_name)?;
+            let current_branch = git_chain
+                .get_current_branch_name_if_attached()?
+                .unwrap_or_default();
+            new_chain.display_list(git_chain, &current_branch)?;
+        }
+        ("setup", Some(sub_matches)) => {
+            // Set up a chain.
+
+            let chain_name = sub_matches.value_of("chain_name").unwrap().to_string();
+            let root_branch = sub_matches.value_of("root_branch").unwrap().to_string();
+
+            let branches: Vec<String> = sub_matches
+                .values_of("branch")
+                .unwrap()
+                .map(|x| x.to_string())
+                .collect();
+            let scope = parse_scope_option(sub_matches.value_of("scope"));
+            let extend = sub_matches.is_present("extend");
+
+            validate_chain_name(&chain_name);
+            for branch_name in &branches {
+                validate_branch_name(branch_name);
+            }
+
+            // ensure root branch exists
+            let root_branch = match git_chain.resolve_root_branch(&root_branch)? {
+                Some(root_branch) => root_branch,
+                None => {
+                    eprintln!("Root branch does not exist: {}", root_branch.bold());
+                    exit(1);
+                }
+            };
+
+            // With --extend, the branches already in the chain are left as they are.
+            let existing_chain = if extend && Chain::chain_exists(git_chain, &chain_name)? {
+                let chain = Chain::get_chain(git_chain, &chain_name)?;
+                if chain.root_branch != root_branch {
+                    eprintln!("🛑 Unable to extend chain: {}", chain_name.bold());
+                    eprintln!(
+                        "Its root branch is {}, not {}.",
+                        chain.root_branch.bold(),
+                        root_branch.bold()
+                    );
+                    exit(1);
+                }
+                Some(chain)
+            } else {
+                None
+            };
+            let is_member = |branch_name: &str| {
+                existing_chain.as_ref().is_some_and(|chain| {
+                    chain
+                        .branches
+                        .iter()
+                        .any(|branch| branch.branch_name == branch_name)
+                })
+            };
+
+            let mut visited_branches = HashSet::new();
+
+            for branch_name in &branches {
+                if branch_name == &root_branch {
+                    eprintln!(
+                        "Branch being added to the chain cannot be the root branch: {}",
+                        branch_name.bold()
+                    );
+                    exit(1);
+                }
+
+                if !git_chain.git_local_branch_exists(branch_name)?
+                    && !git_chain.create_local_branch_from_remote(branch_name)?
+                {
+                    eprintln!("Branch does not exist: {}", branch_name.bold());
+                    exit(1);
+                }
+
+                let results = Branch::get_branch_with_chain(git_chain, branch_name)?;
+
+                match results {
+                    BranchSearchResult::Branch(_) if is_member(branch_name) => {}
+                    BranchSearchResult::Branch(branch) => {
+                        eprintln!("❌ Unable to initialize branch to a chain.");
+                        eprintln!();
+                        eprintln!("Branch already part of a chain: {}", branch_name.bold());
+                        eprintln!("It is part of the chain: {}", branch.chain_name.bold());
+                        eprintln!("With root branch: {}", branch.root_branch.bold());
+                        exit(1);
+                    }
+                    BranchSearchResult::NotPartOfAnyChain(_) => {}
+                }
+
+                if visited_branches.contains(branch_name) {
+                    eprintln!(
+                        "Branch defined on the chain at least twice: {}",
+                        branch_name.bold()
+                    );
+                    eprintln!("Branches should be unique when setting up a new chain.");
+                    exit(1);
+                }
+                visited_branches.insert(branch_name);
+            }
+
+            if let Some(existing_chain) = &existing_chain {
+                // The branches already in the chain are not reordered.
+                let positions: Vec<usize> = branches
+                    .iter()
+                    .filter_map(|branch_name| {
+                        existing_chain
+                            .branches
+                            .iter()
+                            .position(|branch| &branch.branch_name == branch_name)
+                    })
+                    .collect();
+                if positions.windows(2).any(|pair| pair[0] > pair[1]) {
+                    eprintln!("🛑 Unable to extend chain: {}", chain_name.bold());
+                    eprintln!("Its branches are listed in a different order than in the chain.");
+                    eprintln!(
+                        "To change the order of the branches of the chain, run: {} reorder",
+                        git_chain.executable_name
+                    );
+                    exit(1);
+                }
+            }
+
+            // Each new branch is added after the branch listed before it, or before the first
+            // branch of the chain that is listed after it.
+            let mut previous_branch: Option<Branch> = None;
+            for (index, branch_name) in branches.iter().enumerate() {
+                if is_member(branch_name) {
+                    println!("Unchanged branch: {}", branch_name.bold());
+                    previous_branch = Branch::from_configs(git_chain, branch_name)?;
+                    continue;
+                }
+
+                let next_member = branches[index..]
+                    .iter()
+                    .find(|branch_name| is_member(branch_name));
+                let sort_option = match (previous_branch.take(), next_member) {
+                    (Some(previous_branch), _) => SortBranch::After(previous_branch),
+                    (None, Some(next_member)) => {
+                        match Branch::from_configs(git_chain, next_member)? {
+                            Some(next_member) => SortBranch::Before(next_member),
+                            None => SortBranch::Last,
+                        }
+                    }
+                    (None, None) => SortBranch::Last,
+                };
+                Branch::setup_branch(
+                    git_chain,
+                    &chain_name,
+                    &root_branch,
+                    branch_name,
+                    &sort_option,
+                )?;
+                if existing_chain.is_some() {
+                    println!("Added branch: {}", branch_name.bold());
+                }
+                previous_branch = Branch::from_configs(git_chain, branch_name)?;
+            }
+
+            let chain = Chain::get_chain(git_chain, &chain_name)?;
+            if let Some(scope) = &scope {
+                chain.set_scope(git_chain, Some(scope))?;
+            }
+
+            if existing_chain.is_some() {
+                println!();
+                println!("🔗 Succesfully extended chain: {}", chain_name.bold());
+            } else {
+                println!("🔗 Succesfully set up chain: {}", chain_name.bold());
+            }
+            println!();
+
+            let current_branch = git_chain
+                .get_current_branch_name_if_attached()?
+                .unwrap_or_default();
+            chain.display_list(git_chain, &current_branch)?;
+        }
+        ("new-stack", Some(sub_matches)) => {
+            // Create the branches and the chain of a stack from a template.
+            let name = sub_matches.value_of("name").unwrap();
+            let template = sub_matches.value_of("template").unwrap_or("default");
+            let root = sub_matches.value_of("root");
+            git_chain.new_stack(name, template, root)?;
+        }
+        ("migrate-root", Some(sub_matches)) => {
+            // Update the root branch of chains after the default branch of a remote was renamed.
+            let remote_name = sub_matches.value_of("remote").unwrap_or("origin");
+            let from = sub_matches.value_of("from");
+            let dry_run = sub_matches.is_present("dry_run");
+            git_chain.migrate_root(remote_name, from, dry_run)?;
+        }
+        ("publish", Some(sub_matches)) => {
+            // Push the layout of the chain to the remote as refs/chains/<chain_name>.
+            let chain_name = git_chain.resolve_chain_name(sub_matches.value_of("chain_name"))?;
+            let remote_name = sub_matches.value_of("remote").unwrap_or("origin");
+            git_chain.publish(&chain_name, remote_name)?;
+        }
+        ("import", Some(sub_matches)) => {
+            // Set up a chain from a stack of pull requests.
+            let remote_name = sub_matches.value_of("remote").unwrap_or("origin");
+            git_chain.import_from_prs(
+                sub_matches.value_of("pull_request"),
+                sub_matches.value_of("chain_name"),
+                remote_name,
+            )?;
+        }
+        ("subscribe", Some(sub_matches)) => {
+            // Set up a chain published to the remote.
+            let chain_name = sub_matches.value_of("chain_name").unwrap();
+            let remote_name = sub_matches.value_of("remote").unwrap_or("origin");
+            git_chain.subscribe(chain_name, remote_name)?;
+        }
+        ("first", Some(sub_matches)) => {
+            // Switch to the first branch of the chain.
+            let chain = git_chain.navigation_chain(sub_matches.value_of("chain_name"))?;
+            let branch_name = git_chain.get_current_branch_name()?;
+            let first_branch = chain.branches.first().unwrap();
+
+            if branch_name == first_branch.branch_name {
+                println!(
+                    "Already on the first branch of the chain {}",
+                    chain.name.bold()
+                );
+                return Ok(());
+            }
+
+            git_chain.checkout_branch(&first_branch.branch_name)?;
+
+            println!("Switched to branch: {}", first_branch.branch_name.bold());
+        }
+        ("last", Some(sub_matches)) => {
+            // Switch to the last branch of the chain.
+            let chain = git_chain.navigation_chain(sub_matches.value_of("chain_name"))?;
+            let branch_name = git_chain.get_current_branch_name()?;
+            let last_branch = chain.branches.last().unwrap();
+
+            if branch_name == last_branch.branch_name {
+                println!(
+                    "Already on the last branch of the chain {}",
+                    chain.name.bold()
+                );
+                return Ok(());
+            }
+
+            git_chain.checkout_branch(&last_branch.branch_name)?;
+
+            println!("Switched to branch: {}", last_branch.branch_name.bold());
+        }
+        ("next", Some(sub_matches)) => {
+            // Switch to the next branch of the chain.
+            let chain = git_chain.navigation_chain(sub_matches.value_of("chain_name"))?;
+            let index_of_branch = git_chain.index_of_current_branch(&chain)?;
+
+            let index_of_next_branch = index_of_branch + 1;
+
+            if index_of_next_branch == chain.branches.len() {
+                eprintln!("There is no next branch of the chain.");
+                exit(1);
+            }
+
+            let next_branch = &chain.branches[index_of_next_branch];
+
+            git_chain.checkout_branch(&next_branch.branch_name)?;
+
+            println!("Switched to branch: {}", next_branch.branch_name.bold());
+        }
+        ("prev", Some(sub_matches)) => {
+            // Switch to the previous branch of the chain.
+            let chain = git_chain.navigation_chain(sub_matches.value_of("chain_name"))?;
+            let index_of_branch = git_chain.index_of_current_branch(&chain)?;
+
+            if index_of_branch == 0 {
+                eprintln!("There is no previous branch of the chain.");
+                exit(1);
+            }
+
+            let index_of_prev_branch = index_of_branch - 1;
+            let prev_branch = &chain.branches[index_of_prev_branch];
+
+            git_chain.checkout_branch(&prev_branch.branch_name)?;
+
+            println!("Switched to branch: {}", prev_branch.branch_name.bold());
+        }
+        ("status", Some(sub_matches)) => {
+            // Display the chain of the current branch.
+            let short = sub_matches.is_present("short");
+            let markdown = sub_matches.value_of("format") == Some("markdown");
+            let check_conflicts = sub_matches.is_present("check_conflicts");
+            git_chain.run_status(
+                sub_matches.value_of("chain_name"),
+                short,
+                markdown,
+                check_conflicts,
+            )?;
+        }
+        _ => {
+            git_chain.run_status(None, false, false, false)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_arg_matches<'a, I, T>(arguments: I) -> ArgMatches<'a>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let init_subcommand = SubCommand::with_name("init")
+        .about("Initialize the current branch to a chain.")
+        .arg(
+            Arg::with_name("before")
+                .short("b")
+                .long("before")
+                .value_name("branch_name")
+                .help("Sort current branch before another branch.")
+                .conflicts_with("after")
+                .conflicts_with("first")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("after")
+                .short("a")
+                .long("after")
+                .value_name("branch_name")
+                .help("Sort current branch after another branch.")
+                .conflicts_with("before")
+                .conflicts_with("first")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("first")
+                .short("f")
+                .long("first")
+                .help("Sort current branch as the first branch of the chain.")
+                .conflicts_with("before")
+                .conflicts_with("after")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("scope")
+                .long("scope")
+                .value_name("directory")
+                .help("Directory of the repository that the branches of the chain are expected to change.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("all_descendants")
+                .long("all-descendants")
+                .help("Also add the local branches stacked on top of the current branch, in order.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("chain_name")
+                .help("The name of the chain.")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("root_branch")
+                .help("The root branch which the chain of branches will merge into. Defaults to the default branch of the repository (origin/HEAD or init.defaultBranch).")
+                .required(false)
+                .index(2),
+        );
+
+    let remove_subcommand = SubCommand::with_name("remove")
+        .about("Remove current branch from its chain.")
+        .arg(
+            Arg::with_name("chain_name")
+                .short("c")
+                .long("chain")
+                .value_name("chain_name")
+                .help("Delete chain by removing all of its branches.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .help("List the branches and the metadata that deleting the chain would remove, without deleting it.")
+                .requires("chain_name")
+                .takes_value(false),
+        );
+
+    let move_subcommand = SubCommand::with_name("move")
+        .about("Move current branch or chain.")
+        .arg(
+            Arg::with_name("before")
+                .short("b")
+                .long("before")
+                .value_name("branch_name")
+                .help("Sort current branch before another branch.")
+                .conflicts_with("after")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("after")
+                .short("a")
+                .long("after")
+                .value_name("branch_name")
+                .help("Sort current branch after another branch.")
+                .conflicts_with("before")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("root")
+                .short("r")
+                .long("root")
+                .value_name("root_branch")
+                .help("Set root branch of current branch and the chain it is a part of.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("chain_name")
+                .short("c")
+                .long("chain")
+                .value_name("chain_name")
+                .help("Move current branch to another chain.")
+                .conflicts_with("root")
+                .takes_value(true),
+        );
+
+    let reorder_subcommand = SubCommand::with_name("reorder")
+        .about("Reorder the branches of the current chain.")
+        .arg(
+            Arg::with_name("topological")
+                .long("topological")
+                .help("Sort each branch after the branches of the chain it is based on, e.g. after branches were created out of order.")
+                .required(true)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("chain_name")
+                .short("c")
+                .long("chain")
+                .value_name("chain_name")
+                .help("Reorder this chain instead of the chain of the current branch.")
+                .takes_value(true),
+        );
+
+    let doctor_subcommand = SubCommand::with_name("doctor")
+        .about("Check the metadata of chains, and repair it: keys set several times, missing or invalid sort keys, and missing or mismatched root branches.")
+        .arg(
+            Arg::with_name("renumber")
+                .long("renumber")
+                .help("Also replace the sort keys of the branches of every chain with evenly spread keys, keeping their order. Done automatically when a branch is inserted and the keys have no room left.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("chain_name")
+                .short("c")
+                .long("chain")
+                .value_name("chain_name")
+                .help("Only repair this chain.")
+                .takes_value(true),
+        );
+
+    let adopt_subcommand = SubCommand::with_name("adopt")
+        .about("Add an existing branch to the current chain, at the position given by its merge-bases with the branches of the chain.")
+        .arg(
+            Arg::with_name("branch")
+                .help("The branch to add to the chain.")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("chain_name")
+                .short("c")
+                .long("chain")
+                .value_name("chain_name")
+                .help("Add the branch to this chain instead of the chain of the current branch.")
+                .takes_value(true),
+        );
+
+    let chain_arg = Arg::with_name("chain_name")
+        .long("chain")
+        .value_name("chain_name")
+        .help("Act on this chain instead of the chain of the current branch. Defaults to GIT_CHAIN_CHAIN.")
+        .takes_value(true);
+
+    let signoff_arg = Arg::with_name("signoff")
+        .long("signoff")
+        .value_name("signoff")
+        .help("Add a Signed-off-by trailer to the commits that are created or rewritten. Defaults to GIT_CHAIN_SIGNOFF or chain.signoff.")
+        .takes_value(false);
+
+    let stay_arg = Arg::with_name("stay")
+        .long("stay")
+        .value_name("stay")
+        .help("End on the last branch that was checked out, instead of switching back to the original branch, including when stopped by an error.")
+        .takes_value(false);
+
+    let allow_unrelated_arg = Arg::with_name("allow_unrelated")
+        .long("allow-unrelated")
+        .value_name("allow_unrelated")
+        .help("Leave the branches that share no history with their parent branch as they are, instead of refusing to go ahead.")
+        .takes_value(false);
+
+    let verify_arg = Arg::with_name("verify")
+        .long("verify")
+        .value_name("verify")
+        .help("Afterwards, run git chain verify on the branches, and fail with how to restore the last backup if an invariant is broken. Defaults to GIT_CHAIN_VERIFY, then chain.verify.")
+        .takes_value(false);
+
+    let from_arg = Arg::with_name("from")
+        .long("from")
+        .value_name("branch_name")
+        .help("Start at this branch of the chain, instead of the first branch.")
+        .takes_value(true);
+
+    let to_arg = Arg::with_name("to")
+        .long("to")
+        .value_name("branch_name")
+        .help("Stop at this branch of the chain, instead of the last branch.")
+        .takes_value(true);
+
+    let rebase_subcommand = SubCommand::with_name("rebase")
+        .about("Rebase all branches for the current chain.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("timings")
+                .long("timings")
+                .value_name("timings")
+                .help("Print the time spent per branch and per phase at the end.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("step")
+                .short("s")
+                .long("step")
+                .value_name("step")
+                .help("Stop at the first rebase.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("ignore_root")
+                .short("i")
+                .long("ignore-root")
+                .value_name("ignore_root")
+                .help("Rebase each branch of the chain except for the first branch.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("committer_date_is_author_date")
+                .long("committer-date-is-author-date")
+                .value_name("committer_date_is_author_date")
+                .help("Passed to git rebase: use the author date of each commit as its committer date.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("reset_author_date")
+                .long("reset-author-date")
+                .value_name("reset_author_date")
+                .help("Passed to git rebase: use the current time as the author date of each rebased commit.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("interactive")
+                .long("interactive")
+                .value_name("interactive")
+                .help("Edit the todo list of git rebase --interactive for each branch of the chain.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("worktree")
+                .long("worktree")
+                .value_name("worktree")
+                .help("Rebase in a temporary worktree, leaving the working directory and the checked out branch alone. The branches are only updated if every rebase succeeds.")
+                .conflicts_with_all(&["step", "interactive", "keep_base"])
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("keep_base")
+                .long("keep-base")
+                .value_name("keep_base")
+                .help("Replay each branch onto its current merge-base with its parent branch, like git rebase --keep-base, e.g. to tidy up the branches with --interactive without pulling in new commits of the root branch. Branches stacked on the tip of their parent branch stay stacked on it.")
+                .takes_value(false),
+        )
+        .arg(allow_unrelated_arg.clone())
+        .arg(verify_arg.clone())
+        .arg(
+            Arg::with_name("update_root")
+                .long("update-root")
+                .value_name("update_root")
+                .help("Fast-forward the root branch to its upstream branch (as of the last fetch) before rebasing onto it.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("delete_empty")
+                .long("delete-empty")
+                .value_name("delete_empty")
+                .help("Also delete the branches that are removed from the chain because they became empty.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("reconcile")
+                .long("reconcile")
+                .value_name("mode")
+                .help("Before rebasing, include the commits of the upstream of each branch that are not in the branch (e.g. pushed by a reviewer): off (only warn about them), rebase (cherry-pick them onto the branch) or merge. Defaults to GIT_CHAIN_RECONCILE or chain.reconcile, or off.")
+                .possible_values(&["off", "rebase", "merge"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("strategy_option")
+                .short("X")
+                .long("strategy-option")
+                .value_name("option")
+                .help("Passed to git rebase for every branch (e.g. theirs), instead of the strategy option set for each branch with git chain strategy.")
+                .takes_value(true),
+        )
+        .arg(signoff_arg.clone())
+        .arg(stay_arg.clone())
+        .arg(from_arg.clone())
+        .arg(to_arg.clone());
+
+    let push_subcommand = SubCommand::with_name("push")
+        .about("Push all branches of the current chain to their upstreams.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("timings")
+                .long("timings")
+                .value_name("timings")
+                .help("Print the time spent per branch and per phase at the end.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .value_name("force")
+                .help("Push branches with --force-with-lease")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("no_force_push")
+                .long("no-force-push")
+                .help("Do not force push, even if GIT_CHAIN_FORCE_PUSH or chain.forcePush is set.")
+                .conflicts_with("force")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("only_changed")
+                .short("c")
+                .long("only-changed")
+                .value_name("only_changed")
+                .help("Skip branches that are already up-to-date with their upstreams.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("check_pr")
+                .long("check-pr")
+                .value_name("check_pr")
+                .help("Before force pushing, warn about pull requests that are approved or have merge conflicts (requires gh).")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .value_name("strict")
+                .help("Do not force push if --check-pr found any issues.")
+                .requires("check_pr")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("protect_reviewed")
+                .long("protect-reviewed")
+                .value_name("protect_reviewed")
+                .help("Do not force push branches with approved PRs (via gh), or whose upstream has commits that are not in the local branch (e.g. pushed by a reviewer).")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("create_pr")
+                .long("create-pr")
+                .value_name("create_pr")
+                .help("After pushing, create pull requests (via gh) for the branches of the chain that do not have one, each against its parent branch. Branches without an upstream are pushed first.")
+                .conflicts_with_all(&["from", "to"])
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("draft")
+                .long("draft")
+                .value_name("draft")
+                .help("Create draft pull requests.")
+                .requires("create_pr")
+                .takes_value(false),
+        )
+        .arg(from_arg)
+        .arg(to_arg);
+
+    let prune_subcommand = SubCommand::with_name("prune")
+        .about("Prune any branches of the current chain that are ancestors of the root branch, or whose upstream branch is gone.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("dry_run")
+                .short("d")
+                .long("dry-run")
+                .value_name("dry_run")
+                .help("Output branches that will be pruned.")
+                .takes_value(false),
+        );
+
+    let lint_subcommand = SubCommand::with_name("lint")
+        .about("Check the commit messages of each branch of the current chain.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("conventional")
+                .long("conventional")
+                .value_name("conventional")
+                .help("Require commit subjects to follow Conventional Commits.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("pattern")
+                .long("pattern")
+                .value_name("regex")
+                .help("Require commit subjects to match this regular expression.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max_subject_length")
+                .long("max-subject-length")
+                .value_name("length")
+                .help("Maximum length of commit subjects. Defaults to 72. Use 0 to disable.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ticket_pattern")
+                .long("ticket-pattern")
+                .value_name("regex")
+                .help("Require commit messages to contain a ticket ID matching this regular expression.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("fix")
+                .long("fix")
+                .value_name("fix")
+                .help("Repair the metadata of the chain: remove branches that no longer exist, and give the branches that are out of order a place in the chain. Commit messages are not reworded.")
+                .takes_value(false),
+        );
+
+    let size_guard_subcommand = SubCommand::with_name("size-guard")
+        .about("Display the size of each branch of the current chain, and warn about branches that are too large.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("max_commits")
+                .long("max-commits")
+                .value_name("commits")
+                .help("Maximum number of commits of a branch. Use 0 to disable.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max_changed_lines")
+                .long("max-changed-lines")
+                .value_name("lines")
+                .help("Maximum number of changed lines of a branch. Use 0 to disable.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .value_name("strict")
+                .help("Exit with an error if any branch is too large.")
+                .takes_value(false),
+        );
+
+    let test_subcommand = SubCommand::with_name("test")
+        .about("Run a command on each branch of the current chain, and report which branches fail.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("worktrees")
+                .short("w")
+                .long("worktrees")
+                .value_name("worktrees")
+                .help("Run the command in a temporary worktree per branch, instead of checking out each branch.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("jobs")
+                .short("j")
+                .long("jobs")
+                .value_name("jobs")
+                .help("Number of branches to test at the same time. Implies --worktrees.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("fail_fast")
+                .long("fail-fast")
+                .value_name("fail_fast")
+                .help("Stop at the first branch that fails.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("command")
+                .help("The command to run, after --. A single argument is run by the shell.")
+                .required(true)
+                .multiple(true)
+                .last(true),
+        );
+
+    let rename_subcommand = SubCommand::with_name("rename")
+        .about("Rename current chain.")
+        .arg(
+            Arg::with_name("chain_name")
+                .help("The new name of the chain.")
+                .required(true)
+                .index(1),
+        );
+
+    let rename_prefix_subcommand = SubCommand::with_name("rename-prefix")
+        .about("Rename the branches of the current chain by replacing the prefix of their names, e.g. user/foo/* to user/foo-v2/*.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("suffix")
+                .long("suffix")
+                .value_name("suffix")
+                .help("Replace the suffix of the names of the branches instead, e.g. *-wip to *-v2.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("regex")
+                .long("regex")
+                .value_name("regex")
+                .help("Substitute a regex in the names of the branches instead, where $1 or ${name} in the replacement are its capture groups.")
+                .conflicts_with("suffix")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("remote")
+                .long("remote")
+                .value_name("remote")
+                .help("Also rename the upstream branches of the same name: on GitHub via gh, which keeps their pull requests, or else by pushing the renamed branches and deleting the old ones.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .value_name("dry_run")
+                .help("Output the branches that would be renamed.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("from")
+                .help("The prefix to replace (e.g. user/foo/ or user/foo/*), the suffix with --suffix, or the regex with --regex.")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("to")
+                .help("What it is replaced with.")
+                .required(true)
+                .index(2),
+        );
+
+    let setup_subcommand = SubCommand::with_name("setup")
+        .about("Set up a chain.")
+        .arg(
+            Arg::with_name("extend")
+                .long("extend")
+                .value_name("extend")
+                .help("If the chain exists, add the branches that are not part of it yet, each after the branch listed before it, and leave its other branches as they are. Re-running setup with a longer list of branches extends the chain.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("scope")
+                .long("scope")
+                .value_name("directory")
+                .help("Directory of the repository that the branches of the chain are expected to change.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("chain_name")
+                .help("The new name of the chain.")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("root_branch")
+                .help("The root branch which the chain of branches will merge into.")
+                .required(true)
+                .index(2),
+        )
+        .arg(
+            Arg::with_name("branch")
+                .help("A branch to add to the chain")
+                .required(true)
+                .multiple(true)
+                .index(3),
+        );
+
+    let new_stack_subcommand = SubCommand::with_name("new-stack")
+        .about("Create the branches and the chain of a new stack from a template defined in git config.")
+        .arg(
+            Arg::with_name("template")
+                .short("t")
+                .long("template")
+                .value_name("template")
+                .help("The template to use, defined by chain.template.<template>.branches. Defaults to default.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("root")
+                .short("r")
+                .long("root")
+                .value_name("root_branch")
+                .help("The root branch of the chain. Defaults to chain.template.<template>.root, or the default branch of the repository.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("name")
+                .help("The name of the chain, substituted for {name} in the branch names of the template.")
+                .required(true)
+                .index(1),
+        );
+
+    let migrate_root_subcommand = SubCommand::with_name("migrate-root")
+        .about("Update the root branch of all chains after the default branch of the remote was renamed (e.g. master to main).")
+        .arg(
+            Arg::with_name("remote")
+                .long("remote")
+                .value_name("remote")
+                .help("The remote whose default branch is checked. Defaults to origin.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("from")
+                .long("from")
+                .value_name("branch")
+                .help("The previous default branch. Defaults to the branch that refs/remotes/<remote>/HEAD points to.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .short("d")
+                .long("dry-run")
+                .value_name("dry_run")
+                .help("Output the chains that would be updated.")
+                .takes_value(false),
+        );
+
+    let publish_subcommand = SubCommand::with_name("publish")
+        .about("Push the layout of the chain to the remote as refs/chains/<chain_name>, so that it can be set up elsewhere with subscribe.")
+        .arg(
+            Arg::with_name("remote")
+                .long("remote")
+                .value_name("remote")
+                .help("The remote to publish the chain to. Defaults to origin.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("chain_name")
+                .short("c")
+                .long("chain")
+                .value_name("chain_name")
+                .help("The chain to publish, instead of the chain of the current branch.")
+                .takes_value(true),
+        );
+
+    let import_subcommand = SubCommand::with_name("import")
+        .about("Set up a chain from the branches of an existing stack.")
+        .arg(
+            Arg::with_name("from_prs")
+                .long("from-prs")
+                .value_name("from_prs")
+                .help("Follow the base branches of a stack of GitHub pull requests (requires gh).")
+                .required(true)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("pull_request")
+                .help("A pull request of the stack (number, URL or branch). Defaults to the pull request of the current branch.")
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("chain_name")
+                .short("c")
+                .long("chain")
+                .value_name("chain_name")
+                .help("The name of the chain. Defaults to the branch of the first pull request.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("remote")
+                .long("remote")
+                .value_name("remote")
+                .help("The remote to fetch the branches from. Defaults to origin.")
+                .takes_value(true),
+        );
+
+    let subscribe_subcommand = SubCommand::with_name("subscribe")
+        .about("Fetch a chain published to the remote and set it up locally, creating its branches from their remote-tracking branches.")
+        .arg(
+            Arg::with_name("remote")
+                .long("remote")
+                .value_name("remote")
+                .help("The remote to fetch the chain from. Defaults to origin.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("chain_name")
+                .help("The name of the published chain.")
+                .required(true)
+                .index(1),
+        );
+
+    let note_branch_arg = Arg::with_name("branch")
+        .short("b")
+        .long("branch")
+        .value_name("branch")
+        .help("The branch of the note, instead of the current branch.")
+        .takes_value(true);
+
+    let note_subcommand = SubCommand::with_name("note")
+        .about("Display, set or clear the note of the current branch (e.g. blocked on API review), shown by status and list.")
+        .arg(note_branch_arg.clone())
+        .arg(chain_arg.clone())
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Display the note of the current branch.")
+                .arg(note_branch_arg.clone())
+                .arg(chain_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("set")
+                .about("Set the note of the current branch.")
+                .arg(note_branch_arg.clone())
+                .arg(chain_arg.clone())
+                .arg(
+                    Arg::with_name("note")
+                        .help("The note.")
+                        .required(true)
+                        .multiple(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("clear")
+                .about("Clear the note of the current branch.")
+                .arg(note_branch_arg)
+                .arg(chain_arg.clone()),
+        );
+
+    let stash_subcommand = SubCommand::with_name("stash")
+        .about("Stash changes per chain. Without a subcommand, list the stashes of the current chain.")
+        .arg(chain_arg.clone())
+        .subcommand(
+            SubCommand::with_name("push")
+                .about("Stash the changes of the current branch, recording its chain.")
+                .arg(chain_arg.clone())
+                .arg(
+                    Arg::with_name("message")
+                        .short("m")
+                        .long("message")
+                        .value_name("message")
+                        .help("The message of the stash.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("include_untracked")
+                        .short("u")
+                        .long("include-untracked")
+                        .help("Also stash untracked files.")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pop")
+                .about("Pop the most recent stash of the current chain, switching to the branch it was made on.")
+                .arg(chain_arg.clone())
+                .arg(
+                    Arg::with_name("here")
+                        .long("here")
+                        .help("Pop the stash onto the current branch, even if it was made on another branch.")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("stash")
+                        .help("The stash to pop (e.g. stash@{1}), instead of the most recent stash of the current chain.")
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("List the stashes of the current chain.")
+                .arg(chain_arg.clone()),
+        );
+
+    let strategy_branch_arg = Arg::with_name("branch")
+        .short("b")
+        .long("branch")
+        .value_name("branch")
+        .help("The branch of the strategy option, instead of the current branch.")
+        .takes_value(true);
+
+    let strategy_subcommand = SubCommand::with_name("strategy")
+        .about("Display, set or clear the strategy option (e.g. theirs for a branch of generated code) used when rebasing or cherry-picking onto the current branch.")
+        .arg(strategy_branch_arg.clone())
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Display the strategy option of the current branch.")
+                .arg(strategy_branch_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("set")
+                .about("Set the strategy option of the current branch, passed to git rebase and git cherry-pick as --strategy-option.")
+                .arg(strategy_branch_arg.clone())
+                .arg(
+                    Arg::with_name("option")
+                        .help("The strategy option, e.g. theirs, ours or ignore-all-space.")
+                        .required(true)
+                        .multiple(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("clear")
+                .about("Clear the strategy option of the current branch.")
+                .arg(strategy_branch_arg),
+        );
+
+    let push_ref_branch_arg = Arg::with_name("branch")
+        .short("b")
+        .long("branch")
+        .value_name("branch")
+        .help("The branch to push, instead of the current branch.")
+        .takes_value(true);
+
+    let push_ref_subcommand = SubCommand::with_name("push-ref")
+        .about("Display, set or clear the ref on the remote that the current branch is pushed to, and that its pull request is opened from.")
+        .arg(push_ref_branch_arg.clone())
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Display the remote and the ref that the current branch is pushed to.")
+                .arg(push_ref_branch_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("set")
+                .about("Push the current branch to this ref, instead of following push.default and the push refspecs of the remote.")
+                .arg(push_ref_branch_arg.clone())
+                .arg(
+                    Arg::with_name("ref")
+                        .help("The ref on the remote, e.g. users/me/feature or refs/heads/users/me/feature.")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("clear")
+                .about("Push the current branch as configured by push.default and the push refspecs of the remote again.")
+                .arg(push_ref_branch_arg),
+        );
+
+    let scope_subcommand = SubCommand::with_name("scope")
+        .about("Display or set the directory that the branches of the current chain are expected to change.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("clear")
+                .long("clear")
+                .value_name("clear")
+                .help("Remove the scope of the current chain.")
+                .conflicts_with("scope")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("scope")
+                .help("The directory, relative to the root of the repository.")
+                .index(1),
+        );
 
-fn parse_arg_matches<'a, I, T>(arguments: I) -> ArgMatches<'a>
-where
-    I: IntoIterator<Item = T>,
-    T: Into<OsString> + Clone,
-{
-    let init_subcommand = SubCommand::with_name("init")
-        .about("Initialize the current branch to a chain.")
+    let changes_subcommand = SubCommand::with_name("changes")
+        .about(
+            "Warn about files changed by branches of the current chain outside of the chain's scope.",
+        )
+        .arg(chain_arg.clone());
+
+    let pr_subcommand = SubCommand::with_name("pr")
+        .about("Create pull requests (via gh) for the branches of the current chain, from the first to the last branch.")
+        .arg(chain_arg.clone())
         .arg(
-            Arg::with_name("before")
-                .short("b")
-                .long("before")
-                .value_name("branch_name")
-                .help("Sort current branch before another branch.")
-                .conflicts_with("after")
-                .conflicts_with("first")
+            Arg::with_name("draft")
+                .short("d")
+                .long("draft")
+                .value_name("draft")
+                .help("Create draft pull requests.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("draft_until")
+                .long("draft-until")
+                .value_name("branch")
+                .help("Create ready for review pull requests for the branches up to this branch, and draft pull requests for the branches after it.")
+                .conflicts_with("draft")
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("after")
+            Arg::with_name("stack_order_labels")
+                .long("stack-order-labels")
+                .value_name("stack_order_labels")
+                .help("Label each pull request with its position in the chain (e.g. stack:2/4), replacing outdated position labels.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("update_base_on_reorder")
+                .long("update-base-on-reorder")
+                .value_name("update_base_on_reorder")
+                .help("Update the base branch of existing open pull requests to the parent branch of their branch, e.g. after move.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("open")
+                .long("open")
+                .value_name("open")
+                .help("Open each created or existing pull request in the browser.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .value_name("strict")
+                .help("Exit with an error, before pushing any branch, if a branch exceeds chain.maxCommits or chain.maxChangedLines.")
+                .takes_value(false),
+        );
+
+    let after_land_subcommand = SubCommand::with_name("after-land")
+        .about("Rebase the current chain and remove the branches that landed on the root branch.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("push")
+                .short("p")
+                .long("push")
+                .value_name("push")
+                .help("Force push (with --force-with-lease) the remaining branches that changed.")
+                .takes_value(false),
+        );
+
+    let diff_subcommand = SubCommand::with_name("diff")
+        .about("Display the changes of the current branch against its parent branch.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("all")
                 .short("a")
-                .long("after")
-                .value_name("branch_name")
-                .help("Sort current branch after another branch.")
-                .conflicts_with("before")
-                .conflicts_with("first")
-                .takes_value(true),
+                .long("all")
+                .value_name("all")
+                .help("Display the combined changes of the entire chain against the root branch.")
+                .takes_value(false),
         )
         .arg(
-            Arg::with_name("first")
-                .short("f")
-                .long("first")
-                .help("Sort current branch as the first branch of the chain.")
-                .conflicts_with("before")
-                .conflicts_with("after")
+            Arg::with_name("stat")
+                .long("stat")
+                .value_name("stat")
+                .help("Display a diffstat instead of the full diff.")
                 .takes_value(false),
+        );
+
+    let blame_subcommand = SubCommand::with_name("blame")
+        .about(
+            "Annotate each line of a file with the branch of the current chain that introduced it.",
         )
+        .arg(chain_arg.clone())
         .arg(
-            Arg::with_name("chain_name")
-                .help("The name of the chain.")
+            Arg::with_name("path")
+                .help("The file to annotate, as of the current branch.")
                 .required(true)
                 .index(1),
-        )
-        .arg(
-            Arg::with_name("root_branch")
-                .help("The root branch which the chain of branches will merge into.")
-                .required(false)
-                .index(2),
         );
 
-    let remove_subcommand = SubCommand::with_name("remove")
-        .about("Remove current branch from its chain.")
+    let root_subcommand = SubCommand::with_name("root")
+        .about("Print the root branch of the current chain.")
         .arg(
             Arg::with_name("chain_name")
                 .short("c")
                 .long("chain")
                 .value_name("chain_name")
-                .help("Delete chain by removing all of its branches.")
+                .help("Print the root branch of this chain instead.")
+                .conflicts_with("branch")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("branch")
+                .short("b")
+                .long("branch")
+                .value_name("branch")
+                .help("Print the root branch of the chain of this branch instead.")
                 .takes_value(true),
         );
 
-    let move_subcommand = SubCommand::with_name("move")
-        .about("Move current branch or chain.")
+    let parent_subcommand = SubCommand::with_name("parent")
+        .about("Print the parent branch of the current branch.")
         .arg(
-            Arg::with_name("before")
-                .short("b")
-                .long("before")
-                .value_name("branch_name")
-                .help("Sort current branch before another branch.")
-                .conflicts_with("after")
+            Arg::with_name("chain_name")
+                .short("c")
+                .long("chain")
+                .value_name("chain_name")
+                .help("Act on this chain instead: print the parent branch of the current branch if it is part of it, or else of its last branch. Defaults to GIT_CHAIN_CHAIN.")
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("after")
-                .short("a")
-                .long("after")
-                .value_name("branch_name")
-                .help("Sort current branch after another branch.")
-                .conflicts_with("before")
+            Arg::with_name("branch")
+                .short("b")
+                .long("branch")
+                .value_name("branch")
+                .help("Print the parent branch of this branch instead.")
                 .takes_value(true),
+        );
+
+    let browse_subcommand = SubCommand::with_name("browse")
+        .about("Open the pull request of the current branch in the browser, or its compare page if there is none.")
+        .arg(chain_arg.clone())
+        .arg(
+            Arg::with_name("all")
+                .short("a")
+                .long("all")
+                .value_name("all")
+                .help("Open the pull requests of all branches of the current chain.")
+                .takes_value(false),
         )
         .arg(
-            Arg::with_name("root")
-                .short("r")
-                .long("root")
-                .value_name("root_branch")
-                .help("Set root branch of current branch and the chain it is a part of.")
+            Arg::with_name("print")
+                .short("p")
+                .long("print")
+                .value_name("print")
+                .help("Print the URLs instead of opening them.")
+                .takes_value(false),
+        );
+
+    let copy_subcommand = SubCommand::with_name("copy")
+        .about("Copy a chain by creating new branches for each of its branches.")
+        .arg(
+            Arg::with_name("template")
+                .short("t")
+                .long("template")
+                .value_name("template")
+                .help("Name of the new branches, where {branch} is replaced by the original branch name. Defaults to {branch}-v2")
                 .takes_value(true),
         )
         .arg(
             Arg::with_name("chain_name")
-                .short("c")
-                .long("chain")
-                .value_name("chain_name")
-                .help("Move current branch to another chain.")
-                .conflicts_with("root")
-                .takes_value(true),
+                .help("The name of the chain to copy.")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("new_chain_name")
+                .help("The name of the new chain.")
+                .required(true)
+                .index(2),
         );
 
-    let rebase_subcommand = SubCommand::with_name("rebase")
-        .about("Rebase all branches for the current chain.")
+    let stats_subcommand = SubCommand::with_name("stats")
+        .about("Display metrics of the current chain, or of all chains, e.g. for dashboards: ahead/behind counts, ages of the branches, pull requests and the last rebase.")
+        .arg(chain_arg.clone())
         .arg(
-            Arg::with_name("step")
-                .short("s")
-                .long("step")
-                .value_name("step")
-                .help("Stop at the first rebase.")
+            Arg::with_name("all")
+                .short("a")
+                .long("all")
+                .value_name("all")
+                .help("Display the metrics of all chains.")
+                .conflicts_with("chain_name")
                 .takes_value(false),
         )
         .arg(
-            Arg::with_name("ignore_root")
-                .short("i")
-                .long("ignore-root")
-                .value_name("ignore_root")
-                .help("Rebase each branch of the chain except for the first branch.")
-                .takes_value(false),
+            Arg::with_name("format")
+                .long("format")
+                .value_name("format")
+                .help("Display the metrics as text, or as JSON.")
+                .possible_values(&["text", "json"])
+                .takes_value(true),
         );
 
-    let push_subcommand = SubCommand::with_name("push")
-        .about("Push all branches of the current chain to their upstreams.")
+    let verify_subcommand = SubCommand::with_name("verify")
+        .about("Check that each branch of the current chain is based on its parent branch, that no branch lost commits compared to the last backup of the chain, and that the working directory is clean. Prints how to restore the backup otherwise.")
+        .arg(chain_arg.clone());
+
+    let backup_subcommand = SubCommand::with_name("backup")
+        .about("Back up all branches of the current chain.")
+        .arg(chain_arg.clone())
         .arg(
-            Arg::with_name("force")
-                .short("f")
-                .long("force")
-                .value_name("force")
-                .help("Push branches with --force-with-lease")
+            Arg::with_name("list")
+                .short("l")
+                .long("list")
+                .value_name("list")
+                .help("List the backups of the current chain.")
+                .conflicts_with("prune")
                 .takes_value(false),
-        );
-
-    let prune_subcommand = SubCommand::with_name("prune")
-        .about("Prune any branches of the current chain that are ancestors of the root branch.")
+        )
+        .arg(
+            Arg::with_name("prune")
+                .long("prune")
+                .value_name("prune")
+                .help("Remove old backups of the current chain. Keeps the last chain.backupRetention backups, unless --keep is given.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("keep")
+                .long("keep")
+                .value_name("N")
+                .help("Number of backups to keep with --prune.")
+                .requires("prune")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("dry_run")
-                .short("d")
                 .long("dry-run")
-                .value_name("dry_run")
-                .help("Output branches that will be pruned.")
+                .help("List the backups that --prune would remove, without removing them.")
+                .requires("prune")
                 .takes_value(false),
-        );
-
-    let rename_subcommand = SubCommand::with_name("rename")
-        .about("Rename current chain.")
+        )
         .arg(
-            Arg::with_name("chain_name")
-                .help("The new name of the chain.")
-                .required(true)
-                .index(1),
+            Arg::with_name("include_root")
+                .long("include-root")
+                .value_name("include_root")
+                .help("Also back up the root branch. With --restore, also restore the root branch from the backup.")
+                .conflicts_with("list")
+                .conflicts_with("prune")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("restore")
+                .long("restore")
+                .value_name("timestamp")
+                .help("Restore the branches of the chain, and the chain itself (its branches, their order and its root branch), from a backup.")
+                .conflicts_with("list")
+                .conflicts_with("prune")
+                .takes_value(true),
         );
 
-    let setup_subcommand = SubCommand::with_name("setup")
-        .about("Set up a chain.")
-        .arg(
-            Arg::with_name("chain_name")
-                .help("The new name of the chain.")
-                .required(true)
-                .index(1),
-        )
+    let format_arg = Arg::with_name("format")
+        .long("format")
+        .value_name("format")
+        .help("Display the chain as text, or as a markdown table of its branches with their pull requests (e.g. for a PR description).")
+        .possible_values(&["text", "markdown"])
+        .takes_value(true);
+
+    let status_subcommand = SubCommand::with_name("status")
+        .about("Display the chain of the current branch.")
+        .arg(chain_arg.clone())
         .arg(
-            Arg::with_name("root_branch")
-                .help("The root branch which the chain of branches will merge into.")
-                .required(true)
-                .index(2),
+            Arg::with_name("short")
+                .short("s")
+                .long("short")
+                .value_name("short")
+                .help("Display one line per branch.")
+                .takes_value(false),
         )
+        .arg(format_arg.clone().conflicts_with("short"))
         .arg(
-            Arg::with_name("branch")
-                .help("A branch to add to the chain")
-                .required(true)
-                .multiple(true)
-                .index(3),
+            Arg::with_name("check_conflicts")
+                .long("check-conflicts")
+                .value_name("check_conflicts")
+                .help("Mark the branches that would conflict when rebased onto their parent branch, predicted with in-memory merges.")
+                .conflicts_with("format")
+                .takes_value(false),
         );
 
+    let subcommands = vec![
+        init_subcommand,
+        remove_subcommand,
+        move_subcommand,
+        reorder_subcommand,
+        doctor_subcommand,
+        adopt_subcommand,
+        rebase_subcommand,
+        SubCommand::with_name("cherry")
+            .about("Cherry-pick the new commits of each parent branch onto the branches of the current chain, keeping their existing history.")
+            .arg(chain_arg.clone())
+            .arg(
+                Arg::with_name("backport")
+                    .long("backport")
+                    .value_name("branch_or_commit")
+                    .help("Instead, cherry-pick this commit, or the commits of this branch, onto every branch of the chain (e.g. a hotfix).")
+                    .takes_value(true),
+            )
+            .arg(signoff_arg.clone())
+            .arg(stay_arg)
+            .arg(allow_unrelated_arg)
+            .arg(verify_arg),
+        SubCommand::with_name("squash-branch")
+            .about("Squash the commits of the current branch into one commit, and rebase the branches that depend on it.")
+            .arg(chain_arg.clone())
+            .arg(
+                Arg::with_name("message")
+                    .short("m")
+                    .long("message")
+                    .value_name("message")
+                    .help("Use this commit message instead of editing the combined commit messages.")
+                    .takes_value(true),
+            )
+            .arg(signoff_arg),
+        push_subcommand,
+        SubCommand::with_name("fetch")
+            .about(
+                "Fetch the upstream branches of the root branch and the branches of the current chain.",
+            )
+            .arg(chain_arg.clone()),
+        prune_subcommand,
+        lint_subcommand,
+        size_guard_subcommand,
+        test_subcommand,
+        setup_subcommand,
+        new_stack_subcommand,
+        migrate_root_subcommand,
+        publish_subcommand,
+        subscribe_subcommand,
+        import_subcommand,
+        rename_subcommand,
+        rename_prefix_subcommand,
+        copy_subcommand,
+        scope_subcommand,
+        note_subcommand,
+        stash_subcommand,
+        strategy_subcommand,
+        push_ref_subcommand,
+        changes_subcommand,
+        browse_subcommand,
+        diff_subcommand,
+        blame_subcommand,
+        root_subcommand,
+        parent_subcommand,
+        pr_subcommand,
+        after_land_subcommand,
+        status_subcommand,
+        SubCommand::with_name("list")
+            .about("List all chains.")
+            .arg(format_arg)
+            .arg(
+                Arg::with_name("tree")
+                    .long("tree")
+                    .value_name("tree")
+                    .help("Display the chains as a tree, nesting chains rooted at a branch of another chain under that branch.")
+                    .conflicts_with("format")
+                    .takes_value(false),
+            )
+            .arg(
+                Arg::with_name("pr")
+                    .long("pr")
+                    .help("Display the URL of the pull request of each branch, from gh, or else rendered from chain.prUrlTemplate.")
+                    .conflicts_with_all(&["format", "tree"])
+                    .takes_value(false),
+            )
+            .arg(
+                Arg::with_name("verbose")
+                    .short("v")
+                    .long("verbose")
+                    .help("Display when the last commit of each branch was authored, and by whom, e.g. to spot stale branches and abandoned chains.")
+                    .conflicts_with_all(&["format", "tree"])
+                    .takes_value(false),
+            ),
+        SubCommand::with_name("history")
+            .about("Display the commands that modified branches or chains, newest first.")
+            .arg(
+                Arg::with_name("limit")
+                    .short("n")
+                    .long("limit")
+                    .value_name("limit")
+                    .help("Display at most this number of commands.")
+                    .takes_value(true),
+            ),
+        SubCommand::with_name("undo")
+            .about("Restore the branches moved by the last command that moved branches, as recorded by history."),
+        backup_subcommand,
+        verify_subcommand,
+        stats_subcommand,
+        SubCommand::with_name("first")
+            .about("Switch to the first branch of the chain.")
+            .arg(chain_arg.clone()),
+        SubCommand::with_name("last")
+            .about("Switch to the last branch of the chain.")
+            .arg(chain_arg.clone()),
+        SubCommand::with_name("next")
+            .about("Switch to the next branch of the chain.")
+            .arg(chain_arg.clone()),
+        SubCommand::with_name("prev")
+            .about("Switch to the previous branch of the chain.")
+            .arg(chain_arg.clone()),
+        SubCommand::with_name("disable")
+            .about("Disable git chain in this repository: every command but enable prints the reason and exits.")
+            .arg(
+                Arg::with_name("reason")
+                    .help("The reason, e.g. the chains are being migrated.")
+                    .required(true)
+                    .multiple(true)
+                    .index(1),
+            ),
+        SubCommand::with_name("enable").about("Enable git chain again in this repository."),
+        SubCommand::with_name("help")
+            .about("Display a guide (e.g. stacking, conflicts, pull-requests), or the help of a subcommand.")
+            .arg(
+                Arg::with_name("topic")
+                    .help("The guide, or the subcommand.")
+                    .index(1),
+            ),
+    ];
+
+    // The examples outlive the subcommands that display them.
+    let examples: Vec<Option<String>> = subcommands
+        .iter()
+        .map(|subcommand| guide::examples(subcommand.get_name()))
+        .collect();
+
+    let subcommands: Vec<App> = subcommands
+        .into_iter()
+        .zip(&examples)
+        .map(|(subcommand, examples)| {
+            let subcommand = match examples {
+                Some(examples) => subcommand.after_help(examples.as_str()),
+                None => subcommand,
+            };
+            match SUBCOMMAND_ALIASES
+                .iter()
+                .find(|(name, _alias)| *name == subcommand.get_name())
+            {
+                Some((_name, alias)) => subcommand.visible_alias(*alias),
+                None => subcommand,
+            }
+        })
+        .collect();
+
+    let mut subcommand_names: Vec<&str> = subcommands.iter().map(|s| s.get_name()).collect();
+    subcommand_names.extend(SUBCOMMAND_ALIASES.iter().map(|(_name, alias)| *alias));
+
+    let arguments = expand_alias(
+        arguments.into_iter().map(Into::into).collect(),
+        &subcommand_names,
+    );
+
+    let topics_help = guide::topics_help();
+
     let arg_matches = App::new("git-chain")
         .bin_name(executable_name())
         .version("0.0.9")
         .author("Alberto Leal <mailforalberto@gmail.com>")
         .about("Tool for rebasing a chain of local git branches.")
-        .subcommand(init_subcommand)
-        .subcommand(remove_subcommand)
-        .subcommand(move_subcommand)
-        .subcommand(rebase_subcommand)
-        .subcommand(push_subcommand)
-        .subcommand(prune_subcommand)
-        .subcommand(setup_subcommand)
-        .subcommand(rename_subcommand)
-        .subcommand(SubCommand::with_name("list").about("List all chains."))
-        .subcommand(
-            SubCommand::with_name("backup").about("Back up all branches of the current chain."),
+        .setting(AppSettings::DisableHelpSubcommand)
+        // Report arguments that are not valid UTF-8 (e.g. branch names), rather than panicking.
+        .global_setting(AppSettings::StrictUtf8)
+        .after_help(topics_help.as_str())
+        .arg(
+            Arg::with_name("version")
+                .short("V")
+                .long("version")
+                .help("Prints version information")
+                .takes_value(false),
         )
-        .subcommand(
-            SubCommand::with_name("first").about("Switch to the first branch of the chain."),
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("With --version, print build metadata as JSON.")
+                .requires("version")
+                .takes_value(false),
         )
-        .subcommand(SubCommand::with_name("last").about("Switch to the last branch of the chain."))
-        .subcommand(SubCommand::with_name("next").about("Switch to the next branch of the chain."))
-        .subcommand(
-            SubCommand::with_name("prev").about("Switch to the previous branch of the chain."),
+        .arg(
+            Arg::with_name("yes")
+                .short("y")
+                .long("yes")
+                .help("Answer yes to all confirmation prompts.")
+                .conflicts_with("no_input")
+                .global(true)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("no_input")
+                .long("no-input")
+                .help("Never prompt; fail if a confirmation is required.")
+                .conflicts_with("yes")
+                .global(true)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("hostname")
+                .long("hostname")
+                .value_name("hostname")
+                .help("GitHub host used for pull requests and URLs (e.g. a GitHub Enterprise instance).")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("debug")
+                .long("debug")
+                .help("Log the git commands that are run and the decisions that are made to stderr.")
+                .global(true)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("debug_file")
+                .long("debug-file")
+                .value_name("path")
+                .help("Like --debug, but append the debug output to this file.")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("offline")
+                .long("offline")
+                .help("Do not reach GitHub: display the pull requests cached by earlier runs, and fail commands that change pull requests.")
+                .global(true)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("no_pager")
+                .long("no-pager")
+                .help("Do not pipe long outputs (e.g. list, history, blame, diff) into the pager.")
+                .global(true)
+                .takes_value(false),
         )
+        .arg(
+            Arg::with_name("read_only")
+                .long("read-only")
+                .help("Fail instead of running commands that modify branches or chains.")
+                .global(true)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("force_unlock")
+                .long("force-unlock")
+                .help("Remove the lock held by another git chain command before running.")
+                .global(true)
+                .takes_value(false),
+        )
+        .subcommands(subcommands)
         .get_matches_from(arguments);
 
     arg_matches
 }
 
+fn display_help(topic: Option<&str>, no_pager: bool) {
+    let topic = match topic {
+        // clap prints the help, and exits.
+        None => {
+            parse_arg_matches(vec!["git-chain", "--help"]);
+            return;
+        }
+        Some(topic) => match guide::topic(topic) {
+            Some(topic) => topic,
+            None => {
+                parse_arg_matches(vec!["git-chain", topic, "--help"]);
+                return;
+            }
+        },
+    };
+
+    let config = match Repository::open_from_env() {
+        Ok(repo) => repo.config(),
+        Err(_) => Config::open_default(),
+    };
+    let paging_enabled = config
+        .and_then(|config| config.get_bool("pager.chain"))
+        .unwrap_or(true);
+    if pager::in_use() {
+        pager::exit_quietly_on_broken_pipe();
+    } else if !no_pager && paging_enabled {
+        pager::page(std::env::args_os().skip(1).collect());
+    }
+
+    println!("{}", topic.body);
+}
+
+fn get_user_alias(alias: &str) -> Option<String> {
+    // User-defined aliases are read from git config, e.g. chain.alias.rs = "rebase --step"
+    let config = match Repository::open_from_env() {
+        Ok(repo) => repo.config(),
+        Err(_) => Config::open_default(),
+    };
+    config
+        .ok()?
+        .get_string(&format!("chain.alias.{}", alias))
+        .ok()
+}
+
+fn expand_alias(arguments: Vec<OsString>, subcommand_names: &[&str]) -> Vec<OsString> {
+    // Find the subcommand: the first argument that is not an option, or the value of an option.
+    let mut index = 1;
+    while index < arguments.len() {
+        match arguments[index].to_str() {
+            Some("--hostname") | Some("--debug-file") => index += 2,
+            Some(argument) if argument.starts_with('-') => index += 1,
+            _ => break,
+        }
+    }
+
+    let alias = match arguments.get(index).and_then(|argument| argument.to_str()) {
+        Some(alias) => alias,
+        None => return arguments,
+    };
+
+    // Like git, aliases cannot override subcommands. Aliases are not expanded recursively.
+    if subcommand_names.contains(&alias) {
+        return arguments;
+    }
+
+    match get_user_alias(alias) {
+        Some(expansion) => {
+            let mut expanded = arguments[..index].to_vec();
+            expanded.extend(expansion.split_whitespace().map(OsString::from));
+            expanded.extend(arguments[index + 1..].iter().cloned());
+            expanded
+        }
+        None => arguments,
+    }
+}
+
 fn run_app<I, T>(arguments: I)
 where
     I: IntoIterator<Item = T>,
@@ -2317,7 +13555,7 @@ where
         Ok(()) => {}
         Err(err) => {
             eprintln!("{} {}", "error:".red().bold(), err);
-            process::exit(1);
+            exit(1);
         }
     }
 }
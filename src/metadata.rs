@@ -0,0 +1,125 @@
+// Validation of the chain metadata stored in the git config of the repository.
+//
+// The metadata is usually written by git chain, but it may also be edited by hand (e.g. with
+// git config --add), so each chain is checked whenever it is loaded. Problems are tolerated where
+// possible and reported along with the config key involved; git chain doctor repairs them.
+
+use std::sync::Mutex;
+
+use between::Between;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    // The branch has a chain name, but not the given key; it is left out of the chain when the
+    // sort key is missing.
+    MissingKey {
+        branch_name: String,
+        key: String,
+    },
+    // The key was set several times (e.g. with git config --add); the last value is used, as git does.
+    MultipleValues {
+        branch_name: String,
+        key: String,
+        values: Vec<String>,
+    },
+    InvalidChainOrder {
+        branch_name: String,
+        key: String,
+        chain_order: String,
+    },
+    // Branches sharing a sort key are ordered by name.
+    DuplicateChainOrder {
+        branch_names: Vec<String>,
+        chain_order: String,
+    },
+    // The root branch of the chain is taken from its first branch.
+    RootMismatch {
+        branch_name: String,
+        key: String,
+        root_branch: String,
+        expected: String,
+    },
+}
+
+impl Problem {
+    pub fn branch_names(&self) -> Vec<&str> {
+        match self {
+            Problem::MissingKey { branch_name, .. }
+            | Problem::MultipleValues { branch_name, .. }
+            | Problem::InvalidChainOrder { branch_name, .. }
+            | Problem::RootMismatch { branch_name, .. } => vec![branch_name],
+            Problem::DuplicateChainOrder { branch_names, .. } => {
+                branch_names.iter().map(String::as_str).collect()
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Problem::MissingKey { branch_name, key } if key.ends_with(".chain-order") => format!(
+                "{} is not set, so branch {} is left out of the chain.",
+                key, branch_name
+            ),
+            Problem::MissingKey { key, .. } => {
+                format!("{} is not set; the root branch of the chain is used.", key)
+            }
+            Problem::MultipleValues { key, values, .. } => format!(
+                "{} has {} values; the last one is used: {}",
+                key,
+                values.len(),
+                values.last().unwrap()
+            ),
+            Problem::InvalidChainOrder {
+                key, chain_order, ..
+            } => format!("{} is not a valid sort key: {:?}", key, chain_order),
+            Problem::DuplicateChainOrder {
+                branch_names,
+                chain_order,
+            } => format!(
+                "Branches {} have the same sort key {:?}; they are ordered by name.",
+                branch_names.join(", "),
+                chain_order
+            ),
+            Problem::RootMismatch {
+                key,
+                root_branch,
+                expected,
+                ..
+            } => format!(
+                "{} is {}, but the root branch of the chain is {}.",
+                key, root_branch, expected
+            ),
+        }
+    }
+}
+
+// Sort keys are generated from the characters of Between, and compared as strings. A key made of
+// the lowest character only leaves no room for a branch before it.
+pub fn is_valid_chain_order(chain_order: &str) -> bool {
+    let between = Between::init();
+    let chars = between.chars();
+    chain_order.chars().all(|c| chars.contains(&c)) && chain_order.chars().any(|c| c != chars[0])
+}
+
+// Chains whose problems were reported; a chain is loaded many times by some commands.
+static REPORTED_CHAINS: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+// Config keys that were ignored as they are not valid UTF-8, rendered with display_bytes.
+static REPORTED_KEYS: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+pub fn report_once(chain_name: &str) -> bool {
+    first_report(&REPORTED_CHAINS, chain_name)
+}
+
+pub fn report_key_once(key: &str) -> bool {
+    first_report(&REPORTED_KEYS, key)
+}
+
+fn first_report(reported: &Mutex<Vec<String>>, name: &str) -> bool {
+    let mut reported = reported.lock().unwrap();
+    if reported.iter().any(|reported| reported == name) {
+        return false;
+    }
+    reported.push(name.to_string());
+    true
+}
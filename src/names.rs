@@ -0,0 +1,83 @@
+// Validation of the names of chains and branches.
+//
+// Chain names end up in git config values, in refs/chains/<chain_name> when a chain is
+// published, and in the names of backup branches, so they are restricted to a small charset.
+
+pub const CHAIN_NAME_CHARSET: &str =
+    "Chain names may only contain ASCII letters, digits, '-', '_', '.' and '/'.";
+
+pub fn chain_name_error(chain_name: &str) -> Option<String> {
+    if chain_name.is_empty() {
+        return Some("Chain name is empty".to_string());
+    }
+
+    if let Some(c) = chain_name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/')))
+    {
+        return Some(format!("Chain name contains {}", describe_char(c)));
+    }
+
+    if chain_name.starts_with('-') {
+        return Some("Chain name cannot start with '-'".to_string());
+    }
+
+    if !git2::Reference::is_valid_name(&format!("refs/chains/{}", chain_name)) {
+        return Some("Chain name is not a valid git reference name".to_string());
+    }
+
+    None
+}
+
+pub fn branch_name_error(branch_name: &str) -> Option<String> {
+    if let Some(c) = branch_name
+        .chars()
+        .find(|c| c.is_control() || c.is_whitespace())
+    {
+        return Some(format!("Branch name contains {}", describe_char(c)));
+    }
+
+    if !git2::Branch::name_is_valid(branch_name).unwrap_or(false) {
+        return Some("Branch name is not a valid git branch name".to_string());
+    }
+
+    None
+}
+
+// Render a name so that control characters cannot mangle the output of the terminal.
+pub fn display_name(name: &str) -> String {
+    name.escape_debug().to_string()
+}
+
+// git allows any bytes in branch names, but git chain stores them in git config and passes them
+// to git and gh as arguments, so it only supports names that are valid UTF-8.
+pub const BRANCH_NAME_ENCODING: &str = "git chain only supports branch names that are valid UTF-8.";
+
+pub fn non_utf8_branch_error(branch_name: &[u8]) -> String {
+    format!(
+        "Branch name is not valid UTF-8: {}",
+        display_bytes(branch_name)
+    )
+}
+
+// Render a name that is not valid UTF-8, with its invalid bytes escaped, e.g. caf\xe9.
+pub fn display_bytes(name: &[u8]) -> String {
+    let mut displayed = String::new();
+    for chunk in name.utf8_chunks() {
+        displayed.push_str(&display_name(chunk.valid()));
+        for byte in chunk.invalid() {
+            displayed.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    displayed
+}
+
+fn describe_char(c: char) -> String {
+    if c.is_control() {
+        "a control character".to_string()
+    } else if c.is_whitespace() {
+        "whitespace".to_string()
+    } else {
+        format!("an invalid character: '{}'", c)
+    }
+}
@@ -0,0 +1,158 @@
+// Notifications about events of a chain (e.g. for chat or CI integrations).
+//
+// Each event is described by a JSON payload, which is passed to the command set in
+// chain.notifyCommand on its stdin, and posted to the URL set in chain.notifyUrl (with curl, in
+// builds with the webhook cargo feature). Notifications are best effort: failures only warn.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+pub enum Event {
+    // Every branch of the chain was rebased.
+    RebaseCompleted,
+    // The rebase of a branch onto its parent branch stopped on conflicts.
+    RebaseConflict {
+        branch: String,
+        parent: String,
+    },
+    PullRequestCreated {
+        branch: String,
+        base: String,
+        url: String,
+    },
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::RebaseCompleted => "rebase-completed",
+            Event::RebaseConflict { .. } => "rebase-conflict",
+            Event::PullRequestCreated { .. } => "pr-created",
+        }
+    }
+
+    fn fields(&self) -> Vec<(&'static str, &str)> {
+        match self {
+            Event::RebaseCompleted => vec![],
+            Event::RebaseConflict { branch, parent } => {
+                vec![("branch", branch), ("parent", parent)]
+            }
+            Event::PullRequestCreated { branch, base, url } => {
+                vec![("branch", branch), ("base", base), ("url", url)]
+            }
+        }
+    }
+}
+
+pub struct BranchState {
+    pub name: String,
+    pub parent: String,
+    // None for branches that no longer exist.
+    pub commit: Option<String>,
+}
+
+pub struct ChainState {
+    pub name: String,
+    pub root_branch: String,
+    pub branches: Vec<BranchState>,
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+pub fn payload(event: &Event, chain: &ChainState) -> String {
+    let mut lines = vec![
+        format!(r#"  "event": {},"#, json_string(event.name())),
+        format!(r#"  "chain": {},"#, json_string(&chain.name)),
+        format!(r#"  "root_branch": {},"#, json_string(&chain.root_branch)),
+    ];
+    for (name, value) in event.fields() {
+        lines.push(format!(r#"  "{}": {},"#, name, json_string(value)));
+    }
+
+    let branches: Vec<String> = chain
+        .branches
+        .iter()
+        .map(|branch| {
+            format!(
+                r#"    {{ "name": {}, "parent": {}, "commit": {} }}"#,
+                json_string(&branch.name),
+                json_string(&branch.parent),
+                branch
+                    .commit
+                    .as_deref()
+                    .map(json_string)
+                    .unwrap_or_else(|| "null".to_string())
+            )
+        })
+        .collect();
+    if branches.is_empty() {
+        lines.push(r#"  "branches": []"#.to_string());
+    } else {
+        lines.push(format!("  \"branches\": [\n{}\n  ]", branches.join(",\n")));
+    }
+
+    format!("{{\n{}\n}}\n", lines.join("\n"))
+}
+
+// Run the command with the payload on its stdin, and the name of the event in
+// GIT_CHAIN_EVENT.
+pub fn run_command(command: &str, event: &Event, payload: &str) -> Result<(), String> {
+    debug_log!("notifying {} with: {}", event.name(), command);
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("GIT_CHAIN_EVENT", event.name())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| err.to_string())?;
+    // The command may not read its stdin.
+    let _ = child.stdin.take().unwrap().write_all(payload.as_bytes());
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "webhook")]
+pub fn post(url: &str, payload: &str) -> Result<(), String> {
+    debug_log!("posting notification to {}", url);
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", "10"])
+        .args(["--header", "Content-Type: application/json"])
+        .args(["--data-binary", "@-"])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Unable to run curl: {}", err))?;
+    let _ = child.stdin.take().unwrap().write_all(payload.as_bytes());
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "webhook"))]
+pub fn post(_url: &str, _payload: &str) -> Result<(), String> {
+    Err("this build of git chain does not support webhooks (cargo feature webhook)".to_string())
+}
@@ -0,0 +1,55 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+// The branch that was checked out when an operation on the branches of a chain (e.g. rebase)
+// started. It is recorded while the operation is stopped on conflicts, which leave the user on
+// the branch to resolve them, so that the resumed operation switches back to it rather than to
+// the branch with the conflicts.
+pub struct Record {
+    pub chain_name: String,
+    // The subcommand that resumes the operation, e.g. rebase.
+    pub operation: String,
+    pub branch_name: String,
+}
+
+pub fn record_path(chain_dir: &Path) -> PathBuf {
+    chain_dir.join("original-branch")
+}
+
+// One line, with tab separated fields: <chain_name> <operation> <branch_name>
+pub fn read(chain_dir: &Path) -> io::Result<Option<Record>> {
+    let contents = match fs::read_to_string(record_path(chain_dir)) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    let fields: Vec<&str> = contents.trim_end_matches('\n').split('\t').collect();
+    match fields[..] {
+        [chain_name, operation, branch_name] => Ok(Some(Record {
+            chain_name: chain_name.to_string(),
+            operation: operation.to_string(),
+            branch_name: branch_name.to_string(),
+        })),
+        _ => Ok(None),
+    }
+}
+
+pub fn write(chain_dir: &Path, record: &Record) -> io::Result<()> {
+    fs::create_dir_all(chain_dir)?;
+    fs::write(
+        record_path(chain_dir),
+        format!(
+            "{}\t{}\t{}\n",
+            record.chain_name, record.operation, record.branch_name
+        ),
+    )
+}
+
+pub fn clear(chain_dir: &Path) -> io::Result<()> {
+    match fs::remove_file(record_path(chain_dir)) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
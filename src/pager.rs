@@ -0,0 +1,98 @@
+use std::env;
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::panic;
+use std::process::{self, Command, Stdio};
+
+use crate::debug::LoggedCommand;
+
+// Set by git for the commands whose output goes to its pager; git then still colors its output
+// (e.g. git diff run by git chain diff) and does not start another pager.
+const PAGER_IN_USE: &str = "GIT_PAGER_IN_USE";
+
+pub fn in_use() -> bool {
+    env::var_os(PAGER_IN_USE).is_some()
+}
+
+// The pager of git: GIT_PAGER, core.pager, PAGER, or else less. None if paging is disabled, i.e.
+// the pager is empty or cat.
+fn pager_command() -> Option<String> {
+    let output = Command::new("git")
+        .arg("var")
+        .arg("GIT_PAGER")
+        .logged_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let pager = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if pager.is_empty() || pager == "cat" {
+        return None;
+    }
+    Some(pager)
+}
+
+// Run git chain again with its output piped into the pager, and exit once the pager exits. The
+// output of the running process cannot be redirected without unsafe code.
+// Returns when the output is not a terminal, or the pager cannot be started, so that the output
+// is printed as usual.
+pub fn page(arguments: Vec<OsString>) {
+    if in_use() || !std::io::stdout().is_terminal() {
+        return;
+    }
+
+    let pager = match pager_command() {
+        Some(pager) => pager,
+        None => return,
+    };
+    let current_exe = match env::current_exe() {
+        Ok(current_exe) => current_exe,
+        Err(_) => return,
+    };
+
+    // Like git: quit if the output fits on one screen, and let colors through.
+    let mut pager_command = Command::new("sh");
+    pager_command.arg("-c").arg(&pager).stdin(Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        pager_command.env("LESS", "FRX");
+    }
+    if env::var_os("LV").is_none() {
+        pager_command.env("LV", "-c");
+    }
+    let mut pager_process = match pager_command.spawn() {
+        Ok(pager_process) => pager_process,
+        Err(_) => return,
+    };
+    let pager_stdin = pager_process.stdin.take().unwrap();
+
+    let status = Command::new(current_exe)
+        .args(arguments)
+        .env(PAGER_IN_USE, "true")
+        .stdout(pager_stdin)
+        .status();
+    let _ = pager_process.wait();
+
+    match status {
+        Ok(status) => process::exit(status.code().unwrap_or(1)),
+        Err(_) => process::exit(1),
+    }
+}
+
+// Quitting the pager before the end of the output closes the pipe that git chain writes to, which
+// makes println! panic. Exit quietly instead, as git does.
+pub fn exit_quietly_on_broken_pipe() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let message = info
+            .payload()
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .unwrap_or_default();
+        if message.contains("failed printing to stdout") {
+            crate::lock::release();
+            process::exit(0);
+        }
+        default_hook(info);
+    }));
+}
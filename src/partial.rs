@@ -0,0 +1,111 @@
+// Support for partial clones (e.g. git clone --filter=blob:none) and sparse checkouts.
+//
+// git fetches the objects missing from a partial clone on demand, and only checks out the files
+// of the sparse-checkout patterns, but libgit2 does neither: reading a missing blob fails, and a
+// checkout or a diff against the working directory covers every file of the tree. In these
+// repositories, git is run instead of libgit2 where the working directory is involved, and the
+// missing objects are fetched in a single batch before libgit2 reads them.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use git2::Repository;
+
+use crate::debug::LoggedCommand;
+
+// The remote that the missing objects of a partial clone are fetched from; None if the repository
+// is not a partial clone.
+pub fn promisor_remote(repo: &Repository) -> Option<String> {
+    let config = repo.config().ok()?;
+    if let Ok(remote_name) = config.get_string("extensions.partialClone") {
+        return Some(remote_name);
+    }
+
+    let mut remote_name = None;
+    config
+        .entries(Some(r"remote\..*\.promisor"))
+        .ok()?
+        .for_each(|entry| {
+            let name = entry.name().unwrap_or_default();
+            if remote_name.is_none() && entry.value() == Some("true") {
+                remote_name = name
+                    .strip_prefix("remote.")
+                    .and_then(|name| name.strip_suffix(".promisor"))
+                    .map(str::to_string);
+            }
+        })
+        .ok()?;
+    remote_name
+}
+
+pub fn is_sparse_checkout(repo: &Repository) -> bool {
+    repo.config()
+        .and_then(|config| config.get_bool("core.sparseCheckout"))
+        .unwrap_or(false)
+}
+
+// Whether libgit2 cannot be trusted with the working directory of the repository.
+pub fn needs_git_for_worktree(repo: &Repository) -> bool {
+    is_sparse_checkout(repo) || promisor_remote(repo).is_some()
+}
+
+// The objects of the given revisions (e.g. a branch and its history of a file), limited to the
+// given paths, that are missing from a partial clone.
+pub fn missing_objects(revisions: &[String], paths: &[&str]) -> Result<Vec<String>, String> {
+    // git rev-list --objects --missing=print <revisions> -- <paths>
+    // Missing objects are printed as ?<oid>, without being fetched.
+    let output = Command::new("git")
+        .args(["rev-list", "--objects", "--missing=print"])
+        .args(revisions)
+        .arg("--")
+        .args(paths)
+        .logged_output()
+        .map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix('?'))
+        .map(str::to_string)
+        .collect())
+}
+
+// Fetch the given objects from the promisor remote in a single batch.
+pub fn fetch_objects(remote_name: &str, oids: &[String]) -> Result<(), String> {
+    if oids.is_empty() {
+        return Ok(());
+    }
+    debug_log!(
+        "fetching {} missing objects from {}",
+        oids.len(),
+        remote_name
+    );
+
+    // As git itself fetches missing objects: without negotiating the commits in common.
+    let mut child = Command::new("git")
+        .args([
+            "-c",
+            "fetch.negotiationAlgorithm=noop",
+            "fetch",
+            remote_name,
+        ])
+        .args(["--quiet", "--no-tags", "--no-write-fetch-head"])
+        .args(["--recurse-submodules=no", "--filter=blob:none", "--stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| err.to_string())?;
+    let _ = child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(format!("{}\n", oids.join("\n")).as_bytes());
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use colored::*;
+use git2::Error;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PromptMode {
+    // Ask on the terminal; fail if stdin is not a terminal.
+    Interactive,
+    // Answer yes to every confirmation (--yes).
+    AssumeYes,
+    // Never prompt; fail whenever a confirmation is required (--no-input).
+    NoInput,
+}
+
+// Whether confirm can be answered without failing.
+pub fn can_confirm(mode: PromptMode) -> bool {
+    match mode {
+        PromptMode::AssumeYes => true,
+        PromptMode::Interactive => io::stdin().is_terminal(),
+        PromptMode::NoInput => false,
+    }
+}
+
+pub fn confirm(mode: PromptMode, question: &str) -> Result<bool, Error> {
+    match mode {
+        PromptMode::AssumeYes => return Ok(true),
+        PromptMode::Interactive if io::stdin().is_terminal() => {
+            print!("{} [y/N] ", question);
+            io::stdout().flush().unwrap();
+
+            let mut answer = String::new();
+            io::stdin().lock().read_line(&mut answer).unwrap();
+
+            let answer = answer.trim().to_lowercase();
+            return Ok(answer == "y" || answer == "yes");
+        }
+        PromptMode::Interactive | PromptMode::NoInput => {}
+    }
+
+    Err(confirmation_required(question))
+}
+
+// Like confirm, for commands that did not ask for confirmation before: without a terminal (e.g. in
+// a script) they proceed as they used to. Only --no-input fails.
+pub fn confirm_or_proceed(mode: PromptMode, question: &str) -> Result<bool, Error> {
+    match mode {
+        PromptMode::Interactive if !io::stdin().is_terminal() => Ok(true),
+        _ => confirm(mode, question),
+    }
+}
+
+fn confirmation_required(question: &str) -> Error {
+    Error::from_str(&format!(
+        "Confirmation required: {}\nRe-run with {} to confirm without a prompt.",
+        question,
+        "--yes".bold()
+    ))
+}
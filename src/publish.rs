@@ -0,0 +1,69 @@
+// A chain definition, as published to refs/chains/<chain_name>, and as stored with each backup
+// of the chain in refs/chain-backups/<chain_name>/<timestamp>.
+// The ref points to a commit whose tree holds the definition in a file named chain,
+// with one entry per line:
+//
+// root master
+// scope services/api
+// branch feature-1
+// branch feature-2
+#[derive(Debug, PartialEq)]
+pub struct ChainDefinition {
+    pub root_branch: String,
+    pub scope: Option<String>,
+    pub branches: Vec<String>,
+}
+
+pub const DEFINITION_FILE: &str = "chain";
+
+pub fn chain_ref(chain_name: &str) -> String {
+    format!("refs/chains/{}", chain_name)
+}
+
+pub fn backup_ref(chain_name: &str, timestamp: &str) -> String {
+    format!("refs/chain-backups/{}/{}", chain_name, timestamp)
+}
+
+impl ChainDefinition {
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("root {}", self.root_branch)];
+        if let Some(scope) = &self.scope {
+            lines.push(format!("scope {}", scope));
+        }
+        lines.extend(
+            self.branches
+                .iter()
+                .map(|branch_name| format!("branch {}", branch_name)),
+        );
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    pub fn from_text(text: &str) -> Result<ChainDefinition, String> {
+        let mut root_branch = None;
+        let mut scope = None;
+        let mut branches = vec![];
+
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            match line.trim().split_once(' ') {
+                Some(("root", value)) => root_branch = Some(value.trim().to_string()),
+                Some(("scope", value)) => scope = Some(value.trim().to_string()),
+                Some(("branch", value)) => branches.push(value.trim().to_string()),
+                // Entries added by later versions are ignored.
+                Some(_) => {}
+                None => return Err(format!("Invalid line: {}", line)),
+            }
+        }
+
+        let root_branch = root_branch.ok_or_else(|| "Missing root branch".to_string())?;
+        if branches.is_empty() {
+            return Err("Missing branches".to_string());
+        }
+
+        Ok(ChainDefinition {
+            root_branch,
+            scope,
+            branches,
+        })
+    }
+}
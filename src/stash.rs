@@ -0,0 +1,46 @@
+// Stashes made with git chain stash push carry the name of their chain in their message,
+// e.g. "On feature-2: [my-chain] WIP", so that they can be listed and popped per chain.
+#[derive(Debug, PartialEq)]
+pub struct ChainStash {
+    // e.g. stash@{0}
+    pub reference: String,
+    // The branch that was checked out when the stash was made.
+    pub branch_name: String,
+    pub chain_name: Option<String>,
+    pub message: String,
+}
+
+pub fn stash_message(chain_name: &str, message: Option<&str>) -> String {
+    format!("[{}] {}", chain_name, message.unwrap_or("WIP"))
+}
+
+// Parses the output of: git stash list --format=%gd%x09%gs
+pub fn parse_stash_list(output: &str) -> Vec<ChainStash> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (reference, subject) = line.split_once('\t')?;
+
+            // "On <branch>: <message>" with a message, or else "WIP on <branch>: <commit>"
+            let subject = subject
+                .strip_prefix("On ")
+                .or_else(|| subject.strip_prefix("WIP on "))?;
+            let (branch_name, message) = subject.split_once(": ")?;
+
+            let (chain_name, message) = match message
+                .strip_prefix('[')
+                .and_then(|message| message.split_once("] "))
+            {
+                Some((chain_name, message)) => (Some(chain_name.to_string()), message),
+                None => (None, message),
+            };
+
+            Some(ChainStash {
+                reference: reference.to_string(),
+                branch_name: branch_name.to_string(),
+                chain_name,
+                message: message.to_string(),
+            })
+        })
+        .collect()
+}
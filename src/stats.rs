@@ -0,0 +1,196 @@
+// Metrics of chains for dashboards tracking the health of stacks, as displayed by stats.
+//
+// The JSON output is meant to be stable: fields are only added. Timestamps are UNIX timestamps in
+// seconds, and unknown values (e.g. pull requests without gh) are null.
+
+use crate::notify::json_string;
+use crate::relative_time;
+
+pub struct ChainStats {
+    pub name: String,
+    pub root_branch: String,
+    // From the first to the last branch of the chain.
+    pub branches: Vec<BranchStats>,
+    // Branches of the chain that no longer exist.
+    pub missing_branches: Vec<String>,
+    // The last git chain rebase that moved branches of the chain, from the history.
+    pub last_rebased_at: Option<u64>,
+}
+
+pub struct BranchStats {
+    pub name: String,
+    pub parent: String,
+    // Relative to the parent branch; None if the branch shares no history with it.
+    pub ahead_behind: Option<(usize, usize)>,
+    // Author dates, which are kept when the branch is rebased.
+    pub first_commit_at: Option<i64>,
+    pub last_commit_at: i64,
+    pub pull_request: Option<PullRequestStats>,
+}
+
+pub struct PullRequestStats {
+    pub number: u64,
+    pub url: String,
+    pub state: String,
+    pub review_decision: String,
+}
+
+fn json_option<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "null".to_string())
+}
+
+impl BranchStats {
+    fn to_json(&self) -> String {
+        let (ahead, behind) = match self.ahead_behind {
+            Some((ahead, behind)) => (Some(ahead), Some(behind)),
+            None => (None, None),
+        };
+        let pull_request = match &self.pull_request {
+            Some(pull_request) => format!(
+                r#"{{ "number": {}, "url": {}, "state": {}, "review_decision": {} }}"#,
+                pull_request.number,
+                json_string(&pull_request.url),
+                json_string(&pull_request.state),
+                json_string(&pull_request.review_decision)
+            ),
+            None => "null".to_string(),
+        };
+
+        [
+            "        {".to_string(),
+            format!(r#"          "name": {},"#, json_string(&self.name)),
+            format!(r#"          "parent": {},"#, json_string(&self.parent)),
+            format!(r#"          "ahead": {},"#, json_option(ahead)),
+            format!(r#"          "behind": {},"#, json_option(behind)),
+            format!(
+                r#"          "first_commit_at": {},"#,
+                json_option(self.first_commit_at)
+            ),
+            format!(r#"          "last_commit_at": {},"#, self.last_commit_at),
+            format!(r#"          "pull_request": {}"#, pull_request),
+            "        }".to_string(),
+        ]
+        .join("\n")
+    }
+
+    fn to_text(&self) -> String {
+        let mut fields = vec![self.name.clone()];
+        fields.push(match self.ahead_behind {
+            Some((ahead, behind)) => format!("{} ahead ⦁ {} behind", ahead, behind),
+            None => "unrelated history".to_string(),
+        });
+        if let Some(first_commit_at) = self.first_commit_at {
+            fields.push(format!("first commit {}", relative_time(first_commit_at)));
+        }
+        fields.push(format!(
+            "last commit {}",
+            relative_time(self.last_commit_at)
+        ));
+        if let Some(pull_request) = &self.pull_request {
+            fields.push(format!(
+                "PR #{} {}",
+                pull_request.number, pull_request.state
+            ));
+        }
+        fields.join(" ⦁ ")
+    }
+}
+
+impl ChainStats {
+    fn last_commit_at(&self) -> Option<i64> {
+        self.branches
+            .iter()
+            .map(|branch| branch.last_commit_at)
+            .max()
+    }
+
+    fn to_json(&self) -> String {
+        let open_pull_requests = self
+            .branches
+            .iter()
+            .filter(|branch| {
+                branch
+                    .pull_request
+                    .as_ref()
+                    .is_some_and(|pull_request| pull_request.state == "OPEN")
+            })
+            .count();
+        let branches = if self.branches.is_empty() {
+            "[]".to_string()
+        } else {
+            let branches: Vec<String> = self.branches.iter().map(BranchStats::to_json).collect();
+            format!("[\n{}\n      ]", branches.join(",\n"))
+        };
+        let missing_branches: Vec<String> = self
+            .missing_branches
+            .iter()
+            .map(|branch_name| json_string(branch_name))
+            .collect();
+
+        [
+            "    {".to_string(),
+            format!(r#"      "name": {},"#, json_string(&self.name)),
+            format!(
+                r#"      "root_branch": {},"#,
+                json_string(&self.root_branch)
+            ),
+            format!(r#"      "num_of_branches": {},"#, self.branches.len()),
+            format!(
+                r#"      "missing_branches": [{}],"#,
+                missing_branches.join(", ")
+            ),
+            format!(r#"      "open_pull_requests": {},"#, open_pull_requests),
+            format!(
+                r#"      "last_commit_at": {},"#,
+                json_option(self.last_commit_at())
+            ),
+            format!(
+                r#"      "last_rebased_at": {},"#,
+                json_option(self.last_rebased_at)
+            ),
+            format!(r#"      "branches": {}"#, branches),
+            "    }".to_string(),
+        ]
+        .join("\n")
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut header = vec![
+            self.name.clone(),
+            format!("{} branches", self.branches.len()),
+        ];
+        if !self.missing_branches.is_empty() {
+            header.push(format!("{} missing", self.missing_branches.len()));
+        }
+        if let Some(last_commit_at) = self.last_commit_at() {
+            header.push(format!("last commit {}", relative_time(last_commit_at)));
+        }
+        header.push(match self.last_rebased_at {
+            Some(last_rebased_at) => {
+                format!("last rebased {}", relative_time(last_rebased_at as i64))
+            }
+            None => "never rebased".to_string(),
+        });
+
+        let mut lines = vec![header.join(" ⦁ ")];
+        for branch in &self.branches {
+            lines.push(format!("  {}", branch.to_text()));
+        }
+        lines.join("\n")
+    }
+}
+
+pub fn to_json(generated_at: u64, chains: &[ChainStats]) -> String {
+    let chains = if chains.is_empty() {
+        "[]".to_string()
+    } else {
+        let chains: Vec<String> = chains.iter().map(ChainStats::to_json).collect();
+        format!("[\n{}\n  ]", chains.join(",\n"))
+    };
+    format!(
+        "{{\n  \"generated_at\": {},\n  \"chains\": {}\n}}",
+        generated_at, chains
+    )
+}
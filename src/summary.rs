@@ -0,0 +1,298 @@
+// The state of a chain as displayed by status and list with --format, independent of how it is
+// rendered.
+pub struct ChainSummary {
+    pub name: String,
+    pub root_branch: String,
+    pub scope: Option<String>,
+    pub landed: LandedSummary,
+    // From the first to the last branch of the chain.
+    pub branches: Vec<BranchSummary>,
+}
+
+pub struct BranchSummary {
+    pub branch_name: String,
+    pub current: bool,
+    pub missing: bool,
+    // Relative to the parent branch.
+    pub ahead: usize,
+    pub behind: usize,
+    pub needs_rebase: bool,
+    pub note: Option<String>,
+    pub pull_request: Option<PullRequestSummary>,
+}
+
+// How much of a chain has already landed in its root branch, by branches whose commits all
+// landed (merged, rebased or squashed and merged) and by commits.
+#[derive(Default)]
+pub struct LandedSummary {
+    pub branches: usize,
+    pub total_branches: usize,
+    pub commits: usize,
+    pub total_commits: usize,
+}
+
+pub struct PullRequestSummary {
+    pub number: u64,
+    pub url: String,
+    pub state: String,
+}
+
+// Pipes would end the cell of a table.
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+impl BranchSummary {
+    fn ahead_behind(&self) -> String {
+        match (self.ahead, self.behind) {
+            (0, 0) => "".to_string(),
+            (ahead, 0) => format!("{} ahead", ahead),
+            (0, behind) => format!("{} behind", behind),
+            (ahead, behind) => format!("{} ahead ⦁ {} behind", ahead, behind),
+        }
+    }
+
+    fn markdown_row(&self) -> String {
+        let mut branch = format!("`{}`", escape_cell(&self.branch_name));
+        if self.current {
+            branch = format!("➜ **{}**", branch);
+        }
+
+        let pull_request = match &self.pull_request {
+            Some(pull_request) if pull_request.state == "OPEN" => {
+                format!("[#{}]({})", pull_request.number, pull_request.url)
+            }
+            Some(pull_request) => format!(
+                "[#{}]({}) ({})",
+                pull_request.number,
+                pull_request.url,
+                pull_request.state.to_lowercase()
+            ),
+            None => "".to_string(),
+        };
+
+        let mut status = vec![];
+        if self.missing {
+            status.push("missing".to_string());
+        } else {
+            let ahead_behind = self.ahead_behind();
+            if !ahead_behind.is_empty() {
+                status.push(ahead_behind);
+            }
+            if self.needs_rebase {
+                status.push("needs rebase".to_string());
+            }
+        }
+
+        let note = self.note.as_deref().map(escape_cell).unwrap_or_default();
+
+        format!(
+            "| {} | {} | {} | {} |",
+            branch,
+            pull_request,
+            status.join(" ⦁ "),
+            note
+        )
+    }
+}
+
+impl LandedSummary {
+    pub fn percent(&self) -> usize {
+        if self.total_commits == 0 {
+            return 0;
+        }
+        self.commits * 100 / self.total_commits
+    }
+
+    // Nothing is shown for chains of which nothing landed yet.
+    pub fn to_text(&self) -> Option<String> {
+        if self.branches == 0 && self.commits == 0 {
+            return None;
+        }
+        Some(format!(
+            "{}% landed ({} of {} branches, {} of {} commits)",
+            self.percent(),
+            self.branches,
+            self.total_branches,
+            self.commits,
+            self.total_commits
+        ))
+    }
+}
+
+impl ChainSummary {
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![format!("### {}", self.name), String::new()];
+
+        let mut root_line = format!("Root branch: `{}`", self.root_branch);
+        if let Some(scope) = &self.scope {
+            root_line.push_str(&format!(" ⦁ Scope: `{}`", scope));
+        }
+        if let Some(landed) = self.landed.to_text() {
+            root_line.push_str(&format!(" ⦁ {}", landed));
+        }
+        lines.push(root_line);
+        lines.push(String::new());
+
+        lines.push("| Branch | Pull request | Status | Note |".to_string());
+        lines.push("| --- | --- | --- | --- |".to_string());
+        lines.extend(self.branches.iter().map(BranchSummary::markdown_row));
+
+        lines.join("\n")
+    }
+}
+
+// What happened to a branch of the chain during a rebase.
+pub enum RebaseOutcome {
+    // The rebase stopped before reaching the branch.
+    Pending,
+    // The first branch was not rebased against the root branch (--ignore-root).
+    Skipped,
+    // The branch was squashed and merged onto its parent branch, and was reset to it.
+    Reset,
+    UpToDate,
+    Rebased {
+        commits: usize,
+    },
+    // The rebase stopped on conflicts in these files.
+    Conflict {
+        commits: usize,
+        files: Vec<ConflictingFile>,
+    },
+}
+
+pub struct ConflictingFile {
+    pub path: String,
+    // The closest branch before the conflicting branch (or else the root branch) that changed the
+    // file, i.e. where the other side of the conflict comes from.
+    pub last_changed_by: Option<String>,
+}
+
+impl ConflictingFile {
+    fn to_text(&self) -> String {
+        match &self.last_changed_by {
+            Some(branch_name) => format!("{} (last changed by {})", self.path, branch_name),
+            None => self.path.clone(),
+        }
+    }
+}
+
+pub struct RebaseEntry {
+    pub branch_name: String,
+    pub parent: String,
+    pub outcome: RebaseOutcome,
+}
+
+// The outcome of each branch of a rebase, reported once the rebase ends or stops.
+pub struct RebaseReport {
+    pub chain_name: String,
+    pub entries: Vec<RebaseEntry>,
+}
+
+fn commits(count: usize) -> String {
+    if count == 1 {
+        "1 commit".to_string()
+    } else {
+        format!("{} commits", count)
+    }
+}
+
+impl RebaseEntry {
+    fn to_text(&self) -> String {
+        let outcome = match &self.outcome {
+            RebaseOutcome::Pending => "not rebased".to_string(),
+            RebaseOutcome::Skipped => {
+                format!("skipped, not rebased against root branch {}", self.parent)
+            }
+            RebaseOutcome::Reset => format!("reset to {} (squashed and merged)", self.parent),
+            RebaseOutcome::UpToDate => format!("up-to-date with {}", self.parent),
+            RebaseOutcome::Rebased { commits: count } => {
+                format!("replayed {} onto {}", commits(*count), self.parent)
+            }
+            RebaseOutcome::Conflict {
+                commits: count,
+                files,
+            } => {
+                let mut text = format!(
+                    "conflicts while replaying {} onto {}",
+                    commits(*count),
+                    self.parent
+                );
+                if !files.is_empty() {
+                    let files: Vec<String> = files.iter().map(ConflictingFile::to_text).collect();
+                    text.push_str(&format!("\n      in: {}", files.join(", ")));
+                }
+                text
+            }
+        };
+        format!("  {}: {}", self.branch_name, outcome)
+    }
+}
+
+impl RebaseReport {
+    // None when every branch was already up-to-date, as there is nothing to report.
+    pub fn to_text(&self, resume_command: Option<&str>) -> Option<String> {
+        if self
+            .entries
+            .iter()
+            .all(|entry| matches!(entry.outcome, RebaseOutcome::UpToDate))
+        {
+            return None;
+        }
+
+        let mut lines = vec![format!("📋 Rebase summary for chain {}:", self.chain_name)];
+        lines.extend(self.entries.iter().map(RebaseEntry::to_text));
+        if let Some(resume_command) = resume_command {
+            lines.push(format!("To resume, run: {}", resume_command));
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+pub enum TestOutcome {
+    // Not run, as an earlier branch failed with --fail-fast.
+    Pending,
+    Missing,
+    Passed,
+    // The exit code is None when the command was killed by a signal.
+    Failed { code: Option<i32> },
+}
+
+pub struct TestEntry {
+    pub branch_name: String,
+    pub outcome: TestOutcome,
+}
+
+// The outcome of running a command on each branch of a chain.
+pub struct TestReport {
+    pub chain_name: String,
+    pub entries: Vec<TestEntry>,
+}
+
+impl TestEntry {
+    fn to_text(&self) -> String {
+        let outcome = match &self.outcome {
+            TestOutcome::Pending => "not run".to_string(),
+            TestOutcome::Missing => "skipped, branch does not exist".to_string(),
+            TestOutcome::Passed => "✅ passed".to_string(),
+            TestOutcome::Failed { code: Some(code) } => format!("❌ failed (exit code {})", code),
+            TestOutcome::Failed { code: None } => "❌ failed (killed by a signal)".to_string(),
+        };
+        format!("  {}: {}", self.branch_name, outcome)
+    }
+}
+
+impl TestReport {
+    pub fn num_of_failures(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.outcome, TestOutcome::Failed { .. }))
+            .count()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("📋 Test summary for chain {}:", self.chain_name)];
+        lines.extend(self.entries.iter().map(TestEntry::to_text));
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,131 @@
+use std::ffi::OsStr;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use colored::*;
+
+// Time spent per branch and per phase, collected for --timings.
+struct Timings {
+    started_at: Instant,
+    // The branch that the phases being recorded are attributed to.
+    branch: Option<String>,
+    // (branch, phase, duration), in the order they were recorded.
+    entries: Vec<(Option<String>, &'static str, Duration)>,
+}
+
+static TIMINGS: Mutex<Option<Timings>> = Mutex::new(None);
+
+const PHASES: [&str; 7] = [
+    "fetch",
+    "merge-base",
+    "squash detection",
+    "rebase",
+    "checkout",
+    "push",
+    "other",
+];
+
+pub fn enable() {
+    *TIMINGS.lock().unwrap() = Some(Timings {
+        started_at: Instant::now(),
+        branch: None,
+        entries: vec![],
+    });
+}
+
+pub fn set_branch(branch: Option<&str>) {
+    if let Some(timings) = TIMINGS.lock().unwrap().as_mut() {
+        timings.branch = branch.map(str::to_string);
+    }
+}
+
+pub fn record(phase: &'static str, duration: Duration) {
+    if let Some(timings) = TIMINGS.lock().unwrap().as_mut() {
+        let branch = timings.branch.clone();
+        timings.entries.push((branch, phase, duration));
+    }
+}
+
+pub fn record_command(command: &Command, duration: Duration) {
+    let mut args = command.get_args().map(OsStr::to_string_lossy);
+    let phase = match (command.get_program().to_str(), args.next().as_deref()) {
+        (Some("git"), Some("fetch")) | (Some("git"), Some("ls-remote")) => "fetch",
+        (Some("git"), Some("merge-base")) => "merge-base",
+        (Some("git"), Some("commit-tree")) | (Some("git"), Some("cherry")) => "squash detection",
+        (Some("git"), Some("rebase")) => "rebase",
+        (Some("git"), Some("checkout")) => "checkout",
+        (Some("git"), Some("push")) => "push",
+        _ => "other",
+    };
+    record(phase, duration);
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{} ms", duration.as_millis())
+}
+
+// e.g. merge-base 12 ms, rebase 340 ms; phases that took no time are left out.
+fn format_phases(duration_of: impl Fn(&str) -> Duration) -> String {
+    PHASES
+        .iter()
+        .filter_map(|phase| {
+            let duration = duration_of(phase);
+            if duration.is_zero() {
+                return None;
+            }
+            Some(format!("{} {}", phase, format_duration(duration)))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Prints the time spent per branch and per phase, if --timings was given.
+pub fn report() {
+    let timings = match TIMINGS.lock().unwrap().take() {
+        Some(timings) => timings,
+        None => return,
+    };
+
+    let mut branches: Vec<Option<String>> = vec![];
+    for (branch, _phase, _duration) in &timings.entries {
+        if !branches.contains(branch) {
+            branches.push(branch.clone());
+        }
+    }
+
+    let total_of = |branch: Option<&Option<String>>, phase: Option<&str>| -> Duration {
+        timings
+            .entries
+            .iter()
+            .filter(|(entry_branch, _, _)| branch.is_none() || branch == Some(entry_branch))
+            .filter(|(_, entry_phase, _)| phase.is_none() || phase == Some(*entry_phase))
+            .map(|(_, _, duration)| *duration)
+            .sum()
+    };
+
+    println!();
+    println!(
+        "⏱  Timings (total {})",
+        format_duration(timings.started_at.elapsed()).bold()
+    );
+
+    for branch in &branches {
+        let phases = format_phases(|phase| total_of(Some(branch), Some(phase)));
+        if phases.is_empty() {
+            continue;
+        }
+
+        println!(
+            "  {}: {} ({})",
+            branch.as_deref().unwrap_or("(chain)").bold(),
+            format_duration(total_of(Some(branch), None)),
+            phases
+        );
+    }
+
+    let phases = format_phases(|phase| total_of(None, Some(phase)));
+    if !phases.is_empty() {
+        println!("  {}: {}", "per phase".bold(), phases);
+    }
+}
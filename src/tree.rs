@@ -0,0 +1,39 @@
+// A tree rendered with box-drawing characters, e.g.
+//
+// master
+// └─ chain_1
+//    ├─ some_branch_1
+//    │  └─ chain_2
+//    └─ some_branch_2
+pub struct TreeNode {
+    pub label: String,
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    pub fn new(label: String) -> TreeNode {
+        TreeNode {
+            label,
+            children: vec![],
+        }
+    }
+
+    pub fn render(&self) -> Vec<String> {
+        let mut lines = vec![self.label.clone()];
+        render_children(&self.children, "", &mut lines);
+        lines
+    }
+}
+
+fn render_children(children: &[TreeNode], prefix: &str, lines: &mut Vec<String>) {
+    for (index, child) in children.iter().enumerate() {
+        let is_last = index == children.len() - 1;
+        let (branch, indent) = if is_last {
+            ("└─ ", "   ")
+        } else {
+            ("├─ ", "│  ")
+        };
+        lines.push(format!("{}{}{}", prefix, branch, child.label));
+        render_children(&child.children, &format!("{}{}", prefix, indent), lines);
+    }
+}
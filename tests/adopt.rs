@@ -0,0 +1,104 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_test_bin_expect_err, run_test_bin_expect_ok, setup_git_repo,
+    teardown_git_repo,
+};
+
+#[test]
+fn adopt_subcommand() {
+    let repo_name = "adopt_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // master <- api <- ui <- docs <- tests, and hotfix off master
+    for (parent, branch_name) in [
+        ("master", "api"),
+        ("api", "ui"),
+        ("ui", "docs"),
+        ("docs", "tests"),
+        ("master", "hotfix"),
+    ] {
+        checkout_branch(&repo, parent);
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "api", "docs"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "api");
+
+    // docs is stacked on ui
+    let args: Vec<&str> = vec!["adopt", "ui"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🔗 Succesfully adopted branch ui into chain chain_name before docs
+
+chain_name
+      docs ⦁ 1 ahead
+      ui ⦁ 1 ahead
+    ➜ api ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    // tests is stacked on docs
+    let args: Vec<&str> = vec!["adopt", "tests"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .starts_with("🔗 Succesfully adopted branch tests into chain chain_name after docs\n\n"));
+
+    // hotfix forked from master, before any branch of the chain
+    let args: Vec<&str> = vec!["adopt", "hotfix", "--chain", "chain_name"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🔗 Succesfully adopted branch hotfix into chain chain_name as its first branch
+
+chain_name
+      tests ⦁ 1 ahead
+      docs ⦁ 1 ahead
+      ui ⦁ 1 ahead
+    ➜ api ⦁ 1 ahead ⦁ 1 behind
+      hotfix ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["adopt", "ui"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to adopt branch: ui
+It is already part of the chain: chain_name
+To move it to another chain, run: git chain move --chain=chain_name
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["adopt", "does_not_exist"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Branch does not exist: does_not_exist\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
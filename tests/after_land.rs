@@ -0,0 +1,104 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_test_bin_expect_ok,
+    run_test_bin_for_rebase, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn after_land_subcommand() {
+    let repo_name = "after_land_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1.5");
+        commit_all(&repo, "message");
+    };
+
+    // create and checkout new branch named some_branch_2
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // nothing landed yet
+    let args: Vec<&str> = vec!["after-land"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .ends_with("No landed branches found for chain: chain_name\n"));
+
+    // squash and merge some_branch_1 onto master
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1.5");
+        commit_all(&repo, "squashed some_branch_1");
+        checkout_branch(&repo, "some_branch_2");
+    };
+
+    let args: Vec<&str> = vec!["after-land"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout
+        .contains("⚠️  Branch some_branch_1 is detected to be squashed and merged onto master."));
+    assert!(stdout.contains("🎉 Successfully rebased chain chain_name"));
+    assert!(stdout.ends_with(
+        r#"
+Removed the following landed branches from chain: chain_name
+
+some_branch_1
+"#
+    ));
+
+    let args: Vec<&str> = vec![];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_2
+
+chain_name
+    ➜ some_branch_2 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
@@ -1,12 +1,38 @@
+use std::thread;
+use std::time::Duration;
+
+use git2::{BranchType, Repository};
+
 pub mod common;
 use common::{
     branch_equal, branch_exists, checkout_branch, commit_all, create_branch, create_new_file,
-    first_commit_all, generate_path_to_repo, get_current_branch_name, run_test_bin_expect_ok,
-    setup_git_repo, teardown_git_repo,
+    first_commit_all, generate_path_to_repo, get_current_branch_name, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
 };
 
-fn backup_name(chain_name: &str, branch_name: &str) -> String {
-    format!("backup-{}/{}", chain_name, branch_name)
+fn backup_name(repo: &Repository, chain_name: &str, branch_name: &str) -> String {
+    // name of the backup branch of branch_name in the latest backup of the chain
+    let prefix = format!("backup-{}/", chain_name);
+    let suffix = format!("/{}", branch_name);
+
+    let mut backups: Vec<String> = repo
+        .branches(Some(BranchType::Local))
+        .unwrap()
+        .map(|branch| branch.unwrap().0.name().unwrap().unwrap().to_string())
+        .filter(|name| name.starts_with(&prefix) && name.ends_with(&suffix))
+        .collect();
+    // backups made within the same second get a counter, e.g. 20240131T235959-2
+    backups.sort_by_key(|name| {
+        let timestamp = name[prefix.len()..].split('/').next().unwrap();
+        match timestamp.split_once('-') {
+            Some((timestamp, counter)) => (timestamp.to_string(), counter.parse().unwrap()),
+            None => (timestamp.to_string(), 1),
+        }
+    });
+
+    backups
+        .pop()
+        .unwrap_or_else(|| format!("{}<none>{}", prefix, suffix))
 }
 
 #[test]
@@ -107,11 +133,11 @@ fn backup_subcommand() {
     run_test_bin_expect_ok(&path_to_repo, args);
 
     assert_eq!(
-        branch_exists(&repo, &backup_name("chain_name_2", "some_branch_2")),
+        branch_exists(&repo, &backup_name(&repo, "chain_name_2", "some_branch_2")),
         false
     );
     assert_eq!(
-        branch_exists(&repo, &backup_name("chain_name_2", "some_branch_3")),
+        branch_exists(&repo, &backup_name(&repo, "chain_name_2", "some_branch_3")),
         false
     );
 
@@ -128,21 +154,21 @@ fn backup_subcommand() {
 
     assert!(branch_exists(
         &repo,
-        &backup_name("chain_name_2", "some_branch_2")
+        &backup_name(&repo, "chain_name_2", "some_branch_2")
     ));
     assert!(branch_exists(
         &repo,
-        &backup_name("chain_name_2", "some_branch_3")
+        &backup_name(&repo, "chain_name_2", "some_branch_3")
     ));
     assert!(branch_equal(
         &repo,
         "some_branch_2",
-        &backup_name("chain_name_2", "some_branch_2")
+        &backup_name(&repo, "chain_name_2", "some_branch_2")
     ));
     assert!(branch_equal(
         &repo,
         "some_branch_3",
-        &backup_name("chain_name_2", "some_branch_3")
+        &backup_name(&repo, "chain_name_2", "some_branch_3")
     ));
 
     {
@@ -158,7 +184,7 @@ fn backup_subcommand() {
         branch_equal(
             &repo,
             "some_branch_3",
-            &backup_name("chain_name_2", "some_branch_3")
+            &backup_name(&repo, "chain_name_2", "some_branch_3")
         ) == false
     );
 
@@ -175,22 +201,261 @@ fn backup_subcommand() {
 
     assert!(branch_exists(
         &repo,
-        &backup_name("chain_name_2", "some_branch_2")
+        &backup_name(&repo, "chain_name_2", "some_branch_2")
     ));
     assert!(branch_exists(
         &repo,
-        &backup_name("chain_name_2", "some_branch_3")
+        &backup_name(&repo, "chain_name_2", "some_branch_3")
     ));
     assert!(branch_equal(
         &repo,
         "some_branch_2",
-        &backup_name("chain_name_2", "some_branch_2")
+        &backup_name(&repo, "chain_name_2", "some_branch_2")
     ));
     assert!(branch_equal(
         &repo,
         "some_branch_3",
-        &backup_name("chain_name_2", "some_branch_3")
+        &backup_name(&repo, "chain_name_2", "some_branch_3")
     ));
 
     teardown_git_repo(repo_name);
 }
+
+fn num_of_backups(repo: &Repository, chain_name: &str) -> usize {
+    let prefix = format!("backup-{}/", chain_name);
+
+    let mut timestamps: Vec<String> = repo
+        .branches(Some(BranchType::Local))
+        .unwrap()
+        .map(|branch| branch.unwrap().0.name().unwrap().unwrap().to_string())
+        .filter_map(|name| {
+            name.strip_prefix(&prefix)
+                .map(|rest| rest.split('/').next().unwrap().to_string())
+        })
+        .collect();
+    timestamps.dedup();
+    timestamps.len()
+}
+
+#[test]
+fn backup_subcommand_retention() {
+    let repo_name = "backup_subcommand_retention";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["init", "chain_name", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["backup", "--list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "No backups of chain: chain_name\n"
+    );
+
+    // backups made back to back (e.g. within the same second) do not overwrite each other
+    for _ in 0..2 {
+        let args: Vec<&str> = vec!["backup"];
+        run_test_bin_expect_ok(&path_to_repo, args);
+    }
+    assert_eq!(num_of_backups(&repo, "chain_name"), 2);
+
+    let args: Vec<&str> = vec!["backup", "--prune", "--keep", "0"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(num_of_backups(&repo, "chain_name"), 0);
+
+    // backups are timestamped to the second
+    for _ in 0..3 {
+        let args: Vec<&str> = vec!["backup"];
+        run_test_bin_expect_ok(&path_to_repo, args);
+        thread::sleep(Duration::from_millis(1100));
+    }
+    assert_eq!(num_of_backups(&repo, "chain_name"), 3);
+
+    let args: Vec<&str> = vec!["backup", "--list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Backups of chain: chain_name\n"));
+    assert_eq!(stdout.matches("/some_branch_1\n").count(), 3);
+
+    // --prune requires the number of backups to keep
+    let args: Vec<&str> = vec!["backup", "--prune"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Please provide the number of backups to keep with --keep"));
+
+    let args: Vec<&str> = vec!["backup", "--prune", "--keep", "2", "--dry-run"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .starts_with("Would remove 1 old backups of chain: chain_name\n"));
+    assert_eq!(num_of_backups(&repo, "chain_name"), 3);
+
+    let args: Vec<&str> = vec!["backup", "--prune", "--keep", "2"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .starts_with("Removed 1 old backups of chain: chain_name\n"));
+    assert_eq!(num_of_backups(&repo, "chain_name"), 2);
+
+    // old backups are removed after backing up when chain.backupRetention is set
+    let mut config = repo.config().unwrap();
+    config.set_str("chain.backupRetention", "1").unwrap();
+
+    let args: Vec<&str> = vec!["backup"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🎉 Successfully backed up chain: chain_name
+Removed 2 old backups of chain: chain_name
+"#
+        .trim_start()
+    );
+    assert_eq!(num_of_backups(&repo, "chain_name"), 1);
+    assert!(branch_equal(
+        &repo,
+        "some_branch_1",
+        &backup_name(&repo, "chain_name", "some_branch_1")
+    ));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn backup_subcommand_restore() {
+    let repo_name = "backup_subcommand_restore";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // the root branch and the chain definition are backed up along with the branches
+    let args: Vec<&str> = vec!["backup", "--include-root"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "🎉 Successfully backed up chain: chain_name\n"
+    );
+    let root_backup = backup_name(&repo, "chain_name", "master");
+    assert!(branch_equal(&repo, "master", &root_backup));
+    let timestamp = root_backup.split('/').nth(1).unwrap().to_string();
+    assert!(repo
+        .find_reference(&format!("refs/chain-backups/chain_name/{}", timestamp))
+        .is_ok());
+
+    let args: Vec<&str> = vec!["backup", "--list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains(&format!("    {} (root branch)\n", root_backup)));
+
+    // rewrite the branches, and delete the chain
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "master.txt", "contents");
+    commit_all(&repo, "message");
+    checkout_branch(&repo, "some_branch_1");
+    create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+    commit_all(&repo, "message");
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["remove", "--chain", "chain_name", "--yes"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec![
+        "backup",
+        "--restore",
+        "20000101T000000",
+        "--chain",
+        "chain_name",
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Chain chain_name has no backup 20000101T000000
+To list the backups of the chain, run: git chain backup --list --chain chain_name
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec![
+        "backup",
+        "--restore",
+        &timestamp,
+        "--include-root",
+        "--chain",
+        "chain_name",
+    ];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            r#"
+Restored branch master from backup-chain_name/{0}/master
+Restored branch some_branch_1 from backup-chain_name/{0}/some_branch_1
+Restored branch some_branch_2 from backup-chain_name/{0}/some_branch_2
+🔗 Succesfully restored chain chain_name from backup {0}
+
+chain_name
+    ➜ some_branch_2 ⦁ 1 ahead
+      some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#,
+            timestamp
+        )
+        .trim_start()
+    );
+    assert!(branch_equal(&repo, "master", &root_backup));
+    assert!(branch_equal(
+        &repo,
+        "some_branch_1",
+        &backup_name(&repo, "chain_name", "some_branch_1")
+    ));
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+    // pruning a backup also removes its chain definition
+    let args: Vec<&str> = vec!["backup", "--prune", "--keep", "0"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(repo
+        .find_reference(&format!("refs/chain-backups/chain_name/{}", timestamp))
+        .is_err());
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,80 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_test_bin_expect_err, run_test_bin_expect_ok, setup_git_repo,
+    teardown_git_repo,
+};
+
+#[test]
+fn blame_subcommand() {
+    let repo_name = "blame_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "file.txt", "line 1\nline 2\nline 3");
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file.txt", "line 1\nline 2\nline 3\nline 4");
+        commit_all(&repo, "add line 4");
+    };
+
+    {
+        create_branch(&repo, "some_branch_2");
+        checkout_branch(&repo, "some_branch_2");
+        create_new_file(
+            &path_to_repo,
+            "file.txt",
+            "line 1\nline two\nline 3\nline 4",
+        );
+        commit_all(&repo, "edit line 2");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["blame", "file.txt"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("master        "));
+    assert!(lines[0].ends_with(" 1) line 1"));
+    assert!(lines[1].starts_with("some_branch_2 "));
+    assert!(lines[1].ends_with(" 2) line two"));
+    assert!(lines[2].starts_with("master        "));
+    assert!(lines[2].ends_with(" 3) line 3"));
+    assert!(lines[3].starts_with("some_branch_1 "));
+    assert!(lines[3].ends_with(" 4) line 4"));
+
+    // as of the current branch
+    checkout_branch(&repo, "some_branch_1");
+    let args: Vec<&str> = vec!["blame", "file.txt"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("master        "));
+    assert!(lines[1].ends_with(" 2) line 2"));
+
+    let args: Vec<&str> = vec!["blame", "missing.txt"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 File does not exist on branch some_branch_1: missing.txt\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,250 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin,
+    run_test_bin_expect_err, run_test_bin_expect_ok, run_test_bin_with_env, setup_git_repo,
+    teardown_git_repo,
+};
+
+#[test]
+fn browse_subcommand() {
+    let repo_name = "browse_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    // create and checkout new branch named some_branch_2
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // no remote
+    let args: Vec<&str> = vec!["browse", "--print"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Remote does not exist: origin"));
+
+    run_git_command(
+        &path_to_repo,
+        vec!["remote", "add", "origin", "git@github.com:owner/repo.git"],
+    );
+
+    // The branches have no pull requests, so the compare pages are used.
+    let args: Vec<&str> = vec!["browse", "--print"];
+    let output = run_test_bin(&path_to_repo, args);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "https://github.com/owner/repo/compare/some_branch_1...some_branch_2?expand=1\n"
+    );
+
+    let args: Vec<&str> = vec!["browse", "--all", "--print"];
+    let output = run_test_bin(&path_to_repo, args);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+https://github.com/owner/repo/compare/master...some_branch_1?expand=1
+https://github.com/owner/repo/compare/some_branch_1...some_branch_2?expand=1
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn browse_subcommand_hostname() {
+    let repo_name = "browse_subcommand_hostname";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["init", "chain_name", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // SSH host alias of a GitHub Enterprise instance
+    run_git_command(
+        &path_to_repo,
+        vec!["remote", "add", "origin", "git@ghe:owner/repo.git"],
+    );
+
+    let args: Vec<&str> = vec!["browse", "--print"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GH_HOST", "")]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "https://ghe/owner/repo/compare/master...some_branch_1?expand=1\n"
+    );
+
+    // git config
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "chain.ghHost", "config.example.com"],
+    );
+
+    let args: Vec<&str> = vec!["browse", "--print"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GH_HOST", "")]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "https://config.example.com/owner/repo/compare/master...some_branch_1?expand=1\n"
+    );
+
+    // GH_HOST takes precedence over git config
+    let args: Vec<&str> = vec!["browse", "--print"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GH_HOST", "env.example.com")]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "https://env.example.com/owner/repo/compare/master...some_branch_1?expand=1\n"
+    );
+
+    // --hostname takes precedence over GH_HOST
+    let args: Vec<&str> = vec![
+        "browse",
+        "--print",
+        "--hostname",
+        "https://flag.example.com/",
+    ];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GH_HOST", "env.example.com")]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "https://flag.example.com/owner/repo/compare/master...some_branch_1?expand=1\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn browse_subcommand_pr_url_template() {
+    let repo_name = "browse_subcommand_pr_url_template";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "remote",
+            "add",
+            "origin",
+            "ssh://git@gitea.example.com:2222/team/project.git",
+        ],
+    );
+
+    // without a template, list --pr has no URLs to display
+    let args: Vec<&str> = vec!["list", "--pr"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("https://"));
+
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "config",
+            "chain.prUrlTemplate",
+            "https://{host}/{repo}/compare/{base}...{branch}",
+        ],
+    );
+
+    let args: Vec<&str> = vec!["list", "--pr"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+chain_name
+    ➜ some_branch_2 ⦁ 1 ahead ⦁ https://gitea.example.com/team/project/compare/some_branch_1...some_branch_2
+      some_branch_1 ⦁ 1 ahead ⦁ https://gitea.example.com/team/project/compare/master...some_branch_1
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    // GIT_CHAIN_PR_URL_TEMPLATE takes precedence over chain.prUrlTemplate, and is used by browse
+    let args: Vec<&str> = vec!["browse", "--print"];
+    let output = run_test_bin_with_env(
+        &path_to_repo,
+        args,
+        vec![(
+            "GIT_CHAIN_PR_URL_TEMPLATE",
+            "https://{host}/{repo}/pulls?head={branch}",
+        )],
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "https://gitea.example.com/team/project/pulls?head=some_branch_2\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,193 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn cherry_subcommand() {
+    let repo_name = "cherry_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    // run git chain setup
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let rev_parse = |rev: &str| -> String {
+        let output = run_git_command(&path_to_repo, vec!["rev-parse", rev]);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let some_branch_1_before = rev_parse("some_branch_1");
+    let some_branch_2_before = rev_parse("some_branch_2");
+
+    // add a commit to master
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "file_master.txt", "contents master");
+    commit_all(&repo, "master commit");
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["cherry"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Cherry-picking 1 commits of master onto some_branch_1"));
+    assert!(stdout.contains("Cherry-picking 1 commits of some_branch_1 onto some_branch_2"));
+    assert!(stdout
+        .contains("🎉 Successfully cherry-picked 2 commits onto the branches of chain chain_name"));
+
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+    // the existing history of each branch is kept
+    assert_eq!(rev_parse("some_branch_1~1"), some_branch_1_before);
+    assert_eq!(rev_parse("some_branch_2~1"), some_branch_2_before);
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        let output = run_git_command(&path_to_repo, vec!["log", "-1", "--format=%s", branch_name]);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "master commit\n");
+    }
+
+    // nothing left to cherry-pick
+    let args: Vec<&str> = vec!["cherry"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("Chain chain_name is already up-to-date.")
+    );
+
+    // conflicting commit on master
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "some_branch_1.txt", "conflict");
+    commit_all(&repo, "conflicting commit");
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["cherry"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Unable to completely cherry-pick the commits of master onto some_branch_1"));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn cherry_subcommand_backport() {
+    let repo_name = "cherry_subcommand_backport";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    {
+        create_branch(&repo, "some_branch_2");
+        checkout_branch(&repo, "some_branch_2");
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, some_branch_2!");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // a hotfix branch off master, which conflicts with some_branch_2
+    {
+        checkout_branch(&repo, "master");
+        create_branch(&repo, "hotfix");
+        checkout_branch(&repo, "hotfix");
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, hotfix!");
+        commit_all(&repo, "hotfix");
+        checkout_branch(&repo, "some_branch_2");
+    };
+
+    let args: Vec<&str> = vec!["cherry", "--backport", "hotfix"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Backporting hotfix to chain chain_name
+
+✅ some_branch_1: cherry-picked 1 commits
+🛑 some_branch_2: unable to cherry-pick the commits; the branch was left as it was
+
+"#
+        .trim_start()
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to backport hotfix to 1 branches of chain chain_name: some_branch_2
+Cherry-pick the commits onto these branches manually, resolving any conflicts.
+"#
+        .trim_start()
+    );
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+    let rev_parse = |rev: &str| -> String {
+        let output = run_git_command(&path_to_repo, vec!["rev-parse", rev]);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let some_branch_2_tree = rev_parse("some_branch_2^{tree}");
+
+    // a single commit
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "fix.txt", "fix");
+        commit_all(&repo, "fix");
+        checkout_branch(&repo, "some_branch_2");
+    };
+    let fix = rev_parse("master");
+
+    let args: Vec<&str> = vec!["cherry", "--backport", &fix];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✅ some_branch_1: cherry-picked 1 commits\n"));
+    assert!(stdout.contains("✅ some_branch_2: cherry-picked 1 commits\n"));
+    assert!(stdout.contains("🎉 Successfully backported"));
+    assert_ne!(rev_parse("some_branch_2^{tree}"), some_branch_2_tree);
+
+    // branches that already contain the changes are left as they are
+    let args: Vec<&str> = vec!["cherry", "--backport", &fix];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✅ some_branch_1: already contains the changes\n"));
+    assert!(stdout.contains("✅ some_branch_2: already contains the changes\n"));
+
+    teardown_git_repo(repo_name);
+}
@@ -222,6 +222,29 @@ where
         .expect("Failed to run git-chain")
 }
 
+pub fn run_test_bin_with_env<I, T, P: AsRef<Path>>(
+    current_dir: P,
+    arguments: I,
+    envs: Vec<(&str, &str)>,
+) -> Output
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    let mut current_dir_buf: PathBuf = current_dir.as_ref().into();
+    if current_dir_buf.is_relative() {
+        current_dir_buf = current_dir_buf.canonicalize().unwrap();
+    }
+
+    assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .expect("Failed to get git-chain")
+        .current_dir(current_dir_buf)
+        .envs(envs)
+        .args(arguments)
+        .output()
+        .expect("Failed to run git-chain")
+}
+
 pub fn run_test_bin_expect_err<I, T, P: AsRef<Path>>(current_dir: P, arguments: I) -> Output
 where
     I: IntoIterator<Item = T>,
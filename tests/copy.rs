@@ -0,0 +1,130 @@
+pub mod common;
+use common::{
+    branch_equal, checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn copy_subcommand() {
+    let repo_name = "copy_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    // create and checkout new branch named some_branch_2
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    // run git chain setup
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // git chain copy
+    let args: Vec<&str> = vec!["copy", "chain_name", "chain_name_v2"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🔗 Succesfully copied chain chain_name to chain_name_v2
+
+chain_name_v2
+      some_branch_2-v2 ⦁ 1 ahead
+      some_branch_1-v2 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    assert!(branch_equal(&repo, "some_branch_1", "some_branch_1-v2"));
+    assert!(branch_equal(&repo, "some_branch_2", "some_branch_2-v2"));
+
+    // the new branches already exist
+    let args: Vec<&str> = vec!["copy", "chain_name", "chain_name_v3"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Branch already exists: some_branch_1-v2")
+    );
+
+    // git chain copy --template
+    let args: Vec<&str> = vec![
+        "copy",
+        "chain_name",
+        "chain_name_v3",
+        "--template",
+        "v3/{branch}",
+    ];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🔗 Succesfully copied chain chain_name to chain_name_v3
+
+chain_name_v3
+      v3/some_branch_2 ⦁ 1 ahead
+      v3/some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    // the original chain is left untouched
+    let args: Vec<&str> = vec![];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_2
+
+chain_name
+    ➜ some_branch_2 ⦁ 1 ahead
+      some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
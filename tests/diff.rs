@@ -0,0 +1,145 @@
+use std::fs;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn diff_subcommand() {
+    let repo_name = "diff_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    // create and checkout new branch named some_branch_2
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // add commit to master, which is not part of the chain
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "file_master.txt", "contents");
+        commit_all(&repo, "message");
+        checkout_branch(&repo, "some_branch_2");
+    };
+
+    // git chain diff
+    let args: Vec<&str> = vec!["diff"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("+++ b/file_2.txt\n"));
+    assert!(!stdout.contains("file_1.txt"));
+
+    // git chain diff --all --stat
+    let args: Vec<&str> = vec!["diff", "--all", "--stat"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+ file_1.txt | 1 +
+ file_2.txt | 1 +
+ 2 files changed, 2 insertions(+)
+"#
+        .trim_start_matches('\n')
+    );
+
+    // an untracked file named like the range is not mistaken for it
+    create_new_file(&path_to_repo, "some_branch_1...some_branch_2", "contents");
+    let args: Vec<&str> = vec!["diff", "--stat"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        " file_2.txt | 1 +\n 1 file changed, 1 insertion(+)\n"
+    );
+    fs::remove_file(path_to_repo.join("some_branch_1...some_branch_2")).unwrap();
+
+    // with --chain, the current branch may be part of another chain, or of none
+    checkout_branch(&repo, "master");
+
+    let args: Vec<&str> = vec!["diff", "--stat"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Branch is not part of any chain: master")
+    );
+
+    // the last branch of the chain is diffed against its parent branch
+    let args: Vec<&str> = vec!["diff", "--chain", "chain_name", "--stat"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        " file_2.txt | 1 +\n 1 file changed, 1 insertion(+)\n"
+    );
+
+    let args: Vec<&str> = vec!["diff", "--chain", "chain_name", "--all", "--stat"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+ file_1.txt | 1 +
+ file_2.txt | 1 +
+ 2 files changed, 2 insertions(+)
+"#
+        .trim_start_matches('\n')
+    );
+
+    // the current branch is diffed if it is part of the chain
+    checkout_branch(&repo, "some_branch_1");
+    let args: Vec<&str> = vec!["diff", "--chain", "chain_name", "--stat"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        " file_1.txt | 1 +\n 1 file changed, 1 insertion(+)\n"
+    );
+
+    let args: Vec<&str> = vec!["diff", "--chain", "does_not_exist"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Chain does not exist: does_not_exist")
+    );
+
+    teardown_git_repo(repo_name);
+}
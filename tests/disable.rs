@@ -0,0 +1,80 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_git_command, run_test_bin_expect_err, run_test_bin_expect_ok,
+    setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn disable_subcommand() {
+    let repo_name = "disable_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    create_branch(&repo, "some_branch_1");
+    checkout_branch(&repo, "some_branch_1");
+    create_new_file(&path_to_repo, "some_branch_1.txt", "contents");
+    commit_all(&repo, "message");
+
+    let args: Vec<&str> = vec!["init", "chain_name", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["disable", "chains", "are", "being", "migrated"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🛑 Disabled git chain in this repository: chains are being migrated
+To enable it again, run: git chain enable
+"#
+        .trim_start()
+    );
+
+    let output = run_git_command(&path_to_repo, vec!["config", "chain.disabled"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "chains are being migrated\n"
+    );
+
+    // every command stops, including the ones that only display chains
+    for args in [vec![], vec!["list"], vec!["rebase"]] {
+        let output = run_test_bin_expect_err(&path_to_repo, args);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            r#"
+🛑 git chain is disabled in this repository: chains are being migrated
+To enable it again, run: git chain enable
+"#
+            .trim_start()
+        );
+    }
+
+    let args: Vec<&str> = vec!["enable"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "✅ Enabled git chain in this repository.\n"
+    );
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("chain_name\n"));
+
+    let args: Vec<&str> = vec!["enable"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "git chain is not disabled in this repository.\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,243 @@
+pub mod common;
+use common::{
+    checkout_branch, create_branch, create_new_file, first_commit_all, generate_path_to_repo,
+    run_git_command, run_test_bin, run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+// The branches of the chain, ordered by their sort keys, along with the keys.
+fn chain_orders(path_to_repo: &std::path::Path) -> Vec<(String, String)> {
+    let output = run_git_command(
+        path_to_repo,
+        vec!["config", "--get-regexp", "^branch\\..*\\.chain-order$"],
+    );
+    let mut chain_orders: Vec<(String, String)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            let (key, chain_order) = line.split_once(' ').unwrap();
+            let branch_name = key
+                .strip_prefix("branch.")
+                .unwrap()
+                .strip_suffix(".chain-order")
+                .unwrap();
+            (branch_name.to_string(), chain_order.to_string())
+        })
+        .collect();
+    chain_orders.sort_by(|a, b| a.1.cmp(&b.1));
+    chain_orders
+}
+
+#[test]
+fn doctor_subcommand_renumber() {
+    let repo_name = "doctor_subcommand_renumber";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    create_branch(&repo, "first");
+    create_branch(&repo, "last");
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "first", "last"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // Insert branches right after the first branch and right before the branch after it, in turn,
+    // so that the same gap between sort keys is split over and over.
+    let mut expected = vec!["first".to_string(), "last".to_string()];
+    let mut renumbered = false;
+    for index in 0..200 {
+        let branch_name = format!("branch_{}", index);
+        create_branch(&repo, &branch_name);
+        checkout_branch(&repo, &branch_name);
+
+        let position = if index % 2 == 0 {
+            "--after=first".to_string()
+        } else {
+            format!("--before={}", expected[1])
+        };
+        expected.insert(1, branch_name.clone());
+
+        let args: Vec<&str> = vec!["init", "chain_name", "master", &position, "--debug"];
+        let output = run_test_bin(&path_to_repo, args);
+        assert!(output.status.success());
+        renumbered |= String::from_utf8_lossy(&output.stderr)
+            .contains("renumbering the sort keys of chain chain_name");
+    }
+    assert!(renumbered);
+
+    let actual = chain_orders(&path_to_repo);
+    let branch_names: Vec<String> = actual.iter().map(|(name, _)| name.clone()).collect();
+    assert_eq!(branch_names, expected);
+    // the keys are renumbered before they grow too long
+    assert!(actual
+        .iter()
+        .all(|(_, chain_order)| chain_order.len() <= 24));
+
+    let args: Vec<&str> = vec!["doctor", "--renumber"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "🔗 Renumbered the sort keys of the 202 branches of chain chain_name\n"
+    );
+
+    let actual = chain_orders(&path_to_repo);
+    let branch_names: Vec<String> = actual.iter().map(|(name, _)| name.clone()).collect();
+    assert_eq!(branch_names, expected);
+    let key_length = actual[0].1.len();
+    assert!(actual
+        .iter()
+        .all(|(_, chain_order)| chain_order.len() == key_length));
+
+    let args: Vec<&str> = vec!["doctor", "--renumber", "--chain", "chain_name"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "The sort keys of chain chain_name are already evenly spread.\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn doctor_subcommand_repair() {
+    let repo_name = "doctor_subcommand_repair";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    create_branch(&repo, "first");
+    create_branch(&repo, "second");
+    create_branch(&repo, "third");
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "first", "second", "third"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // Corrupt the metadata by hand.
+    let first_chain_order = chain_orders(&path_to_repo)[0].1.clone();
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "branch.third.chain-order", &first_chain_order],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "--unset", "branch.second.root-branch"],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "--add", "branch.first.root-branch", "master"],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "branch.third.root-branch", "main"],
+    );
+
+    // The chain is still loaded, and the problems are reported.
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin(&path_to_repo, args);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("first"));
+    assert!(stdout.contains("second"));
+    assert!(stdout.contains("third"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stderr,
+        format!(
+            r#"⚠️  Found problems in the metadata of chain chain_name:
+  - branch.first.root-branch has 2 values; the last one is used: master
+  - branch.second.root-branch is not set; the root branch of the chain is used.
+  - Branches first, third have the same sort key "{}"; they are ordered by name.
+  - branch.third.root-branch is main, but the root branch of the chain is master.
+To repair them, run: git chain doctor --chain chain_name
+"#,
+            first_chain_order
+        )
+    );
+
+    let args: Vec<&str> = vec!["doctor"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Found problems in the metadata of chain chain_name:\n"));
+    assert!(stdout.ends_with("🔗 Repaired the metadata of chain chain_name\n"));
+
+    let branch_names: Vec<String> = chain_orders(&path_to_repo)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(branch_names, vec!["first", "third", "second"]);
+    for branch_name in ["first", "second", "third"] {
+        let key = format!("branch.{}.root-branch", branch_name);
+        let output = run_git_command(&path_to_repo, vec!["config", "--get-all", &key]);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "master\n");
+    }
+
+    let args: Vec<&str> = vec!["doctor"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "✅ The metadata of chain chain_name is fine.\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn doctor_subcommand_missing_chain_order() {
+    let repo_name = "doctor_subcommand_missing_chain_order";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    create_branch(&repo, "first");
+    create_branch(&repo, "second");
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "first", "second"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "--unset", "branch.first.chain-order"],
+    );
+
+    // The branch is left out of the chain.
+    checkout_branch(&repo, "second");
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin(&path_to_repo, args);
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("first"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(
+        "branch.first.chain-order is not set, so branch first is left out of the chain."
+    ));
+    assert!(stderr.contains("To repair them, run: git chain doctor --chain chain_name"));
+
+    let args: Vec<&str> = vec!["doctor", "--chain", "chain_name"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with(
+        "🔗 Repaired the metadata of chain chain_name\nBranch first was added back as the last branch of the chain. To move it, run: git chain move\n"
+    ));
+
+    let branch_names: Vec<String> = chain_orders(&path_to_repo)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(branch_names, vec!["second", "first"]);
+
+    teardown_git_repo(repo_name);
+}
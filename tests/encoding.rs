@@ -0,0 +1,103 @@
+#![cfg(unix)]
+
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_git_command, run_test_bin, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+// caf\xe9 is café in Latin-1, which git accepts as a branch name.
+const NON_UTF8_BRANCH: &[u8] = b"caf\xe9";
+
+#[test]
+fn non_utf8_branch_names() {
+    let repo_name = "non_utf8_branch_names";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+    let non_utf8_branch = OsStr::from_bytes(NON_UTF8_BRANCH);
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    run_git_command(&path_to_repo, [OsStr::new("branch"), non_utf8_branch]);
+
+    // arguments are rejected by the argument parser
+    let args = [
+        OsStr::new("setup"),
+        OsStr::new("chain_name"),
+        OsStr::new("master"),
+        OsStr::new("some_branch_1"),
+        non_utf8_branch,
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("error: Invalid UTF-8 was detected in one or more arguments\n"));
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "some_branch_1"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // chain metadata of the branch, e.g. as set up by hand, is ignored with a warning
+    let key = [b"branch.".as_slice(), NON_UTF8_BRANCH, b".chain-name"].concat();
+    run_git_command(
+        &path_to_repo,
+        [
+            OsStr::new("config"),
+            OsStr::from_bytes(&key),
+            OsStr::new("chain_name"),
+        ],
+    );
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin(&path_to_repo, args);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "⚠️  Ignoring git config branch.caf\\xe9.chain-name: git chain only supports branch names that are valid UTF-8.\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+chain_name
+    ➜ some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    // the current branch is rejected with a clear error
+    run_git_command(
+        &path_to_repo,
+        [
+            OsStr::new("checkout"),
+            OsStr::new("--quiet"),
+            non_utf8_branch,
+        ],
+    );
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+error: Branch name is not valid UTF-8: caf\xe9
+git chain only supports branch names that are valid UTF-8.
+Rename the branch to use it with git chain, e.g.: git branch -m <new_branch_name>
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
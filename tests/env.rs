@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_bare_repo, generate_path_to_repo, get_current_branch_name, run_git_command,
+    run_test_bin_expect_ok, run_test_bin_with_env, setup_git_bare_repo, setup_git_repo,
+    teardown_git_bare_repo, teardown_git_repo,
+};
+
+#[test]
+fn env_var_overrides() {
+    let repo_name = "env_var_overrides";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["init", "chain_1", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // create and checkout new branch named some_branch_2
+    {
+        checkout_branch(&repo, "master");
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["init", "chain_2", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // GIT_CHAIN_CHAIN selects a chain other than the current branch's chain
+    checkout_branch(&repo, "master");
+
+    let args: Vec<&str> = vec!["backup"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_CHAIN", "chain_1")]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🎉 Successfully backed up chain: chain_1
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["backup", "--list"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_CHAIN", "chain_1")]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("/some_branch_1\n"));
+
+    let args: Vec<&str> = vec!["backup", "--list"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_CHAIN", "chain_2")]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "No backups of chain: chain_2\n"
+    );
+
+    // GIT_CHAIN_COLOR forces colored output
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_COLOR", "always")]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\u{1b}["));
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_COLOR", "sometimes")]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid color setting: sometimes"));
+
+    // GIT_CHAIN_FORCE_PUSH takes precedence over chain.forcePush
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--all", "--set-upstream", "origin"],
+    );
+    run_git_command(&path_to_repo, vec!["config", "chain.forcePush", "true"]);
+
+    let args: Vec<&str> = vec!["push"];
+    let output = run_test_bin_with_env(
+        &path_to_repo,
+        args,
+        vec![
+            ("GIT_CHAIN_CHAIN", "chain_1"),
+            ("GIT_CHAIN_FORCE_PUSH", "false"),
+        ],
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Pushed some_branch_1
+Pushed 1 branches.
+"#
+        .trim_start()
+    );
+
+    // invalid booleans are reported the same way for every environment variable
+    let args: Vec<&str> = vec!["push"];
+    let output = run_test_bin_with_env(
+        &path_to_repo,
+        args,
+        vec![
+            ("GIT_CHAIN_CHAIN", "chain_1"),
+            ("GIT_CHAIN_FORCE_PUSH", "maybe"),
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Invalid boolean value maybe for GIT_CHAIN_FORCE_PUSH"));
+
+    let args: Vec<&str> = vec!["push"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_CHAIN", "chain_1")]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Force pushed some_branch_1
+Pushed 1 branches.
+"#
+        .trim_start()
+    );
+
+    // --no-force-push takes precedence over chain.forcePush
+    let args: Vec<&str> = vec!["push", "--no-force-push"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_CHAIN", "chain_1")]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Pushed some_branch_1
+Pushed 1 branches.
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_bare_repo, generate_path_to_repo, run_git_command, run_test_bin_expect_ok,
+    setup_git_bare_repo, setup_git_repo, teardown_git_bare_repo, teardown_git_repo,
+};
+
+#[test]
+fn fetch_subcommand() {
+    let repo_name = "fetch_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--all", "--set-upstream", "origin"],
+    );
+
+    let rev_parse = |rev: &str| -> String {
+        let output = run_git_command(&path_to_repo, vec!["rev-parse", "--short=7", rev]);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let args: Vec<&str> = vec!["fetch"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Remote branches of chain chain_name are already up-to-date.\n"
+    );
+
+    // some_branch_1 moves on the remote, some_branch_2 is deleted on the remote,
+    // and an unrelated branch is created on the remote.
+    let old_some_branch_1 = rev_parse("origin/some_branch_1");
+    let old_some_branch_2 = rev_parse("origin/some_branch_2");
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "push",
+            "origin",
+            "some_branch_2:refs/heads/some_branch_1",
+            "--force",
+        ],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "origin", "--delete", "some_branch_2"],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "origin", "master:refs/heads/other_branch"],
+    );
+    for (tracking_ref, oid) in [
+        ("refs/remotes/origin/some_branch_1", &old_some_branch_1),
+        ("refs/remotes/origin/some_branch_2", &old_some_branch_2),
+    ] {
+        run_git_command(&path_to_repo, vec!["update-ref", tracking_ref, oid]);
+    }
+    run_git_command(
+        &path_to_repo,
+        vec!["update-ref", "-d", "refs/remotes/origin/other_branch"],
+    );
+
+    let args: Vec<&str> = vec!["fetch"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            r#"
+origin/some_branch_1 {}..{}
+origin/some_branch_2 (deleted on the remote)
+
+✅ Updated 2 remote branches of chain chain_name
+"#,
+            old_some_branch_1,
+            rev_parse("some_branch_2")
+        )
+        .trim_start()
+    );
+
+    assert_eq!(
+        rev_parse("origin/some_branch_1"),
+        rev_parse("some_branch_2")
+    );
+
+    // only the remote branches of the chain are fetched
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["branch", "--remotes", "--format=%(refname:short)"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "origin/master\norigin/some_branch_1\n"
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
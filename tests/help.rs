@@ -0,0 +1,108 @@
+pub mod common;
+use common::{
+    generate_path_to_repo, run_test_bin, run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn help_subcommand() {
+    let repo_name = "help_subcommand";
+    let _repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    // the guides are listed after the subcommands
+    let args: Vec<&str> = vec!["help"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("SUBCOMMANDS:"));
+    assert!(stdout.ends_with(
+        r#"
+GUIDES:
+    stacking         Stack branches with a chain, from setting it up to landing it.
+    conflicts        Recover from conflicts and mistakes while rebasing a chain.
+    pull-requests    Publish a chain as a stack of pull requests, and keep it up to date.
+
+Run git chain help <topic> to read a guide.
+"#
+    ));
+
+    // guides are not paged when the output is not a terminal
+    let args: Vec<&str> = vec!["help", "conflicts"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("RECOVERING FROM CONFLICTS\n"));
+    assert!(stdout.contains("    git chain rebase --worktree\n"));
+
+    // the help of a subcommand, or of its alias
+    for subcommand in ["rebase", "rb"] {
+        let args: Vec<&str> = vec!["help", subcommand];
+        let output = run_test_bin_expect_ok(&path_to_repo, args);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("Rebase all branches for the current chain."));
+        assert!(stdout.contains(
+            r#"
+EXAMPLES:
+    # Rebase each branch of the chain onto its parent branch
+    git chain rebase
+"#
+        ));
+    }
+
+    // unknown topics
+    let args: Vec<&str> = vec!["help", "stacked"];
+    let output = run_test_bin(&path_to_repo, args);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("The subcommand 'stacked' wasn't recognized"));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn help_outside_of_repository() {
+    let args: Vec<&str> = vec!["help", "stacking"];
+    let output = run_test_bin_expect_ok(std::env::temp_dir(), args);
+    assert!(
+        String::from_utf8_lossy(&output.stdout).starts_with("STACKING BRANCHES WITH GIT CHAIN\n")
+    );
+}
+
+#[test]
+fn every_subcommand_has_examples() {
+    let repo_name = "every_subcommand_has_examples";
+    let _repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let args: Vec<&str> = vec!["--help"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let subcommands: Vec<&str> = stdout
+        .split("SUBCOMMANDS:\n")
+        .nth(1)
+        .unwrap()
+        .split("\n\n")
+        .next()
+        .unwrap()
+        .lines()
+        .filter(|line| line.starts_with("    ") && !line.starts_with("     "))
+        .map(|line| line.split_whitespace().next().unwrap())
+        .collect();
+    assert!(subcommands.len() > 40);
+
+    for subcommand in subcommands {
+        let args: Vec<&str> = vec![subcommand, "--help"];
+        let output = run_test_bin_expect_ok(&path_to_repo, args);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.contains("\nEXAMPLES:\n    # "),
+            "no examples for {}",
+            subcommand
+        );
+        assert!(
+            stdout.contains(&format!("\n    git chain {}", subcommand)),
+            "examples of {} do not run it",
+            subcommand
+        );
+    }
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,142 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin_expect_err,
+    run_test_bin_expect_ok, run_test_bin_for_rebase, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn history_subcommand() {
+    let repo_name = "history_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    let args: Vec<&str> = vec!["history"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "No history.\n");
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let rev_parse = |rev: &str| -> String {
+        let output = run_git_command(&path_to_repo, vec!["rev-parse", "--short=7", rev]);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let some_branch_1_before = rev_parse("some_branch_1");
+    let some_branch_2_before = rev_parse("some_branch_2");
+
+    // add a commit to master
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "file_master.txt", "contents master");
+    commit_all(&repo, "message");
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["rebase"];
+    run_test_bin_for_rebase(&path_to_repo, args);
+
+    // read-only commands are not recorded
+    let args: Vec<&str> = vec!["status"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["history"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].ends_with(" git chain rebase"));
+    assert_eq!(
+        lines[1],
+        format!(
+            "  some_branch_1 {} → {}",
+            some_branch_1_before,
+            rev_parse("some_branch_1")
+        )
+    );
+    assert_eq!(
+        lines[2],
+        format!(
+            "  some_branch_2 {} → {}",
+            some_branch_2_before,
+            rev_parse("some_branch_2")
+        )
+    );
+    assert!(lines[3].ends_with(" git chain setup chain_name master some_branch_1 some_branch_2"));
+
+    let args: Vec<&str> = vec!["history", "-n", "1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 3);
+
+    // commands that exit with an error are recorded as well
+    let args: Vec<&str> = vec!["rebase", "--chain", "does_not_exist"];
+    run_test_bin_expect_err(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["history", "-n", "1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.ends_with(" git chain rebase --chain does_not_exist\n"));
+
+    // undo restores the branches moved by the rebase, skipping the failed command
+    let some_branch_1_after = rev_parse("some_branch_1");
+    let some_branch_2_after = rev_parse("some_branch_2");
+
+    let args: Vec<&str> = vec!["undo"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!(
+        "Restored branch some_branch_1 to {}\n",
+        some_branch_1_before
+    )));
+    assert!(stdout.contains(&format!(
+        "Restored branch some_branch_2 to {}\n",
+        some_branch_2_before
+    )));
+    assert!(stdout.ends_with("✅ Undid git chain rebase\n"));
+
+    assert_eq!(rev_parse("some_branch_1"), some_branch_1_before);
+    assert_eq!(rev_parse("some_branch_2"), some_branch_2_before);
+    assert_eq!(rev_parse("HEAD"), some_branch_2_before);
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+    // undo is recorded too, so undoing it redoes the rebase
+    let args: Vec<&str> = vec!["undo"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with("✅ Undid git chain undo\n"));
+
+    assert_eq!(rev_parse("some_branch_1"), some_branch_1_after);
+    assert_eq!(rev_parse("some_branch_2"), some_branch_2_after);
+
+    // branches that moved since are not reset
+    create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+    commit_all(&repo, "message");
+
+    let args: Vec<&str> = vec!["undo"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Unable to undo git chain undo: branch some_branch_2 moved since."));
+
+    teardown_git_repo(repo_name);
+}
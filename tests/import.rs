@@ -0,0 +1,163 @@
+use std::fs;
+use std::path::PathBuf;
+
+pub mod common;
+use common::{
+    branch_exists, checkout_branch, commit_all, create_branch, create_new_file,
+    delete_local_branch, first_commit_all, generate_path_to_bare_repo, generate_path_to_repo,
+    run_git_command, run_test_bin_with_env, setup_git_bare_repo, setup_git_repo,
+    teardown_git_bare_repo, teardown_git_repo,
+};
+
+// A stand-in for gh, with a stack of three pull requests on top of master.
+const FAKE_GH_STACK: &str = r#"#!/bin/sh
+echo "$@" >> "$GH_LOG"
+case "$1 $2 $3" in
+  "pr view 1"|"pr view some_branch_1")
+    printf '1\tsome_branch_1\tmaster\tOPEN\n'
+    ;;
+  "pr view 2"|"pr view some_branch_2")
+    printf '2\tsome_branch_2\tsome_branch_1\tOPEN\n'
+    ;;
+  "pr view 3"|"pr view some_branch_3")
+    printf '3\tsome_branch_3\tsome_branch_2\tOPEN\n'
+    ;;
+  "pr view "*)
+    echo "no pull requests found for branch \"$3\"" >&2
+    exit 1
+    ;;
+  "pr list --base")
+    case "$4" in
+      some_branch_1) printf '2\tsome_branch_2\tsome_branch_1\tOPEN\n' ;;
+      some_branch_2) printf '3\tsome_branch_3\tsome_branch_2\tOPEN\n' ;;
+    esac
+    ;;
+esac
+"#;
+
+#[cfg(unix)]
+#[test]
+fn import_subcommand_from_prs() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "import_subcommand_from_prs";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2", "some_branch_3"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "push",
+            "origin",
+            "master",
+            "some_branch_1",
+            "some_branch_2",
+            "some_branch_3",
+        ],
+    );
+
+    // a reviewer only has the first branch of the stack
+    checkout_branch(&repo, "some_branch_1");
+    delete_local_branch(&repo, "some_branch_2");
+    delete_local_branch(&repo, "some_branch_3");
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH_STACK).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("gh.log");
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("GH_LOG", path_to_log.to_str().unwrap()),
+    ];
+
+    // starting from the pull request in the middle of the stack
+    let args: Vec<&str> = vec!["import", "--from-prs", "2", "--chain", "chain_name"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Found 3 pull requests into master: #1 some_branch_1, #2 some_branch_2, #3 some_branch_3
+Created branch some_branch_2 from origin/some_branch_2
+Created branch some_branch_3 from origin/some_branch_3
+🔗 Succesfully imported chain chain_name from 3 pull requests
+
+chain_name
+      some_branch_3 ⦁ 1 ahead
+      some_branch_2 ⦁ 1 ahead
+    ➜ some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    assert!(branch_exists(&repo, "some_branch_3"));
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["rev-parse", "--abbrev-ref", "some_branch_3@{upstream}"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "origin/some_branch_3\n"
+    );
+
+    // the branches are already part of a chain
+    let args: Vec<&str> = vec!["import", "--from-prs", "--chain", "other_chain"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 Branch some_branch_1 is already part of the chain chain_name\n"
+    );
+
+    // no pull request
+    let args: Vec<&str> = vec!["import", "--from-prs", "master"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 No pull request found for: master\n"
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
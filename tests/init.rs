@@ -31,13 +31,36 @@ fn init_subcommand() {
         .contains("The following required arguments were not provided"));
     assert!(String::from_utf8_lossy(&output.stderr).contains("<chain_name>"));
 
-    // init subcommand with chain name, but no root branch
+    // init subcommand with chain name, but no root branch, and the default branch does not exist
+    repo.config()
+        .unwrap()
+        .open_level(ConfigLevel::Local)
+        .unwrap()
+        .set_str("init.defaultBranch", "does_not_exist")
+        .unwrap();
+
     let args: Vec<&str> = vec!["init", "chain_name"];
     let output = run_test_bin_expect_err(&path_to_repo, args);
 
     assert!(String::from_utf8_lossy(&output.stdout).is_empty());
     assert!(String::from_utf8_lossy(&output.stderr).contains("Please provide the root branch."));
 
+    // init subcommand with chain name, but no root branch, and detect the default branch
+    repo.config()
+        .unwrap()
+        .open_level(ConfigLevel::Local)
+        .unwrap()
+        .set_str("init.defaultBranch", "master")
+        .unwrap();
+
+    let args: Vec<&str> = vec!["init", "chain_name"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("Using default branch master as the root branch."));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Current branch cannot be the root branch: master"));
+
     // init subcommand with chain name, and use current branch as the root branch
     assert_eq!(&get_current_branch_name(&repo), "master");
 
@@ -350,3 +373,71 @@ chain_name
 
     teardown_git_repo(repo_name);
 }
+
+#[test]
+fn init_subcommand_all_descendants() {
+    let repo_name = "init_subcommand_all_descendants";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // some_branch_1 <- some_branch_2 <- some_branch_3
+    for branch_name in ["some_branch_1", "some_branch_2", "some_branch_3"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    // an unrelated branch off master is not a descendant
+    checkout_branch(&repo, "master");
+    create_branch(&repo, "unrelated");
+    checkout_branch(&repo, "unrelated");
+    create_new_file(&path_to_repo, "unrelated.txt", "contents");
+    commit_all(&repo, "message");
+
+    checkout_branch(&repo, "some_branch_1");
+
+    let args: Vec<&str> = vec!["init", "chain_name", "master", "--all-descendants"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🔗 Succesfully set up branch: some_branch_1
+🔗 Succesfully set up descendant branches: some_branch_2, some_branch_3
+
+chain_name
+      some_branch_3 ⦁ 1 ahead
+      some_branch_2 ⦁ 1 ahead
+    ➜ some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    // descendants that are not stacked on one another cannot form a chain
+    let args: Vec<&str> = vec!["remove", "--chain", "chain_name", "--yes"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "some_branch_2");
+    create_branch(&repo, "some_branch_2b");
+    checkout_branch(&repo, "some_branch_2b");
+    create_new_file(&path_to_repo, "some_branch_2b.txt", "contents");
+    commit_all(&repo, "message");
+    checkout_branch(&repo, "some_branch_1");
+
+    let args: Vec<&str> = vec!["init", "chain_name", "master", "--all-descendants"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Branches some_branch_2b and some_branch_3 are not stacked on one another."));
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,213 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn lint_subcommand() {
+    let repo_name = "lint_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "feat: add file_1 (ABC-123)");
+    };
+
+    // create and checkout new branch named some_branch_2
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "add file_2");
+    };
+
+    // run git chain setup
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // git chain lint
+    let args: Vec<&str> = vec!["lint"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+some_branch_1
+  ✅ No issues
+some_branch_2
+  ✅ No issues
+
+🎉 All commit messages in chain chain_name passed
+"#
+        .trim_start()
+    );
+
+    // git chain lint --conventional
+    let args: Vec<&str> = vec![
+        "lint",
+        "--conventional",
+        "--ticket-pattern",
+        "[A-Z]+-[0-9]+",
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("some_branch_1\n  ✅ No issues\nsome_branch_2\n"));
+    assert!(stdout.contains(" add file_2\n"));
+    assert!(stdout.contains("⚠️  Subject does not follow Conventional Commits"));
+    assert!(stdout.contains("⚠️  Message does not reference a ticket matching: [A-Z]+-[0-9]+"));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("🛑 Found 2 commit message issues in chain: chain_name"));
+
+    // git chain lint --max-subject-length
+    let args: Vec<&str> = vec!["lint", "--max-subject-length", "12"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("⚠️  Subject is longer than 12 characters (26)"));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("🛑 Found 1 commit message issues in chain: chain_name"));
+
+    // lint rules can be set with git config
+    let mut config = repo.config().unwrap();
+    config.set_str("chain.lintPattern", "^feat").unwrap();
+
+    let args: Vec<&str> = vec!["lint"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("⚠️  Subject does not match pattern: ^feat"));
+
+    // invalid pattern
+    let args: Vec<&str> = vec!["lint", "--pattern", "("];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid lint pattern: ("));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn lint_subcommand_fix() {
+    let repo_name = "lint_subcommand_fix";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2", "some_branch_3"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, &format!("add {}", branch_name));
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // the parent of some_branch_3 is missing, and some_branch_1 has no place in the chain
+    checkout_branch(&repo, "some_branch_3");
+    run_git_command(
+        &path_to_repo,
+        vec!["update-ref", "-d", "refs/heads/some_branch_2"],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "--unset", "branch.some_branch_1.chain-order"],
+    );
+
+    let args: Vec<&str> = vec!["lint"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with(
+        r#"
+Chain metadata
+  ⚠️  Branch some_branch_2 does not exist: it is the parent of branch some_branch_3
+  ⚠️  branch.some_branch_1.chain-order is not set, so branch some_branch_1 is left out of the chain.
+some_branch_3
+  ✅ No issues
+"#
+        .trim_start()
+    ));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Found 2 issues in the metadata of chain: chain_name
+To repair them, run: git chain lint --fix --chain chain_name
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["lint", "--fix"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Chain metadata
+  ⚠️  Branch some_branch_2 does not exist: it is the parent of branch some_branch_3
+  ⚠️  branch.some_branch_1.chain-order is not set, so branch some_branch_1 is left out of the chain.
+
+Found problems in the metadata of chain chain_name:
+  - branch.some_branch_1.chain-order is not set, so branch some_branch_1 is left out of the chain.
+🔗 Repaired the metadata of chain chain_name
+Branch some_branch_1 was added back as the last branch of the chain. To move it, run: git chain move
+Removed branch some_branch_2 from the chain, as it does not exist
+
+some_branch_3
+  ✅ No issues
+some_branch_1
+  ✅ No issues
+
+🎉 All commit messages in chain chain_name passed
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["lint"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    teardown_git_repo(repo_name);
+}
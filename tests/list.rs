@@ -1,8 +1,8 @@
 pub mod common;
 use common::{
     checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
-    generate_path_to_repo, get_current_branch_name, run_test_bin_expect_ok, setup_git_repo,
-    teardown_git_repo,
+    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin_expect_ok,
+    run_test_bin_with_env, setup_git_repo, teardown_git_repo,
 };
 
 #[test]
@@ -123,5 +123,279 @@ chain_name_2
         .trim_start()
     );
 
+    // output that is not a terminal does not go through the pager
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_PAGER", "false")]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("chain_name\n"));
+
+    let args: Vec<&str> = vec!["--no-pager", "list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("chain_name\n"));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn list_subcommand_tree() {
+    let repo_name = "list_subcommand_tree";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // chain_1: master <- api <- ui
+    // chain_2: api <- api_docs
+    // chain_3: master <- hotfix
+    for (parent, branch_name) in [
+        ("master", "api"),
+        ("api", "ui"),
+        ("api", "api_docs"),
+        ("master", "hotfix"),
+    ] {
+        checkout_branch(&repo, parent);
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec!["setup", "chain_1", "master", "api", "ui"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    let args: Vec<&str> = vec!["setup", "chain_2", "api", "api_docs"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    let args: Vec<&str> = vec!["setup", "chain_3", "master", "hotfix"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "api_docs");
+
+    let args: Vec<&str> = vec!["list", "--tree"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+master (root branch)
+├─ chain_1
+│  ├─ api ⦁ 1 ahead
+│  │  └─ chain_2
+│  │     └─ ➜ api_docs ⦁ 1 ahead
+│  └─ ui ⦁ 1 ahead
+└─ chain_3
+   └─ hotfix ⦁ 1 ahead
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn list_subcommand_landed() {
+    let repo_name = "list_subcommand_landed";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // api has 1 commit, ui has 2 commits, docs has 1 commit
+    for (parent, branch_name, file_names) in [
+        ("master", "api", vec!["api.txt"]),
+        ("api", "ui", vec!["ui_1.txt", "ui_2.txt"]),
+        ("ui", "docs", vec!["docs.txt"]),
+    ] {
+        checkout_branch(&repo, parent);
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        for file_name in file_names {
+            create_new_file(&path_to_repo, file_name, "contents");
+            commit_all(&repo, file_name);
+        }
+    }
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "api", "ui", "docs"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // nothing landed yet
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+chain_name
+    ➜ docs ⦁ 1 ahead
+      ui ⦁ 2 ahead
+      api ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    // api is squashed and merged, and the first commit of ui is cherry-picked onto master
+    checkout_branch(&repo, "master");
+    run_git_command(&path_to_repo, vec!["merge", "--squash", "api"]);
+    run_git_command(&path_to_repo, vec!["commit", "-m", "api (#1)"]);
+    run_git_command(&path_to_repo, vec!["cherry-pick", "ui~1"]);
+    checkout_branch(&repo, "docs");
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+chain_name ⦁ 50% landed (1 of 3 branches, 2 of 4 commits)
+    ➜ docs ⦁ 1 ahead
+      ui ⦁ 2 ahead
+      api ⦁ 1 ahead ⦁ 2 behind
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn list_subcommand_merge_commits() {
+    let repo_name = "list_subcommand_merge_commits";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for (parent, branch_name, file_name) in [("master", "api", "api.txt"), ("api", "ui", "ui.txt")]
+    {
+        checkout_branch(&repo, parent);
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, file_name, "contents");
+        commit_all(&repo, file_name);
+    }
+
+    // master moves on, and is merged into ui but not into api
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "master.txt", "contents");
+    commit_all(&repo, "master");
+    checkout_branch(&repo, "ui");
+    run_git_command(
+        &path_to_repo,
+        vec!["merge", "--quiet", "--no-edit", "master"],
+    );
+
+    // docs has no commits of its own
+    create_branch(&repo, "docs");
+    checkout_branch(&repo, "docs");
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "api", "ui", "docs"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // the counts of each branch match git rev-list --left-right --count <branch>...<parent>
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+chain_name
+    ➜ docs
+      ui ⦁ 3 ahead
+      api ⦁ 1 ahead ⦁ 1 behind
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["status", "--short"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+➜ docs
+  ui +3
+  api +1 -1
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn list_subcommand_verbose() {
+    let repo_name = "list_subcommand_verbose";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    // api was last worked on 3 weeks ago by someone else, ui 2 days ago
+    for (parent, branch_name, secs_ago, author) in [
+        ("master", "api", 21 * 86400, "Other Name <other@email>"),
+        ("api", "ui", 2 * 86400, "name <email>"),
+    ] {
+        checkout_branch(&repo, parent);
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        run_git_command(&path_to_repo, vec!["add", "."]);
+        let date = format!("{} +0000", now - secs_ago);
+        run_git_command(
+            &path_to_repo,
+            vec![
+                "commit",
+                "--quiet",
+                "--message",
+                branch_name,
+                "--date",
+                &date,
+                "--author",
+                author,
+            ],
+        );
+    }
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "api", "ui"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["list", "-v"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+chain_name ⦁ last commit 2 days ago
+    ➜ ui ⦁ 1 ahead ⦁ 2 days ago by name
+      api ⦁ 1 ahead ⦁ 3 weeks ago by Other Name
+      master (root branch)
+"#
+        .trim_start()
+    );
+
     teardown_git_repo(repo_name);
 }
@@ -0,0 +1,86 @@
+use std::fs;
+use std::process;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_test_bin_expect_err, run_test_bin_expect_ok, setup_git_repo,
+    teardown_git_repo,
+};
+
+#[test]
+fn lock_mutating_subcommands() {
+    let repo_name = "lock_mutating_subcommands";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["init", "chain_name", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // the lock is released after each command
+    let path_to_lock = path_to_repo.join(".git").join("chain").join("lock");
+    assert!(!path_to_lock.exists());
+
+    // lock held by a running process
+    fs::write(&path_to_lock, format!("{}\n", process::id())).unwrap();
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&format!(
+        "Another git chain command is running on this repository (pid {}).",
+        process::id()
+    )));
+
+    // commands that only read are not blocked
+    let args: Vec<&str> = vec!["status"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["rebase", "--force-unlock"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(!path_to_lock.exists());
+
+    // stale lock of a process that is no longer running
+    fs::write(&path_to_lock, "2147483647\n").unwrap();
+
+    let args: Vec<&str> = vec!["rebase"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(!path_to_lock.exists());
+
+    // the lock is released when a command exits with an error
+    let args: Vec<&str> = vec!["rebase", "--chain", "does_not_exist"];
+    run_test_bin_expect_err(&path_to_repo, args);
+    assert!(!path_to_lock.exists());
+
+    // stale lock without a pid
+    fs::write(&path_to_lock, "").unwrap();
+
+    let args: Vec<&str> = vec!["rebase"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(!path_to_lock.exists());
+    assert_eq!(
+        fs::read_dir(path_to_lock.parent().unwrap())
+            .unwrap()
+            .filter_map(|entry| entry.unwrap().file_name().into_string().ok())
+            .filter(|file_name| file_name.starts_with("lock."))
+            .count(),
+        0
+    );
+
+    teardown_git_repo(repo_name);
+}
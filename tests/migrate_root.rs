@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_bare_repo, generate_path_to_repo, run_git_command, run_test_bin_expect_ok,
+    setup_git_bare_repo, setup_git_repo, teardown_git_bare_repo, teardown_git_repo,
+};
+
+#[test]
+fn migrate_root_subcommand() {
+    let repo_name = "migrate_root_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    run_git_command(&path_to_repo, vec!["push", "origin", "master"]);
+    run_git_command(
+        &path_to_bare_repo,
+        vec!["symbolic-ref", "HEAD", "refs/heads/master"],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec!["remote", "set-head", "origin", "master"],
+    );
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        checkout_branch(&repo, "master");
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec!["setup", "chain_1", "master", "some_branch_1"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    let args: Vec<&str> = vec!["setup", "chain_2", "origin/master", "some_branch_2"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["migrate-root"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ The default branch of remote origin is still master.
+To update chains rooted at another branch, run: git chain migrate-root --from <branch>
+"#
+        .trim_start()
+    );
+
+    // the default branch of the remote is renamed from master to main
+    run_git_command(&path_to_repo, vec!["push", "origin", "master:main"]);
+    run_git_command(
+        &path_to_bare_repo,
+        vec!["symbolic-ref", "HEAD", "refs/heads/main"],
+    );
+    run_git_command(&path_to_repo, vec!["push", "origin", "--delete", "master"]);
+    run_git_command(&path_to_repo, vec!["fetch", "origin"]);
+    run_git_command(&path_to_repo, vec!["branch", "main", "origin/main"]);
+
+    let args: Vec<&str> = vec!["migrate-root", "--dry-run"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+The default branch of remote origin changed from master to main.
+
+chain_1: master ➜ main
+chain_2: origin/master ➜ origin/main
+
+This was a dry-run, no chains were updated!
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["migrate-root", "--yes"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .ends_with("✅ Updated the root branch of 2 chains.\n"));
+
+    // the root branch metadata of each branch is rewritten
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["config", "branch.some_branch_1.root-branch"],
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "main\n");
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["config", "branch.some_branch_2.root-branch"],
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "origin/main\n");
+
+    // refs/remotes/origin/HEAD is updated, so the rename is only detected once
+    let args: Vec<&str> = vec!["migrate-root"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .starts_with("✅ The default branch of remote origin is still main."));
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
@@ -1,7 +1,11 @@
+use std::fs;
+
 pub mod common;
 use common::{
-    create_new_file, first_commit_all, generate_path_to_repo, get_current_branch_name,
-    run_test_bin_expect_err, setup_git_repo, teardown_git_repo,
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin_expect_err,
+    run_test_bin_expect_ok, run_test_bin_for_rebase, run_test_bin_with_env, setup_git_repo,
+    teardown_git_repo,
 };
 
 #[test]
@@ -29,3 +33,478 @@ fn no_subcommand() {
 
     teardown_git_repo(repo_name);
 }
+
+#[test]
+fn operation_in_progress() {
+    let repo_name = "operation_in_progress";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["init", "chain_name", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // pretend a cherry-pick is in progress
+    let head_oid = repo.head().unwrap().target().unwrap();
+    fs::write(
+        repo.path().join("CHERRY_PICK_HEAD"),
+        format!("{}\n", head_oid),
+    )
+    .unwrap();
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout).is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("🛑 A cherry-pick is in progress."));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("git cherry-pick --continue or git cherry-pick --abort"));
+
+    // read-only subcommands still work
+    let args: Vec<&str> = vec!["list"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn version_json() {
+    let args: Vec<&str> = vec!["--version"];
+    let output = run_test_bin_expect_ok(".", args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("git-chain {}\n", env!("CARGO_PKG_VERSION"))
+    );
+
+    let args: Vec<&str> = vec!["--version", "--json"];
+    let output = run_test_bin_expect_ok(".", args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.starts_with("{\n"));
+    assert!(stdout.contains(&format!(r#""version": "{}","#, env!("CARGO_PKG_VERSION"))));
+    assert!(stdout.contains(r#""commit": ""#));
+    assert!(stdout.contains(r#""libgit2": ""#));
+    assert!(stdout.contains(r#""features": ["#));
+
+    // --json requires --version
+    let args: Vec<&str> = vec!["--json"];
+    run_test_bin_expect_err(".", args);
+}
+
+#[test]
+fn detached_head() {
+    let repo_name = "detached_head";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["init", "chain_name", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(&path_to_repo, vec!["checkout", "--detach"]);
+
+    // commands that need a current branch explain why they cannot run
+    let args: Vec<&str> = vec![];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("HEAD is detached."));
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("HEAD is detached."));
+
+    // read-only commands display the chains without a current branch
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+chain_name
+      some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    // the chain can be given explicitly
+    let args: Vec<&str> = vec!["backup"];
+    let output =
+        run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_CHAIN", "chain_name")]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "🎉 Successfully backed up chain: chain_name\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn subcommand_aliases() {
+    let repo_name = "subcommand_aliases";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["init", "chain_name", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // built-in aliases
+    let args: Vec<&str> = vec!["list"];
+    let list_output = run_test_bin_expect_ok(&path_to_repo, args);
+    let args: Vec<&str> = vec!["ls"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(output.stdout, list_output.stdout);
+
+    let args: Vec<&str> = vec!["st", "--short"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "➜ some_branch_1 +1\n"
+    );
+
+    // user-defined aliases, with extra arguments
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "chain.alias.s", "status --short"],
+    );
+    let args: Vec<&str> = vec!["--no-input", "s"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "➜ some_branch_1 +1\n"
+    );
+
+    run_git_command(&path_to_repo, vec!["config", "chain.alias.d", "diff"]);
+    let args: Vec<&str> = vec!["d", "--stat"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("file_1.txt | 1 +"));
+
+    // aliases cannot override subcommands
+    run_git_command(&path_to_repo, vec!["config", "chain.alias.list", "status"]);
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(output.stdout, list_output.stdout);
+
+    let args: Vec<&str> = vec!["does_not_exist"];
+    run_test_bin_expect_err(&path_to_repo, args);
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn debug_output() {
+    let repo_name = "debug_output";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "some_branch_1"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "file_master.txt", "contents master");
+        commit_all(&repo, "master commit");
+        checkout_branch(&repo, "some_branch_1");
+    };
+
+    // without --debug, nothing is logged
+    let args: Vec<&str> = vec!["status"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // git commands and decisions are logged to stderr
+    let args: Vec<&str> = vec!["rebase", "--debug"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![]);
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[debug] git chain rebase --debug\n"));
+    assert!(stderr.contains("[debug] ran: git merge-base --fork-point master some_branch_1"));
+    assert!(
+        stderr.contains("[debug] squash and merge of some_branch_1 onto master detected: false")
+    );
+    assert!(stderr.contains("[debug] ran: git rebase --keep-empty --onto master "));
+    assert!(stderr.contains("[debug] set git config branch.some_branch_1.chain-base = "));
+
+    // the debug output can be appended to a file instead
+    let debug_file = path_to_repo.canonicalize().unwrap().join("debug.log");
+    let args: Vec<&str> = vec!["status", "--debug-file", debug_file.to_str().unwrap()];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("On branch: some_branch_1"));
+
+    let contents = fs::read_to_string(&debug_file).unwrap();
+    assert!(contents.starts_with("[debug] git chain status --debug-file "));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn read_only_mode() {
+    let repo_name = "read_only_mode";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "some_branch_1"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let expected_stderr = r#"
+🛑 Unable to run git chain rebase: the repository is in read-only mode.
+Read-only mode is enabled by --read-only, GIT_CHAIN_READ_ONLY or chain.readOnly. Commands that display chains (e.g. status, list) still work.
+"#
+    .trim_start();
+
+    let args: Vec<&str> = vec!["rebase", "--read-only"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), expected_stderr);
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_READ_ONLY", "true")]);
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), expected_stderr);
+
+    run_git_command(&path_to_repo, vec!["config", "chain.readOnly", "true"]);
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), expected_stderr);
+
+    // commands that only display chains keep working
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("On branch: some_branch_1"));
+
+    let args: Vec<&str> = vec!["list"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["scope", "--read-only"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Chain has no scope: chain_name\n"
+    );
+
+    let args: Vec<&str> = vec!["scope", "--read-only", "src"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("🛑 Unable to run git chain scope: the repository is in read-only mode."));
+
+    let args: Vec<&str> = vec!["backup", "--read-only", "--list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "No backups of chain: chain_name\n"
+    );
+
+    let args: Vec<&str> = vec!["backup", "--read-only"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("🛑 Unable to run git chain backup: the repository is in read-only mode."));
+
+    // the environment variable overrides git config
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_READ_ONLY", "false")]);
+    assert!(output.status.success());
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn chain_option() {
+    let repo_name = "chain_option";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_1b"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_1",
+        "master",
+        "some_branch_1",
+        "some_branch_1b",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "master");
+    create_branch(&repo, "some_branch_2");
+    checkout_branch(&repo, "some_branch_2");
+    create_new_file(&path_to_repo, "some_branch_2.txt", "contents");
+    commit_all(&repo, "message");
+
+    let args: Vec<&str> = vec!["init", "chain_2", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // the chain of another branch, from a branch that is not part of any chain
+    checkout_branch(&repo, "master");
+
+    let args: Vec<&str> = vec!["backup", "--chain", "chain_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "🎉 Successfully backed up chain: chain_1\n"
+    );
+
+    // --chain takes precedence over GIT_CHAIN_CHAIN
+    let args: Vec<&str> = vec!["backup", "--list", "--chain", "chain_2"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_CHAIN", "chain_1")]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "No backups of chain: chain_2\n"
+    );
+
+    let args: Vec<&str> = vec!["first", "--chain", "chain_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Switched to branch: some_branch_1\n"
+    );
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+
+    // next and prev move relative to the current branch, which must be part of the chain
+    let args: Vec<&str> = vec!["next", "--chain", "chain_2"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 Branch some_branch_1 is not part of chain chain_2.\n"
+    );
+
+    let args: Vec<&str> = vec!["next", "--chain", "chain_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Switched to branch: some_branch_1b\n"
+    );
+
+    let args: Vec<&str> = vec!["last", "--chain", "chain_2"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Switched to branch: some_branch_2\n"
+    );
+
+    let args: Vec<&str> = vec!["first", "--chain", "chain_3"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Chain does not exist: chain_3"));
+
+    // rebase another chain onto new commits of master, from a branch of chain_2
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "master.txt", "contents");
+    commit_all(&repo, "new commit");
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["rebase", "--chain", "chain_1"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("🎉 Successfully rebased chain chain_1")
+    );
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["merge-base", "--is-ancestor", "master", "some_branch_1b"],
+    );
+    assert!(output.status.success());
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["merge-base", "--is-ancestor", "master", "some_branch_2"],
+    );
+    assert!(!output.status.success());
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,110 @@
+pub mod common;
+use common::{
+    create_new_file, first_commit_all, generate_path_to_repo, get_current_branch_name,
+    run_git_command, run_test_bin_expect_err, run_test_bin_expect_ok, setup_git_repo,
+    teardown_git_repo,
+};
+
+#[test]
+fn new_stack_subcommand() {
+    let repo_name = "new_stack_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // an undefined template
+    let args: Vec<&str> = vec!["new-stack", "search"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Template is not defined: default
+To define it, run: git config chain.template.default.branches "{name}/api {name}/ui"
+"#
+        .trim_start()
+    );
+
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "config",
+            "chain.template.default.branches",
+            "{name}/api {name}/ui {name}/tests",
+        ],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "chain.template.default.root", "master"],
+    );
+
+    let args: Vec<&str> = vec!["new-stack", "search"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🔗 Succesfully created chain search from template default
+
+search
+      search/tests
+      search/ui
+    ➜ search/api
+      master (root branch)
+"#
+        .trim_start()
+    );
+    assert_eq!(&get_current_branch_name(&repo), "search/api");
+
+    // the chain already exists
+    let args: Vec<&str> = vec!["new-stack", "search"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 Chain already exists: search\n"
+    );
+
+    // a named template with its own root
+    run_git_command(&path_to_repo, vec!["branch", "release"]);
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "chain.template.fix.branches", "fix/{name}"],
+    );
+
+    let args: Vec<&str> = vec![
+        "new-stack",
+        "login",
+        "--template",
+        "fix",
+        "--root",
+        "release",
+    ];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🔗 Succesfully created chain login from template fix
+
+login
+    ➜ fix/login
+      release (root branch)
+"#
+        .trim_start()
+    );
+
+    // branches of the template that already exist are not overwritten
+    run_git_command(&path_to_repo, vec!["branch", "billing/ui"]);
+    let args: Vec<&str> = vec!["new-stack", "billing"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 Branch already exists: billing/ui\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
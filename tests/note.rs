@@ -0,0 +1,129 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_test_bin_expect_err, run_test_bin_expect_ok, setup_git_repo,
+    teardown_git_repo,
+};
+
+#[test]
+fn note_subcommand() {
+    let repo_name = "note_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["note"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Branch has no note: some_branch_2\n"
+    );
+
+    let args: Vec<&str> = vec!["note", "set", "blocked on API review"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Set the note of branch some_branch_2 to: blocked on API review\n"
+    );
+
+    let args: Vec<&str> = vec![
+        "note",
+        "set",
+        "--branch",
+        "some_branch_1",
+        "ready",
+        "for",
+        "review",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["note", "show"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "blocked on API review\n"
+    );
+
+    let args: Vec<&str> = vec!["note", "-b", "some_branch_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "ready for review\n"
+    );
+
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_2
+
+chain_name
+    ➜ some_branch_2 ⦁ 1 ahead ⦁ 📝 blocked on API review
+      some_branch_1 ⦁ 1 ahead ⦁ 📝 ready for review
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["status", "--short"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+➜ some_branch_2 +1 📝 blocked on API review
+  some_branch_1 +1 📝 ready for review
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["note", "clear"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Cleared the note of branch: some_branch_2\n"
+    );
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+chain_name
+    ➜ some_branch_2 ⦁ 1 ahead
+      some_branch_1 ⦁ 1 ahead ⦁ 📝 ready for review
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    // only branches of a chain have notes
+    checkout_branch(&repo, "master");
+    let args: Vec<&str> = vec!["note", "set", "a note"];
+    run_test_bin_expect_err(&path_to_repo, args);
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,208 @@
+use std::fs;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_git_command, run_test_bin, run_test_bin_expect_ok,
+    run_test_bin_for_rebase, run_test_bin_with_env, setup_git_repo, teardown_git_repo,
+};
+
+// A stand-in for curl, which logs its arguments and the posted payload.
+const FAKE_CURL: &str = r#"#!/bin/sh
+echo "$@" >> "$CURL_LOG"
+cat >> "$CURL_LOG"
+"#;
+
+#[test]
+fn notify_command() {
+    let repo_name = "notify_command";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // The payload is passed on stdin, and the event in GIT_CHAIN_EVENT.
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "config",
+            "chain.notifyCommand",
+            r#"echo "$GIT_CHAIN_EVENT" >> .git/events; cat > .git/payload"#,
+        ],
+    );
+    let path_to_events = path_to_repo.join(".git").join("events");
+    let path_to_payload = path_to_repo.join(".git").join("payload");
+
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "master.txt", "contents");
+    commit_all(&repo, "master change");
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["rebase"];
+    run_test_bin_for_rebase(&path_to_repo, args);
+
+    let rev_parse = |revision: &str| -> String {
+        let output = run_git_command(&path_to_repo, vec!["rev-parse", revision]);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    assert_eq!(
+        fs::read_to_string(&path_to_events).unwrap(),
+        "rebase-completed\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&path_to_payload).unwrap(),
+        format!(
+            r#"{{
+  "event": "rebase-completed",
+  "chain": "chain_name",
+  "root_branch": "master",
+  "branches": [
+    {{ "name": "some_branch_1", "parent": "master", "commit": "{}" }},
+    {{ "name": "some_branch_2", "parent": "some_branch_1", "commit": "{}" }}
+  ]
+}}
+"#,
+            rev_parse("some_branch_1"),
+            rev_parse("some_branch_2")
+        )
+    );
+
+    // create a conflict
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "some_branch_1.txt", "conflict");
+    commit_all(&repo, "add conflict");
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin(&path_to_repo, args);
+    assert!(!output.status.success());
+    run_git_command(&path_to_repo, vec!["rebase", "--abort"]);
+
+    assert_eq!(
+        fs::read_to_string(&path_to_events).unwrap(),
+        "rebase-completed\nrebase-conflict\n"
+    );
+    let payload = fs::read_to_string(&path_to_payload).unwrap();
+    assert!(payload.contains(
+        r#"  "event": "rebase-conflict",
+  "chain": "chain_name",
+  "root_branch": "master",
+  "branch": "some_branch_1",
+  "parent": "master",
+"#
+    ));
+
+    // A failing command only warns.
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "chain.notifyCommand", "exit 1"],
+    );
+    checkout_branch(&repo, "master");
+    run_git_command(&path_to_repo, vec!["reset", "-q", "--hard", "HEAD~1"]);
+    create_new_file(&path_to_repo, "master_2.txt", "contents");
+    commit_all(&repo, "master change");
+    checkout_branch(&repo, "some_branch_2");
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin(&path_to_repo, args);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("⚠️  Unable to notify exit 1 of rebase-completed"));
+
+    teardown_git_repo(repo_name);
+}
+
+#[cfg(unix)]
+#[test]
+fn notify_url() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "notify_url";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    create_branch(&repo, "some_branch_1");
+    checkout_branch(&repo, "some_branch_1");
+    create_new_file(&path_to_repo, "some_branch_1.txt", "contents");
+    commit_all(&repo, "message");
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "some_branch_1"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "chain.notifyUrl", "https://example.com/hook"],
+    );
+
+    let path_to_bin = path_to_repo
+        .canonicalize()
+        .unwrap()
+        .join(".git")
+        .join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_curl = path_to_bin.join("curl");
+    fs::write(&path_to_curl, FAKE_CURL).unwrap();
+    fs::set_permissions(&path_to_curl, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("curl.log");
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("CURL_LOG", path_to_log.to_str().unwrap()),
+    ];
+
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "master.txt", "contents");
+    commit_all(&repo, "master change");
+    checkout_branch(&repo, "some_branch_1");
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs);
+    assert!(output.status.success());
+
+    if cfg!(feature = "webhook") {
+        let log = fs::read_to_string(&path_to_log).unwrap();
+        assert!(log.starts_with(
+            "--silent --show-error --fail --max-time 10 --header Content-Type: application/json --data-binary @- https://example.com/hook\n{\n  \"event\": \"rebase-completed\",\n"
+        ));
+    } else {
+        assert!(!path_to_log.exists());
+        assert!(String::from_utf8_lossy(&output.stderr).contains(
+            "⚠️  Unable to notify https://example.com/hook of rebase-completed: this build of git chain does not support webhooks (cargo feature webhook)"
+        ));
+    }
+
+    teardown_git_repo(repo_name);
+}
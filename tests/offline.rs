@@ -0,0 +1,142 @@
+use std::fs;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_test_bin_expect_ok, run_test_bin_with_env, setup_git_repo,
+    teardown_git_repo,
+};
+
+// A stand-in for gh, which logs its arguments. some_branch_1 has a pull request.
+// With GH_NETWORK_ERROR set, GitHub cannot be reached.
+const FAKE_GH: &str = r#"#!/bin/sh
+printf '%s\n' "$*" >> "$GH_LOG"
+if [ -n "$GH_NETWORK_ERROR" ]; then
+  echo "error connecting to api.github.com" >&2
+  exit 1
+fi
+case "$1 $2 $3" in
+  "pr view some_branch_1")
+    printf '12\thttps://github.com/owner/repo/pull/12\tOPEN\t\tMERGEABLE\n'
+    ;;
+  *)
+    echo "no pull requests found for branch \"$3\"" >&2
+    exit 1
+    ;;
+esac
+"#;
+
+const MARKDOWN: &str = r#"### chain_name
+
+Root branch: `master`
+
+| Branch | Pull request | Status | Note |
+| --- | --- | --- | --- |
+| `some_branch_1` | [#12](https://github.com/owner/repo/pull/12) | 1 ahead |  |
+| ➜ **`some_branch_2`** |  | 1 ahead |  |
+"#;
+
+#[cfg(unix)]
+#[test]
+fn offline_pull_requests() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "offline_pull_requests";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("gh.log");
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("GH_LOG", path_to_log.to_str().unwrap()),
+    ];
+
+    // the pull requests that are found are cached
+    let args: Vec<&str> = vec!["status", "--format=markdown"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), MARKDOWN);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    fs::remove_file(&path_to_log).unwrap();
+
+    // in offline mode, gh is not run and the cached pull requests are displayed
+    let args: Vec<&str> = vec!["--offline", "status", "--format=markdown"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), MARKDOWN);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "⚠️  Offline mode is enabled; using the pull requests cached by earlier runs.\n"
+    );
+    assert!(!path_to_log.exists());
+
+    // once GitHub cannot be reached, gh is not run again and a single warning is displayed
+    let mut network_error_envs = envs.clone();
+    network_error_envs.push(("GH_NETWORK_ERROR", "1"));
+    let args: Vec<&str> = vec!["status", "--format=markdown"];
+    let output = run_test_bin_with_env(&path_to_repo, args, network_error_envs);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), MARKDOWN);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "⚠️  Unable to reach GitHub; using the pull requests cached by earlier runs.\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&path_to_log).unwrap(),
+        "pr view some_branch_1 --json number,url,state,reviewDecision,mergeable --jq \"\\(.number)\\t\\(.url)\\t\\(.state)\\t\\(.reviewDecision)\\t\\(.mergeable)\"\n"
+    );
+    fs::remove_file(&path_to_log).unwrap();
+
+    // commands that change pull requests fail right away
+    let args: Vec<&str> = vec!["pr"];
+    let mut offline_envs = envs.clone();
+    offline_envs.push(("GIT_CHAIN_OFFLINE", "true"));
+    let output = run_test_bin_with_env(&path_to_repo, args, offline_envs);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to create pull requests: Offline mode is enabled.
+Pull requests can only be changed while GitHub can be reached (without --offline, GIT_CHAIN_OFFLINE or chain.offline).
+"#
+        .trim_start()
+    );
+    assert!(!path_to_log.exists());
+
+    teardown_git_repo(repo_name);
+}
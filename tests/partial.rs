@@ -0,0 +1,209 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_git_command, run_test_bin_expect_ok, run_test_bin_for_rebase,
+    setup_git_repo, teardown_git_repo,
+};
+
+use std::fs;
+
+#[test]
+fn partial_clone() {
+    let repo_name = "partial_clone";
+    let source_repo_name = "partial_clone_source";
+    let source_repo = setup_git_repo(source_repo_name);
+    let path_to_source_repo = generate_path_to_repo(source_repo_name)
+        .canonicalize()
+        .unwrap();
+
+    {
+        create_new_file(&path_to_source_repo, "file.txt", "line 1\nline 2");
+        first_commit_all(&source_repo, "first commit");
+
+        create_new_file(&path_to_source_repo, "file.txt", "line 1\nline 2\nline 3");
+        commit_all(&source_repo, "second commit");
+    };
+
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&source_repo, branch_name);
+        checkout_branch(&source_repo, branch_name);
+        create_new_file(&path_to_source_repo, "file.txt", "line 1\nline two\nline 3");
+        commit_all(&source_repo, "message");
+    };
+
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&source_repo, branch_name);
+        checkout_branch(&source_repo, branch_name);
+        create_new_file(&path_to_source_repo, "file_2.txt", "contents 2");
+        commit_all(&source_repo, "message");
+    };
+
+    checkout_branch(&source_repo, "master");
+    run_git_command(
+        &path_to_source_repo,
+        vec!["config", "uploadpack.allowFilter", "true"],
+    );
+    run_git_command(
+        &path_to_source_repo,
+        vec!["config", "uploadpack.allowAnySHA1InWant", "true"],
+    );
+
+    // only the files of master are fetched by the clone
+    let path_to_repo = std::env::current_dir()
+        .unwrap()
+        .join(generate_path_to_repo(repo_name));
+    fs::remove_dir_all(&path_to_repo).ok();
+    let source_url = format!("file://{}", path_to_source_repo.display());
+    run_git_command(
+        &path_to_source_repo,
+        vec![
+            "clone",
+            "--quiet",
+            "--filter=blob:none",
+            &source_url,
+            path_to_repo.to_str().unwrap(),
+        ],
+    );
+    run_git_command(&path_to_repo, vec!["config", "user.name", "name"]);
+    run_git_command(&path_to_repo, vec!["config", "user.email", "email"]);
+    run_git_command(
+        &path_to_repo,
+        vec!["branch", "some_branch_1", "origin/some_branch_1"],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec!["branch", "some_branch_2", "origin/some_branch_2"],
+    );
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // the changed lines of some_branch_2 are counted from files missing from the clone
+    run_git_command(&path_to_repo, vec!["checkout", "--quiet", "some_branch_1"]);
+    let args: Vec<&str> = vec!["size-guard"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+some_branch_1 ⦁ 1 commits ⦁ 2 changed lines
+some_branch_2 ⦁ 1 commits ⦁ 1 changed lines
+"#
+        .trim_start()
+    );
+
+    // blame reads the versions of the file missing from the clone
+    let args: Vec<&str> = vec!["blame", "file.txt"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("master        "));
+    assert!(lines[0].ends_with(" 1) line 1"));
+    assert!(lines[1].starts_with("some_branch_1 "));
+    assert!(lines[1].ends_with(" 2) line two"));
+    assert!(lines[2].starts_with("master        "));
+    assert!(lines[2].ends_with(" 3) line 3"));
+
+    // rebase the chain onto new commits of master, whose files are not fetched
+    create_new_file(&path_to_source_repo, "file_3.txt", "contents 3");
+    commit_all(&source_repo, "third commit");
+    run_git_command(&path_to_repo, vec!["fetch", "--quiet", "origin"]);
+    run_git_command(
+        &path_to_repo,
+        vec!["branch", "-f", "master", "origin/master"],
+    );
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("🎉 Successfully rebased chain chain_name"));
+
+    let output = run_git_command(&path_to_repo, vec!["rev-parse", "--abbrev-ref", "HEAD"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "some_branch_1"
+    );
+    assert!(path_to_repo.join("file_3.txt").exists());
+    let output = run_git_command(&path_to_repo, vec!["status", "--porcelain"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+
+    teardown_git_repo(repo_name);
+    teardown_git_repo(source_repo_name);
+}
+
+#[test]
+fn sparse_checkout() {
+    let repo_name = "sparse_checkout";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        fs::create_dir_all(path_to_repo.join("services/api")).unwrap();
+        fs::create_dir_all(path_to_repo.join("services/web")).unwrap();
+        create_new_file(&path_to_repo, "services/api/api.txt", "api");
+        create_new_file(&path_to_repo, "services/web/web.txt", "web");
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "services/api/api_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "services/api/api_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "services/web/web_2.txt", "web 2");
+    commit_all(&repo, "web commit");
+    checkout_branch(&repo, "some_branch_1");
+
+    // services/web is outside of the sparse checkout
+    run_git_command(
+        &path_to_repo,
+        vec!["sparse-checkout", "set", "--cone", "services/api"],
+    );
+    assert!(!path_to_repo.join("services/web").exists());
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("🎉 Successfully rebased chain chain_name"));
+
+    let output = run_git_command(&path_to_repo, vec!["rev-parse", "--abbrev-ref", "HEAD"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "some_branch_1"
+    );
+    assert!(path_to_repo.join("services/api/api_1.txt").exists());
+    assert!(!path_to_repo.join("services/web").exists());
+    let output = run_git_command(&path_to_repo, vec!["status", "--porcelain"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,849 @@
+use std::fs;
+use std::path::PathBuf;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_bare_repo, generate_path_to_repo, run_git_command, run_test_bin_expect_ok,
+    run_test_bin_with_env, setup_git_bare_repo, setup_git_repo, teardown_git_bare_repo,
+    teardown_git_repo,
+};
+
+// A stand-in for gh, which logs its arguments and has no pull requests.
+const FAKE_GH: &str = r#"#!/bin/sh
+printf '%s\n' "$*" >> "$GH_LOG"
+case "$1 $2" in
+  "pr view")
+    echo "no pull requests found for branch \"$3\"" >&2
+    exit 1
+    ;;
+  "pr create")
+    echo "https://github.com/owner/repo/pull/$4"
+    ;;
+esac
+"#;
+
+// A stand-in for xdg-open, which logs the URLs that it would open.
+const FAKE_XDG_OPEN: &str = r#"#!/bin/sh
+echo "xdg-open $@" >> "$GH_LOG"
+"#;
+
+#[cfg(unix)]
+#[test]
+fn pr_subcommand() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "pr_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("gh.log");
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("GH_LOG", path_to_log.to_str().unwrap()),
+    ];
+
+    // the root branch must exist on the remote
+    let args: Vec<&str> = vec!["pr"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Root branch master does not exist on remote origin."));
+
+    run_git_command(&path_to_repo, vec!["push", "origin", "master"]);
+    fs::remove_file(&path_to_log).unwrap();
+
+    // missing branches are pushed, and pull requests are created from the first to the last branch
+    let args: Vec<&str> = vec!["pr", "--draft"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Pushing branch some_branch_1 to remote origin
+🎉 Created pull request of branch some_branch_1 into master: https://github.com/owner/repo/pull/some_branch_1
+Pushing branch some_branch_2 to remote origin
+🎉 Created pull request of branch some_branch_2 into some_branch_1: https://github.com/owner/repo/pull/some_branch_2
+"#
+        .trim_start()
+    );
+
+    assert_eq!(
+        fs::read_to_string(&path_to_log).unwrap(),
+        r#"
+--version
+pr view some_branch_1 --json number,url,state,reviewDecision,mergeable --jq "\(.number)\t\(.url)\t\(.state)\t\(.reviewDecision)\t\(.mergeable)"
+pr create --head some_branch_1 --base master --fill --draft
+pr view some_branch_2 --json number,url,state,reviewDecision,mergeable --jq "\(.number)\t\(.url)\t\(.state)\t\(.reviewDecision)\t\(.mergeable)"
+pr create --head some_branch_2 --base some_branch_1 --fill --draft
+"#
+        .trim_start()
+    );
+
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["rev-parse", "--abbrev-ref", "some_branch_2@{upstream}"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "origin/some_branch_2\n"
+    );
+
+    // --open opens each pull request in the browser, with xdg-open on Linux
+    if cfg!(target_os = "linux") {
+        let path_to_xdg_open = path_to_bin.join("xdg-open");
+        fs::write(&path_to_xdg_open, FAKE_XDG_OPEN).unwrap();
+        fs::set_permissions(&path_to_xdg_open, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_file(&path_to_log).unwrap();
+
+        let args: Vec<&str> = vec!["pr", "--open"];
+        let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).ends_with(
+            r#"
+Opening https://github.com/owner/repo/pull/some_branch_1
+Opening https://github.com/owner/repo/pull/some_branch_2
+"#
+        ));
+        assert!(fs::read_to_string(&path_to_log).unwrap().ends_with(
+            r#"
+xdg-open https://github.com/owner/repo/pull/some_branch_1
+xdg-open https://github.com/owner/repo/pull/some_branch_2
+"#
+        ));
+    }
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[cfg(unix)]
+#[test]
+fn push_subcommand_create_pr() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "push_subcommand_create_pr";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // only the first branch has an upstream
+    run_git_command(&path_to_repo, vec!["push", "origin", "master"]);
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--set-upstream", "origin", "some_branch_1"],
+    );
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("gh.log");
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("GH_LOG", path_to_log.to_str().unwrap()),
+    ];
+
+    let args: Vec<&str> = vec!["push", "--create-pr", "--draft"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Pushed some_branch_1
+Pushed 1 branches.
+
+🎉 Created pull request of branch some_branch_1 into master: https://github.com/owner/repo/pull/some_branch_1
+Pushing branch some_branch_2 to remote origin
+🎉 Created pull request of branch some_branch_2 into some_branch_1: https://github.com/owner/repo/pull/some_branch_2
+"#
+        .trim_start()
+    );
+
+    assert_eq!(
+        fs::read_to_string(&path_to_log).unwrap(),
+        r#"
+--version
+--version
+pr view some_branch_1 --json number,url,state,reviewDecision,mergeable --jq "\(.number)\t\(.url)\t\(.state)\t\(.reviewDecision)\t\(.mergeable)"
+pr create --head some_branch_1 --base master --fill --draft
+pr view some_branch_2 --json number,url,state,reviewDecision,mergeable --jq "\(.number)\t\(.url)\t\(.state)\t\(.reviewDecision)\t\(.mergeable)"
+pr create --head some_branch_2 --base some_branch_1 --fill --draft
+"#
+        .trim_start()
+    );
+
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["rev-parse", "--abbrev-ref", "some_branch_2@{upstream}"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "origin/some_branch_2\n"
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+// A stand-in for gh, whose pull requests are open and labeled with outdated positions.
+const FAKE_GH_LABELED: &str = r#"#!/bin/sh
+printf '%s\n' "$*" >> "$GH_LOG"
+case "$1 $2" in
+  "pr view")
+    if [ "$5" = "labels" ]; then
+      case "$3" in
+        some_branch_1) echo "stack:1/2" ;;
+        some_branch_2) printf 'stack:1/3\nbug\n' ;;
+      esac
+    else
+      printf '1\thttps://github.com/owner/repo/pull/%s\tOPEN\t\tMERGEABLE\n' "$3"
+    fi
+    ;;
+esac
+"#;
+
+#[cfg(unix)]
+#[test]
+fn pr_subcommand_stack_order_labels() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "pr_subcommand_stack_order_labels";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--all", "--set-upstream", "origin"],
+    );
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH_LABELED).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("gh.log");
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("GH_LOG", path_to_log.to_str().unwrap()),
+    ];
+
+    // the label of some_branch_1 is up to date, the one of some_branch_2 is replaced
+    let args: Vec<&str> = vec!["pr", "--stack-order-labels"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Pull request of branch some_branch_1 already exists: https://github.com/owner/repo/pull/some_branch_1
+✅ Pull request of branch some_branch_2 already exists: https://github.com/owner/repo/pull/some_branch_2
+🏷️  Labeled pull request of branch some_branch_2: stack:2/2
+"#
+        .trim_start()
+    );
+
+    assert_eq!(
+        fs::read_to_string(&path_to_log).unwrap(),
+        r#"
+--version
+pr view some_branch_1 --json number,url,state,reviewDecision,mergeable --jq "\(.number)\t\(.url)\t\(.state)\t\(.reviewDecision)\t\(.mergeable)"
+pr view some_branch_1 --json labels --jq .labels[].name
+pr view some_branch_2 --json number,url,state,reviewDecision,mergeable --jq "\(.number)\t\(.url)\t\(.state)\t\(.reviewDecision)\t\(.mergeable)"
+pr view some_branch_2 --json labels --jq .labels[].name
+label create stack:2/2 --description Position of the pull request in its stack --force
+pr edit some_branch_2 --add-label stack:2/2 --remove-label stack:1/3
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[cfg(unix)]
+#[test]
+fn pr_subcommand_draft_until() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "pr_subcommand_draft_until";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2", "some_branch_3"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    run_git_command(&path_to_repo, vec!["push", "--quiet", "origin", "master"]);
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("gh.log");
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("GH_LOG", path_to_log.to_str().unwrap()),
+    ];
+
+    // the branch must be part of the chain
+    let args: Vec<&str> = vec!["pr", "--draft-until", "master"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 Branch master is not part of chain chain_name.\n"
+    );
+
+    // the pull requests of the branches after some_branch_2 are drafts
+    let args: Vec<&str> = vec!["pr", "--draft-until", "some_branch_2"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+
+    let log = fs::read_to_string(&path_to_log).unwrap();
+    let created: Vec<&str> = log
+        .lines()
+        .filter(|line| line.starts_with("pr create"))
+        .collect();
+    assert_eq!(
+        created,
+        vec![
+            "pr create --head some_branch_1 --base master --fill",
+            "pr create --head some_branch_2 --base some_branch_1 --fill",
+            "pr create --head some_branch_3 --base some_branch_2 --fill --draft",
+        ]
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[cfg(unix)]
+#[test]
+fn pr_subcommand_push_ref() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "pr_subcommand_push_ref";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    run_git_command(&path_to_repo, vec!["push", "origin", "master"]);
+
+    // the branches are pushed under users/me on the remote
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        let push_ref = format!("users/me/{}", branch_name);
+        let args: Vec<&str> = vec!["push-ref", "set", "--branch", branch_name, &push_ref];
+        run_test_bin_expect_ok(&path_to_repo, args);
+    }
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("gh.log");
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("GH_LOG", path_to_log.to_str().unwrap()),
+    ];
+
+    // the pull requests are between the branches on the remote
+    let args: Vec<&str> = vec!["pr"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Pushing branch some_branch_1 to remote origin
+🎉 Created pull request of branch some_branch_1 into master: https://github.com/owner/repo/pull/users/me/some_branch_1
+Pushing branch some_branch_2 to remote origin
+🎉 Created pull request of branch some_branch_2 into users/me/some_branch_1: https://github.com/owner/repo/pull/users/me/some_branch_2
+"#
+        .trim_start()
+    );
+
+    assert_eq!(
+        fs::read_to_string(&path_to_log).unwrap(),
+        r#"
+--version
+pr view users/me/some_branch_1 --json number,url,state,reviewDecision,mergeable --jq "\(.number)\t\(.url)\t\(.state)\t\(.reviewDecision)\t\(.mergeable)"
+pr create --head users/me/some_branch_1 --base master --fill
+pr view users/me/some_branch_2 --json number,url,state,reviewDecision,mergeable --jq "\(.number)\t\(.url)\t\(.state)\t\(.reviewDecision)\t\(.mergeable)"
+pr create --head users/me/some_branch_2 --base users/me/some_branch_1 --fill
+"#
+        .trim_start()
+    );
+
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["rev-parse", "--abbrev-ref", "some_branch_2@{upstream}"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "origin/users/me/some_branch_2\n"
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+// A stand-in for gh, whose pull requests are open and into the parent branches of the order
+// some_branch_1, some_branch_2.
+const FAKE_GH_REORDERED: &str = r#"#!/bin/sh
+printf '%s\n' "$*" >> "$GH_LOG"
+case "$1 $2" in
+  "pr view")
+    if [ "$5" = "number,headRefName,baseRefName,state" ]; then
+      case "$3" in
+        some_branch_1) printf '1\tsome_branch_1\tmaster\tOPEN\n' ;;
+        some_branch_2) printf '2\tsome_branch_2\tsome_branch_1\tOPEN\n' ;;
+      esac
+    else
+      printf '1\thttps://github.com/owner/repo/pull/%s\tOPEN\t\tMERGEABLE\n' "$3"
+    fi
+    ;;
+esac
+"#;
+
+#[cfg(unix)]
+#[test]
+fn pr_subcommand_update_base_on_reorder() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "pr_subcommand_update_base_on_reorder";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--all", "--set-upstream", "origin"],
+    );
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH_REORDERED).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("gh.log");
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("GH_LOG", path_to_log.to_str().unwrap()),
+    ];
+
+    // the pull requests match the order of the chain
+    let args: Vec<&str> = vec!["pr", "--update-base-on-reorder"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Pull request of branch some_branch_1 already exists: https://github.com/owner/repo/pull/some_branch_1
+✅ Pull request of branch some_branch_2 already exists: https://github.com/owner/repo/pull/some_branch_2
+"#
+        .trim_start()
+    );
+
+    // some_branch_2 now comes first
+    let args: Vec<&str> = vec!["move", "--before=some_branch_1"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    fs::remove_file(&path_to_log).unwrap();
+
+    let args: Vec<&str> = vec!["pr", "--update-base-on-reorder"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Pull request of branch some_branch_2 already exists: https://github.com/owner/repo/pull/some_branch_2
+✅ Updated the base branch of the pull request of branch some_branch_2 from some_branch_1 to master
+✅ Pull request of branch some_branch_1 already exists: https://github.com/owner/repo/pull/some_branch_1
+✅ Updated the base branch of the pull request of branch some_branch_1 from master to some_branch_2
+"#
+        .trim_start()
+    );
+
+    let log = fs::read_to_string(&path_to_log).unwrap();
+    let edits: Vec<&str> = log
+        .lines()
+        .filter(|line| line.starts_with("pr edit"))
+        .collect();
+    assert_eq!(
+        edits,
+        vec![
+            "pr edit some_branch_2 --base master",
+            "pr edit some_branch_1 --base some_branch_2"
+        ]
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[cfg(unix)]
+#[test]
+fn pr_subcommand_size_limits() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "pr_subcommand_size_limits";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    // some_branch_2 has two commits
+    create_new_file(&path_to_repo, "some_branch_2_more.txt", "contents");
+    commit_all(&repo, "message");
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    run_git_command(&path_to_repo, vec!["push", "--quiet", "origin", "master"]);
+    run_git_command(&path_to_repo, vec!["config", "chain.maxCommits", "1"]);
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("gh.log");
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("GH_LOG", path_to_log.to_str().unwrap()),
+    ];
+
+    // with --strict, nothing is pushed or created
+    let args: Vec<&str> = vec!["pr", "--strict"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "⚠️  Branch some_branch_2 is too large: 2 commits (max 1)\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("🛑 Not creating pull requests for chain chain_name: 1 branch is too large."));
+    assert!(!path_to_log.exists());
+
+    let output = run_git_command(&path_to_bare_repo, vec!["branch", "--list"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "* master\n");
+
+    // otherwise, the pull requests are created after the warning
+    let args: Vec<&str> = vec!["pr"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with(
+        r#"
+⚠️  Branch some_branch_2 is too large: 2 commits (max 1)
+
+Pushing branch some_branch_1 to remote origin
+"#
+        .trim_start()
+    ));
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
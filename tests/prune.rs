@@ -1,8 +1,12 @@
+use std::path::PathBuf;
+
 pub mod common;
 use common::{
     checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
-    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin_expect_ok,
-    run_test_bin_for_rebase, setup_git_repo, teardown_git_repo,
+    generate_path_to_bare_repo, generate_path_to_repo, get_current_branch_name, run_git_command,
+    run_test_bin_expect_err, run_test_bin_expect_ok, run_test_bin_for_rebase,
+    run_test_bin_with_env, setup_git_bare_repo, setup_git_repo, teardown_git_bare_repo,
+    teardown_git_repo,
 };
 
 #[test]
@@ -119,7 +123,19 @@ chain_name
         .trim_start()
     );
 
-    // git chain prune
+    // git chain prune --no-input requires confirmation
+    let args: Vec<&str> = vec!["--no-input", "prune"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Confirmation required: Remove 1 branch from chain chain_name? (some_branch_1)"));
+
+    let args: Vec<&str> = vec!["prune"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_CHAIN_YES", "maybe")]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Invalid boolean value maybe for GIT_CHAIN_YES"));
+
+    // git chain prune proceeds when stdin is not a terminal
     let args: Vec<&str> = vec!["prune"];
     let output = run_test_bin_expect_ok(&path_to_repo, args);
 
@@ -154,3 +170,211 @@ chain_name
 
     teardown_git_repo(repo_name);
 }
+
+#[test]
+fn prune_subcommand_restack_children() {
+    let repo_name = "prune_subcommand_restack_children";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2", "some_branch_3"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // merge some_branch_2 (and thereby some_branch_1) onto master with a merge commit
+    checkout_branch(&repo, "master");
+    run_git_command(
+        &path_to_repo,
+        vec!["merge", "--no-ff", "-m", "merge", "some_branch_2"],
+    );
+    checkout_branch(&repo, "some_branch_3");
+
+    let args: Vec<&str> = vec!["prune", "--yes"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.starts_with(
+        r#"
+Removed the following branches from chain: chain_name
+
+some_branch_1
+some_branch_2
+
+Pruned 2 branches.
+
+⚠️  Branch some_branch_3 was stacked on the pruned branch some_branch_2. It needs to be rebased onto master.
+"#
+        .trim_start()
+    ));
+    assert!(stdout.contains("🎉 Successfully rebased chain chain_name"));
+
+    // some_branch_3 is now stacked on master
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["merge-base", "--is-ancestor", "master", "some_branch_3"],
+    );
+    assert!(output.status.success());
+
+    let args: Vec<&str> = vec![];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_3
+
+chain_name
+    ➜ some_branch_3 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn prune_subcommand_upstream_gone() {
+    let repo_name = "prune_subcommand_upstream_gone";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "push",
+            "--quiet",
+            "-u",
+            "origin",
+            "some_branch_1",
+            "some_branch_2",
+        ],
+    );
+
+    // nothing to prune while the upstream branches exist
+    let args: Vec<&str> = vec!["prune", "--dry-run"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "This was a dry-run, no branches pruned for chain: chain_name\n"
+    );
+
+    // the upstream branch of some_branch_1 is deleted, e.g. once its pull request was merged
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--quiet", "origin", "--delete", "some_branch_1"],
+    );
+
+    let args: Vec<&str> = vec!["prune", "--dry-run"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Removed the following branches from chain: chain_name
+
+some_branch_1
+
+Pruned 1 branches.
+
+This was a dry-run, no branches pruned!
+"#
+        .trim_start()
+    );
+
+    // its commits are not on master, so it is confirmed first
+    let args: Vec<&str> = vec!["prune"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(
+        "⚠️  The upstream branch of some_branch_1 is gone, but its commits are not on master."
+    ));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Confirmation required: Remove 1 branch from chain chain_name? (some_branch_1)"));
+
+    let args: Vec<&str> = vec!["prune", "--yes"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+⚠️  The upstream branch of some_branch_1 is gone, but its commits are not on master.
+
+Removed the following branches from chain: chain_name
+
+some_branch_1
+
+Pruned 1 branches.
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+
+pub mod common;
+use common::{
+    branch_exists, checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_bare_repo, generate_path_to_repo, run_git_command, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_bare_repo, setup_git_repo, teardown_git_bare_repo,
+    teardown_git_repo,
+};
+
+#[test]
+fn publish_and_subscribe_subcommands() {
+    let repo_name = "publish_and_subscribe_subcommands";
+    let teammate_repo_name = "publish_and_subscribe_subcommands_teammate";
+    let repo = setup_git_repo(repo_name);
+    let teammate_repo = setup_git_repo(teammate_repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+    let path_to_teammate_repo = generate_path_to_repo(teammate_repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    for path in [&path_to_repo, &path_to_teammate_repo] {
+        run_git_command(path, vec!["remote", "add", "origin", &path_to_bare_repo]);
+    }
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    run_test_bin_expect_ok(&path_to_repo, vec!["scope", "src"]);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "origin", "master", "some_branch_1", "some_branch_2"],
+    );
+
+    let args: Vec<&str> = vec!["publish"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Published chain chain_name to origin as refs/chains/chain_name
+To set it up in another clone, run: git chain subscribe chain_name
+"#
+        .trim_start()
+    );
+
+    let output = run_git_command(
+        &path_to_bare_repo,
+        vec!["show", "refs/chains/chain_name:chain"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+root master
+scope src
+branch some_branch_1
+branch some_branch_2
+"#
+        .trim_start()
+    );
+
+    // publishing an unchanged layout does not add to its history
+    let args: Vec<&str> = vec!["publish"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .starts_with("The layout of chain chain_name is unchanged since it was last published."));
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["rev-list", "--count", "refs/chains/chain_name"],
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+
+    // the teammate gets the same chain, with branches created from the remote
+    {
+        run_git_command(&path_to_teammate_repo, vec!["fetch", "origin"]);
+        run_git_command(
+            &path_to_teammate_repo,
+            vec!["checkout", "-b", "master", "origin/master"],
+        );
+    };
+
+    let args: Vec<&str> = vec!["subscribe", "chain_name"];
+    let output = run_test_bin_expect_ok(&path_to_teammate_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Created branch some_branch_1 from origin/some_branch_1
+Created branch some_branch_2 from origin/some_branch_2
+🔗 Succesfully subscribed to chain chain_name from origin
+
+chain_name (scope: src)
+      some_branch_2 ⦁ 1 ahead
+      some_branch_1 ⦁ 1 ahead
+    ➜ master (root branch)
+"#
+        .trim_start()
+    );
+    assert!(branch_exists(&teammate_repo, "some_branch_1"));
+    let output = run_git_command(
+        &path_to_teammate_repo,
+        vec!["rev-parse", "--abbrev-ref", "some_branch_2@{upstream}"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "origin/some_branch_2\n"
+    );
+
+    // the chain already exists
+    let args: Vec<&str> = vec!["subscribe", "chain_name"];
+    let output = run_test_bin_expect_err(&path_to_teammate_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Chain already exists: chain_name
+To replace it, remove it first: git chain remove --chain chain_name
+"#
+        .trim_start()
+    );
+
+    // a chain that was never published
+    let args: Vec<&str> = vec!["subscribe", "other_chain"];
+    let output = run_test_bin_expect_err(&path_to_teammate_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("🛑 Unable to fetch chain other_chain from remote origin\n"));
+
+    teardown_git_repo(repo_name);
+    teardown_git_repo(teammate_repo_name);
+    teardown_git_bare_repo(repo_name);
+}
@@ -1,13 +1,22 @@
+use std::fs;
 use std::path::PathBuf;
 
 pub mod common;
 use common::{
-    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
-    generate_path_to_bare_repo, generate_path_to_repo, get_current_branch_name, run_git_command,
-    run_test_bin_expect_ok, setup_git_bare_repo, setup_git_repo, teardown_git_bare_repo,
+    checkout_branch, commit_all, create_branch, create_new_file, delete_local_branch,
+    first_commit_all, generate_path_to_bare_repo, generate_path_to_repo, get_current_branch_name,
+    run_git_command, run_test_bin, run_test_bin_expect_err, run_test_bin_expect_ok,
+    run_test_bin_with_env, setup_git_bare_repo, setup_git_repo, teardown_git_bare_repo,
     teardown_git_repo,
 };
 
+// A stand-in for gh, which finds no pull requests.
+#[cfg(unix)]
+const FAKE_GH_NO_PULL_REQUESTS: &str = r#"#!/bin/sh
+echo "no pull requests found for branch \"$3\"" >&2
+exit 1
+"#;
+
 #[test]
 fn push_subcommand() {
     let repo_name = "push_subcommand";
@@ -126,6 +135,77 @@ Pushed 2 branches.
         .trim_start()
     );
 
+    // git chain push --only-changed
+    let args: Vec<&str> = vec!["push", "--only-changed"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Already up-to-date some_branch_1
+✅ Already up-to-date some_branch_2
+Pushed 0 branches.
+"#
+        .trim_start()
+    );
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2.5");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["push", "--only-changed"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Already up-to-date some_branch_1
+✅ Pushed some_branch_2
+Pushed 1 branches.
+"#
+        .trim_start()
+    );
+
+    // only the branches from --from to --to are pushed
+    let args: Vec<&str> = vec!["push", "--to", "some_branch_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Only branch some_branch_1 of chain chain_name is selected.
+✅ Pushed some_branch_1
+Pushed 1 branches.
+"#
+        .trim_start()
+    );
+
+    // a chain whose branches were all deleted has no branches to select
+    checkout_branch(&repo, "master");
+    run_git_command(
+        &path_to_repo,
+        vec!["update-ref", "-d", "refs/heads/some_branch_1"],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec!["update-ref", "-d", "refs/heads/some_branch_2"],
+    );
+
+    let args: Vec<&str> = vec!["push", "--chain", "chain_name"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to push chain: chain_name
+Chain chain_name has no branches
+"#
+        .trim_start()
+    );
+
     teardown_git_repo(repo_name);
     teardown_git_bare_repo(repo_name);
 }
@@ -248,6 +328,437 @@ Pushed 2 branches.
         .trim_start()
     );
 
+    // --strict requires --check-pr
+    let args: Vec<&str> = vec!["push", "--force", "--strict"];
+    run_test_bin_expect_err(&path_to_repo, args);
+
+    // git chain push --force --check-pr --strict
+    // The pull requests cannot be checked (gh is not installed, or does not know the remote), so
+    // nothing is pushed.
+    let args: Vec<&str> = vec!["push", "--force", "--check-pr", "--strict"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("🛑 Not force pushing branches of the chain: chain_name"));
+    assert!(stderr.contains("The review state of the pull requests could not be checked with gh."));
+
+    // with a gh that finds no pull requests, the branches are pushed
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+        fs::create_dir_all(&path_to_bin).unwrap();
+        let path_to_gh = path_to_bin.join("gh");
+        fs::write(&path_to_gh, FAKE_GH_NO_PULL_REQUESTS).unwrap();
+        fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+        let path = format!(
+            "{}:{}",
+            path_to_bin.display(),
+            std::env::var("PATH").unwrap()
+        );
+
+        let args: Vec<&str> = vec!["push", "--force", "--check-pr", "--strict"];
+        let output = run_test_bin_with_env(&path_to_repo, args, vec![("PATH", path.as_str())]);
+
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            r#"
+✅ Force pushed some_branch_1
+✅ Force pushed some_branch_2
+Pushed 2 branches.
+"#
+            .trim_start()
+        );
+    }
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[test]
+fn push_subcommand_protect_reviewed() {
+    let repo_name = "push_subcommand_protect_reviewed";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    // create and checkout new branch named some_branch_2
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--all", "--set-upstream", "origin"],
+    );
+
+    // a reviewer pushes a commit to some_branch_1
+    {
+        checkout_branch(&repo, "some_branch_1");
+        create_branch(&repo, "reviewer");
+        checkout_branch(&repo, "reviewer");
+        create_new_file(&path_to_repo, "file_review.txt", "review");
+        commit_all(&repo, "reviewer commit");
+        run_git_command(
+            &path_to_repo,
+            vec!["push", "origin", "reviewer:some_branch_1"],
+        );
+        run_git_command(&path_to_repo, vec!["fetch", "origin"]);
+        checkout_branch(&repo, "some_branch_2");
+        delete_local_branch(&repo, "reviewer");
+    };
+
+    // git chain push --force --protect-reviewed
+    let args: Vec<&str> = vec!["push", "--force", "--protect-reviewed"];
+    let output = run_test_bin(&path_to_repo, args);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🛑 Not force pushing some_branch_1: origin/some_branch_1 has 1 commits that are not in the local branch
+✅ Force pushed some_branch_2
+Pushed 1 branches.
+"#
+        .trim_start()
+    );
+
+    // without --protect-reviewed, the reviewer's commit is overwritten
+    let args: Vec<&str> = vec!["push", "--force"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Force pushed some_branch_1
+✅ Force pushed some_branch_2
+Pushed 2 branches.
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[test]
+fn push_subcommand_force_unknown_remote_commits() {
+    let repo_name = "push_subcommand_force_unknown_remote_commits";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--all", "--set-upstream", "origin"],
+    );
+
+    // the tips of the remote branches are recorded when pushing
+    let args: Vec<&str> = vec!["push"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // someone else pushes a commit to some_branch_1, which is fetched but not pulled;
+    // --force-with-lease alone would overwrite it
+    {
+        checkout_branch(&repo, "some_branch_1");
+        create_branch(&repo, "teammate");
+        checkout_branch(&repo, "teammate");
+        create_new_file(&path_to_repo, "file_teammate.txt", "teammate");
+        commit_all(&repo, "teammate commit");
+        run_git_command(
+            &path_to_repo,
+            vec!["push", "origin", "teammate:some_branch_1"],
+        );
+        run_git_command(&path_to_repo, vec!["fetch", "origin"]);
+        checkout_branch(&repo, "some_branch_2");
+        delete_local_branch(&repo, "teammate");
+    };
+
+    let args: Vec<&str> = vec!["push", "--force"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🛑 Not force pushing some_branch_1: origin/some_branch_1 has commits that were pushed since you last fetched or pushed it
+To keep them, run: git fetch, and rebase some_branch_1 onto origin/some_branch_1
+✅ Force pushed some_branch_2
+Pushed 1 branches.
+"#
+        .trim_start()
+    );
+
+    // once the commit is included in the local branch, it can be force pushed
+    run_git_command(&path_to_repo, vec!["checkout", "some_branch_1"]);
+    run_git_command(
+        &path_to_repo,
+        vec!["merge", "--ff-only", "origin/some_branch_1"],
+    );
+
+    let args: Vec<&str> = vec!["push", "--force"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Force pushed some_branch_1
+✅ Force pushed some_branch_2
+Pushed 2 branches.
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[test]
+fn push_subcommand_push_ref() {
+    let repo_name = "push_subcommand_push_ref";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--all", "--set-upstream", "origin"],
+    );
+
+    let remote_oid = |remote_ref: &str| {
+        let output = run_git_command(&path_to_bare_repo, vec!["rev-parse", remote_ref]);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let local_oid = |branch_name: &str| {
+        let output = run_git_command(&path_to_repo, vec!["rev-parse", branch_name]);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    // by default, branches are pushed to the branch of the same name
+    let args: Vec<&str> = vec!["push-ref"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "origin refs/heads/some_branch_2\n"
+    );
+
+    // the push ref of a branch
+    let args: Vec<&str> = vec![
+        "push-ref",
+        "set",
+        "--branch",
+        "some_branch_1",
+        "users/me/some_branch_1",
+    ];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Set the push ref of branch some_branch_1 to: refs/heads/users/me/some_branch_1\n"
+    );
+
+    let args: Vec<&str> = vec!["push-ref", "-b", "some_branch_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "origin refs/heads/users/me/some_branch_1\n"
+    );
+
+    // push.default=upstream pushes to the upstream branch, whatever its name
+    run_git_command(&path_to_repo, vec!["config", "push.default", "upstream"]);
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "push",
+            "origin",
+            "some_branch_2~1:refs/heads/team/some_branch_2",
+        ],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "branch",
+            "--set-upstream-to",
+            "origin/team/some_branch_2",
+            "some_branch_2",
+        ],
+    );
+
+    let args: Vec<&str> = vec!["push"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Pushed some_branch_1
+✅ Pushed some_branch_2
+Pushed 2 branches.
+"#
+        .trim_start()
+    );
+    assert_eq!(
+        remote_oid("users/me/some_branch_1"),
+        local_oid("some_branch_1")
+    );
+    assert_eq!(remote_oid("team/some_branch_2"), local_oid("some_branch_2"));
+
+    // the branches pushed elsewhere are compared with their own remote-tracking branches
+    let args: Vec<&str> = vec!["push", "--only-changed"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Already up-to-date some_branch_1
+✅ Already up-to-date some_branch_2
+Pushed 0 branches.
+"#
+        .trim_start()
+    );
+
+    // the push refspecs of the remote
+    run_git_command(&path_to_repo, vec!["config", "--unset", "push.default"]);
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "config",
+            "remote.origin.push",
+            "refs/heads/*:refs/heads/mirror/*",
+        ],
+    );
+    let args: Vec<&str> = vec!["push-ref", "clear", "--branch", "some_branch_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Cleared the push ref of branch: some_branch_1\n"
+    );
+
+    let args: Vec<&str> = vec!["push", "--to", "some_branch_1"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        remote_oid("mirror/some_branch_1"),
+        local_oid("some_branch_1")
+    );
+
     teardown_git_repo(repo_name);
     teardown_git_bare_repo(repo_name);
 }
@@ -0,0 +1,116 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn root_and_parent_subcommands() {
+    let repo_name = "root_and_parent_subcommands";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    for branch_name in ["some_branch_1", "some_branch_2", "other_branch"] {
+        checkout_branch(&repo, "master");
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    // run git chain setup
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["setup", "other_chain", "some_branch_1", "other_branch"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["root"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "master\n");
+
+    let args: Vec<&str> = vec!["root", "--chain", "other_chain"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "some_branch_1\n");
+
+    let args: Vec<&str> = vec!["root", "--branch", "other_branch"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "some_branch_1\n");
+
+    let args: Vec<&str> = vec!["parent"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "some_branch_1\n");
+
+    let args: Vec<&str> = vec!["parent", "--branch", "some_branch_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "master\n");
+
+    let args: Vec<&str> = vec!["parent", "--chain", "chain_name"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "some_branch_1\n");
+
+    let args: Vec<&str> = vec![
+        "parent",
+        "--chain",
+        "other_chain",
+        "--branch",
+        "other_branch",
+    ];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "some_branch_1\n");
+
+    // the current branch is not part of the chain, so the parent of its last branch is printed
+    let args: Vec<&str> = vec!["parent", "--chain", "other_chain"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "some_branch_1\n");
+
+    let args: Vec<&str> = vec![
+        "parent",
+        "--chain",
+        "other_chain",
+        "--branch",
+        "some_branch_2",
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Branch some_branch_2 is not part of chain other_chain"));
+
+    // errors are reported on stderr only
+    checkout_branch(&repo, "master");
+
+    let args: Vec<&str> = vec!["parent"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Branch is not part of any chain: master")
+    );
+
+    let args: Vec<&str> = vec!["root", "--chain", "does_not_exist"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Chain does not exist: does_not_exist")
+    );
+
+    teardown_git_repo(repo_name);
+}
@@ -1,12 +1,14 @@
 use console;
 
-use git2::RepositoryState;
+use git2::{RepositoryState, Signature, Time};
 
 pub mod common;
 use common::{
-    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
-    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin_expect_err,
-    run_test_bin_expect_ok, run_test_bin_for_rebase, setup_git_repo, teardown_git_repo,
+    branch_exists, checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_bare_repo, generate_path_to_repo, get_current_branch_name, run_git_command,
+    run_test_bin_expect_err, run_test_bin_expect_ok, run_test_bin_for_rebase,
+    run_test_bin_with_env, setup_git_bare_repo, setup_git_repo, stage_everything,
+    teardown_git_bare_repo, teardown_git_repo,
 };
 
 #[test]
@@ -172,8 +174,18 @@ chain_name
     assert!(
         String::from_utf8_lossy(&output.stdout).contains("Switching back to branch: some_branch_0")
     );
-    assert!(String::from_utf8_lossy(&output.stdout)
-        .contains("🎉 Successfully rebased chain chain_name"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains(
+        r#"
+📋 Rebase summary for chain chain_name:
+  some_branch_0: up-to-date with master
+  some_branch_1: replayed 1 commit onto some_branch_0
+  some_branch_1.5: replayed 1 commit onto some_branch_1
+  some_branch_2: replayed 1 commit onto some_branch_1.5
+  some_branch_2.5: replayed 1 commit onto some_branch_2
+  some_branch_3: replayed 1 commit onto some_branch_2.5
+
+🎉 Successfully rebased chain chain_name"#
+    ));
 
     let actual = console::strip_ansi_codes(&String::from_utf8_lossy(&output.stderr))
         .trim()
@@ -328,6 +340,16 @@ chain_name
     assert!(String::from_utf8_lossy(&output.stdout)
         .contains("Current branch some_branch_1 is up to date"));
 
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with(
+        r#"
+📋 Rebase summary for chain chain_name:
+  some_branch_1: up-to-date with master
+  some_branch_2: conflicts while replaying 1 commit onto some_branch_1
+      in: file_2.txt (last changed by some_branch_1)
+To resume, run: git chain rebase
+"#
+    ));
+
     assert_eq!(
         String::from_utf8_lossy(&output.stderr),
         r#"
@@ -1231,3 +1253,1429 @@ chain_name
 
     teardown_git_repo(repo_name);
 }
+
+#[test]
+fn rebase_subcommand_needs_rebase() {
+    let repo_name = "rebase_subcommand_needs_rebase";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    // create and checkout new branch named some_branch_2
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    // run git chain setup
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // git chain rebase records the base of each branch
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("Chain chain_name is already up-to-date.")
+    );
+
+    // rewind some_branch_1 to master; some_branch_2 still contains its old commit,
+    // and its ahead/behind counts look clean.
+    run_git_command(
+        &path_to_repo,
+        vec!["branch", "-f", "some_branch_1", "master"],
+    );
+
+    let args: Vec<&str> = vec![];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_2
+
+chain_name
+    ➜ some_branch_2 ⦁ 2 ahead ⦁ needs rebase
+      some_branch_1
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["status", "--short"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+➜ some_branch_2 +2 ⟳
+  some_branch_1
+"#
+        .trim_start()
+    );
+
+    // add a new commit to some_branch_1
+    {
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1.5");
+        commit_all(&repo, "message");
+        checkout_branch(&repo, "some_branch_2");
+    };
+
+    // git chain rebase
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("🎉 Successfully rebased chain chain_name"));
+
+    let args: Vec<&str> = vec![];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_2
+
+chain_name
+    ➜ some_branch_2 ⦁ 1 ahead
+      some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_commit_dates() {
+    let repo_name = "rebase_subcommand_commit_dates";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // create a commit on branch_name authored and committed at a fixed time in the past
+    let commit_at_fixed_time = |branch_name: &str, file_name: &str| {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, file_name, "contents");
+
+        let tree_oid = stage_everything(&repo);
+        let tree = repo.find_tree(tree_oid).unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        let signature = Signature::new("name", "email", &Time::new(1_000_000_000, 0)).unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "message",
+            &tree,
+            &[&parent],
+        )
+        .unwrap();
+    };
+
+    commit_at_fixed_time("some_branch_1", "file_1.txt");
+    commit_at_fixed_time("some_branch_2", "file_2.txt");
+
+    // run git chain setup
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // add commit to master
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "file_master.txt", "contents");
+        commit_all(&repo, "message");
+        checkout_branch(&repo, "some_branch_2");
+    };
+
+    let commit_times = |branch_name: &str| {
+        let commit = repo
+            .revparse_single(branch_name)
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        let author_time = commit.author().when().seconds();
+        let committer_time = commit.committer().when().seconds();
+        (author_time, committer_time)
+    };
+
+    // git chain rebase --committer-date-is-author-date
+    let args: Vec<&str> = vec!["rebase", "--committer-date-is-author-date"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("git rebase --keep-empty --committer-date-is-author-date --onto master"));
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("🎉 Successfully rebased chain chain_name"));
+
+    assert_eq!(
+        commit_times("some_branch_1"),
+        (1_000_000_000, 1_000_000_000)
+    );
+    assert_eq!(
+        commit_times("some_branch_2"),
+        (1_000_000_000, 1_000_000_000)
+    );
+
+    // add commit to master
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "file_master.txt", "contents 2");
+        commit_all(&repo, "message");
+        checkout_branch(&repo, "some_branch_2");
+    };
+
+    // git chain rebase --reset-author-date
+    let args: Vec<&str> = vec!["rebase", "--reset-author-date"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("🎉 Successfully rebased chain chain_name"));
+
+    let (author_time, committer_time) = commit_times("some_branch_1");
+    assert!(author_time > 1_000_000_000);
+    assert!(committer_time > 1_000_000_000);
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_interactive() {
+    let repo_name = "rebase_subcommand_interactive";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // create and checkout new branch named some_branch_1 with two commits
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1.5");
+        commit_all(&repo, "fixup message");
+    };
+
+    let args: Vec<&str> = vec!["init", "chain_name", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // add commit to master
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "file_master.txt", "contents");
+        commit_all(&repo, "message");
+        checkout_branch(&repo, "some_branch_1");
+    };
+
+    // stdin is not a terminal
+    let args: Vec<&str> = vec!["rebase", "--interactive"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("🛑 Unable to rebase interactively: stdin is not a terminal."));
+
+    // squash the second commit of some_branch_1 into the first one
+    let args: Vec<&str> = vec!["rebase", "--interactive"];
+    let output = run_test_bin_with_env(
+        &path_to_repo,
+        args,
+        vec![("GIT_SEQUENCE_EDITOR", "sed -i -e '2s/^pick/fixup/'")],
+    );
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("git rebase --keep-empty --interactive --onto master"));
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("🎉 Successfully rebased chain chain_name"));
+
+    let args: Vec<&str> = vec![];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_1
+
+chain_name
+    ➜ some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+fn setup_chain_with_landed_branch(repo_name: &str) -> git2::Repository {
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // the changes of some_branch_1 land on master
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "some_branch_1.txt", "contents");
+    commit_all(&repo, "landed some_branch_1");
+    checkout_branch(&repo, "some_branch_1");
+
+    repo
+}
+
+#[test]
+fn rebase_subcommand_empty_branch() {
+    let repo_name = "rebase_subcommand_empty_branch";
+    let _repo = setup_chain_with_landed_branch(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    // without a confirmation, the empty branch is kept in the chain
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(
+        "⚠️  Branch some_branch_1 has no commits after the rebase. Its changes are already in master."
+    ));
+    assert!(stdout
+        .contains("To remove a branch from the chain, check it out and run: git chain remove"));
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("some_branch_1"));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_delete_empty_branch() {
+    let repo_name = "rebase_subcommand_delete_empty_branch";
+    let repo = setup_chain_with_landed_branch(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let args: Vec<&str> = vec!["rebase", "--yes", "--delete-empty"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Removed branch some_branch_1 from chain chain_name"));
+    assert!(stdout.contains("Switching to branch: master"));
+    assert!(stdout.contains("Deleted branch some_branch_1"));
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+    assert!(!branch_exists(&repo, "some_branch_1"));
+
+    checkout_branch(&repo, "some_branch_2");
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_2
+
+chain_name
+    ➜ some_branch_2 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_timings() {
+    let repo_name = "rebase_subcommand_timings";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "file_master.txt", "contents master");
+        commit_all(&repo, "master commit");
+        checkout_branch(&repo, "some_branch_2");
+    };
+
+    // without --timings, no report is printed
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Timings"));
+
+    let args: Vec<&str> = vec!["rebase", "--timings"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let report = &stdout[stdout.find("⏱  Timings (total ").unwrap()..];
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("  some_branch_1: "));
+    assert!(lines[1].contains("(merge-base "));
+    assert!(lines[2].starts_with("  some_branch_2: "));
+    assert!(lines[3].starts_with("  per phase: merge-base "));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_strategy_option() {
+    let repo_name = "rebase_subcommand_strategy_option";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "generated.txt", "version 1");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // some_branch_1 regenerates the file
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "generated.txt", "version 2 of some_branch_1");
+        commit_all(&repo, "regenerate");
+    };
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "some_branch_1"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["strategy"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Branch has no strategy option: some_branch_1\n"
+    );
+
+    // the root branch also regenerates the file, which conflicts
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "generated.txt", "version 2 of master");
+        commit_all(&repo, "regenerate");
+        checkout_branch(&repo, "some_branch_1");
+    };
+
+    // the changes of the branch being rebased win
+    let args: Vec<&str> = vec!["strategy", "set", "theirs", "--branch", "some_branch_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Set the strategy option of branch some_branch_1 to: theirs\n"
+    );
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("git rebase --keep-empty --strategy-option=theirs --onto master"));
+    assert_eq!(repo.state(), RepositoryState::Clean);
+    assert_eq!(
+        std::fs::read_to_string(path_to_repo.join("generated.txt")).unwrap(),
+        "version 2 of some_branch_1\n"
+    );
+
+    // the strategy option given to rebase overrides the one of the branch
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "generated.txt", "version 3 of master");
+        commit_all(&repo, "regenerate");
+        checkout_branch(&repo, "some_branch_1");
+    };
+
+    let args: Vec<&str> = vec!["rebase", "-X", "ours"];
+    run_test_bin_for_rebase(&path_to_repo, args);
+    assert_eq!(repo.state(), RepositoryState::Clean);
+    assert_eq!(
+        std::fs::read_to_string(path_to_repo.join("generated.txt")).unwrap(),
+        "version 3 of master\n"
+    );
+
+    let args: Vec<&str> = vec!["strategy", "clear"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Cleared the strategy option of branch: some_branch_1\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_reconcile() {
+    let repo_name = "rebase_subcommand_reconcile";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--all", "--set-upstream", "origin"],
+    );
+
+    // a reviewer pushes a fix to some_branch_1, and master moves on
+    {
+        checkout_branch(&repo, "some_branch_1");
+        create_branch(&repo, "reviewer");
+        checkout_branch(&repo, "reviewer");
+        create_new_file(&path_to_repo, "file_review.txt", "review");
+        commit_all(&repo, "reviewer fix");
+        run_git_command(
+            &path_to_repo,
+            vec!["push", "origin", "reviewer:some_branch_1"],
+        );
+        run_git_command(&path_to_repo, vec!["fetch", "origin"]);
+        run_git_command(&path_to_repo, vec!["branch", "-D", "reviewer"]);
+
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "file_master.txt", "master");
+        commit_all(&repo, "master change");
+        checkout_branch(&repo, "some_branch_2");
+    };
+
+    // by default, the commit of the reviewer is only reported
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with(
+        r#"
+⚠️  origin/some_branch_1 has 1 commits that are not in branch some_branch_1 (e.g. pushed by a reviewer).
+To include them before rebasing, run: git chain rebase --reconcile=rebase
+"#
+        .trim_start()
+    ));
+
+    let git_output = |args: Vec<&str>| -> String {
+        let output = run_git_command(&path_to_repo, args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    assert_eq!(
+        git_output(vec!["ls-tree", "--name-only", "some_branch_2"]),
+        "file_master.txt\nhello_world.txt\nsome_branch_1.txt\nsome_branch_2.txt"
+    );
+
+    // the commit of the reviewer is cherry-picked onto the rebased branch first
+    let args: Vec<&str> = vec!["rebase", "--reconcile=rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("✅ Included 1 commits of origin/some_branch_1 into some_branch_1"));
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+    assert_eq!(
+        git_output(vec!["ls-tree", "--name-only", "some_branch_2"]),
+        "file_master.txt\nfile_review.txt\nhello_world.txt\nsome_branch_1.txt\nsome_branch_2.txt"
+    );
+
+    // nothing is left to include
+    let args: Vec<&str> = vec!["rebase", "--reconcile=rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Included"));
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_signoff_and_trailers() {
+    let repo_name = "rebase_subcommand_signoff_and_trailers";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "--add", "chain.trailer", "Chain: {chain}"],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "config",
+            "--add",
+            "chain.trailer",
+            "Reviewed-by: someone's bot",
+        ],
+    );
+
+    let trailers_of = |branch_name: &str| -> String {
+        let output = run_git_command(
+            &path_to_repo,
+            vec!["log", "-1", "--format=%(trailers)", branch_name],
+        );
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    for master_file in ["master_1.txt", "master_2.txt"] {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, master_file, "contents");
+        commit_all(&repo, "master change");
+        checkout_branch(&repo, "some_branch_2");
+
+        let args: Vec<&str> = vec!["rebase", "--signoff"];
+        run_test_bin_for_rebase(&path_to_repo, args);
+        assert_eq!(repo.state(), RepositoryState::Clean);
+
+        // the trailers are not repeated when the commits are rewritten again
+        for branch_name in ["some_branch_1", "some_branch_2"] {
+            assert_eq!(
+                trailers_of(branch_name),
+                "Signed-off-by: name <email>\nChain: chain_name\nReviewed-by: someone's bot"
+            );
+        }
+    }
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_from_to() {
+    let repo_name = "rebase_subcommand_from_to";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for (parent, branch_name) in [
+        ("master", "some_branch_1"),
+        ("some_branch_1", "some_branch_2"),
+        ("some_branch_2", "some_branch_3"),
+    ] {
+        checkout_branch(&repo, parent);
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // master and some_branch_1 both move
+    for branch_name in ["master", "some_branch_1"] {
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}_2.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let is_ancestor = |ancestor: &str, descendant: &str| {
+        let ancestor = repo.revparse_single(ancestor).unwrap().id();
+        let descendant = repo.revparse_single(descendant).unwrap().id();
+        repo.graph_descendant_of(descendant, ancestor).unwrap()
+    };
+
+    // only some_branch_2 is rebased
+    let args: Vec<&str> = vec!["rebase", "--from", "some_branch_2", "--to", "some_branch_2"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Only branch some_branch_2 of chain chain_name is selected.\n"));
+    assert!(stdout.ends_with("🎉 Successfully rebased chain chain_name\n"));
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+    assert!(is_ancestor("some_branch_1", "some_branch_2"));
+    assert!(!is_ancestor("master", "some_branch_1"));
+    assert!(!is_ancestor("some_branch_2", "some_branch_3"));
+
+    // --from alone selects the branches up to the last one
+    let args: Vec<&str> = vec!["rebase", "--from", "some_branch_3"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .starts_with("Only branch some_branch_3 of chain chain_name is selected.\n"));
+    assert!(is_ancestor("some_branch_2", "some_branch_3"));
+    assert!(!is_ancestor("master", "some_branch_1"));
+
+    let args: Vec<&str> = vec!["rebase", "--from", "some_branch_3", "--to", "some_branch_1"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to rebase chain: chain_name
+Branch some_branch_3 comes after branch some_branch_1 in chain chain_name
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["rebase", "--to", "master"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to rebase chain: chain_name
+Branch master is not part of chain chain_name
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_edited_squash() {
+    let repo_name = "rebase_subcommand_edited_squash";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // some_branch_1 has two commits, some_branch_2 has one commit
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "feature.txt", "line 1");
+        commit_all(&repo, "add feature");
+        create_new_file(&path_to_repo, "feature.txt", "line 1\nline 2");
+        commit_all(&repo, "extend feature");
+
+        create_branch(&repo, "some_branch_2");
+        checkout_branch(&repo, "some_branch_2");
+        create_new_file(&path_to_repo, "some_branch_2.txt", "contents");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // some_branch_1 is squashed and merged, and the squash commit is edited to add a changelog entry
+    {
+        checkout_branch(&repo, "master");
+        run_git_command(&path_to_repo, vec!["merge", "--squash", "some_branch_1"]);
+        create_new_file(&path_to_repo, "CHANGELOG.md", "- Add feature");
+        run_git_command(&path_to_repo, vec!["add", "CHANGELOG.md"]);
+        run_git_command(&path_to_repo, vec!["commit", "-m", "Add feature (#1)"]);
+        checkout_branch(&repo, "some_branch_2");
+    };
+
+    // the patch-id of the squash commit does not match the changes of some_branch_1
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("chain_name\n"));
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_with_env(
+        &path_to_repo,
+        args,
+        vec![("GIT_CHAIN_DETECT_EDITED_SQUASH", "true")],
+    );
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .starts_with("chain_name ⦁ 66% landed (1 of 2 branches, 2 of 3 commits)\n"));
+
+    run_git_command(
+        &path_to_repo,
+        vec!["config", "chain.detectEditedSquash", "true"],
+    );
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout
+        .contains("⚠️  Branch some_branch_1 is detected to be squashed and merged onto master."));
+    assert!(stdout.ends_with("🎉 Successfully rebased chain chain_name\n"));
+    assert_eq!(repo.state(), RepositoryState::Clean);
+
+    let master = repo.revparse_single("master").unwrap().id();
+    assert_eq!(repo.revparse_single("some_branch_1").unwrap().id(), master);
+    assert_eq!(
+        repo.revparse_single("some_branch_2~1").unwrap().id(),
+        master
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_worktree() {
+    let repo_name = "rebase_subcommand_worktree";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "master.txt", "contents");
+    commit_all(&repo, "update master");
+    checkout_branch(&repo, "some_branch_1");
+
+    // uncommitted changes are kept
+    create_new_file(&path_to_repo, "hello_world.txt", "work in progress");
+
+    let args: Vec<&str> = vec!["rebase", "--worktree"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with(
+        r#"
+📋 Rebase summary for chain chain_name:
+  some_branch_1: replayed 1 commit onto master
+  some_branch_2: replayed 1 commit onto some_branch_1
+
+🎉 Successfully rebased chain chain_name
+"#
+    ));
+
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+    assert_eq!(
+        std::fs::read_to_string(path_to_repo.join("hello_world.txt")).unwrap(),
+        "work in progress\n"
+    );
+    assert!(path_to_repo.join("master.txt").exists());
+
+    let output = run_git_command(&path_to_repo, vec!["status", "--porcelain"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        " M hello_world.txt\n"
+    );
+
+    let output = run_git_command(&path_to_repo, vec!["worktree", "list"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+    let output = run_git_command(&path_to_repo, vec!["for-each-ref", "refs/chain-rebase/"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+chain_name
+      some_branch_2 ⦁ 1 ahead
+    ➜ some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    // a conflict leaves every branch as it was
+    run_git_command(&path_to_repo, vec!["stash"]);
+    create_new_file(&path_to_repo, "some_branch_2.txt", "conflict");
+    commit_all(&repo, "add conflict");
+    let some_branch_2 = run_git_command(&path_to_repo, vec!["rev-parse", "some_branch_2"]).stdout;
+
+    let args: Vec<&str> = vec!["rebase", "--worktree"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout).contains(
+        r#"
+📋 Rebase summary for chain chain_name:
+  some_branch_1: up-to-date with master
+  some_branch_2: conflicts while replaying 1 commit onto some_branch_1
+      in: some_branch_2.txt (last changed by some_branch_1)
+"#
+    ));
+    assert!(String::from_utf8_lossy(&output.stderr).ends_with(
+        r#"
+🛑 Unable to rebase branch some_branch_2 onto some_branch_1 in a temporary worktree.
+No branches were updated, and your working directory was left alone.
+To resolve the conflicts, run git chain rebase without --worktree.
+"#
+    ));
+
+    assert_eq!(repo.state(), RepositoryState::Clean);
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+    assert_eq!(
+        run_git_command(&path_to_repo, vec!["rev-parse", "some_branch_2"]).stdout,
+        some_branch_2
+    );
+    let output = run_git_command(&path_to_repo, vec!["worktree", "list"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_keep_base() {
+    let repo_name = "rebase_subcommand_keep_base";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "master.txt", "contents");
+    commit_all(&repo, "master change");
+    checkout_branch(&repo, "some_branch_2");
+
+    let is_ancestor = |ancestor: &str, descendant: &str| -> bool {
+        run_git_command(
+            &path_to_repo,
+            vec!["merge-base", "--is-ancestor", ancestor, descendant],
+        )
+        .status
+        .success()
+    };
+    let rev_parse = |revision: &str| -> String {
+        let output = run_git_command(&path_to_repo, vec!["rev-parse", revision]);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let orig_some_branch_1 = rev_parse("some_branch_1");
+
+    // The commits are rewritten with --signoff, but stay on their merge-base with master.
+    let args: Vec<&str> = vec!["rebase", "--keep-base", "--signoff"];
+    run_test_bin_for_rebase(&path_to_repo, args);
+    assert_eq!(repo.state(), RepositoryState::Clean);
+
+    assert_ne!(rev_parse("some_branch_1"), orig_some_branch_1);
+    assert_eq!(
+        rev_parse("some_branch_1~1"),
+        rev_parse("master~1"),
+        "new commits of master are not pulled in"
+    );
+    assert!(!is_ancestor("master", "some_branch_1"));
+    assert_eq!(rev_parse("some_branch_2~1"), rev_parse("some_branch_1"));
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        let output = run_git_command(
+            &path_to_repo,
+            vec!["log", "-1", "--format=%(trailers)", branch_name],
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            "Signed-off-by: name <email>"
+        );
+    }
+
+    // The chain still needs to be rebased onto master.
+    let args: Vec<&str> = vec!["rebase"];
+    run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(is_ancestor("master", "some_branch_1"));
+    assert!(is_ancestor("some_branch_1", "some_branch_2"));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_unrelated_history() {
+    let repo_name = "rebase_subcommand_unrelated_history";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // a branch with a history of its own, and a branch on top of it
+    run_git_command(&path_to_repo, vec!["checkout", "--orphan", "unrelated"]);
+    run_git_command(&path_to_repo, vec!["rm", "-r", "-q", "--cached", "."]);
+    run_git_command(&path_to_repo, vec!["clean", "-f", "-q"]);
+    create_new_file(&path_to_repo, "unrelated.txt", "contents");
+    run_git_command(&path_to_repo, vec!["add", "unrelated.txt"]);
+    run_git_command(&path_to_repo, vec!["commit", "-q", "-m", "unrelated"]);
+
+    create_branch(&repo, "some_branch_2");
+    checkout_branch(&repo, "some_branch_2");
+    create_new_file(&path_to_repo, "some_branch_2.txt", "contents");
+    commit_all(&repo, "message");
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "unrelated",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // unrelated is not rebased onto master
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to rebase chain chain_name: some of its branches share no history with their parent branch.
+  - unrelated (parent branch: master)
+To leave these branches as they are, and rebase the other branches, run again with --allow-unrelated.
+"#
+        .trim_start()
+    );
+
+    let rev_parse = |revision: &str| -> String {
+        let output = run_git_command(&path_to_repo, vec!["rev-parse", revision]);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let orig_unrelated = rev_parse("unrelated");
+
+    checkout_branch(&repo, "unrelated");
+    create_new_file(&path_to_repo, "unrelated_2.txt", "contents");
+    commit_all(&repo, "unrelated change");
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["rebase", "--allow-unrelated"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("⚠️  Branch unrelated shares no history with master. Skipping."));
+    assert!(stdout.contains("🎉 Successfully rebased chain chain_name"));
+
+    assert_eq!(rev_parse("unrelated~1"), orig_unrelated);
+    assert_eq!(rev_parse("some_branch_2~1"), rev_parse("unrelated"));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_update_root() {
+    let repo_name = "rebase_subcommand_update_root";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        &path_to_repo,
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--quiet", "--set-upstream", "origin", "master"],
+    );
+
+    create_branch(&repo, "some_branch_1");
+    checkout_branch(&repo, "some_branch_1");
+    create_new_file(&path_to_repo, "some_branch_1.txt", "contents");
+    commit_all(&repo, "message");
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "some_branch_1"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // master moves on the remote, but not locally
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "master.txt", "contents");
+    commit_all(&repo, "master");
+    run_git_command(&path_to_repo, vec!["push", "--quiet", "origin", "master"]);
+    run_git_command(&path_to_repo, vec!["reset", "--quiet", "--hard", "HEAD~1"]);
+    checkout_branch(&repo, "some_branch_1");
+
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_1
+
+chain_name
+    ➜ some_branch_1 ⦁ 1 ahead
+      master (root branch)
+
+⚠️  Root branch master is 1 commit behind its upstream branch origin/master.
+The branches of chain chain_name are not based on the latest commits of origin/master.
+To update the root branch, and rebase the chain onto it, run: git chain rebase --update-root
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["rebase", "--update-root"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("✅ Updated root branch master to origin/master (1 new commit).\n"));
+    assert!(stdout.contains("🎉 Successfully rebased chain chain_name"));
+
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["rev-list", "--count", "origin/master..some_branch_1"],
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "1");
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["rev-list", "--count", "some_branch_1..origin/master"],
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "0");
+
+    // the root branch is up-to-date
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("⚠️"));
+
+    // a root branch that diverged from its upstream is not updated
+    checkout_branch(&repo, "master");
+    run_git_command(&path_to_repo, vec!["reset", "--quiet", "--hard", "HEAD~1"]);
+    create_new_file(&path_to_repo, "local.txt", "contents");
+    commit_all(&repo, "local");
+    checkout_branch(&repo, "some_branch_1");
+
+    let args: Vec<&str> = vec!["rebase", "--update-root"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to update root branch master: it has diverged from its upstream branch origin/master.
+Merge or rebase it onto origin/master, then run again.
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_conflict_owners() {
+    let repo_name = "rebase_subcommand_conflict_owners";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // some_branch_3 changes a file of some_branch_1, and a file of master
+    for (branch_name, file_names) in [
+        ("some_branch_1", vec!["shared.txt"]),
+        ("some_branch_2", vec!["some_branch_2.txt"]),
+        ("some_branch_3", vec!["shared.txt", "hello_world.txt"]),
+    ] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        for file_name in file_names {
+            create_new_file(&path_to_repo, file_name, branch_name);
+        }
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "hello_world.txt", "conflict");
+    commit_all(&repo, "conflict");
+    checkout_branch(&repo, "some_branch_1");
+    create_new_file(&path_to_repo, "shared.txt", "conflict");
+    commit_all(&repo, "conflict");
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with(
+        r#"
+  some_branch_3: conflicts while replaying 1 commit onto some_branch_2
+      in: hello_world.txt (last changed by master), shared.txt (last changed by some_branch_1)
+To resume, run: git chain rebase
+"#
+    ));
+
+    run_git_command(&path_to_repo, vec!["rebase", "--abort"]);
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn rebase_subcommand_original_branch() {
+    let repo_name = "rebase_subcommand_original_branch";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    for (branch_name, file_name) in [
+        ("some_branch_1", "file_1.txt"),
+        ("some_branch_2", "file_2.txt"),
+    ] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, file_name, "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // create a conflict
+    checkout_branch(&repo, "some_branch_1");
+    create_new_file(&path_to_repo, "file_2.txt", "conflict");
+    commit_all(&repo, "add conflict");
+    checkout_branch(&repo, "master");
+
+    let args: Vec<&str> = vec!["rebase", "--chain", "chain_name"];
+    run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(repo.state(), RepositoryState::RebaseInteractive);
+
+    // aborting leaves the branch with the conflicts checked out
+    run_git_command(&path_to_repo, vec!["rebase", "--abort"]);
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+    run_git_command(
+        &path_to_repo,
+        vec!["branch", "--force", "some_branch_1", "some_branch_1~1"],
+    );
+
+    // the resumed rebase switches back to the branch that the first rebase started from
+    let args: Vec<&str> = vec!["rebase", "--chain", "chain_name"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Switching back to branch: master"));
+    assert_eq!(&get_current_branch_name(&repo), "master");
+    assert!(!path_to_repo.join(".git/chain/original-branch").exists());
+
+    // with --stay, the rebase ends on the last rebased branch
+    create_new_file(&path_to_repo, "file_3.txt", "contents 3");
+    commit_all(&repo, "master message");
+    let args: Vec<&str> = vec!["rebase", "--chain", "chain_name", "--stay"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("🎉 Successfully rebased chain chain_name"));
+    assert!(!stdout.contains("Switching back"));
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+    teardown_git_repo(repo_name);
+}
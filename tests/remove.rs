@@ -0,0 +1,238 @@
+use std::path::PathBuf;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_bare_repo, generate_path_to_repo, run_git_command, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_bare_repo, setup_git_repo, teardown_git_bare_repo,
+    teardown_git_repo,
+};
+
+#[test]
+fn remove_subcommand_chain() {
+    let repo_name = "remove_subcommand_chain";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for (parent, branch_name) in [
+        ("master", "some_branch_1"),
+        ("some_branch_1", "some_branch_2"),
+    ] {
+        checkout_branch(&repo, parent);
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["note", "set", "needs review"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let chain_order_1 = run_git_command(
+        &path_to_repo,
+        vec!["config", "branch.some_branch_1.chain-order"],
+    );
+    let chain_order_1 = String::from_utf8_lossy(&chain_order_1.stdout)
+        .trim()
+        .to_string();
+    let chain_order_2 = run_git_command(
+        &path_to_repo,
+        vec!["config", "branch.some_branch_2.chain-order"],
+    );
+    let chain_order_2 = String::from_utf8_lossy(&chain_order_2.stdout)
+        .trim()
+        .to_string();
+
+    let preview = format!(
+        r#"
+The following branches will be removed from chain chain_name (the branches themselves are kept):
+
+some_branch_1
+some_branch_2
+
+The following chain metadata will be removed from the git config:
+
+branch.some_branch_1.chain-name = chain_name
+branch.some_branch_1.chain-order = {}
+branch.some_branch_1.root-branch = master
+branch.some_branch_2.chain-name = chain_name
+branch.some_branch_2.chain-order = {}
+branch.some_branch_2.root-branch = master
+branch.some_branch_2.chain-note = needs review
+
+"#,
+        chain_order_1, chain_order_2
+    );
+
+    let args: Vec<&str> = vec!["remove", "--chain", "chain_name", "--dry-run"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "{}This was a dry-run, no chain deleted!\n",
+            preview.trim_start()
+        )
+    );
+
+    // without a terminal, deleting the chain requires --yes
+    let args: Vec<&str> = vec!["remove", "--chain", "chain_name"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "Confirmation required: Delete chain chain_name and remove its 2 branches from it?"
+    ));
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("some_branch_2"));
+
+    let args: Vec<&str> = vec!["remove", "--chain", "chain_name", "--yes"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "{}Successfully deleted chain: chain_name\n",
+            preview.trim_start()
+        )
+    );
+
+    let output = run_git_command(&path_to_repo, vec!["config", "--get-regexp", "^branch\\."]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("chain"));
+
+    // the branches are kept
+    let output = run_git_command(&path_to_repo, vec!["branch", "--list", "some_branch_*"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 2);
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "some_branch_1"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["remove", "--chain", "chain_name"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "Confirmation required: Delete chain chain_name and remove its 1 branch from it?"
+    ));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn remove_subcommand_last_branch() {
+    let repo_name = "remove_subcommand_last_branch";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // the layout of the chain is kept in refs/chains/chain_name once published
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "origin", "master", "some_branch_1", "some_branch_2"],
+    );
+    run_test_bin_expect_ok(&path_to_repo, vec!["publish"]);
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["for-each-ref", "--format=%(refname)", "refs/chains/"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "refs/chains/chain_name\n"
+    );
+
+    let args: Vec<&str> = vec!["remove"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Removed branch some_branch_2 from chain chain_name
+Its root branch was: master
+"#
+        .trim_start()
+    );
+
+    // removing the last branch deletes the chain
+    checkout_branch(&repo, "some_branch_1");
+    let args: Vec<&str> = vec!["remove"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Removed branch some_branch_1 from chain chain_name
+Its root branch was: master
+Deleted chain chain_name, as it has no branches left.
+"#
+        .trim_start()
+    );
+
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["for-each-ref", "--format=%(refname)", "refs/chains/"],
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("No chains to list."));
+
+    // the published layout is kept on the remote
+    let output = run_git_command(
+        &path_to_bare_repo,
+        vec!["for-each-ref", "--format=%(refname)", "refs/chains/"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "refs/chains/chain_name\n"
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
@@ -0,0 +1,232 @@
+use std::fs;
+use std::path::PathBuf;
+
+pub mod common;
+use common::{
+    branch_exists, checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_bare_repo, generate_path_to_repo, get_current_branch_name, run_git_command,
+    run_test_bin_expect_err, run_test_bin_expect_ok, run_test_bin_with_env, setup_git_bare_repo,
+    setup_git_repo, teardown_git_bare_repo, teardown_git_repo,
+};
+
+// A stand-in for gh, which logs its arguments and renames branches in the bare repository.
+const FAKE_GH: &str = r#"#!/bin/sh
+echo "$@" >> "$GH_LOG"
+if [ "$1" = "api" ]; then
+  branch_name=${4#*/branches/}
+  branch_name=${branch_name%/rename}
+  git --git-dir="$BARE_REPO" branch -m "$branch_name" "${6#new_name=}"
+fi
+"#;
+
+#[test]
+fn rename_prefix_subcommand() {
+    let repo_name = "rename_prefix_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["user/foo/a", "user/foo/b", "other"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(
+            &path_to_repo,
+            &format!("{}.txt", branch_name.replace('/', "_")),
+            "contents",
+        );
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "user/foo/a", "user/foo/b"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    // a chain rooted at a branch of the chain
+    let args: Vec<&str> = vec!["setup", "other_chain", "user/foo/b", "other"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    checkout_branch(&repo, "user/foo/b");
+
+    let args: Vec<&str> = vec!["rename-prefix", "--dry-run", "user/foo/*", "user/foo-v2/*"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Would rename branch user/foo/a to user/foo-v2/a
+Would rename branch user/foo/b to user/foo-v2/b
+
+This was a dry-run, no branches renamed!
+"#
+        .trim_start()
+    );
+    assert!(branch_exists(&repo, "user/foo/a"));
+
+    let args: Vec<&str> = vec!["rename-prefix", "user/foo/*", "user/foo-v2/*"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Renamed branch user/foo/a to user/foo-v2/a
+Renamed branch user/foo/b to user/foo-v2/b
+🔗 Succesfully renamed 2 branches of chain chain_name
+
+chain_name
+    ➜ user/foo-v2/b ⦁ 1 ahead
+      user/foo-v2/a ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+    assert!(!branch_exists(&repo, "user/foo/a"));
+    assert!(!branch_exists(&repo, "user/foo/b"));
+    assert_eq!(&get_current_branch_name(&repo), "user/foo-v2/b");
+
+    // the other chain follows its renamed root branch
+    let output = run_git_command(&path_to_repo, vec!["config", "branch.other.root-branch"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "user/foo-v2/b\n");
+
+    let args: Vec<&str> = vec!["rename-prefix", "user/foo/", "user/foo-v2/"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 No branches of chain chain_name would be renamed.\n"
+    );
+
+    // with a regex, only the matching branches are renamed
+    let args: Vec<&str> = vec!["rename-prefix", "--regex", "^user/foo-v2/(a)$", "u/$1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .starts_with("Renamed branch user/foo-v2/a to u/a\n🔗 Succesfully renamed 1 branches"));
+
+    // existing branches are not overwritten
+    create_branch(&repo, "user/foo-v2/b-v2");
+    let args: Vec<&str> = vec!["rename-prefix", "--suffix", "*", "*-v2"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 Unable to rename branch user/foo-v2/b. Branch already exists: user/foo-v2/b-v2\n"
+    );
+    assert!(branch_exists(&repo, "u/a"));
+
+    teardown_git_repo(repo_name);
+}
+
+#[cfg(unix)]
+#[test]
+fn rename_prefix_subcommand_remote() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "rename_prefix_subcommand_remote";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["user/foo/a", "user/foo/b"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(
+            &path_to_repo,
+            &format!("{}.txt", branch_name.replace('/', "_")),
+            "contents",
+        );
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "user/foo/a", "user/foo/b"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--quiet", "origin", "master", "user/foo/a"],
+    );
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "branch",
+            "--set-upstream-to",
+            "origin/user/foo/a",
+            "user/foo/a",
+        ],
+    );
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+    let path_to_log = path_to_bin.join("gh.log");
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![
+        ("PATH", path.as_str()),
+        ("GH_LOG", path_to_log.to_str().unwrap()),
+        ("BARE_REPO", path_to_bare_repo.as_str()),
+    ];
+
+    let args: Vec<&str> = vec!["rename-prefix", "--remote", "user/foo/", "user/foo-v2/"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Renamed branch user/foo/a to user/foo-v2/a
+Renamed branch user/foo/b to user/foo-v2/b
+Renamed branch user/foo/a to user/foo-v2/a on remote origin
+Did not rename branch user/foo/b on the remote: it has no upstream
+🔗 Succesfully renamed 2 branches of chain chain_name
+
+chain_name
+    ➜ user/foo-v2/b ⦁ 1 ahead
+      user/foo-v2/a ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+    assert_eq!(
+        fs::read_to_string(&path_to_log).unwrap(),
+        "api --method POST repos/{owner}/{repo}/branches/user/foo/a/rename -f new_name=user/foo-v2/a\n"
+    );
+
+    // the renamed branch tracks its renamed upstream branch
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["rev-parse", "--abbrev-ref", "user/foo-v2/a@{upstream}"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "origin/user/foo-v2/a\n"
+    );
+    let output = run_git_command(&path_to_repo, vec!["branch", "--remotes"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "  origin/master\n  origin/user/foo-v2/a\n"
+    );
+    let output = run_git_command(&path_to_bare_repo, vec!["branch", "--list", "user/*"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "  user/foo-v2/a\n");
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
@@ -0,0 +1,83 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_test_bin_expect_err, run_test_bin_expect_ok, setup_git_repo,
+    teardown_git_repo,
+};
+
+#[test]
+fn reorder_subcommand_topological() {
+    let repo_name = "reorder_subcommand_topological";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // master <- api <- ui <- docs, and hotfix and sibling off master
+    for (parent, branch_name) in [
+        ("master", "api"),
+        ("api", "ui"),
+        ("ui", "docs"),
+        ("master", "hotfix"),
+        ("master", "sibling"),
+    ] {
+        checkout_branch(&repo, parent);
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    // the configured order does not match the ancestry of the branches
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "ui", "docs", "api"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "ui");
+
+    let args: Vec<&str> = vec!["reorder", "--topological"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🔗 Succesfully reordered chain chain_name to match the ancestry of its branches.
+
+chain_name
+      docs ⦁ 1 ahead
+    ➜ ui ⦁ 1 ahead
+      api ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["reorder", "--topological"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Chain chain_name is already in topological order.\n"
+    );
+
+    // branches that are not stacked on one another
+    let args: Vec<&str> = vec!["setup", "other_chain", "master", "hotfix", "sibling"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["reorder", "--topological", "--chain", "other_chain"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to reorder chain: other_chain
+Branches hotfix and sibling are not stacked on one another.
+To move a branch of the chain, run: git chain move --before=<branch_name> or git chain move --after=<branch_name>
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
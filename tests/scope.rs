@@ -0,0 +1,141 @@
+use std::fs;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn scope_subcommand() {
+    let repo_name = "scope_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    fs::create_dir_all(path_to_repo.join("services/api")).unwrap();
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "services/api/file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    // create and checkout new branch named some_branch_2
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        create_new_file(&path_to_repo, "services/api/file_2.txt", "contents 2");
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    // git chain changes without a scope
+    let args: Vec<&str> = vec!["init", "chain_name", "master", "--first"];
+    checkout_branch(&repo, "some_branch_1");
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["changes"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Chain has no scope: chain_name"));
+
+    // branches added to a scoped chain inherit its scope
+    let args: Vec<&str> = vec!["scope", "./services/api/"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Set the scope of chain chain_name to: services/api\n"
+    );
+
+    checkout_branch(&repo, "some_branch_2");
+    let args: Vec<&str> = vec!["init", "chain_name"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🔗 Succesfully set up branch: some_branch_2
+
+chain_name (scope: services/api)
+    ➜ some_branch_2 ⦁ 1 ahead
+      some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["changes"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+some_branch_1
+  ✅ No changes outside of scope
+some_branch_2
+  ⚠️  file_2.txt
+
+⚠️  1 files changed outside of scope services/api in chain: chain_name
+"#
+        .trim_start()
+    );
+
+    // git chain scope --clear
+    let args: Vec<&str> = vec!["scope", "--clear"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["scope"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Chain has no scope: chain_name\n"
+    );
+
+    // git chain setup --scope
+    let args: Vec<&str> = vec![
+        "setup",
+        "other_chain",
+        "master",
+        "some_branch_1",
+        "--scope",
+        ".",
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid scope: ."));
+
+    let args: Vec<&str> = vec!["remove", "--chain", "chain_name", "--yes"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "--scope",
+        "services",
+    ];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("chain_name (scope: services)"));
+
+    teardown_git_repo(repo_name);
+}
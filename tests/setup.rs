@@ -1,8 +1,11 @@
+use std::path::PathBuf;
+
 pub mod common;
 use common::{
-    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
-    generate_path_to_repo, get_current_branch_name, run_test_bin_expect_ok, setup_git_repo,
-    teardown_git_repo,
+    checkout_branch, commit_all, create_branch, create_new_file, delete_local_branch,
+    first_commit_all, generate_path_to_bare_repo, generate_path_to_repo, get_current_branch_name,
+    run_git_command, run_test_bin_expect_err, run_test_bin_expect_ok, run_test_bin_for_rebase,
+    setup_git_bare_repo, setup_git_repo, teardown_git_bare_repo, teardown_git_repo,
 };
 
 #[test]
@@ -128,6 +131,12 @@ fn setup_subcommand() {
 
     assert_eq!(&get_current_branch_name(&repo), "some_branch_0");
 
+    // metadata left over from an earlier chain is not kept
+    repo.config()
+        .unwrap()
+        .set_str("branch.some_branch_1.chain-remote-oid", "stale")
+        .unwrap();
+
     // run git chain setup
     let args: Vec<&str> = vec![
         "setup",
@@ -159,6 +168,14 @@ chain_name
         .trim_start()
     );
 
+    assert!(repo
+        .config()
+        .unwrap()
+        .snapshot()
+        .unwrap()
+        .get_str("branch.some_branch_1.chain-remote-oid")
+        .is_err());
+
     // git chain
     let args: Vec<&str> = vec![];
     let output = run_test_bin_expect_ok(&path_to_repo, args);
@@ -182,3 +199,556 @@ chain_name
 
     teardown_git_repo(repo_name);
 }
+
+#[test]
+fn setup_subcommand_remote_root() {
+    let repo_name = "setup_subcommand_remote_root";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    run_git_command(&path_to_repo, vec!["push", "origin", "master"]);
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    // the root branch only exists as a remote-tracking branch
+    delete_local_branch(&repo, "master");
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "some_branch_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Using remote-tracking branch origin/master as the root branch.
+🔗 Succesfully set up chain: chain_name
+
+chain_name
+    ➜ some_branch_1 ⦁ 1 ahead
+      origin/master (root branch)
+"#
+        .trim_start()
+    );
+
+    // update master on the remote
+    {
+        create_branch(&repo, "temp");
+        checkout_branch(&repo, "temp");
+        run_git_command(&path_to_repo, vec!["reset", "--hard", "origin/master"]);
+        create_new_file(&path_to_repo, "file_master.txt", "contents");
+        commit_all(&repo, "message");
+        run_git_command(&path_to_repo, vec!["push", "origin", "temp:master"]);
+        run_git_command(&path_to_repo, vec!["fetch", "origin"]);
+        checkout_branch(&repo, "some_branch_1");
+        delete_local_branch(&repo, "temp");
+    };
+
+    let args: Vec<&str> = vec![];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_1
+
+chain_name
+    ➜ some_branch_1 ⦁ 1 ahead ⦁ 1 behind
+      origin/master (root branch)
+"#
+        .trim_start()
+    );
+
+    // git chain rebase
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("🎉 Successfully rebased chain chain_name"));
+
+    let args: Vec<&str> = vec![];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_1
+
+chain_name
+    ➜ some_branch_1 ⦁ 1 ahead
+      origin/master (root branch)
+"#
+        .trim_start()
+    );
+
+    // init without a root branch detects the default branch from origin/HEAD
+    run_git_command(
+        &path_to_repo,
+        vec![
+            "symbolic-ref",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/master",
+        ],
+    );
+
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    let args: Vec<&str> = vec!["init", "chain_name_2"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Using default branch master as the root branch."));
+    assert!(stdout.contains("Using remote-tracking branch origin/master as the root branch."));
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[test]
+fn setup_subcommand_tag_and_commit_root() {
+    let repo_name = "setup_subcommand_tag_and_commit_root";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    run_git_command(&path_to_repo, vec!["tag", "v1.0"]);
+
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    // the tag moves, e.g. a release is re-tagged with a fix
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "fix.txt", "fix");
+        commit_all(&repo, "fix");
+        run_git_command(&path_to_repo, vec!["tag", "-f", "v1.0"]);
+        checkout_branch(&repo, "some_branch_1");
+    };
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "v1.0", "some_branch_1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+🔗 Succesfully set up chain: chain_name
+
+chain_name
+    ➜ some_branch_1 ⦁ 1 ahead ⦁ 1 behind
+      v1.0 (root tag)
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(output.status.success());
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("some_branch_1 ⦁ 1 ahead\n"));
+
+    // landed branches are only detected against a root branch
+    let args: Vec<&str> = vec!["after-land"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to clean up landed branches for the chain: chain_name
+The chain is rooted at the tag v1.0, which is not a branch.
+"#
+        .trim_start()
+    );
+
+    // a revision other than a branch or tag is stored as the commit it points to
+    let first_commit = run_git_command(&path_to_repo, vec!["rev-parse", "master~1"]);
+    let first_commit = String::from_utf8_lossy(&first_commit.stdout)
+        .trim()
+        .to_string();
+
+    create_branch(&repo, "some_branch_2");
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["init", "other_chain", "master~1"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!(
+        "Using commit {} as the root of the chain.",
+        first_commit
+    )));
+
+    let args: Vec<&str> = vec!["list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains(&format!("{} (root commit)", first_commit)));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn setup_subcommand_invalid_names() {
+    let repo_name = "setup_subcommand_invalid_names";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec!["setup", "my chain", "master", "some_branch_1"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Invalid chain name: my chain
+Chain name contains whitespace.
+Chain names may only contain ASCII letters, digits, '-', '_', '.' and '/'.
+"#
+        .trim_start()
+    );
+
+    // control characters are escaped in the error
+    let args: Vec<&str> = vec!["init", "chain\x1b[31m", "master"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with(
+        "🛑 Invalid chain name: chain\\u{1b}[31m\nChain name contains a control character.\n"
+    ));
+
+    for chain_name in ["chain..name", "chain.lock", "chain/", "ch@in"] {
+        let args: Vec<&str> = vec!["init", chain_name, "master"];
+        let output = run_test_bin_expect_err(&path_to_repo, args);
+        assert!(String::from_utf8_lossy(&output.stderr)
+            .starts_with(&format!("🛑 Invalid chain name: {}\n", chain_name)));
+    }
+
+    let args: Vec<&str> = vec!["setup", "chain_name", "master", "some branch"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Invalid branch name: some branch
+Branch name contains whitespace.
+Branch names must follow the rules of git check-ref-format.
+"#
+        .trim_start()
+    );
+
+    // nothing was written to the git config
+    let output = run_git_command(&path_to_repo, vec!["config", "--get-regexp", "^branch\\."]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("chain-name"));
+
+    let args: Vec<&str> = vec!["init", "feature/chain_name-1.0", "master"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["rename", "feature chain"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("🛑 Invalid chain name: feature chain\n"));
+
+    let args: Vec<&str> = vec![
+        "copy",
+        "feature/chain_name-1.0",
+        "copy",
+        "--template",
+        "{branch} v2",
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Branch name contains whitespace: some_branch_1 v2"));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn setup_subcommand_remote_only_branch() {
+    let repo_name = "setup_subcommand_remote_only_branch";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    {
+        create_branch(&repo, "some_branch_2");
+        checkout_branch(&repo, "some_branch_2");
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    // some_branch_2 was pushed by a teammate, and only exists on the remote
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "origin", "master", "some_branch_2"],
+    );
+    checkout_branch(&repo, "some_branch_1");
+    delete_local_branch(&repo, "some_branch_2");
+
+    // without a terminal, creating the local branch requires --yes
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "Confirmation required: Branch some_branch_2 only exists as origin/some_branch_2. Create a local branch tracking it?"
+    ));
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+        "--yes",
+    ];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Created branch some_branch_2 tracking origin/some_branch_2
+🔗 Succesfully set up chain: chain_name
+
+chain_name
+      some_branch_2 ⦁ 1 ahead
+    ➜ some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["rev-parse", "--abbrev-ref", "some_branch_2@{upstream}"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "origin/some_branch_2"
+    );
+
+    // a branch that does not exist anywhere is still refused
+    let args: Vec<&str> = vec!["setup", "other_chain", "master", "does_not_exist", "--yes"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Branch does not exist: does_not_exist\n"
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[test]
+fn setup_subcommand_extend() {
+    let repo_name = "setup_subcommand_extend";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in [
+        "some_branch_0",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_3",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // without --extend, branches of the chain are refused
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Branch already part of a chain: some_branch_1"));
+
+    // new branches are inserted in the order they are listed
+    let args: Vec<&str> = vec![
+        "setup",
+        "--extend",
+        "chain_name",
+        "master",
+        "some_branch_0",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Added branch: some_branch_0
+Unchanged branch: some_branch_1
+Added branch: some_branch_2
+Unchanged branch: some_branch_3
+
+🔗 Succesfully extended chain: chain_name
+
+chain_name
+    ➜ some_branch_3 ⦁ 1 ahead
+      some_branch_2 ⦁ 1 ahead
+      some_branch_1 ⦁ 1 ahead
+      some_branch_0 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    // running it again changes nothing
+    let args: Vec<&str> = vec![
+        "setup",
+        "--extend",
+        "chain_name",
+        "master",
+        "some_branch_0",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with(
+        r#"
+Unchanged branch: some_branch_0
+Unchanged branch: some_branch_1
+Unchanged branch: some_branch_2
+Unchanged branch: some_branch_3
+"#
+        .trim_start()
+    ));
+
+    // the branches of the chain cannot be reordered
+    let args: Vec<&str> = vec![
+        "setup",
+        "--extend",
+        "chain_name",
+        "master",
+        "some_branch_2",
+        "some_branch_1",
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to extend chain: chain_name
+Its branches are listed in a different order than in the chain.
+To change the order of the branches of the chain, run: git chain reorder
+"#
+        .trim_start()
+    );
+
+    // nor moved to another root branch
+    create_branch(&repo, "other_root");
+    let args: Vec<&str> = vec![
+        "setup",
+        "--extend",
+        "chain_name",
+        "other_root",
+        "some_branch_1",
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to extend chain: chain_name
+Its root branch is master, not other_root.
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,113 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_git_command, run_test_bin_expect_err, run_test_bin_expect_ok,
+    setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn size_guard_subcommand() {
+    let repo_name = "size_guard_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // some_branch_1 has 1 commit with 1 changed line
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    // some_branch_2 has 3 commits with 4 changed lines
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_2.txt", "line 1\nline 2");
+        commit_all(&repo, "message");
+        create_new_file(&path_to_repo, "file_3.txt", "line 1");
+        commit_all(&repo, "message");
+        create_new_file(&path_to_repo, "file_4.txt", "line 1");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // without limits, the sizes are displayed
+    let args: Vec<&str> = vec!["size-guard", "--strict"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+some_branch_1 ⦁ 1 commits ⦁ 1 changed lines
+some_branch_2 ⦁ 3 commits ⦁ 4 changed lines
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec![
+        "size-guard",
+        "--max-commits",
+        "2",
+        "--max-changed-lines",
+        "3",
+    ];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+some_branch_1 ⦁ 1 commits ⦁ 1 changed lines
+some_branch_2 ⦁ 3 commits ⦁ 4 changed lines
+  ⚠️  Branch is too large: 3 commits (max 2), 4 changed lines (max 3)
+
+⚠️  1 branches of chain chain_name are too large. Consider splitting them into smaller branches.
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["size-guard", "--max-commits", "2", "--strict"];
+    run_test_bin_expect_err(&path_to_repo, args);
+
+    // limits from git config are displayed in the status
+    run_git_command(&path_to_repo, vec!["config", "chain.maxChangedLines", "3"]);
+
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_2
+
+chain_name
+    ➜ some_branch_2 ⦁ 3 ahead
+      some_branch_1 ⦁ 1 ahead
+      master (root branch)
+
+⚠️  Branch some_branch_2 is too large: 4 changed lines (max 3)
+"#
+        .trim_start()
+    );
+
+    // 0 disables the limit
+    let args: Vec<&str> = vec!["size-guard", "--max-changed-lines", "0", "--strict"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    teardown_git_repo(repo_name);
+}
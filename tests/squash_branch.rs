@@ -0,0 +1,192 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin_expect_err,
+    run_test_bin_expect_ok, run_test_bin_for_rebase, run_test_bin_with_env, setup_git_repo,
+    teardown_git_repo,
+};
+
+#[test]
+fn squash_branch_subcommand() {
+    let repo_name = "squash_branch_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // some_branch_1 has two commits; some_branch_2 has one commit
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_1a.txt", "contents 1a");
+        commit_all(&repo, "first change");
+        create_new_file(&path_to_repo, "file_1b.txt", "contents 1b");
+        commit_all(&repo, "second change");
+    };
+
+    {
+        create_branch(&repo, "some_branch_2");
+        checkout_branch(&repo, "some_branch_2");
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "third change");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let git_output = |args: Vec<&str>| -> String {
+        let output = run_git_command(&path_to_repo, args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    // a branch with a single commit has nothing to squash
+    let args: Vec<&str> = vec!["squash-branch"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Branch some_branch_2 has 1 commits of its own. Nothing to squash.\n"
+    );
+
+    checkout_branch(&repo, "some_branch_1");
+
+    // the combined commit message is accepted as is by the editor
+    let args: Vec<&str> = vec!["squash-branch"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_EDITOR", "true")]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Squashed 2 commits of branch some_branch_1 into one."));
+    assert!(stdout.contains("Switching back to branch: some_branch_1"));
+    assert!(stdout.contains(
+        "🎉 Successfully squashed branch some_branch_1 and rebased 1 dependent branches."
+    ));
+
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+    assert_eq!(
+        git_output(vec!["rev-list", "--count", "master..some_branch_1"]),
+        "1"
+    );
+    assert_eq!(
+        git_output(vec!["log", "-1", "--format=%B", "some_branch_1"]),
+        "first change\n\nsecond change"
+    );
+
+    // some_branch_2 is rebased onto the squashed commit
+    assert_eq!(
+        git_output(vec!["rev-list", "--count", "some_branch_1..some_branch_2"]),
+        "1"
+    );
+    assert_eq!(
+        git_output(vec!["rev-parse", "some_branch_2~1"]),
+        git_output(vec!["rev-parse", "some_branch_1"])
+    );
+    assert_eq!(
+        git_output(vec!["ls-tree", "--name-only", "some_branch_2"]),
+        "file_1a.txt\nfile_1b.txt\nfile_2.txt\nhello_world.txt"
+    );
+
+    // the commit message can be given instead of being edited
+    {
+        create_new_file(&path_to_repo, "file_1c.txt", "contents 1c");
+        commit_all(&repo, "fixup");
+    };
+
+    let args: Vec<&str> = vec!["squash-branch", "-m", "squashed change"];
+    run_test_bin_for_rebase(&path_to_repo, args);
+
+    assert_eq!(
+        git_output(vec!["log", "-1", "--format=%B", "some_branch_1"]),
+        "squashed change"
+    );
+    assert_eq!(
+        git_output(vec!["rev-parse", "some_branch_2~1"]),
+        git_output(vec!["rev-parse", "some_branch_1"])
+    );
+
+    // with --chain, a branch of the chain must be checked out
+    checkout_branch(&repo, "master");
+    let args: Vec<&str> = vec!["squash-branch", "--chain", "chain_name"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to squash the branch: the current branch is not part of chain chain_name.
+Check out one of its branches first, e.g.: git checkout some_branch_2
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn squash_branch_subcommand_failed_restack() {
+    let repo_name = "squash_branch_subcommand_failed_restack";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_1a.txt", "contents 1a");
+        commit_all(&repo, "first change");
+        create_new_file(&path_to_repo, "file_1b.txt", "contents 1b");
+        commit_all(&repo, "second change");
+    };
+
+    for branch_name in ["some_branch_2", "some_branch_3"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "some_branch_1");
+
+    // some_branch_3 is checked out in another worktree, so its rebase fails before it starts
+    run_git_command(
+        &path_to_repo,
+        vec!["worktree", "add", "worktree", "some_branch_3"],
+    );
+
+    let args: Vec<&str> = vec!["squash-branch"];
+    let output = run_test_bin_with_env(&path_to_repo, args, vec![("GIT_EDITOR", "true")]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Switching back to branch: some_branch_1")
+    );
+
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+
+    teardown_git_repo(repo_name);
+}
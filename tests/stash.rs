@@ -0,0 +1,121 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn stash_subcommand() {
+    let repo_name = "stash_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["stash", "push"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "No local changes to stash.\n"
+    );
+
+    create_new_file(&path_to_repo, "some_branch_2.txt", "work in progress");
+    let args: Vec<&str> = vec!["stash", "push", "-m", "half done"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "✅ Stashed the changes of branch some_branch_2 of chain chain_name: half done\n"
+    );
+
+    // stashes of branches outside of the chain are not listed
+    run_git_command(&path_to_repo, vec!["checkout", "-b", "other", "master"]);
+    create_new_file(&path_to_repo, "hello_world.txt", "Hello, other!");
+    run_git_command(&path_to_repo, vec!["stash", "push", "-m", "other"]);
+    checkout_branch(&repo, "some_branch_1");
+
+    let args: Vec<&str> = vec!["stash"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "stash@{1} some_branch_2: half done\n"
+    );
+
+    // the stash is popped onto the branch it was made on
+    let args: Vec<&str> = vec!["stash", "pop"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Switching to branch some_branch_2, where stash stash@{1} was made.
+✅ Popped stash stash@{1} onto branch some_branch_2: half done
+"#
+        .trim_start()
+    );
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+    assert_eq!(
+        std::fs::read_to_string(path_to_repo.join("some_branch_2.txt")).unwrap(),
+        "work in progress\n"
+    );
+
+    let args: Vec<&str> = vec!["stash", "list"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Chain chain_name has no stashes.\n"
+    );
+
+    let args: Vec<&str> = vec!["stash", "pop"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 Chain chain_name has no stashes.\n"
+    );
+
+    // with --here, the stash is popped onto the current branch
+    run_git_command(&path_to_repo, vec!["checkout", "some_branch_2.txt"]);
+    create_new_file(&path_to_repo, "hello_world.txt", "Hello, chain!");
+    let args: Vec<&str> = vec!["stash", "push"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    checkout_branch(&repo, "some_branch_1");
+
+    let args: Vec<&str> = vec!["stash", "pop", "--here"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+⚠️  Stash stash@{0} was made on branch some_branch_2; popping it onto branch some_branch_1.
+✅ Popped stash stash@{0} onto branch some_branch_1: WIP
+"#
+        .trim_start()
+    );
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+    assert_eq!(
+        std::fs::read_to_string(path_to_repo.join("hello_world.txt")).unwrap(),
+        "Hello, chain!\n"
+    );
+
+    teardown_git_repo(repo_name);
+}
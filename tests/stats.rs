@@ -0,0 +1,209 @@
+use std::fs;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_git_command, run_test_bin_expect_ok, run_test_bin_for_rebase,
+    run_test_bin_with_env, setup_git_repo, teardown_git_repo,
+};
+
+// A stand-in for gh. some_branch_1 has a pull request.
+const FAKE_GH: &str = r#"#!/bin/sh
+case "$1 $2 $3" in
+  "pr view some_branch_1")
+    printf '12\thttps://github.com/owner/repo/pull/12\tOPEN\tAPPROVED\tMERGEABLE\n'
+    ;;
+  *)
+    echo "no pull requests found for branch \"$3\"" >&2
+    exit 1
+    ;;
+esac
+"#;
+
+// Timestamps depend on when the test runs.
+fn without_timestamps(json: &str) -> String {
+    json.lines()
+        .map(|line| match line.split_once("_at\": ") {
+            Some((key, value)) if value.starts_with(|c: char| c.is_ascii_digit()) => {
+                let rest = value.trim_start_matches(|c: char| c.is_ascii_digit());
+                format!("{}_at\": <timestamp>{}", key, rest)
+            }
+            _ => line.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(unix)]
+#[test]
+fn stats_subcommand() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "stats_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // chain_1: some_branch_1 has 2 commits, some_branch_2 has 1 commit
+    {
+        create_branch(&repo, "some_branch_1");
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 2");
+        commit_all(&repo, "message");
+
+        create_branch(&repo, "some_branch_2");
+        checkout_branch(&repo, "some_branch_2");
+        create_new_file(&path_to_repo, "file_2.txt", "contents");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_1",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // chain_2: some_branch_4 no longer exists
+    {
+        checkout_branch(&repo, "master");
+        create_branch(&repo, "some_branch_3");
+        checkout_branch(&repo, "some_branch_3");
+        create_new_file(&path_to_repo, "file_3.txt", "contents");
+        commit_all(&repo, "message");
+
+        create_branch(&repo, "some_branch_4");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_2",
+        "master",
+        "some_branch_3",
+        "some_branch_4",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    run_git_command(
+        &path_to_repo,
+        vec!["update-ref", "-d", "refs/heads/some_branch_4"],
+    );
+
+    // master moves on; only chain_1 is rebased onto it
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "master.txt", "contents");
+    commit_all(&repo, "new commit");
+
+    checkout_branch(&repo, "some_branch_1");
+    let args: Vec<&str> = vec!["rebase"];
+    run_test_bin_for_rebase(&path_to_repo, args);
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![("PATH", path.as_str())];
+
+    let args: Vec<&str> = vec!["stats", "--all", "--format=json"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    assert_eq!(
+        without_timestamps(&String::from_utf8_lossy(&output.stdout)),
+        r#"
+{
+  "generated_at": <timestamp>,
+  "chains": [
+    {
+      "name": "chain_1",
+      "root_branch": "master",
+      "num_of_branches": 2,
+      "missing_branches": [],
+      "open_pull_requests": 1,
+      "last_commit_at": <timestamp>,
+      "last_rebased_at": <timestamp>,
+      "branches": [
+        {
+          "name": "some_branch_1",
+          "parent": "master",
+          "ahead": 2,
+          "behind": 0,
+          "first_commit_at": <timestamp>,
+          "last_commit_at": <timestamp>,
+          "pull_request": { "number": 12, "url": "https://github.com/owner/repo/pull/12", "state": "OPEN", "review_decision": "APPROVED" }
+        },
+        {
+          "name": "some_branch_2",
+          "parent": "some_branch_1",
+          "ahead": 1,
+          "behind": 0,
+          "first_commit_at": <timestamp>,
+          "last_commit_at": <timestamp>,
+          "pull_request": null
+        }
+      ]
+    },
+    {
+      "name": "chain_2",
+      "root_branch": "master",
+      "num_of_branches": 1,
+      "missing_branches": ["some_branch_4"],
+      "open_pull_requests": 0,
+      "last_commit_at": <timestamp>,
+      "last_rebased_at": null,
+      "branches": [
+        {
+          "name": "some_branch_3",
+          "parent": "master",
+          "ahead": 1,
+          "behind": 1,
+          "first_commit_at": <timestamp>,
+          "last_commit_at": <timestamp>,
+          "pull_request": null
+        }
+      ]
+    }
+  ]
+}
+"#
+        .trim()
+    );
+
+    // the current chain, as text
+    let args: Vec<&str> = vec!["stats"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("chain_1 ⦁ 2 branches ⦁ last commit "));
+    assert!(lines[0].contains(" ⦁ last rebased "));
+    assert!(lines[1].starts_with("  some_branch_1 ⦁ 2 ahead ⦁ 0 behind ⦁ first commit "));
+    assert!(lines[1].ends_with(" ⦁ PR #12 OPEN"));
+    assert!(lines[2].starts_with("  some_branch_2 ⦁ 1 ahead ⦁ 0 behind ⦁ first commit "));
+
+    let args: Vec<&str> = vec!["stats", "--chain", "chain_2"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("chain_2 ⦁ 1 branches ⦁ 1 missing ⦁ last commit "));
+    assert!(stdout.lines().next().unwrap().ends_with(" ⦁ never rebased"));
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,621 @@
+use std::path::PathBuf;
+
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_bare_repo, generate_path_to_repo, get_current_branch_name, run_git_command,
+    run_test_bin_expect_err, run_test_bin_expect_ok, run_test_bin_with_env, setup_git_bare_repo,
+    setup_git_repo, teardown_git_bare_repo, teardown_git_repo,
+};
+
+#[test]
+fn status_subcommand_short() {
+    let repo_name = "status_subcommand_short";
+    let repo = setup_git_repo(repo_name);
+    let _bare_repo = setup_git_bare_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let path_to_bare_repo = {
+        let mut path_to_bare_repo_buf: PathBuf = generate_path_to_bare_repo(repo_name);
+        if path_to_bare_repo_buf.is_relative() {
+            path_to_bare_repo_buf = path_to_bare_repo_buf.canonicalize().unwrap();
+        }
+
+        path_to_bare_repo_buf.to_str().unwrap().to_string()
+    };
+
+    run_git_command(
+        path_to_repo.clone(),
+        vec!["remote", "add", "origin", &path_to_bare_repo],
+    );
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "message");
+    };
+
+    // create and checkout new branch named some_branch_2
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    // run git chain setup
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    run_git_command(
+        &path_to_repo,
+        vec!["push", "--all", "--set-upstream", "origin"],
+    );
+
+    // add commits to master and some_branch_2
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "file_master.txt", "contents master");
+        commit_all(&repo, "message");
+
+        checkout_branch(&repo, "some_branch_2");
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2.5");
+        commit_all(&repo, "message");
+    };
+
+    // git chain status --short
+    let args: Vec<&str> = vec!["status", "--short"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+➜ some_branch_2 +2 ↑1
+  some_branch_1 +1 -1
+"#
+        .trim_start()
+    );
+
+    // git chain status
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_2
+
+chain_name
+    ➜ some_branch_2 ⦁ 2 ahead
+      some_branch_1 ⦁ 1 ahead ⦁ 1 behind
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+    teardown_git_bare_repo(repo_name);
+}
+
+#[test]
+fn status_subcommand_missing_branch() {
+    let repo_name = "status_subcommand_missing_branch";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for (index, branch_name) in ["some_branch_1", "some_branch_2", "some_branch_3"]
+        .iter()
+        .enumerate()
+    {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(
+            &path_to_repo,
+            &format!("file_{}.txt", index + 1),
+            "contents",
+        );
+        commit_all(&repo, "message");
+    }
+
+    // run git chain setup
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+        "some_branch_3",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // delete some_branch_2 without git chain
+    run_git_command(
+        &path_to_repo,
+        vec!["update-ref", "-d", "refs/heads/some_branch_2"],
+    );
+
+    // git chain status
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_3
+
+chain_name
+    ➜ some_branch_3 ⦁ 2 ahead
+      some_branch_2 (missing)
+      some_branch_1 ⦁ 1 ahead
+      master (root branch)
+
+⚠️  Some branches of the chain no longer exist.
+To remove them from the chain, run: git chain prune
+"#
+        .trim_start()
+    );
+
+    // git chain status --short
+    let args: Vec<&str> = vec!["status", "--short"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with(
+        r#"
+➜ some_branch_3 +2
+  some_branch_2 (missing)
+  some_branch_1 +1
+"#
+        .trim_start()
+    ));
+
+    // git chain rebase refuses to run
+    let args: Vec<&str> = vec!["rebase"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Branch does not exist: some_branch_2")
+    );
+
+    // git chain prune removes the missing branch from the chain
+    let args: Vec<&str> = vec!["prune", "--yes"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("some_branch_2"));
+
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_3
+
+chain_name
+    ➜ some_branch_3 ⦁ 2 ahead
+      some_branch_1 ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+// A stand-in for gh, with a pull request for some_branch_1 only.
+const FAKE_GH: &str = r#"#!/bin/sh
+case "$1 $2 $3" in
+  "pr view some_branch_1")
+    printf '12\thttps://github.com/owner/repo/pull/12\tOPEN\t\tMERGEABLE\n'
+    ;;
+  *)
+    echo "no pull requests found for branch \"$3\"" >&2
+    exit 1
+    ;;
+esac
+"#;
+
+#[cfg(unix)]
+#[test]
+fn status_subcommand_markdown() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo_name = "status_subcommand_markdown";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["note", "set", "waiting | on review"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let path_to_bin = path_to_repo.canonicalize().unwrap().join("bin");
+    fs::create_dir_all(&path_to_bin).unwrap();
+    let path_to_gh = path_to_bin.join("gh");
+    fs::write(&path_to_gh, FAKE_GH).unwrap();
+    fs::set_permissions(&path_to_gh, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!(
+        "{}:{}",
+        path_to_bin.display(),
+        std::env::var("PATH").unwrap()
+    );
+    let envs = vec![("PATH", path.as_str())];
+
+    let args: Vec<&str> = vec!["status", "--format=markdown"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+### chain_name
+
+Root branch: `master`
+
+| Branch | Pull request | Status | Note |
+| --- | --- | --- | --- |
+| `some_branch_1` | [#12](https://github.com/owner/repo/pull/12) | 1 ahead |  |
+| ➜ **`some_branch_2`** |  | 1 ahead | waiting \| on review |
+"#
+        .trim_start()
+    );
+
+    // status --short shows the number of the pull request of each branch
+    let args: Vec<&str> = vec!["status", "--short"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs.clone());
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+➜ some_branch_2 +1 📝 waiting | on review
+  some_branch_1 +1 #12
+"#
+        .trim_start()
+    );
+
+    // list renders every chain the same way
+    let args: Vec<&str> = vec!["list", "--format=markdown"];
+    let output = run_test_bin_with_env(&path_to_repo, args, envs);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("### chain_name\n"));
+
+    // the format of status --short is fixed
+    let args: Vec<&str> = vec!["status", "--short", "--format=markdown"];
+    run_test_bin_expect_err(&path_to_repo, args);
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn status_subcommand_unrelated_history() {
+    let repo_name = "status_subcommand_unrelated_history";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    create_branch(&repo, "some_branch_1");
+    checkout_branch(&repo, "some_branch_1");
+    create_new_file(&path_to_repo, "some_branch_1.txt", "contents");
+    commit_all(&repo, "message");
+
+    // a branch with a history of its own
+    run_git_command(&path_to_repo, vec!["checkout", "--orphan", "unrelated"]);
+    run_git_command(&path_to_repo, vec!["rm", "-r", "-q", "--cached", "."]);
+    run_git_command(&path_to_repo, vec!["clean", "-f", "-q"]);
+    create_new_file(&path_to_repo, "unrelated.txt", "contents");
+    run_git_command(&path_to_repo, vec!["add", "unrelated.txt"]);
+    run_git_command(&path_to_repo, vec!["commit", "-q", "-m", "unrelated"]);
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "unrelated",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["status"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: unrelated
+
+chain_name
+    ➜ unrelated ⦁ unrelated history
+      some_branch_1 ⦁ 1 ahead
+      master (root branch)
+
+⚠️  Branch unrelated shares no history with its parent branch some_branch_1.
+To rebase the other branches of the chain, leaving these as they are, run: git chain rebase --allow-unrelated
+"#
+        .trim_start()
+    );
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn status_subcommand_check_conflicts() {
+    let repo_name = "status_subcommand_check_conflicts";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "file.txt", "line 1\nline 2");
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file.txt", "line one\nline 2");
+        commit_all(&repo, "message");
+    };
+
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // without new commits in the parent branches, nothing would conflict
+    let args: Vec<&str> = vec!["status", "--check-conflicts"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("conflict"));
+
+    // master and some_branch_1 change the lines that their child branches changed
+    {
+        checkout_branch(&repo, "master");
+        create_new_file(&path_to_repo, "file.txt", "line uno\nline 2");
+        create_new_file(&path_to_repo, "file_master.txt", "contents master");
+        commit_all(&repo, "message");
+
+        checkout_branch(&repo, "some_branch_1");
+        create_new_file(&path_to_repo, "file_2.txt", "other contents 2");
+        commit_all(&repo, "message");
+
+        checkout_branch(&repo, "some_branch_2");
+    };
+
+    let args: Vec<&str> = vec!["status", "--check-conflicts"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_2
+
+chain_name
+    ➜ some_branch_2 ⦁ 1 ahead ⦁ 1 behind ⦁ ⚠ will conflict in 1 files
+      some_branch_1 ⦁ 2 ahead ⦁ 1 behind ⦁ ⚠ will conflict in 1 files
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["status", "--short", "--check-conflicts"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+➜ some_branch_2 +1 -1 ⚠1
+  some_branch_1 +2 -1 ⚠1
+"#
+        .trim_start()
+    );
+
+    // the prediction does not touch the working directory
+    let output = run_git_command(&path_to_repo, vec!["status", "--porcelain"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+
+    // without --check-conflicts, status does not merge
+    let args: Vec<&str> = vec!["status", "--short"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("⚠"));
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn status_subcommand_chain() {
+    let repo_name = "status_subcommand_chain";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    // other_branch is created from master, the others are stacked
+    for branch_name in ["other_branch", "some_branch_1", "some_branch_2"] {
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, &format!("{}.txt", branch_name), "contents");
+        commit_all(&repo, "message");
+        if branch_name == "other_branch" {
+            checkout_branch(&repo, "master");
+        }
+    }
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["setup", "other_chain", "master", "other_branch"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // the chain of another branch than the current branch
+    checkout_branch(&repo, "some_branch_2");
+
+    let args: Vec<&str> = vec!["status", "--chain", "other_chain"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+On branch: some_branch_2
+
+other_chain
+      other_branch ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["status", "--short", "--chain", "other_chain"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "  other_branch +1\n"
+    );
+
+    // GIT_CHAIN_CHAIN selects the chain as well, and --chain takes precedence over it
+    let args: Vec<&str> = vec!["status", "--short"];
+    let output = run_test_bin_with_env(
+        &path_to_repo,
+        args,
+        vec![("GIT_CHAIN_CHAIN", "other_chain")],
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "  other_branch +1\n"
+    );
+
+    let args: Vec<&str> = vec!["status", "--short", "--chain", "chain_name"];
+    let output = run_test_bin_with_env(
+        &path_to_repo,
+        args,
+        vec![("GIT_CHAIN_CHAIN", "other_chain")],
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+➜ some_branch_2 +1
+  some_branch_1 +1
+"#
+        .trim_start()
+    );
+
+    // a branch that is not part of any chain, or a detached HEAD
+    checkout_branch(&repo, "master");
+
+    let args: Vec<&str> = vec!["status"];
+    run_test_bin_expect_err(&path_to_repo, args);
+
+    let args: Vec<&str> = vec!["status", "--short", "--chain", "chain_name"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "  some_branch_2 +1\n  some_branch_1 +1\n"
+    );
+
+    run_git_command(&path_to_repo, vec!["checkout", "--quiet", "--detach"]);
+
+    let args: Vec<&str> = vec!["status", "--chain", "other_chain"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+other_chain
+      other_branch ⦁ 1 ahead
+      master (root branch)
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["status", "--chain", "does_not_exist"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Chain does not exist: does_not_exist")
+    );
+
+    teardown_git_repo(repo_name);
+}
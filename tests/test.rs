@@ -0,0 +1,163 @@
+pub mod common;
+use common::{
+    checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, get_current_branch_name, run_git_command, run_test_bin_expect_err,
+    run_test_bin_expect_ok, setup_git_repo, teardown_git_repo,
+};
+
+#[test]
+fn test_subcommand() {
+    let repo_name = "test_subcommand";
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        // create new file
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+
+        // add first commit to master
+        first_commit_all(&repo, "first commit");
+    };
+
+    assert_eq!(&get_current_branch_name(&repo), "master");
+
+    // create and checkout new branch named some_branch_1
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "add file_1");
+    };
+
+    // create and checkout new branch named some_branch_2
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+    };
+
+    {
+        assert_eq!(&get_current_branch_name(&repo), "some_branch_2");
+
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "add file_2");
+    };
+
+    // run git chain setup
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    checkout_branch(&repo, "some_branch_1");
+
+    // every branch passes
+    let args: Vec<&str> = vec!["test", "--", "test -f file_1.txt"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Testing branch: some_branch_1
+
+Testing branch: some_branch_2
+
+Switching back to branch: some_branch_1
+
+📋 Test summary for chain chain_name:
+  some_branch_1: ✅ passed
+  some_branch_2: ✅ passed
+
+🎉 All branches of chain chain_name passed: test -f file_1.txt
+"#
+        .trim_start()
+    );
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+
+    // the first branch fails
+    let args: Vec<&str> = vec!["test", "--", "test", "-f", "file_2.txt"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout).contains(
+        r#"
+📋 Test summary for chain chain_name:
+  some_branch_1: ❌ failed (exit code 1)
+  some_branch_2: ✅ passed
+"#
+    ));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "🛑 1 of 2 branches of chain chain_name failed: test -f file_2.txt\n"
+    );
+    assert_eq!(&get_current_branch_name(&repo), "some_branch_1");
+
+    // stop at the first branch that fails
+    let args: Vec<&str> = vec!["test", "--fail-fast", "--", "test -f file_2.txt"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert!(String::from_utf8_lossy(&output.stdout).contains(
+        r#"
+📋 Test summary for chain chain_name:
+  some_branch_1: ❌ failed (exit code 1)
+  some_branch_2: not run
+"#
+    ));
+
+    // uncommitted changes
+    create_new_file(&path_to_repo, "file_1.txt", "changed");
+    let args: Vec<&str> = vec!["test", "--", "true"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        r#"
+🛑 Unable to test the branches of the chain: chain_name
+You have uncommitted changes in your working directory.
+Please commit or stash them, or run with --worktrees.
+"#
+        .trim_start()
+    );
+
+    // worktrees leave the working directory alone
+    let args: Vec<&str> = vec![
+        "test",
+        "--jobs",
+        "2",
+        "--",
+        "echo $GIT_CHAIN_TEST_BRANCH; test -f file_2.txt",
+    ];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+Testing branch: some_branch_1
+some_branch_1
+
+Testing branch: some_branch_2
+some_branch_2
+
+📋 Test summary for chain chain_name:
+  some_branch_1: ❌ failed (exit code 1)
+  some_branch_2: ✅ passed
+
+"#
+        .trim_start()
+    );
+
+    // the temporary worktrees are removed
+    let output = run_git_command(&path_to_repo, vec!["worktree", "list"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+
+    teardown_git_repo(repo_name);
+}
@@ -0,0 +1,231 @@
+use git2::{BranchType, Repository};
+
+pub mod common;
+use common::{
+    branch_equal, checkout_branch, commit_all, create_branch, create_new_file, first_commit_all,
+    generate_path_to_repo, run_git_command, run_test_bin_expect_err, run_test_bin_expect_ok,
+    run_test_bin_for_rebase, setup_git_repo, teardown_git_repo,
+};
+
+fn latest_backup(repo: &Repository, chain_name: &str) -> String {
+    // timestamp of the latest backup of the chain
+    let prefix = format!("backup-{}/", chain_name);
+
+    let mut timestamps: Vec<String> = repo
+        .branches(Some(BranchType::Local))
+        .unwrap()
+        .filter_map(|branch| {
+            let name = branch.unwrap().0.name().unwrap().unwrap().to_string();
+            let rest = name.strip_prefix(&prefix)?;
+            Some(rest.split('/').next().unwrap().to_string())
+        })
+        .collect();
+    timestamps.sort();
+
+    timestamps.pop().unwrap()
+}
+
+fn setup_chain(repo_name: &str) -> Repository {
+    let repo = setup_git_repo(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    {
+        create_new_file(&path_to_repo, "hello_world.txt", "Hello, world!");
+        first_commit_all(&repo, "first commit");
+    };
+
+    {
+        let branch_name = "some_branch_1";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1");
+        commit_all(&repo, "first message");
+        create_new_file(&path_to_repo, "file_1.txt", "contents 1 again");
+        commit_all(&repo, "second message");
+    };
+
+    {
+        let branch_name = "some_branch_2";
+        create_branch(&repo, branch_name);
+        checkout_branch(&repo, branch_name);
+        create_new_file(&path_to_repo, "file_2.txt", "contents 2");
+        commit_all(&repo, "third message");
+    };
+
+    let args: Vec<&str> = vec![
+        "setup",
+        "chain_name",
+        "master",
+        "some_branch_1",
+        "some_branch_2",
+    ];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    repo
+}
+
+#[test]
+fn verify_subcommand() {
+    let repo_name = "verify_subcommand";
+    let repo = setup_chain(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    // without backups, lost commits cannot be detected
+    let args: Vec<&str> = vec!["verify"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"
+✅ Verified chain chain_name: every branch is based on its parent branch.
+The chain has no backups to detect lost commits. To back it up, run: git chain backup
+"#
+        .trim_start()
+    );
+
+    let args: Vec<&str> = vec!["backup"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+    let timestamp = latest_backup(&repo, "chain_name");
+
+    let args: Vec<&str> = vec!["verify"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "✅ Verified chain chain_name: every branch is based on its parent branch, and no commits were lost since backup {}.\n",
+            timestamp
+        )
+    );
+
+    // drop the last commit of some_branch_1, which some_branch_2 is still based on
+    run_git_command(
+        &path_to_repo,
+        vec!["branch", "--force", "some_branch_1", "some_branch_1~1"],
+    );
+    // commit to master, which is not rebased onto
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "file_3.txt", "contents 3");
+    commit_all(&repo, "master message");
+    checkout_branch(&repo, "some_branch_2");
+    create_new_file(&path_to_repo, "file_2.txt", "uncommitted");
+
+    let args: Vec<&str> = vec!["verify"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines[0], "🛑 Chain chain_name failed verification:");
+    assert_eq!(
+        lines[1],
+        "  - Branch some_branch_1 is not based on its parent branch master"
+    );
+    assert_eq!(
+        lines[2],
+        format!(
+            "  - Branch some_branch_1 lost 1 commits of its backup backup-chain_name/{}/some_branch_1",
+            timestamp
+        )
+    );
+    assert!(lines[3].starts_with("      "));
+    assert!(lines[3].ends_with(" second message"));
+    assert_eq!(
+        lines[4],
+        "  - You have uncommitted changes in your working directory."
+    );
+    assert_eq!(
+        lines[5..].join("\n"),
+        format!(
+            r#"
+To restore the branches of the chain from backup {timestamp}, run:
+    git checkout --detach
+    git branch --force some_branch_1 backup-chain_name/{timestamp}/some_branch_1
+    git branch --force some_branch_2 backup-chain_name/{timestamp}/some_branch_2
+    git checkout some_branch_2"#
+        )
+    );
+
+    // follow the instructions
+    run_git_command(&path_to_repo, vec!["checkout", "--", "file_2.txt"]);
+    run_git_command(&path_to_repo, vec!["checkout", "--quiet", "--detach"]);
+    for branch_name in ["some_branch_1", "some_branch_2"] {
+        let backup_branch = format!("backup-chain_name/{}/{}", timestamp, branch_name);
+        run_git_command(
+            &path_to_repo,
+            vec!["branch", "--force", branch_name, &backup_branch],
+        );
+    }
+    run_git_command(&path_to_repo, vec!["checkout", "--quiet", "some_branch_2"]);
+
+    // the new commit of master is still missing from the chain
+    let args: Vec<&str> = vec!["verify"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("  - Branch some_branch_1 is not based on its parent branch master\n"));
+    assert!(!stderr.contains("lost"));
+
+    // rebasing keeps every commit of the backup
+    let args: Vec<&str> = vec!["rebase", "--verify"];
+    let output = run_test_bin_for_rebase(&path_to_repo, args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("🎉 Successfully rebased chain chain_name"));
+    assert!(stdout.ends_with(&format!(
+        "✅ Verified chain chain_name: every branch is based on its parent branch, and no commits were lost since backup {}.\n",
+        timestamp
+    )));
+    assert!(output.status.success());
+
+    teardown_git_repo(repo_name);
+}
+
+#[test]
+fn verify_landed_commits() {
+    let repo_name = "verify_landed_commits";
+    let repo = setup_chain(repo_name);
+    let path_to_repo = generate_path_to_repo(repo_name);
+
+    let args: Vec<&str> = vec!["backup"];
+    run_test_bin_expect_ok(&path_to_repo, args);
+
+    // the first commit of some_branch_1 lands on master, after another commit
+    checkout_branch(&repo, "master");
+    create_new_file(&path_to_repo, "file_3.txt", "contents 3");
+    commit_all(&repo, "master message");
+    run_git_command(&path_to_repo, vec!["cherry-pick", "some_branch_1~1"]);
+    checkout_branch(&repo, "some_branch_2");
+
+    // rebasing drops the landed commit from some_branch_1
+    let args: Vec<&str> = vec!["rebase"];
+    run_test_bin_for_rebase(&path_to_repo, args);
+    assert!(!branch_equal(
+        &repo,
+        "some_branch_1",
+        &format!(
+            "backup-chain_name/{}/some_branch_1",
+            latest_backup(&repo, "chain_name")
+        )
+    ));
+
+    let output = run_git_command(
+        &path_to_repo,
+        vec!["rev-list", "--count", "master..some_branch_1"],
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "1");
+
+    let args: Vec<&str> = vec!["verify"];
+    let output = run_test_bin_expect_ok(&path_to_repo, args);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("✅ Verified chain chain_name"));
+
+    // verification after cherry is configured with chain.verify
+    run_git_command(&path_to_repo, vec!["config", "chain.verify", "true"]);
+    checkout_branch(&repo, "some_branch_1");
+    run_git_command(
+        &path_to_repo,
+        vec!["branch", "--force", "some_branch_2", "some_branch_1~1"],
+    );
+    let args: Vec<&str> = vec!["cherry"];
+    let output = run_test_bin_expect_err(&path_to_repo, args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("🛑 Chain chain_name failed verification:"));
+    assert!(stderr.contains("  - Branch some_branch_2 lost 1 commits of its backup"));
+
+    teardown_git_repo(repo_name);
+}